@@ -0,0 +1,50 @@
+// resolves the impacts of a grid of balls hitting a static ground, once through resolve_contact's
+// dynamic vs static path and once through the general path. both start from the same bodies and
+// the same contacts found by collect_contacts(), and only the resolving is timed
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use glam::Vec3;
+use physics::{body::Body, scene::PhysicsScene, shapes::Shape};
+
+const BALLS_PER_SIDE: usize = 16;
+const DELTA_SECONDS: f32 = 1.0 / 60.0;
+
+fn make_scene() -> PhysicsScene {
+    let mut scene = PhysicsScene::empty();
+    scene.add_body(Body {
+        position: Vec3::new(0.0, -0.5, 0.0),
+        inv_mass: 0.0,
+        shape: Shape::make_box_from_half_extents(Vec3::new(50.0, 0.5, 50.0)),
+        ..Body::default()
+    });
+    // 0.5 above the ground and moving 1.0 this step, so each gets a ballistic contact
+    for i in 0..BALLS_PER_SIDE * BALLS_PER_SIDE {
+        let x = (i % BALLS_PER_SIDE) as f32 * 2.0 - BALLS_PER_SIDE as f32;
+        let z = (i / BALLS_PER_SIDE) as f32 * 2.0 - BALLS_PER_SIDE as f32;
+        scene.add_body(Body {
+            position: Vec3::new(x, 1.0, z),
+            linear_velocity: Vec3::new(0.0, -60.0, 0.0),
+            shape: Shape::make_sphere(0.5),
+            ..Body::default()
+        });
+    }
+    scene.collect_contacts(DELTA_SECONDS);
+    assert_eq!(BALLS_PER_SIDE * BALLS_PER_SIDE, scene.stats().contacts);
+    scene
+}
+
+fn bench_resolve_contact(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve_contact");
+    for (name, general) in [("dynamic_vs_static", false), ("general", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(
+                make_scene,
+                |scene| scene.resolve_ballistic_contacts(general),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_resolve_contact);
+criterion_main!(benches);
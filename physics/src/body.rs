@@ -1,20 +1,35 @@
-use crate::shapes::Shape;
+use crate::shapes::{MassProperties, Shape};
 use glam::{Mat3, Quat, Vec3};
 
+// the body's slot in the arena and the generation of that slot when it was added. a removed
+// body's slot is reused with a new generation, so a kept copy of the old handle is no longer
+// valid rather than pointing at the wrong body
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct BodyHandle(pub u32);
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyHandle(pub u32, pub u32);
 
 impl Default for BodyHandle {
     // default to invalid value
     fn default() -> Self {
-        Self(u32::MAX)
+        Self(u32::MAX, 0)
     }
 }
 
-#[derive(Debug)]
+// the bodies are kept packed for iterating, the handles point at slots that map to where each
+// body is. removing a body moves the last one into its place but only changes its slot's
+// index, so the other handles stay valid
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct BodyArena {
     bodies: Vec<Body>,
+    // the handle of each body, in the same order
     handles: Vec<BodyHandle>,
+    // the index in bodies of each slot's body
+    slots: Vec<u32>,
+    // the next generation for each slot, bumped when its body is removed
+    generations: Vec<u32>,
+    // the slots of removed bodies, reused before new slots are added
+    free_slots: Vec<u32>,
 }
 
 impl Default for BodyArena {
@@ -28,16 +43,40 @@ impl BodyArena {
         BodyArena {
             bodies: Vec::new(),
             handles: Vec::new(),
+            slots: Vec::new(),
+            generations: Vec::new(),
+            free_slots: Vec::new(),
         }
     }
 
     pub fn add(&mut self, body: Body) -> BodyHandle {
-        let handle = BodyHandle(self.bodies.len() as u32);
+        let slot = self.free_slots.pop().unwrap_or_else(|| {
+            self.slots.push(0);
+            self.generations.push(0);
+            self.slots.len() as u32 - 1
+        });
+        let handle = BodyHandle(slot, self.generations[slot as usize]);
+        self.slots[slot as usize] = self.bodies.len() as u32;
         self.bodies.push(body);
         self.handles.push(handle);
         handle
     }
 
+    // the handle still refers to the body it was given for
+    pub fn is_valid(&self, handle: BodyHandle) -> bool {
+        self.slots
+            .get(handle.0 as usize)
+            .and_then(|&index| self.handles.get(index as usize))
+            == Some(&handle)
+    }
+
+    // where the body is in iter() and handles(), which changes when other bodies are removed.
+    // panics for a removed body's handle, which would otherwise find whichever body took its place
+    pub fn index(&self, handle: BodyHandle) -> usize {
+        assert!(self.is_valid(handle), "stale body handle {:?}", handle);
+        self.slots[handle.0 as usize] as usize
+    }
+
     pub fn iter(&self) -> core::slice::Iter<Body> {
         self.bodies.iter()
     }
@@ -46,9 +85,40 @@ impl BodyArena {
         self.bodies.iter_mut()
     }
 
+    // each body with its handle, in the same order as iter()
+    pub fn iter_with_handles(&self) -> impl Iterator<Item = (BodyHandle, &Body)> {
+        self.handles.iter().copied().zip(self.bodies.iter())
+    }
+
+    // only the removed body's handle stops being valid, its slot is reused by a later add()
+    pub fn remove(&mut self, handle: BodyHandle) -> Body {
+        assert!(
+            self.is_valid(handle),
+            "removing a body that's gone {:?}",
+            handle
+        );
+        let slot = handle.0 as usize;
+        let index = self.slots[slot] as usize;
+        let body = self.bodies.swap_remove(index);
+        self.handles.swap_remove(index);
+        if let Some(moved) = self.handles.get(index) {
+            self.slots[moved.0 as usize] = index as u32;
+        }
+        self.generations[slot] = self.generations[slot].wrapping_add(1);
+        self.free_slots.push(handle.0);
+        body
+    }
+
     pub fn clear(&mut self) {
+        // none of the handles given out so far are valid after this
+        for handle in &self.handles {
+            let generation = &mut self.generations[handle.0 as usize];
+            *generation = generation.wrapping_add(1);
+        }
         self.bodies.clear();
         self.handles.clear();
+        self.free_slots.clear();
+        self.free_slots.extend((0..self.slots.len() as u32).rev());
     }
 
     pub fn len(&self) -> usize {
@@ -88,15 +158,16 @@ impl BodyArena {
         index_a: BodyHandle,
         index_b: BodyHandle,
     ) -> (&mut Body, &mut Body) {
-        self.get_body_pair_mut_from_indices(index_a.0 as usize, index_b.0 as usize)
+        self.get_body_pair_mut_from_indices(self.index(index_a), self.index(index_b))
     }
 
     pub fn get_body_mut(&mut self, handle: BodyHandle) -> &mut Body {
-        &mut self.bodies[handle.0 as usize]
+        let index = self.index(handle);
+        &mut self.bodies[index]
     }
 
     pub fn get_body(&self, handle: BodyHandle) -> &Body {
-        &self.bodies[handle.0 as usize]
+        &self.bodies[self.index(handle)]
     }
 
     pub fn handles(&self) -> &Vec<BodyHandle> {
@@ -139,16 +210,341 @@ impl BodyArena {
 //     }
 // }
 
+// how the friction or elasticity of two bodies are combined for a contact between them. when the
+// bodies ask for different rules the one later in the list is used, the same as PhysX and Unity
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum CombineRule {
+    Average,
+    Min,
+    #[default]
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineRule::Average => (a + b) * 0.5,
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+
+    // the rule for a contact between bodies that may each override the scene's
+    pub fn pick(scene: CombineRule, a: Option<CombineRule>, b: Option<CombineRule>) -> CombineRule {
+        a.max(b).unwrap_or(scene)
+    }
+}
+
+// the rules used for bodies that don't have their own
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CombineRules {
+    pub friction: CombineRule,
+    pub elasticity: CombineRule,
+}
+
+// elasticity and friction presets for common surfaces
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    pub elasticity: f32,
+    pub friction: f32,
+    // override the scene's combine rules, see CombineRule
+    pub elasticity_combine: Option<CombineRule>,
+    pub friction_combine: Option<CombineRule>,
+}
+
+impl Material {
+    pub const RUBBER: Material = Material {
+        elasticity: 0.9,
+        friction: 0.9,
+        elasticity_combine: None,
+        friction_combine: None,
+    };
+    pub const ICE: Material = Material {
+        elasticity: 0.1,
+        friction: 0.02,
+        elasticity_combine: None,
+        friction_combine: None,
+    };
+    pub const WOOD: Material = Material {
+        elasticity: 0.4,
+        friction: 0.5,
+        elasticity_combine: None,
+        friction_combine: None,
+    };
+    pub const METAL: Material = Material {
+        elasticity: 0.6,
+        friction: 0.3,
+        elasticity_combine: None,
+        friction_combine: None,
+    };
+}
+
+impl From<Material> for PhysicsMaterial {
+    fn from(material: Material) -> Self {
+        Self {
+            friction: material.friction,
+            restitution: material.elasticity,
+            friction_combine: material.friction_combine,
+            restitution_combine: material.elasticity_combine,
+            ..Self::default()
+        }
+    }
+}
+
+// a material added to the scene with PhysicsScene::add_material
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialHandle(pub u32);
+
+// properties shared by every body that refers to the material, they replace the body's own
+// friction, elasticity and combine rules at the start of each step so editing the material
+// changes all of them at once
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    // the body's elasticity
+    pub restitution: f32,
+    // sets the mass of dynamic bodies from their shape's volume, 0.0 leaves their inv_mass alone
+    pub density: f32,
+    pub friction_combine: Option<CombineRule>,
+    pub restitution_combine: Option<CombineRule>,
+    pub rolling_friction: f32,
+    pub spinning_friction: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            friction: 0.5,
+            restitution: 0.5,
+            density: 0.0,
+            friction_combine: None,
+            restitution_combine: None,
+            rolling_friction: 0.0,
+            spinning_friction: 0.0,
+        }
+    }
+}
+
+impl PhysicsMaterial {
+    // copies the material onto the body
+    pub fn apply(&self, body: &mut Body) {
+        body.friction = self.friction;
+        body.elasticity = self.restitution;
+        body.friction_combine = self.friction_combine;
+        body.elasticity_combine = self.restitution_combine;
+        body.rolling_friction = self.rolling_friction;
+        body.spinning_friction = self.spinning_friction;
+        if self.density > 0.0 && !body.has_infinite_mass() {
+            body.inv_mass = MassProperties::from_shape(&body.shape, self.density).inv_mass();
+        }
+    }
+}
+
+// which groups a body is in and which groups it collides with, as bit masks. two bodies only
+// collide when each is in a group the other collides with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct InteractionGroups {
+    pub memberships: u32,
+    pub filter: u32,
+}
+
+impl InteractionGroups {
+    // in every group and colliding with every group
+    pub const ALL: InteractionGroups = InteractionGroups {
+        memberships: u32::MAX,
+        filter: u32::MAX,
+    };
+    // colliding with nothing
+    pub const NONE: InteractionGroups = InteractionGroups {
+        memberships: 0,
+        filter: 0,
+    };
+
+    pub fn new(memberships: u32, filter: u32) -> Self {
+        Self {
+            memberships,
+            filter,
+        }
+    }
+
+    pub fn interacts_with(&self, other: &InteractionGroups) -> bool {
+        (self.memberships & other.filter) != 0 && (other.memberships & self.filter) != 0
+    }
+}
+
+impl Default for InteractionGroups {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+// the world axes a body can't move along or turn about, as a bit mask. combine them with |, for
+// example a character that never tips over locks ROTATION and a 2.5D game locks TRANSLATION_Z
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockedAxes(pub u8);
+
+impl LockedAxes {
+    pub const NONE: LockedAxes = LockedAxes(0);
+    pub const TRANSLATION_X: LockedAxes = LockedAxes(1);
+    pub const TRANSLATION_Y: LockedAxes = LockedAxes(1 << 1);
+    pub const TRANSLATION_Z: LockedAxes = LockedAxes(1 << 2);
+    pub const ROTATION_X: LockedAxes = LockedAxes(1 << 3);
+    pub const ROTATION_Y: LockedAxes = LockedAxes(1 << 4);
+    pub const ROTATION_Z: LockedAxes = LockedAxes(1 << 5);
+    pub const TRANSLATION: LockedAxes = LockedAxes(0b111);
+    pub const ROTATION: LockedAxes = LockedAxes(0b111 << 3);
+
+    pub fn contains(self, other: LockedAxes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    // 0.0 along the locked axes and 1.0 along the free ones
+    pub fn translation_mask(self) -> Vec3 {
+        let free = |axis: LockedAxes| if self.contains(axis) { 0.0 } else { 1.0 };
+        Vec3::new(
+            free(Self::TRANSLATION_X),
+            free(Self::TRANSLATION_Y),
+            free(Self::TRANSLATION_Z),
+        )
+    }
+
+    pub fn rotation_mask(self) -> Vec3 {
+        LockedAxes(self.0 >> 3).translation_mask()
+    }
+
+    pub fn locks_translation(self) -> bool {
+        self.0 & Self::TRANSLATION.0 != 0
+    }
+
+    pub fn locks_rotation(self) -> bool {
+        self.0 & Self::ROTATION.0 != 0
+    }
+}
+
+impl std::ops::BitOr for LockedAxes {
+    type Output = LockedAxes;
+
+    fn bitor(self, other: LockedAxes) -> LockedAxes {
+        LockedAxes(self.0 | other.0)
+    }
+}
+
+// how a body is moved
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum BodyType {
+    // moved by gravity, contacts and constraints, a body with an inv_mass of 0.0 never moves
+    #[default]
+    Dynamic,
+    // only moved by its own velocity, which is worked out from its kinematic_target each step
+    // when it has one. it has infinite mass so it pushes dynamic bodies without being pushed back,
+    // like a moving platform
+    Kinematic,
+}
+
+// an extra shape that's only used for collisions, the body's mass and inertia still come from
+// its primary shape
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collider {
+    pub shape: Shape,
+    pub offset: Vec3, // from the body's position, in the body's space
+}
+
+// other bodies only collide with the side of a one way platform facing `direction`, from the
+// other side they pass through it. like jumping up through a ledge and landing on top of it
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct OneWayPlatform {
+    pub direction: Vec3, // in the platform's space
+}
+
+impl OneWayPlatform {
+    // contact normals further than 45 degrees from the direction pass through
+    const MIN_COS_ANGLE: f32 = core::f32::consts::FRAC_1_SQRT_2;
+
+    // normal is the contact normal in world space pointing from the platform to the other body
+    pub fn blocks(&self, platform: &Body, normal: Vec3) -> bool {
+        let direction = (platform.orientation * self.direction).normalize_or_zero();
+        direction.dot(normal) >= Self::MIN_COS_ANGLE
+    }
+}
+
+// how long a body has to stay under its sleep thresholds before it's put to sleep
+const TIME_TO_SLEEP: f32 = 1.0;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Body {
     pub position: Vec3,
     pub orientation: Quat,
+    // where the body was at the start of the last step, for interpolating between steps
+    pub previous_position: Vec3,
+    pub previous_orientation: Quat,
     pub linear_velocity: Vec3,
     pub angular_velocity: Vec3,
     pub inv_mass: f32,
     pub elasticity: f32,
     pub friction: f32,
+    // override the scene's combine rules, see CombineRule
+    pub elasticity_combine: Option<CombineRule>,
+    pub friction_combine: Option<CombineRule>,
+    // how hard the body resists rolling and spinning on what it touches, as the torque for each
+    // unit of force pressing them together. combined with the friction's rule
+    pub rolling_friction: f32,
+    pub spinning_friction: f32,
+    // how fast the surface moves without the body moving, in the body's space. friction drags what
+    // touches it along, like a conveyor belt. only the part along the surface is used
+    pub surface_velocity: Vec3,
+    // a shared material that replaces the values above, see PhysicsMaterial
+    pub material: Option<MaterialHandle>,
+    // the solver treats the body as infinitely heavy along and about these axes
+    pub locked_axes: LockedAxes,
+    // how much of a contact response this body takes, 0.0 leaves it unaffected by contacts
+    pub response_weight: f32,
+    // multiplies the rotational inertia without changing the mass, above 1.0 is harder to spin. it
+    // has to be above 0.0, locked_axes keeps a body from turning
+    pub inertia_scale: f32,
+    // multiplies the scene's gravity for this body, 0.0 floats and negative values fall upwards
+    pub gravity_scale: f32,
+    // false leaves the body out of the scene's force fields, see ForceField
+    pub force_fields_enabled: bool,
+    // continuous collision detection is only used when the body moves at least this fraction of
+    // its bounding radius in a step, 0.0 always uses it
+    pub ccd_motion_threshold: f32,
+    // false never sweeps the body, it's tested where it is with a speculative margin instead. that's
+    // cheaper but thin or small bodies can still be tunneled through by fast ones
+    pub ccd_enabled: bool,
+    // override the scene's damping for this body
+    pub linear_damping: Option<f32>,
+    pub angular_damping: Option<f32>,
+    // override the scene's sleep thresholds for this body
+    pub sleep_linear_threshold: Option<f32>,
+    pub sleep_angular_threshold: Option<f32>,
+    pub sleeping: bool,
+    pub rest_time: f32, // how long the body has been moving slower than its sleep thresholds
+    pub color: Vec3,    // rgb used when rendering the body
     pub shape: Shape,
+    // when not empty these collide instead of the shape
+    pub colliders: Vec<Collider>,
+    pub collision_groups: InteractionGroups,
+    // the world space force and torque about the centre of mass applied over the next steps, see
+    // PhysicsScene::clear_forces
+    pub force: Vec3,
+    pub torque: Vec3,
+    pub body_type: BodyType,
+    // where a kinematic body should be at the end of the next step, as (position, orientation)
+    pub kinematic_target: Option<(Vec3, Quat)>,
+    // sensors send trigger events for the bodies overlapping them, but nothing collides with them
+    pub is_sensor: bool,
+    pub one_way: Option<OneWayPlatform>,
 }
 
 impl Default for Body {
@@ -156,17 +552,81 @@ impl Default for Body {
         Self {
             position: Vec3::ZERO,
             orientation: Quat::IDENTITY,
+            previous_position: Vec3::ZERO,
+            previous_orientation: Quat::IDENTITY,
             linear_velocity: Vec3::ZERO,
             angular_velocity: Vec3::ZERO,
             inv_mass: 1.0,
             elasticity: 0.5,
             friction: 0.5,
+            elasticity_combine: None,
+            friction_combine: None,
+            rolling_friction: 0.0,
+            spinning_friction: 0.0,
+            surface_velocity: Vec3::ZERO,
+            material: None,
+            locked_axes: LockedAxes::NONE,
+            response_weight: 1.0,
+            inertia_scale: 1.0,
+            gravity_scale: 1.0,
+            force_fields_enabled: true,
+            ccd_motion_threshold: 0.0,
+            ccd_enabled: true,
+            linear_damping: None,
+            angular_damping: None,
+            sleep_linear_threshold: None,
+            sleep_angular_threshold: None,
+            sleeping: false,
+            rest_time: 0.0,
+            color: Vec3::ONE,
             shape: Shape::default(),
+            colliders: Vec::new(),
+            collision_groups: InteractionGroups::default(),
+            force: Vec3::ZERO,
+            torque: Vec3::ZERO,
+            body_type: BodyType::default(),
+            kinematic_target: None,
+            is_sensor: false,
+            one_way: None,
         }
     }
 }
 
 impl Body {
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        Self {
+            elasticity: material.elasticity,
+            friction: material.friction,
+            elasticity_combine: material.elasticity_combine,
+            friction_combine: material.friction_combine,
+            ..self
+        }
+    }
+
+    // sets the mass from the shape's volume, which the inertia tensor and centre of mass follow.
+    // a shape with no volume gets infinite mass
+    pub fn with_density(self, density: f32) -> Self {
+        Self {
+            inv_mass: MassProperties::from_shape(&self.shape, density).inv_mass(),
+            ..self
+        }
+    }
+
+    // the mass, centre of mass and inertia tensor the body is simulated with, in its local space.
+    // the mass is infinite for a body with an inv_mass of 0.0
+    pub fn mass_properties(&self) -> MassProperties {
+        let mass = self.inv_mass.recip();
+        MassProperties {
+            mass,
+            centre_of_mass: self.shape.centre_of_mass(),
+            inertia_tensor: self.shape.inertia_tensor() * (mass * self.inertia_scale),
+        }
+    }
+
     pub fn centre_of_mass_world(&self) -> Vec3 {
         let com = self.shape.centre_of_mass();
         self.position + self.orientation * com
@@ -189,11 +649,34 @@ impl Body {
     pub fn inv_intertia_tensor_world(&self) -> Mat3 {
         let inv_inertia_tensor = self.inv_intertia_tensor_local();
         let orientation = Mat3::from_quat(self.orientation);
-        orientation * inv_inertia_tensor * orientation.transpose()
+        let inv_inertia_world = orientation * inv_inertia_tensor * orientation.transpose();
+        if !self.locked_axes.locks_rotation() {
+            return inv_inertia_world;
+        }
+        // no impulse can turn the body about a locked axis
+        let free = Mat3::from_diagonal(self.locked_axes.rotation_mask());
+        free * inv_inertia_world * free
+    }
+
+    // the inverse mass along each world axis, 0.0 along the locked ones
+    pub fn inv_mass_world(&self) -> Vec3 {
+        self.locked_axes.translation_mask() * self.inv_mass
+    }
+
+    // the inverse mass an impulse along the unit direction sees
+    pub fn inv_mass_along(&self, dir: Vec3) -> f32 {
+        if !self.locked_axes.locks_translation() {
+            return self.inv_mass;
+        }
+        dir.dot(self.inv_mass_world() * dir)
     }
 
     pub fn inv_intertia_tensor_local(&self) -> Mat3 {
-        self.shape.inertia_tensor().inverse() * self.inv_mass
+        assert!(
+            self.inertia_scale > 0.0,
+            "inertia_scale has to be above 0.0, use locked_axes to stop a body turning"
+        );
+        self.shape.inertia_tensor().inverse() * (self.inv_mass / self.inertia_scale)
     }
 
     pub fn apply_impulse(&mut self, impulse_point: Vec3, impulse: Vec3) {
@@ -223,14 +706,38 @@ impl Body {
         // L = I w = r x p
         // dL = I dw = r x J
         // => dw = I^-1 * (r x J)
+        // the scene caps the speed with its max_angular_speed once the impulses are applied
         self.angular_velocity += self.inv_intertia_tensor_world() * impulse;
+    }
 
-        // clamp angular_velocity - 30 rad/s is fast enough for us
-        const MAX_ANGULAR_SPEED: f32 = 30.0;
-        const MAX_ANGULAR_SPEED_SQ: f32 = MAX_ANGULAR_SPEED * MAX_ANGULAR_SPEED;
-        if self.angular_velocity.length_squared() > MAX_ANGULAR_SPEED_SQ {
-            self.angular_velocity = self.angular_velocity.normalize() * MAX_ANGULAR_SPEED;
+    // a pure change in angular momentum, e.g. spinning a top in place. unlike the solver's
+    // impulses this wakes the body up
+    pub fn apply_angular_impulse(&mut self, angular_impulse: Vec3) {
+        if self.has_infinite_mass() {
+            return;
         }
+
+        self.wake();
+        self.apply_impulse_angular(angular_impulse);
+    }
+
+    pub fn add_force(&mut self, force: Vec3) {
+        self.force += force;
+    }
+
+    pub fn add_torque(&mut self, torque: Vec3) {
+        self.torque += torque;
+    }
+
+    // a force at a world space point, off the centre of mass it turns the body as well
+    pub fn add_force_at_point(&mut self, force: Vec3, point: Vec3) {
+        self.force += force;
+        self.torque += (point - self.centre_of_mass_world()).cross(force);
+    }
+
+    pub fn clear_forces(&mut self) {
+        self.force = Vec3::ZERO;
+        self.torque = Vec3::ZERO;
     }
 
     pub fn apply_impulse_linear(&mut self, impulse: Vec3) {
@@ -241,10 +748,146 @@ impl Body {
         // p = mv
         // dp = m dv = J
         // => dv = J / m
-        self.linear_velocity += impulse * self.inv_mass;
+        self.linear_velocity += impulse * self.inv_mass_world();
+    }
+
+    // moves and turns the body about its centre of mass as if the impulses were applied for a
+    // second, without changing its velocity. used to push bodies out of each other
+    pub fn apply_position_impulse(&mut self, linear: Vec3, angular: Vec3) {
+        if self.has_infinite_mass() {
+            return;
+        }
+
+        let position_com = self.centre_of_mass_world();
+        let com_to_position = self.position - position_com;
+
+        let d_angle = self.inv_intertia_tensor_world() * angular;
+        let angle = d_angle.length();
+        let rcp_angle = angle.recip();
+        let dq = if rcp_angle.is_finite() {
+            Quat::from_axis_angle(d_angle * rcp_angle, angle)
+        } else {
+            Quat::IDENTITY
+        };
+        self.orientation = (dq * self.orientation).normalize();
+        self.position = position_com + linear * self.inv_mass_world() + dq * com_to_position;
+    }
+
+    // caps the body's speeds. a velocity that's gone infinite or NaN is zeroed instead, so it
+    // can't spread to everything the body touches
+    pub fn clamp_velocity(&mut self, max_linear_speed: f32, max_angular_speed: f32) {
+        if !self.linear_velocity.is_finite() {
+            self.linear_velocity = Vec3::ZERO;
+        }
+        if !self.angular_velocity.is_finite() {
+            self.angular_velocity = Vec3::ZERO;
+        }
+        if self.linear_velocity.length_squared() > max_linear_speed * max_linear_speed {
+            self.linear_velocity = self.linear_velocity.normalize() * max_linear_speed;
+        }
+        if self.angular_velocity.length_squared() > max_angular_speed * max_angular_speed {
+            self.angular_velocity = self.angular_velocity.normalize() * max_angular_speed;
+        }
+    }
+
+    // slows the body down, each damping coefficient takes about that fraction of the speed away
+    // per second. dividing rather than subtracting can't overshoot and reverse the body however
+    // large the coefficient or step
+    pub fn apply_damping(&mut self, delta_seconds: f32, linear_damping: f32, angular_damping: f32) {
+        let linear_damping = self.linear_damping.unwrap_or(linear_damping);
+        let angular_damping = self.angular_damping.unwrap_or(angular_damping);
+        self.linear_velocity /= 1.0 + linear_damping * delta_seconds;
+        self.angular_velocity /= 1.0 + angular_damping * delta_seconds;
+    }
+
+    // tracks how long the body has been resting for, see is_ready_to_sleep
+    pub fn update_sleep(
+        &mut self,
+        delta_seconds: f32,
+        linear_threshold: f32,
+        angular_threshold: f32,
+    ) {
+        if self.has_infinite_mass() {
+            return;
+        }
+
+        if self.sleeping {
+            // keep the solver from building up velocity on a body that isn't moving
+            self.linear_velocity = Vec3::ZERO;
+            self.angular_velocity = Vec3::ZERO;
+            return;
+        }
+
+        let linear_threshold = self.sleep_linear_threshold.unwrap_or(linear_threshold);
+        let angular_threshold = self.sleep_angular_threshold.unwrap_or(angular_threshold);
+        if self.linear_velocity.length() < linear_threshold
+            && self.angular_velocity.length() < angular_threshold
+        {
+            self.rest_time += delta_seconds;
+        } else {
+            self.rest_time = 0.0;
+        }
+    }
+
+    // true once the body has been under its sleep thresholds long enough, the scene puts it to
+    // sleep when the rest of its island is ready too
+    pub fn is_ready_to_sleep(&self) -> bool {
+        self.rest_time >= TIME_TO_SLEEP
+    }
+
+    pub fn sleep(&mut self) {
+        self.sleeping = true;
+        self.linear_velocity = Vec3::ZERO;
+        self.angular_velocity = Vec3::ZERO;
+    }
+
+    // sleeping and static bodies don't move, the solver can skip pairs of them. kinematic bodies
+    // have infinite mass but can still move
+    pub fn is_resting(&self) -> bool {
+        self.sleeping || (self.has_infinite_mass() && !self.is_kinematic())
+    }
+
+    pub fn is_kinematic(&self) -> bool {
+        self.body_type == BodyType::Kinematic
+    }
+
+    // sets the velocities that carry the body to its kinematic target over dt, the target is kept
+    // so the body stops once it's there
+    pub fn drive_to_kinematic_target(&mut self, dt: f32) {
+        let (position, orientation) = match self.kinematic_target {
+            Some(target) => target,
+            None => return,
+        };
+
+        // the centre of mass moves in a straight line and the body turns about it
+        let target_com = position + orientation * self.shape.centre_of_mass();
+        self.linear_velocity = (target_com - self.centre_of_mass_world()) / dt;
+
+        // the shorter way round
+        let mut turn = orientation * self.orientation.conjugate();
+        if turn.w < 0.0 {
+            turn = -turn;
+        }
+        let (axis, angle) = turn.to_axis_angle();
+        self.angular_velocity = axis * (angle / dt);
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.rest_time = 0.0;
     }
 
     pub fn update(&mut self, delta_seconds: f32) {
+        if self.sleeping {
+            return;
+        }
+
+        // velocities set directly can still point along locked axes
+        if self.locked_axes != LockedAxes::NONE {
+            self.linear_velocity *= self.locked_axes.translation_mask();
+            self.angular_velocity *= self.locked_axes.rotation_mask();
+        }
+
         self.position += self.linear_velocity * delta_seconds;
 
         // we have an angular velocity around the centre of mass, this needs to be converted to
@@ -258,13 +901,18 @@ impl Body {
         // T_external = 0 because it was applied in the collision response function
         // T = Ia = w x I * w
         // a = I^-1 (w x I * w)
-        let orientation = Mat3::from_quat(self.orientation);
-        let inertia_tensor = orientation * self.shape.inertia_tensor() * orientation.transpose();
-        let alpha = inertia_tensor.inverse()
-            * (self
-                .angular_velocity
-                .cross(inertia_tensor * self.angular_velocity));
-        self.angular_velocity += alpha * delta_seconds;
+        // kinematic bodies turn at exactly the rate they're given
+        if !self.is_kinematic() {
+            let orientation = Mat3::from_quat(self.orientation);
+            let inertia_tensor =
+                orientation * self.shape.inertia_tensor() * orientation.transpose();
+            let alpha = inertia_tensor.inverse()
+                * (self
+                    .angular_velocity
+                    .cross(inertia_tensor * self.angular_velocity));
+            self.angular_velocity += alpha * delta_seconds;
+            self.angular_velocity *= self.locked_axes.rotation_mask();
+        }
 
         // update orientation
         let d_angle = self.angular_velocity * delta_seconds;
@@ -281,7 +929,388 @@ impl Body {
         self.position = position_com + dq * com_to_position;
     }
 
+    // shifts the shape so the body's origin coincides with its centre of mass, without moving the
+    // body in the world
+    pub fn recenter_to_com(&mut self) {
+        let com_world = self.centre_of_mass_world();
+        self.shape = self.shape.recentered();
+        self.position = com_world - self.orientation * self.shape.centre_of_mass();
+    }
+
+    // where the body will be after dt falling under the scene's gravity, scaled by the body's
+    // gravity_scale. collisions and rotation are ignored
+    pub fn predict_position(&self, dt: f32, gravity: Vec3) -> Vec3 {
+        if self.has_infinite_mass() || self.sleeping {
+            return self.position;
+        }
+        self.position + self.linear_velocity * dt + gravity * (self.gravity_scale * 0.5 * dt * dt)
+    }
+
+    // the position and orientation alpha of the way from the start of the last step to now
+    pub fn interpolated_transform(&self, alpha: f32) -> (Vec3, Quat) {
+        (
+            self.previous_position.lerp(self.position, alpha),
+            self.previous_orientation.slerp(self.orientation, alpha),
+        )
+    }
+
+    // the radius of a sphere around the body's position that contains it
+    pub fn bounding_radius(&self) -> f32 {
+        match &self.shape {
+            Shape::Sphere(sphere) => return sphere.radius,
+            Shape::Capsule(capsule) => return capsule.half_height + capsule.radius,
+            _ => {}
+        }
+        let bounds = self.shape.local_bounds();
+        bounds.mins.length().max(bounds.maxs.length())
+    }
+
+    // whether the body moves far enough in dt to need a swept collision test, rotation is ignored.
+    // one that isn't moving has nothing to sweep
+    pub fn needs_ccd(&self, dt: f32) -> bool {
+        let distance = self.linear_velocity.length() * dt;
+        self.ccd_enabled
+            && distance > 0.0
+            && distance >= self.ccd_motion_threshold * self.bounding_radius()
+    }
+
+    // a capsule (segment start, segment end, radius) in world space that contains the body,
+    // running down the longest axis of its local bounds
+    pub fn bounding_capsule(&self) -> (Vec3, Vec3, f32) {
+        match &self.shape {
+            Shape::Sphere(sphere) => return (self.position, self.position, sphere.radius),
+            Shape::Capsule(capsule) => {
+                let (start, end) = capsule.segment(self.position, self.orientation);
+                return (start, end, capsule.radius);
+            }
+            _ => {}
+        }
+
+        let bounds = self.shape.local_bounds();
+        let centre = (bounds.mins + bounds.maxs) * 0.5;
+        let half_extents = bounds.width() * 0.5;
+
+        let (axis, half_length, radius) =
+            if half_extents.x >= half_extents.y && half_extents.x >= half_extents.z {
+                (
+                    Vec3::X,
+                    half_extents.x,
+                    half_extents.y.hypot(half_extents.z),
+                )
+            } else if half_extents.y >= half_extents.z {
+                (
+                    Vec3::Y,
+                    half_extents.y,
+                    half_extents.x.hypot(half_extents.z),
+                )
+            } else {
+                (
+                    Vec3::Z,
+                    half_extents.z,
+                    half_extents.x.hypot(half_extents.y),
+                )
+            };
+
+        let a = centre - axis * half_length;
+        let b = centre + axis * half_length;
+        (
+            self.position + self.orientation * a,
+            self.position + self.orientation * b,
+            radius,
+        )
+    }
+
+    // the bounds covering everywhere the body moves over dt, this is what the broadphase tests
+    pub fn swept_aabb_over(&self, dt: f32) -> (Vec3, Vec3) {
+        let mut bounds = self.shape.bounds(self.position, self.orientation);
+        for collider in &self.colliders {
+            let position = self.position + self.orientation * collider.offset;
+            let collider_bounds = collider.shape.bounds(position, self.orientation);
+            bounds.expand_by_point(collider_bounds.mins);
+            bounds.expand_by_point(collider_bounds.maxs);
+        }
+
+        // expand the bounds by the linear velocity
+        bounds.expand_by_point(bounds.mins + self.linear_velocity * dt);
+        bounds.expand_by_point(bounds.maxs + self.linear_velocity * dt);
+
+        const BOUNDS_EPS: f32 = 0.01;
+        bounds.expand_by_point(bounds.mins - Vec3::splat(BOUNDS_EPS));
+        bounds.expand_by_point(bounds.maxs + Vec3::splat(BOUNDS_EPS));
+
+        (bounds.mins, bounds.maxs)
+    }
+
+    // a copy of the body for each shape it collides with, placed where the shape is. that's the
+    // colliders when there are any, otherwise the body's own shape, with compounds split into
+    // their children. also returns the rotation and offset from each copy's local space to this
+    // body's local space
+    pub(crate) fn collision_parts(&self) -> Vec<(Body, Quat, Vec3)> {
+        let mut placed = Vec::new();
+        let mut place = |position: Vec3, shape: &Shape| match shape {
+            Shape::Compound(compound) => {
+                for ((child_position, child_orientation), child) in compound.children() {
+                    placed.push((
+                        position + *child_position,
+                        *child_orientation,
+                        child.clone(),
+                    ));
+                }
+            }
+            _ => placed.push((position, Quat::IDENTITY, shape.clone())),
+        };
+        if self.colliders.is_empty() {
+            place(Vec3::ZERO, &self.shape);
+        } else {
+            for collider in &self.colliders {
+                place(collider.offset, &collider.shape);
+            }
+        }
+
+        placed
+            .into_iter()
+            .map(|(position, orientation, shape)| {
+                let local_offset =
+                    position + orientation * shape.centre_of_mass() - self.shape.centre_of_mass();
+                let body = Body {
+                    position: self.position + self.orientation * position,
+                    orientation: self.orientation * orientation,
+                    linear_velocity: self.linear_velocity,
+                    angular_velocity: self.angular_velocity,
+                    inv_mass: self.inv_mass,
+                    elasticity: self.elasticity,
+                    friction: self.friction,
+                    ccd_motion_threshold: self.ccd_motion_threshold,
+                    ccd_enabled: self.ccd_enabled,
+                    shape,
+                    ..Body::default()
+                };
+                (body, orientation, local_offset)
+            })
+            .collect()
+    }
+
     pub fn has_infinite_mass(&self) -> bool {
         self.inv_mass == 0.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_combine_rules() {
+        use crate::{
+            body::{Body, CombineRule, CombineRules, Material},
+            contact::ContactMaterial,
+        };
+
+        let rubber = Body::default().with_material(Material::RUBBER);
+        let ice = Body::default().with_material(Material::ICE);
+        let friction = |rules: CombineRules, a: &Body, b: &Body| {
+            ContactMaterial::from_bodies(a, b, rules).friction
+        };
+
+        let expected = [
+            (CombineRule::Average, 0.46),
+            (CombineRule::Min, 0.02),
+            (CombineRule::Multiply, 0.018),
+            (CombineRule::Max, 0.9),
+        ];
+        for &(rule, expected) in &expected {
+            let rules = CombineRules {
+                friction: rule,
+                ..CombineRules::default()
+            };
+            assert!((friction(rules, &rubber, &ice) - expected).abs() < 1e-6);
+        }
+
+        // a body's own rule beats the scene's, and the later of two bodies' rules wins
+        let min_ice = Body {
+            friction_combine: Some(CombineRule::Min),
+            ..ice.clone()
+        };
+        let average_rubber = Body {
+            friction_combine: Some(CombineRule::Average),
+            ..rubber.clone()
+        };
+        let max_rules = CombineRules {
+            friction: CombineRule::Max,
+            ..CombineRules::default()
+        };
+        assert_eq!(0.02, friction(max_rules, &min_ice, &rubber));
+        assert_eq!(0.02, friction(max_rules, &average_rubber, &min_ice));
+
+        // elasticity is still multiplied
+        let material = ContactMaterial::from_bodies(&rubber, &ice, max_rules);
+        assert!((material.elasticity - 0.09).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recenter_to_com() {
+        use crate::{
+            body::Body,
+            shapes::{Shape, ShapeBox},
+        };
+        use glam::Vec3;
+        use std::sync::Arc;
+
+        let offset_box = ShapeBox::new(&[Vec3::new(1.0, -0.5, -0.5), Vec3::new(3.0, 0.5, 0.5)]);
+        let mut body = Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            angular_velocity: Vec3::new(0.0, 0.0, 2.0),
+            shape: Shape::make_box(Arc::new(offset_box)),
+            ..Body::default()
+        };
+
+        let com = body.centre_of_mass_world();
+        assert!(com.abs_diff_eq(Vec3::new(2.0, 1.0, 0.0), 1e-6));
+
+        body.recenter_to_com();
+        assert!(body.shape.centre_of_mass().abs_diff_eq(Vec3::ZERO, 1e-6));
+        assert!(body.position.abs_diff_eq(com, 1e-6));
+        assert!(body.centre_of_mass_world().abs_diff_eq(com, 1e-6));
+
+        // spinning the body now rotates it about its geometric centre of mass
+        body.update(0.1);
+        assert!(body.centre_of_mass_world().abs_diff_eq(com, 1e-5));
+        assert!(body.position.abs_diff_eq(com, 1e-5));
+    }
+
+    #[test]
+    fn test_apply_angular_impulse() {
+        use crate::body::Body;
+        use glam::{Quat, Vec3};
+
+        let mut body = Body {
+            orientation: Quat::from_rotation_x(0.5),
+            linear_velocity: Vec3::new(1.0, 2.0, 3.0),
+            sleeping: true,
+            ..Body::default()
+        };
+
+        let angular_impulse = Vec3::new(0.0, 0.5, 0.25);
+        let expected = body.inv_intertia_tensor_world() * angular_impulse;
+        body.apply_angular_impulse(angular_impulse);
+
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0), body.linear_velocity);
+        assert!(body.angular_velocity.abs_diff_eq(expected, 1e-6));
+        assert!(!body.sleeping);
+    }
+
+    #[test]
+    fn test_inertia_scale() {
+        use crate::body::Body;
+        use glam::Vec3;
+
+        let impulse_point = Vec3::new(0.0, 0.5, 0.0);
+        let impulse = Vec3::new(2.0, 0.0, 0.0);
+
+        let mut body = Body::default();
+        body.apply_impulse(impulse_point, impulse);
+
+        let mut heavy = Body {
+            inertia_scale: 4.0,
+            ..Body::default()
+        };
+        heavy.apply_impulse(impulse_point, impulse);
+
+        assert_eq!(body.linear_velocity, heavy.linear_velocity);
+        assert!(heavy
+            .angular_velocity
+            .abs_diff_eq(body.angular_velocity * 0.25, 1e-6));
+        assert!(heavy.angular_velocity.length() < body.angular_velocity.length());
+
+        // rather than dividing by zero or spinning backwards
+        for inertia_scale in [0.0, -1.0] {
+            let mut body = Body {
+                inertia_scale,
+                ..Body::default()
+            };
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                body.apply_impulse(impulse_point, impulse)
+            }));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_bounding_capsule() {
+        use crate::{
+            body::Body,
+            shapes::{Shape, ShapeBox},
+        };
+        use glam::{Quat, Vec3};
+        use std::sync::Arc;
+
+        // 4 long in z, 1 x 0.5 in cross-section
+        let long_box = ShapeBox::new(&[Vec3::new(-0.5, -0.25, -2.0), Vec3::new(0.5, 0.25, 2.0)]);
+        let body = Body {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            orientation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            shape: Shape::make_box(Arc::new(long_box)),
+            ..Body::default()
+        };
+
+        let (a, b, radius) = body.bounding_capsule();
+
+        // the segment spans the long axis, rotated onto x
+        assert!((a - b).length() >= 4.0 - 1e-5);
+        assert!(((a + b) * 0.5).abs_diff_eq(body.position, 1e-5));
+        assert!((a - b).normalize().cross(Vec3::X).length() < 1e-5);
+
+        // the radius reaches the corners of the cross-section
+        assert!(radius >= Vec3::new(0.5, 0.25, 0.0).length() - 1e-6);
+        assert!(radius < 1.0);
+    }
+
+    #[test]
+    fn test_predict_position() {
+        use crate::body::Body;
+        use glam::Vec3;
+
+        let p = Vec3::new(1.0, 5.0, -2.0);
+        let v = Vec3::new(3.0, 4.0, 0.5);
+        let g = Vec3::new(0.0, -10.0, 0.0);
+        let dt = 0.25;
+
+        let body = Body {
+            position: p,
+            linear_velocity: v,
+            ..Body::default()
+        };
+        let expected = p + v * dt + 0.5 * g * dt * dt;
+        assert!(body.predict_position(dt, g).abs_diff_eq(expected, 1e-6));
+
+        // half the gravity falls half as far
+        let floaty = Body {
+            gravity_scale: 0.5,
+            ..body.clone()
+        };
+        let expected = p + v * dt + 0.25 * g * dt * dt;
+        assert!(floaty.predict_position(dt, g).abs_diff_eq(expected, 1e-6));
+
+        // static bodies don't go anywhere
+        let fixed = Body {
+            inv_mass: 0.0,
+            ..body
+        };
+        assert_eq!(p, fixed.predict_position(dt, g));
+    }
+
+    #[test]
+    fn test_swept_aabb_over() {
+        use crate::body::Body;
+        use glam::Vec3;
+
+        let mut body = Body {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            ..Body::default()
+        };
+        let (still_mins, still_maxs) = body.swept_aabb_over(0.5);
+
+        body.linear_velocity = Vec3::new(4.0, 0.0, 0.0);
+        let (mins, maxs) = body.swept_aabb_over(0.5);
+        assert_eq!(still_mins, mins);
+        assert!(maxs.abs_diff_eq(still_maxs + Vec3::new(4.0 * 0.5, 0.0, 0.0), 1e-6));
+    }
+}
@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Quat, Vec3};
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign};
 
@@ -24,15 +24,14 @@ impl Bounds {
     //     *self = Self::new();
     // }
 
-    // fn does_intersect(&self, rhs: &Self) -> bool {
-    //     if self.maxs.cmplt(rhs.mins).any() {
-    //         false
-    //     } else if rhs.maxs.cmplt(self.mins).any() {
-    //         false
-    //     } else {
-    //         true
-    //     }
-    // }
+    pub fn does_intersect(&self, rhs: &Self) -> bool {
+        !(self.maxs.cmplt(rhs.mins).any() || rhs.maxs.cmplt(self.mins).any())
+    }
+
+    // true if rhs is entirely inside these bounds
+    pub fn contains(&self, rhs: &Self) -> bool {
+        self.mins.cmple(rhs.mins).all() && rhs.maxs.cmple(self.maxs).all()
+    }
 
     // fn expand_by_points(&mut self, points: &[Vec3]) {
     //     for point in points {
@@ -44,15 +43,42 @@ impl Bounds {
         self.add_assign(pt);
     }
 
-    // fn expand_by_bounds(&mut self, rhs: &Self) {
-    //     self.expand_by_point(rhs.mins);
-    //     self.expand_by_point(rhs.maxs);
-    // }
+    pub fn expand_by_bounds(&mut self, rhs: &Self) {
+        self.expand_by_point(rhs.mins);
+        self.expand_by_point(rhs.maxs);
+    }
+
+    // the bounds around both of these
+    pub fn union(&self, rhs: &Self) -> Self {
+        let mut bounds = *self;
+        bounds.expand_by_bounds(rhs);
+        bounds
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let width = self.width();
+        2.0 * (width.x * width.y + width.y * width.z + width.z * width.x)
+    }
 
     pub fn width(&self) -> Vec3 {
         self.maxs - self.mins
     }
 
+    // the bounds around these bounds once they're rotated and moved
+    pub fn transformed(&self, pos: Vec3, orient: Quat) -> Self {
+        let centre = (self.mins + self.maxs) * 0.5;
+        let half_extents = self.width() * 0.5;
+        let x = (orient * Vec3::new(half_extents.x, 0.0, 0.0)).abs();
+        let y = (orient * Vec3::new(0.0, half_extents.y, 0.0)).abs();
+        let z = (orient * Vec3::new(0.0, 0.0, half_extents.z)).abs();
+        let centre = (orient * centre) + pos;
+        let extent = x + y + z;
+        Self {
+            mins: centre - extent,
+            maxs: centre + extent,
+        }
+    }
+
     // fn width_x(&self) -> f32 {
     //     self.maxs.x - self.mins.x
     // }
@@ -0,0 +1,268 @@
+use super::{groups_interact, Broadphase, CollisionPair};
+use crate::{body::BodyArena, bounds::Bounds};
+use glam::Vec3;
+
+// how far a leaf's bounds are grown past its body's, so the body can move a little before its
+// leaf has to be reinserted
+const FAT_MARGIN: f32 = 0.1;
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+struct Node {
+    bounds: Bounds,
+    parent: Option<usize>,
+    // None for leaves
+    children: Option<[usize; 2]>,
+    // the position in BodyArena::iter() of the body a leaf holds. removing a body swaps the last
+    // one into its position, so the leaf there can hold a different body from one step to the next
+    body: usize,
+}
+
+// a dynamic bounding volume hierarchy over the bodies' swept bounds. a body's leaf is only
+// reinserted once the body leaves its fattened bounds, so most steps just query the tree
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct BroadphaseBvh {
+    nodes: Vec<Node>,
+    free_nodes: Vec<usize>,
+    root: Option<usize>,
+    // the leaf for each position in BodyArena::iter()
+    leaves: Vec<usize>,
+}
+
+impl BroadphaseBvh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // the indices of the bodies whose leaves overlap the bounds. the leaves are fattened so
+    // this can include bodies that are a little way outside the bounds
+    pub fn query(&self, bounds: &Bounds, mut hit: impl FnMut(usize)) {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.does_intersect(bounds) {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend_from_slice(&children),
+                None => hit(node.body),
+            }
+        }
+    }
+
+    // the indices of the bodies that overlap the bounds where they are now, in iter() order, for
+    // queries between steps. bodies can have been moved, added or removed since the tree was last
+    // used, so every body's bounds are found again and the leaves refit first, which is O(n)
+    pub fn bodies_overlapping(&mut self, bodies: &BodyArena, bounds: &Bounds) -> Vec<usize> {
+        let body_bounds: Vec<Bounds> = bodies
+            .iter()
+            .map(|body| {
+                let (mins, maxs) = body.swept_aabb_over(0.0);
+                Bounds { mins, maxs }
+            })
+            .collect();
+        self.update_leaves(&body_bounds);
+
+        let mut indices = Vec::new();
+        self.query(bounds, |i| {
+            if body_bounds[i].does_intersect(bounds) {
+                indices.push(i);
+            }
+        });
+        indices.sort_unstable();
+        indices
+    }
+
+    fn allocate_node(&mut self, node: Node) -> usize {
+        if let Some(index) = self.free_nodes.pop() {
+            self.nodes[index] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    // the node the leaf is cheapest to pair up with, by the surface area it adds to the tree
+    fn find_sibling(&self, root: usize, bounds: &Bounds) -> usize {
+        let mut index = root;
+        while let Some([child_a, child_b]) = self.nodes[index].children {
+            let area = self.nodes[index].bounds.surface_area();
+            let combined_area = self.nodes[index].bounds.union(bounds).surface_area();
+
+            // pairing with this node makes a new parent here
+            let cost = 2.0 * combined_area;
+
+            // going further down grows this node to fit the leaf
+            let inheritance_cost = 2.0 * (combined_area - area);
+            let child_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let combined_area = child.bounds.union(bounds).surface_area();
+                if child.children.is_some() {
+                    combined_area - child.bounds.surface_area() + inheritance_cost
+                } else {
+                    combined_area + inheritance_cost
+                }
+            };
+            let cost_a = child_cost(child_a);
+            let cost_b = child_cost(child_b);
+
+            if cost < cost_a && cost < cost_b {
+                break;
+            }
+            index = if cost_a < cost_b { child_a } else { child_b };
+        }
+        index
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(leaf);
+                self.nodes[leaf].parent = None;
+                return;
+            }
+        };
+
+        let bounds = self.nodes[leaf].bounds;
+        let sibling = self.find_sibling(root, &bounds);
+
+        // a new parent takes the sibling's place with the sibling and the leaf under it
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate_node(Node {
+            bounds: bounds.union(&self.nodes[sibling].bounds),
+            parent: old_parent,
+            children: Some([sibling, leaf]),
+            body: usize::MAX,
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+        match old_parent {
+            Some(old_parent) => {
+                self.replace_child(old_parent, sibling, new_parent);
+                self.refit(old_parent);
+            }
+            None => self.root = Some(new_parent),
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        let parent = match self.nodes[leaf].parent {
+            Some(parent) => parent,
+            None => {
+                self.root = None;
+                return;
+            }
+        };
+
+        // the sibling takes the parent's place
+        let [child_a, child_b] = self.nodes[parent].children.unwrap();
+        let sibling = if child_a == leaf { child_b } else { child_a };
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(grandparent) => {
+                self.replace_child(grandparent, parent, sibling);
+                self.refit(grandparent);
+            }
+            None => self.root = Some(sibling),
+        }
+        self.free_nodes.push(parent);
+    }
+
+    fn replace_child(&mut self, parent: usize, old_child: usize, new_child: usize) {
+        if let Some(children) = &mut self.nodes[parent].children {
+            for child in children.iter_mut() {
+                if *child == old_child {
+                    *child = new_child;
+                }
+            }
+        }
+    }
+
+    // recomputes the bounds of the node and its ancestors from their children
+    fn refit(&mut self, mut index: usize) {
+        loop {
+            if let Some([child_a, child_b]) = self.nodes[index].children {
+                self.nodes[index].bounds = self.nodes[child_a]
+                    .bounds
+                    .union(&self.nodes[child_b].bounds);
+            }
+            match self.nodes[index].parent {
+                Some(parent) => index = parent,
+                None => break,
+            }
+        }
+    }
+
+    // brings the leaves up to date with the bodies. bodies are added and removed from the end of
+    // the arena, a body swapped into a removed body's position is reinserted like a moved body
+    fn update_leaves(&mut self, bounds: &[Bounds]) {
+        while self.leaves.len() > bounds.len() {
+            let leaf = self.leaves.pop().unwrap();
+            self.remove_leaf(leaf);
+            self.free_nodes.push(leaf);
+        }
+
+        for (i, body_bounds) in bounds.iter().enumerate() {
+            let fat_bounds = Bounds {
+                mins: body_bounds.mins - Vec3::splat(FAT_MARGIN),
+                maxs: body_bounds.maxs + Vec3::splat(FAT_MARGIN),
+            };
+            if i == self.leaves.len() {
+                let leaf = self.allocate_node(Node {
+                    bounds: fat_bounds,
+                    parent: None,
+                    children: None,
+                    body: i,
+                });
+                self.insert_leaf(leaf);
+                self.leaves.push(leaf);
+            } else if !self.nodes[self.leaves[i]].bounds.contains(body_bounds) {
+                let leaf = self.leaves[i];
+                self.remove_leaf(leaf);
+                self.nodes[leaf].bounds = fat_bounds;
+                self.insert_leaf(leaf);
+            }
+        }
+    }
+}
+
+impl Broadphase for BroadphaseBvh {
+    fn find_pairs(&mut self, bodies: &BodyArena, dt_sec: f32) -> Vec<CollisionPair> {
+        let bounds: Vec<Bounds> = bodies
+            .iter()
+            .map(|body| {
+                let (mins, maxs) = body.swept_aabb_over(dt_sec);
+                Bounds { mins, maxs }
+            })
+            .collect();
+        self.update_leaves(&bounds);
+
+        // each body's overlaps with the bodies after it in handle order, sorted so the pairs are
+        // the same however the tree is built
+        let handles = bodies.handles();
+        let mut collision_pairs = Vec::new();
+        for (i, body_bounds) in bounds.iter().enumerate() {
+            self.query(body_bounds, |j| {
+                if handles[j].0 > handles[i].0
+                    && bounds[j].does_intersect(body_bounds)
+                    && groups_interact(bodies, handles[i], handles[j])
+                {
+                    collision_pairs.push(CollisionPair {
+                        a: handles[i],
+                        b: handles[j],
+                    });
+                }
+            });
+        }
+        collision_pairs.sort_unstable_by_key(|pair| (pair.a.0, pair.b.0));
+        collision_pairs
+    }
+}
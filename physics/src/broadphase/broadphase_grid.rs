@@ -0,0 +1,129 @@
+use super::{groups_interact, Broadphase, CollisionPair};
+use crate::{
+    body::{BodyArena, BodyHandle},
+    bounds::Bounds,
+};
+use glam::Vec3;
+use std::collections::HashMap;
+
+// bodies covering more cells than this, like the ground, are checked against every body instead
+// of being put in all of their cells
+const MAX_CELLS_PER_BODY: i64 = 64;
+
+type Cell = (i32, i32, i32);
+
+// a uniform grid of cells, hashed so only the cells with bodies in them are stored. works best
+// when the bodies are all about the size of a cell
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct BroadphaseGrid {
+    cell_size: f32,
+    // the bodies in each cell, kept between steps so the cells' allocations are reused. they're
+    // filled again each step so there's nothing to save
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    cells: HashMap<Cell, Vec<BodyHandle>>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    oversized: Vec<BodyHandle>,
+}
+
+impl BroadphaseGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "grid cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, point: Vec3) -> Cell {
+        let cell = (point / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32, cell.z as i32)
+    }
+}
+
+impl Broadphase for BroadphaseGrid {
+    fn find_pairs(&mut self, bodies: &BodyArena, dt_sec: f32) -> Vec<CollisionPair> {
+        let bounds: Vec<Bounds> = bodies
+            .iter()
+            .map(|body| {
+                let (mins, maxs) = body.swept_aabb_over(dt_sec);
+                Bounds { mins, maxs }
+            })
+            .collect();
+
+        // empty cells are dropped, the rest keep their allocations
+        self.cells.retain(|_, handles| !handles.is_empty());
+        for handles in self.cells.values_mut() {
+            handles.clear();
+        }
+        self.oversized.clear();
+
+        for (&handle, body_bounds) in bodies.handles().iter().zip(&bounds) {
+            let min_cell = self.cell(body_bounds.mins);
+            let max_cell = self.cell(body_bounds.maxs);
+            let cell_count = (max_cell.0 - min_cell.0 + 1) as i64
+                * (max_cell.1 - min_cell.1 + 1) as i64
+                * (max_cell.2 - min_cell.2 + 1) as i64;
+            if cell_count > MAX_CELLS_PER_BODY {
+                self.oversized.push(handle);
+                continue;
+            }
+            for x in min_cell.0..=max_cell.0 {
+                for y in min_cell.1..=max_cell.1 {
+                    for z in min_cell.2..=max_cell.2 {
+                        self.cells.entry((x, y, z)).or_default().push(handle);
+                    }
+                }
+            }
+        }
+
+        let mut collision_pairs = Vec::new();
+        let mut push_pair = |a: BodyHandle, b: BodyHandle| {
+            if !groups_interact(bodies, a, b) {
+                return;
+            }
+            let (a, b) = if a.0 < b.0 { (a, b) } else { (b, a) };
+            collision_pairs.push(CollisionPair { a, b });
+        };
+
+        for (&cell, handles) in &self.cells {
+            for (index, &a) in handles.iter().enumerate() {
+                for &b in &handles[index + 1..] {
+                    let bounds_a = &bounds[bodies.index(a)];
+                    let bounds_b = &bounds[bodies.index(b)];
+                    if !bounds_a.does_intersect(bounds_b) {
+                        continue;
+                    }
+
+                    // bodies sharing several cells are only paired in the cell holding the
+                    // corner of their overlap
+                    let overlap_mins = bounds_a.mins.max(bounds_b.mins);
+                    if self.cell(overlap_mins) == cell {
+                        push_pair(a, b);
+                    }
+                }
+            }
+        }
+
+        for (index, &a) in self.oversized.iter().enumerate() {
+            for (&b, bounds_b) in bodies.handles().iter().zip(&bounds) {
+                // the other oversized bodies before this one have already paired with it
+                if b == a || self.oversized[..index].contains(&b) {
+                    continue;
+                }
+                if bounds[bodies.index(a)].does_intersect(bounds_b) {
+                    push_pair(a, b);
+                }
+            }
+        }
+
+        // the cells are hashed so put the pairs back in a consistent order
+        collision_pairs.sort_unstable_by_key(|pair| (pair.a.0, pair.b.0));
+        collision_pairs
+    }
+}
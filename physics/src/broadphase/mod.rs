@@ -0,0 +1,395 @@
+mod broadphase_bvh;
+mod broadphase_grid;
+
+use crate::body::{BodyArena, BodyHandle};
+pub use broadphase_bvh::BroadphaseBvh;
+pub use broadphase_grid::BroadphaseGrid;
+use glam::Vec3;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::hash::{Hash, Hasher};
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionPair {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+}
+
+impl PartialEq for CollisionPair {
+    fn eq(&self, other: &Self) -> bool {
+        (self.a == other.a && self.b == other.b) || (self.a == other.b && self.b == other.a)
+    }
+}
+
+impl Eq for CollisionPair {}
+
+impl Hash for CollisionPair {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // pairs are equal in either order so they have to hash the same in either order
+        let (a, b) = (self.a.0, self.b.0);
+        a.min(b).hash(state);
+        a.max(b).hash(state);
+    }
+}
+
+// finds the pairs of bodies that might be touching, for the narrowphase to test properly
+pub trait Broadphase {
+    // the pairs of bodies whose bounds, swept over dt_sec, might overlap
+    fn find_pairs(&mut self, bodies: &BodyArena, dt_sec: f32) -> Vec<CollisionPair>;
+}
+
+// which broadphase the scene uses
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum BroadphaseKind {
+    #[default]
+    SweepAndPrune,
+    // a dynamic bounding volume hierarchy, for scenes with a lot of bodies
+    Bvh,
+    // a uniform spatial hash, for a lot of bodies that are all about cell_size across
+    Grid {
+        cell_size: f32,
+    },
+}
+
+// bodies whose collision groups keep them apart are never paired
+fn groups_interact(bodies: &BodyArena, a: BodyHandle, b: BodyHandle) -> bool {
+    bodies
+        .get_body(a)
+        .collision_groups
+        .interacts_with(&bodies.get_body(b).collision_groups)
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PsuedoBody {
+    handle: BodyHandle,
+    value: f32,
+    is_min: bool,
+}
+
+fn compare_sat(a: &PsuedoBody, b: &PsuedoBody) -> std::cmp::Ordering {
+    if a.value < b.value {
+        std::cmp::Ordering::Less
+    } else if a.value > b.value {
+        std::cmp::Ordering::Greater
+    } else if cfg!(feature = "determinism") {
+        // ends at the same value go in handle order, a body's min before its max
+        a.handle.0.cmp(&b.handle.0).then(b.is_min.cmp(&a.is_min))
+    } else {
+        std::cmp::Ordering::Equal
+    }
+}
+
+// the world axis the centres of the bounds vary along the most, which separates the most bodies
+fn select_axis(bounds: &[(Vec3, Vec3)]) -> Vec3 {
+    if bounds.is_empty() {
+        return Vec3::X;
+    }
+
+    let rcp_count = 1.0 / bounds.len() as f32;
+    let mean = bounds
+        .iter()
+        .fold(Vec3::ZERO, |sum, (mins, maxs)| sum + (*mins + *maxs) * 0.5)
+        * rcp_count;
+    let variance = bounds.iter().fold(Vec3::ZERO, |sum, (mins, maxs)| {
+        let d = (*mins + *maxs) * 0.5 - mean;
+        sum + d * d
+    });
+
+    if variance.x >= variance.y && variance.x >= variance.z {
+        Vec3::X
+    } else if variance.y >= variance.z {
+        Vec3::Y
+    } else {
+        Vec3::Z
+    }
+}
+
+fn sort_bodies_bounds(
+    bodies: &BodyArena,
+    dt_sec: f32,
+    axis: Option<Vec3>,
+) -> (Vec<PsuedoBody>, Vec3) {
+    // TODO: allocation on sort
+    let mut sorted_bodies = Vec::with_capacity(bodies.len() * 2);
+
+    #[cfg(not(feature = "parallel"))]
+    let bounds: Vec<(Vec3, Vec3)> = bodies
+        .iter()
+        .map(|body| body.swept_aabb_over(dt_sec))
+        .collect();
+    #[cfg(feature = "parallel")]
+    let bounds: Vec<(Vec3, Vec3)> = bodies
+        .iter()
+        .as_slice()
+        .par_iter()
+        .map(|body| body.swept_aabb_over(dt_sec))
+        .collect();
+    let axis = axis.unwrap_or_else(|| select_axis(&bounds));
+    for (&handle, &(mins, maxs)) in bodies.handles().iter().zip(&bounds) {
+        sorted_bodies.push(PsuedoBody {
+            handle,
+            value: axis.dot(mins),
+            is_min: true,
+        });
+        sorted_bodies.push(PsuedoBody {
+            handle,
+            value: axis.dot(maxs),
+            is_min: false,
+        });
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    sorted_bodies.sort_unstable_by(compare_sat);
+    #[cfg(feature = "parallel")]
+    sorted_bodies.par_sort_unstable_by(compare_sat);
+
+    (sorted_bodies, axis)
+}
+
+// the pairs of bodies that overlap the one whose min is at index i, up to its max
+fn build_pairs_from(
+    bodies: &BodyArena,
+    sorted_bodies: &[PsuedoBody],
+    i: usize,
+) -> Vec<CollisionPair> {
+    let a = &sorted_bodies[i];
+    if !a.is_min {
+        return Vec::new();
+    }
+
+    let mut collision_pairs = Vec::new();
+    for b in sorted_bodies.iter().skip(i + 1) {
+        // if we've hit the end of the a element then we're done creating pairs with a
+        if b.handle == a.handle {
+            break;
+        }
+
+        if !b.is_min || !groups_interact(bodies, a.handle, b.handle) {
+            continue;
+        }
+
+        collision_pairs.push(CollisionPair {
+            a: a.handle,
+            b: b.handle,
+        });
+    }
+    collision_pairs
+}
+
+#[cfg(not(feature = "parallel"))]
+fn build_pairs(bodies: &BodyArena, sorted_bodies: &[PsuedoBody]) -> Vec<CollisionPair> {
+    // Now that the bodies are sorted, build the collision pairs
+    (0..sorted_bodies.len())
+        .flat_map(|i| build_pairs_from(bodies, sorted_bodies, i))
+        .collect()
+}
+
+// each body's sweep is independent so they're split across threads, the pairs come out in the same
+// order as the serial sweep
+#[cfg(feature = "parallel")]
+fn build_pairs(bodies: &BodyArena, sorted_bodies: &[PsuedoBody]) -> Vec<CollisionPair> {
+    (0..sorted_bodies.len())
+        .into_par_iter()
+        .flat_map_iter(|i| build_pairs_from(bodies, sorted_bodies, i))
+        .collect()
+}
+
+fn sweep_and_prune_1d(
+    bodies: &BodyArena,
+    dt_sec: f32,
+    axis: Option<Vec3>,
+) -> (Vec<CollisionPair>, Vec3) {
+    let (sorted_bodies, axis) = sort_bodies_bounds(bodies, dt_sec, axis);
+    (build_pairs(bodies, &sorted_bodies), axis)
+}
+
+// returns the potential collision pairs and the axis they were swept along. the axis is chosen
+// automatically unless one is given
+pub fn broadphase(
+    bodies: &BodyArena,
+    dt_sec: f32,
+    axis: Option<Vec3>,
+) -> (Vec<CollisionPair>, Vec3) {
+    sweep_and_prune_1d(bodies, dt_sec, axis)
+}
+
+// sorts the bodies along one axis and pairs up the ones that overlap along it
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepAndPrune {
+    // the axis to sweep along, None picks the axis the bodies are spread along the most
+    pub axis: Option<Vec3>,
+    chosen_axis: Vec3,
+}
+
+impl SweepAndPrune {
+    // the axis swept along in the last step
+    pub fn chosen_axis(&self) -> Vec3 {
+        self.chosen_axis
+    }
+}
+
+impl Default for SweepAndPrune {
+    fn default() -> Self {
+        Self {
+            axis: None,
+            chosen_axis: Vec3::X,
+        }
+    }
+}
+
+impl Broadphase for SweepAndPrune {
+    fn find_pairs(&mut self, bodies: &BodyArena, dt_sec: f32) -> Vec<CollisionPair> {
+        let (collision_pairs, axis) = broadphase(bodies, dt_sec, self.axis);
+        self.chosen_axis = axis;
+        collision_pairs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{body::Body, bounds::Bounds, scene_shapes::make_sphere};
+    use std::collections::HashSet;
+
+    #[cfg(feature = "determinism")]
+    #[test]
+    fn test_sort_ties_by_handle() {
+        // bodies on top of each other have all their ends at the same values
+        let mut bodies = BodyArena::new();
+        for _ in 0..5 {
+            bodies.add(Body {
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+        }
+        let (sorted_bodies, _) = sort_bodies_bounds(&bodies, 0.0, Some(Vec3::X));
+        let order: Vec<(u32, bool)> = sorted_bodies
+            .iter()
+            .map(|body| (body.handle.0, body.is_min))
+            .collect();
+        let mins = (0..5).map(|i| (i, true));
+        let maxs = (0..5).map(|i| (i, false));
+        assert_eq!(mins.chain(maxs).collect::<Vec<_>>(), order);
+    }
+
+    // the pairs whose swept bounds overlap, checking every pair
+    fn brute_force_pairs(bodies: &BodyArena, dt_sec: f32) -> Vec<CollisionPair> {
+        let bounds: Vec<Bounds> = bodies
+            .iter()
+            .map(|body| {
+                let (mins, maxs) = body.swept_aabb_over(dt_sec);
+                Bounds { mins, maxs }
+            })
+            .collect();
+        let handles = bodies.handles();
+        let mut pairs = Vec::new();
+        for i in 0..bounds.len() {
+            for j in 0..bounds.len() {
+                if handles[i].0 < handles[j].0 && bounds[i].does_intersect(&bounds[j]) {
+                    pairs.push(CollisionPair {
+                        a: handles[i],
+                        b: handles[j],
+                    });
+                }
+            }
+        }
+        pairs.sort_unstable_by_key(|pair| (pair.a.0, pair.b.0));
+        pairs
+    }
+
+    // checks the broadphase finds exactly the overlapping pairs in a loose grid of spheres, as
+    // some move far away and then some are removed
+    fn check_broadphase_pairs(broadphase: &mut dyn Broadphase) {
+        let mut bodies = BodyArena::new();
+        for i in 0..64 {
+            let (x, y, z) = (i % 4, (i / 4) % 4, i / 16);
+            bodies.add(Body {
+                position: Vec3::new(x as f32 * 1.9, y as f32 * 2.1, z as f32 * 1.95),
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+        }
+        // something much bigger than the spheres, the grid can't put it in its cells
+        bodies.add(Body {
+            position: Vec3::new(3.0, 3.0, 3.0),
+            shape: make_sphere(20.0),
+            ..Body::default()
+        });
+
+        let dt_sec = 1.0 / 60.0;
+        let pairs = broadphase.find_pairs(&bodies, dt_sec);
+        assert!(!pairs.is_empty());
+        assert_eq!(brute_force_pairs(&bodies, dt_sec), pairs);
+
+        for (i, index) in [3, 17, 40].iter().enumerate() {
+            let handle = bodies.handles()[*index];
+            let body = bodies.get_body_mut(handle);
+            body.position = Vec3::new(i as f32 * 1.5, 50.0, 0.0);
+        }
+        assert_eq!(
+            brute_force_pairs(&bodies, dt_sec),
+            broadphase.find_pairs(&bodies, dt_sec)
+        );
+
+        // the last body is moved into the removed one's place, and a new body reuses a slot
+        bodies.remove(bodies.handles()[5]);
+        bodies.remove(bodies.handles()[20]);
+        bodies.add(Body {
+            position: Vec3::new(1.0, 1.0, 1.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        assert_eq!(
+            brute_force_pairs(&bodies, dt_sec),
+            broadphase.find_pairs(&bodies, dt_sec)
+        );
+    }
+
+    #[test]
+    fn test_bvh_pairs() {
+        check_broadphase_pairs(&mut BroadphaseBvh::new());
+    }
+
+    #[test]
+    fn test_grid_pairs() {
+        check_broadphase_pairs(&mut BroadphaseGrid::new(2.0));
+    }
+
+    #[test]
+    fn test_broadphase_axis() {
+        // a row of touching spheres along x, all at the same height
+        let mut bodies = BodyArena::new();
+        for i in 0..8 {
+            bodies.add(Body {
+                position: Vec3::new(i as f32 * 1.5, 0.0, 0.0),
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+        }
+
+        let (auto_pairs, axis) = broadphase(&bodies, 1.0 / 60.0, None);
+        assert_eq!(Vec3::X, axis);
+
+        // only the neighbours overlap
+        let handles = bodies.handles();
+        let neighbours: HashSet<CollisionPair> = (0..7)
+            .map(|i| CollisionPair {
+                a: handles[i],
+                b: handles[i + 1],
+            })
+            .collect();
+        let auto_pairs: HashSet<CollisionPair> = auto_pairs.into_iter().collect();
+        assert_eq!(neighbours, auto_pairs);
+
+        // sweeping along y can't separate anything, every pair comes back but the overlapping
+        // ones are still there
+        let (y_pairs, axis) = broadphase(&bodies, 1.0 / 60.0, Some(Vec3::Y));
+        assert_eq!(Vec3::Y, axis);
+        assert_eq!(8 * 7 / 2, y_pairs.len());
+        let y_pairs: HashSet<CollisionPair> = y_pairs.into_iter().collect();
+        assert!(neighbours.is_subset(&y_pairs));
+    }
+}
@@ -0,0 +1,350 @@
+// a kinematic character controller. the character is a shape swept through the scene with
+// shape_cast, it slides along what it hits, climbs steps, stays on the ground going down slopes and
+// only walks up slopes gentle enough. it pushes nothing and nothing pushes it, a kinematic body can
+// follow it so dynamic bodies are pushed out of its way
+use crate::{
+    body::{Body, BodyHandle},
+    query::ShapeHit,
+    scene::PhysicsScene,
+    shapes::Shape,
+};
+use glam::{Quat, Vec3};
+
+// moves shorter than this are finished
+const MIN_MOVE: f32 = 1e-5;
+
+pub struct CharacterController {
+    // the shape swept through the scene, its local y axis is turned to the scene's up
+    pub shape: Shape,
+    pub position: Vec3,
+    // the gap kept between the shape and anything it touches, so the next cast doesn't start
+    // touching it
+    pub skin_width: f32,
+    // how high a ledge can be walked up onto
+    pub step_offset: f32,
+    // the steepest slope, in radians from flat, that can be walked up or stood on
+    pub max_slope_angle: f32,
+    // how far the character is pulled down to stay on the ground walking down slopes and steps
+    pub snap_distance: f32,
+    // how many surfaces a single move can slide along
+    pub max_slides: u32,
+    // a kinematic body moved to the character's position, the casts ignore it
+    pub body: Option<BodyHandle>,
+    // standing on a walkable surface at the end of the last move
+    pub grounded: bool,
+    // the normal of the ground stood on, or the up direction while not grounded
+    pub ground_normal: Vec3,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            shape: Shape::make_capsule(0.5, 0.4),
+            position: Vec3::ZERO,
+            skin_width: 0.02,
+            step_offset: 0.3,
+            max_slope_angle: 45f32.to_radians(),
+            snap_distance: 0.2,
+            max_slides: 4,
+            body: None,
+            grounded: false,
+            ground_normal: Vec3::Y,
+        }
+    }
+}
+
+impl CharacterController {
+    // moves the character by the motion as far as it can go, returns how far it actually moved.
+    // gravity is left to the caller, add it to the motion while not grounded
+    pub fn move_by(&mut self, scene: &mut PhysicsScene, motion: Vec3) -> Vec3 {
+        let up = scene.world_up.normalize();
+        let start = self.position;
+        let was_grounded = self.grounded;
+
+        let vertical = up * motion.dot(up);
+        let horizontal = motion - vertical;
+
+        let mut position = self.position;
+        if horizontal.length_squared() > MIN_MOVE * MIN_MOVE {
+            let (flat, blocked) = self.slide(scene, up, position, horizontal);
+            position = flat;
+            if blocked && was_grounded && self.step_offset > 0.0 {
+                if let Some(stepped) = self.step_up(scene, up, self.position, horizontal) {
+                    // only worth it if the step got further
+                    let progress = |end: Vec3| (end - self.position).dot(horizontal);
+                    if progress(stepped) > progress(flat) + MIN_MOVE {
+                        position = stepped;
+                    }
+                }
+            }
+        }
+        if vertical.length_squared() > MIN_MOVE * MIN_MOVE {
+            position = self.slide(scene, up, position, vertical).0;
+        }
+
+        let mut ground = self.find_ground(scene, up, position, self.skin_width * 2.0);
+        // walking off a ledge or down a slope, keep to the ground unless moving up
+        if ground.is_none() && was_grounded && motion.dot(up) <= 0.0 {
+            if let Some(hit) = self.find_ground(scene, up, position, self.snap_distance) {
+                position = self.touching(position, -up * self.snap_distance, &hit);
+                ground = Some(hit);
+            }
+        }
+        self.grounded = ground.is_some();
+        self.ground_normal = ground.map_or(up, |hit| hit.normal);
+        self.position = position;
+
+        if let Some(handle) = self.body {
+            scene.set_kinematic_target(handle, position, self.orientation(up));
+        }
+        position - start
+    }
+
+    // the character's orientation, its shape's y axis along up
+    pub fn orientation(&self, up: Vec3) -> Quat {
+        Quat::from_rotation_arc(Vec3::Y, up)
+    }
+
+    fn is_walkable(&self, up: Vec3, normal: Vec3) -> bool {
+        normal.dot(up) >= self.max_slope_angle.cos()
+    }
+
+    // the first thing the character hits moving from one place to the other, sensors and its own
+    // body are passed through
+    fn cast(&self, scene: &PhysicsScene, up: Vec3, from: Vec3, to: Vec3) -> Option<ShapeHit> {
+        let own_body = self.body;
+        scene.shape_cast(
+            &self.shape,
+            self.orientation(up),
+            from,
+            to,
+            |handle: BodyHandle, body: &Body| Some(handle) != own_body && !body.is_sensor,
+        )
+    }
+
+    // collide and slide, the motion left over at each hit is moved along the surface instead.
+    // returns where it ended up and whether a wall or too steep slope got in the way
+    fn slide(&self, scene: &PhysicsScene, up: Vec3, from: Vec3, motion: Vec3) -> (Vec3, bool) {
+        let mut position = from;
+        let mut remaining = motion;
+        let mut blocked = false;
+        for _ in 0..self.max_slides {
+            let distance = remaining.length();
+            if distance < MIN_MOVE {
+                break;
+            }
+            let hit = match self.cast(scene, up, position, position + remaining) {
+                Some(hit) => hit,
+                None => {
+                    position += remaining;
+                    break;
+                }
+            };
+
+            position = self.touching(position, remaining, &hit);
+            remaining *= 1.0 - hit.time_of_impact;
+
+            // walls and steep slopes are slid along as if they were upright, so they can't be
+            // climbed
+            let mut normal = hit.normal;
+            if !self.is_walkable(up, normal) {
+                blocked = true;
+                if motion.dot(up) <= 0.0 {
+                    normal = (normal - up * normal.dot(up)).normalize_or_zero();
+                }
+            }
+            remaining -= normal * remaining.dot(normal).min(0.0);
+        }
+        (position, blocked)
+    }
+
+    // lifts the character by the step offset, moves it and puts it back down. None if there's no
+    // walkable ground to put it down on
+    fn step_up(&self, scene: &PhysicsScene, up: Vec3, from: Vec3, motion: Vec3) -> Option<Vec3> {
+        let lift = match self.cast(scene, up, from, from + up * self.step_offset) {
+            Some(hit) => (hit.time_of_impact * self.step_offset - self.skin_width).max(0.0),
+            None => self.step_offset,
+        };
+        let (moved, _) = self.slide(scene, up, from + up * lift, motion);
+        let drop = lift + self.skin_width;
+        let hit = self.cast(scene, up, moved, moved - up * drop)?;
+        if !self.is_walkable(up, hit.normal) {
+            return None;
+        }
+        Some(self.touching(moved, -up * drop, &hit))
+    }
+
+    // where a cast by the motion hit, backed off the surface by the skin width so the next cast
+    // doesn't start touching it
+    fn touching(&self, from: Vec3, motion: Vec3, hit: &ShapeHit) -> Vec3 {
+        from + motion * hit.time_of_impact + hit.normal * self.skin_width
+    }
+
+    // walkable ground within the distance below the position
+    fn find_ground(
+        &self,
+        scene: &PhysicsScene,
+        up: Vec3,
+        position: Vec3,
+        distance: f32,
+    ) -> Option<ShapeHit> {
+        self.cast(scene, up, position, position - up * distance)
+            .filter(|hit| self.is_walkable(up, hit.normal))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene_shapes::make_box_ground;
+    use std::sync::Arc;
+
+    // the ground's top is at y = 0, the default capsule's bottom is 0.9 below its position
+    const STANDING: f32 = 0.9;
+
+    fn make_scene() -> PhysicsScene {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        scene
+    }
+
+    fn add_block(scene: &mut PhysicsScene, mins: Vec3, maxs: Vec3) {
+        scene.add_body(Body {
+            position: (mins + maxs) * 0.5,
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents((maxs - mins) * 0.5),
+            ..Body::default()
+        });
+    }
+
+    fn standing_character(x: f32) -> CharacterController {
+        CharacterController {
+            position: Vec3::new(x, STANDING + 0.02, 0.0),
+            grounded: true,
+            ..CharacterController::default()
+        }
+    }
+
+    #[test]
+    fn test_walk_and_fall() {
+        let mut scene = make_scene();
+        let mut character = standing_character(0.0);
+        let moved = character.move_by(&mut scene, Vec3::new(1.0, 0.0, 0.5));
+        assert!(moved.abs_diff_eq(Vec3::new(1.0, 0.0, 0.5), 1e-3));
+        assert!(character.grounded);
+
+        // lifted off the ground it falls back down onto it
+        let mut character = CharacterController {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            ..CharacterController::default()
+        };
+        character.move_by(&mut scene, Vec3::ZERO);
+        assert!(!character.grounded);
+        character.move_by(&mut scene, Vec3::new(0.0, -5.0, 0.0));
+        assert!(character.grounded);
+        assert!((character.position.y - STANDING).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_walls_and_steps() {
+        let mut scene = make_scene();
+        // a low step at x = 2 and a wall at x = -2
+        add_block(
+            &mut scene,
+            Vec3::new(2.0, 0.0, -5.0),
+            Vec3::new(6.0, 0.2, 5.0),
+        );
+        add_block(
+            &mut scene,
+            Vec3::new(-3.0, 0.0, -5.0),
+            Vec3::new(-2.0, 3.0, 5.0),
+        );
+
+        // walks up onto the step
+        let mut character = standing_character(0.0);
+        character.move_by(&mut scene, Vec3::new(3.0, 0.0, 0.0));
+        assert!((character.position.x - 3.0).abs() < 1e-2);
+        assert!((character.position.y - (STANDING + 0.2)).abs() < 0.05);
+        assert!(character.grounded);
+
+        // stops at the wall, sliding along it
+        let mut character = standing_character(0.0);
+        character.move_by(&mut scene, Vec3::new(-3.0, 0.0, 1.0));
+        assert!(character.position.x > -1.65 && character.position.x < -1.5);
+        assert!(character.position.z > 0.9);
+        assert!((character.position.y - (STANDING + 0.02)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_slopes() {
+        let mut scene = make_scene();
+        // a ramp rising along x, gentle or steep
+        let ramp = |scene: &mut PhysicsScene, z: f32, angle: f32| {
+            scene.add_body(Body {
+                position: Vec3::new(5.0, 0.0, z),
+                orientation: Quat::from_rotation_z(angle),
+                inv_mass: 0.0,
+                shape: Shape::make_box(Arc::new(crate::shapes::ShapeBox::new(&[
+                    Vec3::new(-5.0, -1.0, -1.0),
+                    Vec3::new(5.0, 0.0, 1.0),
+                ]))),
+                ..Body::default()
+            });
+        };
+        ramp(&mut scene, 0.0, 20f32.to_radians());
+        ramp(&mut scene, 10.0, 60f32.to_radians());
+
+        // walks up the gentle one, it comes out of the ground at x = 5
+        let mut character = standing_character(3.0);
+        for _ in 0..40 {
+            character.move_by(&mut scene, Vec3::new(0.1, 0.0, 0.0));
+        }
+        assert!(character.grounded);
+        assert!(character.position.y > STANDING + 0.5);
+        let top = character.position;
+
+        // and snaps to it walking back down
+        for _ in 0..30 {
+            character.move_by(&mut scene, Vec3::new(-0.1, 0.0, 0.0));
+            assert!(character.grounded);
+        }
+        assert!(character.position.y < top.y - 0.5);
+
+        // but can't climb the steep one
+        let mut character = standing_character(3.0);
+        character.position.z = 10.0;
+        for _ in 0..40 {
+            character.move_by(&mut scene, Vec3::new(0.1, 0.0, 0.0));
+        }
+        assert!(character.position.y < STANDING + 0.4);
+    }
+
+    #[test]
+    fn test_drives_body() {
+        let mut scene = make_scene();
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, STANDING + 0.02, 0.0),
+            inv_mass: 0.0,
+            body_type: crate::body::BodyType::Kinematic,
+            shape: Shape::make_capsule(0.5, 0.4),
+            ..Body::default()
+        });
+        let mut character = CharacterController {
+            body: Some(handle),
+            ..standing_character(0.0)
+        };
+        // its own body doesn't get in the way
+        character.move_by(&mut scene, Vec3::new(1.0, 0.0, 0.0));
+        assert!((character.position.x - 1.0).abs() < 1e-3);
+        scene.update(1.0 / 60.0);
+        assert!(scene
+            .get_body(handle)
+            .unwrap()
+            .position
+            .abs_diff_eq(character.position, 1e-3));
+    }
+}
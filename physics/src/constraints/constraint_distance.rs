@@ -1,9 +1,12 @@
-use super::{Constraint, ConstraintConfig};
+use super::{Constraint, ConstraintConfig, ConstraintState};
 use crate::{
     body::BodyArena,
-    math::{lcp_gauss_seidel, MatMN, MatN, VecN},
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
 };
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintDistance {
     config: ConstraintConfig,
     jacobian: MatMN<1, 12>,
@@ -20,9 +23,39 @@ impl ConstraintDistance {
             baumgarte: 0.0,
         }
     }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<1>, VecN<1>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
 }
 
 impl Constraint for ConstraintDistance {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Distance(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Distance {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+        })
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
@@ -82,14 +115,10 @@ impl Constraint for ConstraintDistance {
         let jacobian_transpose = self.jacobian.transpose();
 
         // build the system of equations
-        let q_dt = self.config.get_velocities(bodies);
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte;
+        let (j_w_jt, rhs) = self.build_system(bodies);
 
         // solve for the Lagrange multipliers
-        let lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        let lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
 
         // apply the impulses
         let impulses = jacobian_transpose * lambda_n;
@@ -99,6 +128,10 @@ impl Constraint for ConstraintDistance {
         self.cached_lambda += lambda_n;
     }
 
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
     fn post_solve(&mut self) {
         // limit the warm starting to reasonable limits
         if !self.cached_lambda[0].is_finite() {
@@ -113,4 +146,20 @@ impl Constraint for ConstraintDistance {
             self.cached_lambda[0] = -LIMIT;
         }
     }
+
+    fn residual(&self, bodies: &BodyArena) -> f32 {
+        // the anchors should be kept on top of each other
+        let world_anchor_a = bodies
+            .get_body(self.config.handle_a)
+            .local_to_world(self.config.anchor_a);
+        let world_anchor_b = bodies
+            .get_body(self.config.handle_b)
+            .local_to_world(self.config.anchor_b);
+        (world_anchor_b - world_anchor_a).length()
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
 }
@@ -1,10 +1,13 @@
-use super::{quat_left, quat_right, Constraint, ConstraintConfig};
+use super::{quat_left, quat_right, Constraint, ConstraintConfig, ConstraintState, JointMotor};
 use crate::{
     body::BodyArena,
-    math::{lcp_gauss_seidel, MatMN, MatN, VecN},
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
 };
 use glam::{Mat4, Quat, Vec3, Vec4};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintHingeQuat {
     config: ConstraintConfig,
     // the initial relative quaternion q1^-1 * q2
@@ -24,9 +27,30 @@ impl ConstraintHingeQuat {
             baumgarte: 0.0,
         }
     }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<3>, VecN<3>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
 }
 
 impl Constraint for ConstraintHingeQuat {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Hinge(self.clone())
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
@@ -148,14 +172,10 @@ impl Constraint for ConstraintHingeQuat {
         let jacobian_transpose = self.jacobian.transpose();
 
         // build the system of equations
-        let q_dt = self.config.get_velocities(bodies);
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte;
+        let (j_w_jt, rhs) = self.build_system(bodies);
 
         // solve for the Lagrange multipliers
-        let lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        let lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
 
         // apply the impulses
         let impulses = jacobian_transpose * lambda_n;
@@ -165,6 +185,10 @@ impl Constraint for ConstraintHingeQuat {
         self.cached_lambda += lambda_n;
     }
 
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
     fn post_solve(&mut self) {
         // limit the warm starting to reasonable limits
         for cached_lambda in self.cached_lambda.iter_mut() {
@@ -181,21 +205,33 @@ impl Constraint for ConstraintHingeQuat {
             }
         }
     }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintHingeQuatLimited {
     config: ConstraintConfig,
     // the initial relative quaternion q1^-1 * q2
     q0: Quat,
-    jacobian: MatMN<4, 12>,
-    cached_lambda: VecN<4>,
+    jacobian: MatMN<5, 12>,
+    cached_lambda: VecN<5>,
     baumgarte: f32,
     relative_angle: f32, // in degrees
+    // the min and max relative angle in degrees, None lets the hinge turn freely
+    limits: Option<(f32, f32)>,
     is_angle_violated: bool,
+    // drives body_b around the axis, it's off while a limit is being hit
+    motor: Option<JointMotor>,
+    max_motor_impulse: f32,
 }
 
 impl ConstraintHingeQuatLimited {
-    pub fn new(config: ConstraintConfig, q0: Quat) -> Self {
+    pub fn new(config: ConstraintConfig, q0: Quat, limits: Option<(f32, f32)>) -> Self {
         Self {
             config,
             q0,
@@ -203,12 +239,56 @@ impl ConstraintHingeQuatLimited {
             cached_lambda: VecN::zero(),
             baumgarte: 0.0,
             relative_angle: 0.0,
+            limits,
             is_angle_violated: false,
+            motor: None,
+            max_motor_impulse: 0.0,
+        }
+    }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<5>, VecN<5>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        if let Some(motor) = self.motor {
+            rhs[4] += motor.target_velocity;
         }
+        (MatN::from(j_w_jt), rhs)
     }
 }
 
 impl Constraint for ConstraintHingeQuatLimited {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::HingeLimited(self.clone())
+    }
+
+    fn set_motor(&mut self, motor: Option<JointMotor>) {
+        self.motor = motor;
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Hinge {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            axis: self.config.axis_a,
+            limits: self.limits,
+            motor: self.motor,
+            relative_orientation: self.q0,
+        })
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
@@ -246,7 +326,10 @@ impl Constraint for ConstraintHingeQuatLimited {
         self.relative_angle = 2.0 * qrr.xyz().dot(hinge_axis).asin().to_degrees();
 
         // check if there's an angle violation
-        self.is_angle_violated = self.relative_angle.abs() > 45.0;
+        self.is_angle_violated = match self.limits {
+            Some((min, max)) => self.relative_angle < min || self.relative_angle > max,
+            None => false,
+        };
 
         self.jacobian = MatMN::zero();
 
@@ -348,6 +431,29 @@ impl Constraint for ConstraintHingeQuatLimited {
             self.jacobian.rows[3][11] = j4.z;
         }
 
+        // the limit impulse from when it was last violated could be pushing the wrong way
+        if !self.is_angle_violated {
+            self.cached_lambda[3] = 0.0;
+        }
+
+        // the motor row turns body_b around the world space axis relative to body_a
+        match self.motor {
+            Some(motor) if !self.is_angle_violated => {
+                let world_axis = q1 * hinge_axis;
+                self.jacobian.rows[4][3] = -world_axis.x;
+                self.jacobian.rows[4][4] = -world_axis.y;
+                self.jacobian.rows[4][5] = -world_axis.z;
+                self.jacobian.rows[4][9] = world_axis.x;
+                self.jacobian.rows[4][10] = world_axis.y;
+                self.jacobian.rows[4][11] = world_axis.z;
+                self.max_motor_impulse = motor.max_force * dt_sec;
+            }
+            _ => {
+                self.cached_lambda[4] = 0.0;
+                self.max_motor_impulse = 0.0;
+            }
+        }
+
         // apply warm starting from last frame
         let impulses = self.jacobian.transpose() * self.cached_lambda;
         self.config.apply_impulses(bodies, impulses);
@@ -362,22 +468,31 @@ impl Constraint for ConstraintHingeQuatLimited {
     fn solve(&mut self, bodies: &mut BodyArena) {
         let jacobian_transpose = self.jacobian.transpose();
 
-        // build the system of equations
-        let q_dt = self.config.get_velocities(bodies);
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte;
+        // the motor can't push harder than its max torque over the step
+        if let Some(motor) = self.motor.filter(|_| self.max_motor_impulse > 0.0) {
+            self.config.solve_motor_row(
+                bodies,
+                self.jacobian.rows[4],
+                motor.target_velocity,
+                &mut self.cached_lambda[4],
+                self.max_motor_impulse,
+            );
+        }
+
+        // build the system of equations, without the motor row
+        let (mut j_w_jt, mut rhs) = self.build_system(bodies);
+        j_w_jt.rows[4] = VecN::zero();
+        rhs[4] = 0.0;
 
         // solve for the Lagrange multipliers
-        let mut lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        let mut lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
 
         // clamp the torque from the angle constraint
         // we need to make sure it's a restorative torque
-        if self.is_angle_violated {
-            if self.relative_angle > 0.0 {
+        if let (true, Some((min, max))) = (self.is_angle_violated, self.limits) {
+            if self.relative_angle > max {
                 lambda_n[3] = f32::min(0.0, lambda_n[3]);
-            } else if self.relative_angle < 0.0 {
+            } else if self.relative_angle < min {
                 lambda_n[3] = f32::max(0.0, lambda_n[3]);
             }
         }
@@ -390,6 +505,10 @@ impl Constraint for ConstraintHingeQuatLimited {
         self.cached_lambda += lambda_n;
     }
 
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
     fn post_solve(&mut self) {
         // limit the warm starting to reasonable limits
         for cached_lambda in self.cached_lambda.iter_mut() {
@@ -406,4 +525,9 @@ impl Constraint for ConstraintHingeQuatLimited {
             }
         }
     }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
 }
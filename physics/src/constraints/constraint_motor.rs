@@ -1,10 +1,13 @@
-use super::{quat_left, quat_right, Constraint, ConstraintConfig};
+use super::{quat_left, quat_right, Constraint, ConstraintConfig, ConstraintState};
 use crate::{
     body::BodyArena,
-    math::{lcp_gauss_seidel, MatMN, MatN, VecN},
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
 };
 use glam::{Mat4, Quat, Vec3, Vec4};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintMotor {
     config: ConstraintConfig,
     jacobian: MatMN<4, 12>,
@@ -25,9 +28,54 @@ impl ConstraintMotor {
             baumgarte: Vec3::ZERO,
         }
     }
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<4>, VecN<4>) {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let motor_axis = body_a.orientation * self.motor_axis;
+
+        let mut w_dt = VecN::zero();
+        w_dt[3] = motor_axis[0] * -self.motor_speed;
+        w_dt[4] = motor_axis[1] * -self.motor_speed;
+        w_dt[5] = motor_axis[2] * -self.motor_speed;
+        w_dt[9] = motor_axis[0] * self.motor_speed;
+        w_dt[10] = motor_axis[1] * self.motor_speed;
+        w_dt[11] = motor_axis[2] * self.motor_speed;
+
+        let q_dt = self.config.get_velocities(bodies) - w_dt; // by subtracting by the desired velocity, the solver is tricked into applying the impulse to give us that velocity
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte[0];
+        rhs[1] -= self.baumgarte[1];
+        rhs[2] -= self.baumgarte[2];
+        (MatN::from(j_w_jt), rhs)
+    }
 }
 
 impl Constraint for ConstraintMotor {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Motor(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Motor {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            axis: self.motor_axis,
+            speed: self.motor_speed,
+            relative_orientation: self.q0,
+        })
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
@@ -178,33 +226,21 @@ impl Constraint for ConstraintMotor {
     }
 
     fn solve(&mut self, bodies: &mut BodyArena) {
-        let body_a = bodies.get_body(self.config.handle_a);
-        let motor_axis = body_a.orientation * self.motor_axis;
-
-        let mut w_dt = VecN::zero();
-        w_dt[3] = motor_axis[0] * -self.motor_speed;
-        w_dt[4] = motor_axis[1] * -self.motor_speed;
-        w_dt[5] = motor_axis[2] * -self.motor_speed;
-        w_dt[9] = motor_axis[0] * self.motor_speed;
-        w_dt[10] = motor_axis[1] * self.motor_speed;
-        w_dt[11] = motor_axis[2] * self.motor_speed;
-
         let jacobian_transpose = self.jacobian.transpose();
 
         // build the system of equations
-        let q_dt = self.config.get_velocities(bodies) - w_dt; // by subtracting by the desired velocity, the solver is tricked into applying the impulse to give us that velocity
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte[0];
-        rhs[1] -= self.baumgarte[1];
-        rhs[2] -= self.baumgarte[2];
+        let (j_w_jt, rhs) = self.build_system(bodies);
 
         // solve for the lagrange multipliers
-        let lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        let lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
 
         // apply the impulses
         let impulses = jacobian_transpose * lambda_n;
         self.config.apply_impulses(bodies, impulses);
     }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
 }
@@ -1,6 +1,8 @@
-use super::{Constraint, ConstraintConfig};
-use crate::body::BodyArena;
+use super::{Constraint, ConstraintConfig, ConstraintState};
+use crate::{body::BodyArena, level::LevelConstraint};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintMoverSimple {
     config: ConstraintConfig,
     time: f32,
@@ -13,6 +15,24 @@ impl ConstraintMoverSimple {
 }
 
 impl Constraint for ConstraintMoverSimple {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Mover(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Mover {
+            body: bodies.index(self.config.handle_a) as u32,
+        })
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         self.time += dt_sec;
 
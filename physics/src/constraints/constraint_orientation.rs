@@ -1,10 +1,13 @@
-use super::{quat_left, quat_right, Constraint, ConstraintConfig};
+use super::{quat_left, quat_right, Constraint, ConstraintConfig, ConstraintState};
 use crate::{
     body::BodyArena,
-    math::{lcp_gauss_seidel, MatMN, MatN},
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
 };
 use glam::{Mat4, Quat, Vec3, Vec4};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintOrientation {
     config: ConstraintConfig,
     q0: Quat,
@@ -21,9 +24,40 @@ impl ConstraintOrientation {
             baumgarte: 0.0,
         }
     }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<4>, VecN<4>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
 }
 
 impl Constraint for ConstraintOrientation {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Orientation(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Orientation {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            relative_orientation: self.q0,
+        })
+    }
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
@@ -165,17 +199,18 @@ impl Constraint for ConstraintOrientation {
         let jacobian_transpose = self.jacobian.transpose();
 
         // build the system of equations
-        let q_dt = self.config.get_velocities(bodies);
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte;
+        let (j_w_jt, rhs) = self.build_system(bodies);
 
         // solve for the Lagrange multipliers
-        let lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        let lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
 
         // apply the impulses
         let impulses = jacobian_transpose * lambda_n;
         self.config.apply_impulses(bodies, impulses);
     }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
 }
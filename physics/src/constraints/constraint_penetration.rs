@@ -1,210 +1,391 @@
-use super::{Constraint, ConstraintConfig};
+use super::{Constraint, ConstraintConfig, ConstraintState};
 use crate::{
-    body::BodyArena,
-    math::{lcp_gauss_seidel, MatMN, MatN, VecN},
+    body::{Body, BodyArena},
+    contact::{Contact, ContactMaterial},
+    math::{LcpSystem, MatMN, MatN, VecN},
 };
 use glam::Vec3;
 
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintPenetration {
     config: ConstraintConfig,
-    jacobian: MatMN<3, 12>,
+    // the normal row then the two friction rows
+    rows: [ContactRow; 3],
     cached_lambda: VecN<3>,
     normal: Vec3, // in body A's local space
     baumgarte: f32,
     friction: f32,
+    // from the contact's material this step, see set_material()
+    normal_override: Option<Vec3>, // in body A's local space
+    surface_velocity: Vec3,
+    disabled: bool,
+    rolling_friction: f32,
+    spinning_friction: f32,
+    // the spinning row about the normal then the two rolling rows
+    angular_rows: [ContactRow; 3],
+    cached_angular_lambda: VecN<3>,
 }
 
 impl ConstraintPenetration {
+    pub fn from_contact(bodies: &BodyArena, contact: &Contact) -> Self {
+        let body_a = bodies.get_body(contact.handle_a);
+        let body_b = bodies.get_body(contact.handle_b);
+        let normal = body_a.orientation.inverse() * -contact.normal;
+        let mut constraint = Self::new(
+            ConstraintConfig {
+                handle_a: contact.handle_a,
+                handle_b: contact.handle_b,
+                anchor_a: contact.local_point_a,
+                anchor_b: contact.local_point_b,
+                ..ConstraintConfig::default()
+            },
+            normal.normalize(),
+        );
+        constraint.friction = body_a.friction * body_b.friction;
+        constraint
+    }
+
     pub fn new(config: ConstraintConfig, normal: Vec3) -> Self {
         Self {
             config,
-            jacobian: MatMN::zero(),
+            rows: [ContactRow::default(); 3],
             cached_lambda: VecN::zero(),
             normal,
             baumgarte: 0.0,
             friction: 0.0,
+            normal_override: None,
+            surface_velocity: Vec3::ZERO,
+            disabled: false,
+            rolling_friction: 0.0,
+            spinning_friction: 0.0,
+            angular_rows: [ContactRow::default(); 3],
+            cached_angular_lambda: VecN::zero(),
         }
     }
 
+    // the normal it's solved along, in body A's local space
     pub fn normal(&self) -> Vec3 {
+        self.normal_override.unwrap_or(self.normal)
+    }
+
+    // the normal the contact was found with, in body A's local space
+    pub fn contact_normal(&self) -> Vec3 {
         self.normal
     }
 
-    pub fn clear_cached_lambda(&mut self) {
-        self.cached_lambda = VecN::zero();
+    // uses the material for the next step, body_a is needed to bring its normal into local space
+    pub fn set_material(&mut self, material: &ContactMaterial, body_a: &Body) {
+        self.friction = material.friction;
+        self.normal_override = material
+            .normal
+            .map(|normal| (body_a.orientation.inverse() * -normal).normalize());
+        self.surface_velocity = material.surface_velocity;
+        self.rolling_friction = material.rolling_friction;
+        self.spinning_friction = material.spinning_friction;
+        self.disabled = !material.enabled;
+        if self.disabled {
+            // nothing to warm start from when it's enabled again
+            self.clear_cached_lambda();
+        }
     }
-}
 
-impl Constraint for ConstraintPenetration {
-    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    // stops the solver pushing the bodies apart faster to fix the penetration
+    pub fn clear_baumgarte(&mut self) {
+        self.baumgarte = 0.0;
+    }
+
+    // the impulse applied along the normal during the last step
+    pub fn normal_impulse(&self) -> f32 {
+        self.cached_lambda[0]
+    }
+
+    // the magnitude of the friction impulse applied during the last step
+    pub fn friction_impulse(&self) -> f32 {
+        (self.cached_lambda[1] * self.cached_lambda[1]
+            + self.cached_lambda[2] * self.cached_lambda[2])
+            .sqrt()
+    }
+
+    // the normal row followed by the two friction rows, in the order used by the solver
+    pub fn build_jacobian(&self, bodies: &BodyArena) -> MatMN<3, 12> {
+        let mut jacobian = MatMN::zero();
+        for (i, row) in self.build_rows(bodies).iter().enumerate() {
+            jacobian.rows[i] = row.to_jacobian_row();
+        }
+        jacobian
+    }
+
+    fn build_rows(&self, bodies: &BodyArena) -> [ContactRow; 3] {
         let body_a = bodies.get_body(self.config.handle_a);
         let body_b = bodies.get_body(self.config.handle_b);
 
-        // get the world space position of the hinge from body_a's orientation
         let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
-
-        // get the world space position of the hinge from body_b's orientation
         let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
-
         let ra = world_anchor_a - body_a.centre_of_mass_world();
         let rb = world_anchor_b - body_b.centre_of_mass_world();
-        self.friction = body_a.friction * body_b.friction;
 
         // should be equivalent to Vec3::GetOrtho() from the book
-        let (mut u, mut v) = self.normal.any_orthonormal_pair();
+        let normal = self.normal();
+        let (u, v) = normal.any_orthonormal_pair();
 
-        // convert tangent space from model space to world space
-        let normal = body_a.orientation * self.normal;
-        u = body_a.orientation * u;
-        v = body_a.orientation * v;
+        // the tangent space is kept in body_a's space, the rows are in world space
+        let mut rows = [ContactRow::default(); 3];
+        rows[0] = ContactRow::new(body_a, body_b, ra, rb, body_a.orientation * normal);
+        if self.friction > 0.0 {
+            rows[1] = ContactRow::new(body_a, body_b, ra, rb, body_a.orientation * u);
+            rows[2] = ContactRow::new(body_a, body_b, ra, rb, body_a.orientation * v);
+        }
+        rows
+    }
 
-        // penetration constraint
-        self.jacobian = MatMN::zero();
+    fn build_angular_rows(&self, bodies: &BodyArena) -> [ContactRow; 3] {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let normal = self.normal();
+        let (u, v) = normal.any_orthonormal_pair();
 
-        // first row is the primary distance constraint that holds the anchor points together
-        {
-            let j1 = -normal;
-            self.jacobian.rows[0][0] = j1.x;
-            self.jacobian.rows[0][1] = j1.y;
-            self.jacobian.rows[0][2] = j1.z;
+        let mut rows = [ContactRow::default(); 3];
+        if self.spinning_friction > 0.0 {
+            rows[0] = ContactRow::angular(body_a, body_b, body_a.orientation * normal);
         }
+        if self.rolling_friction > 0.0 {
+            rows[1] = ContactRow::angular(body_a, body_b, body_a.orientation * u);
+            rows[2] = ContactRow::angular(body_a, body_b, body_a.orientation * v);
+        }
+        rows
+    }
 
-        {
-            let j2 = ra.cross(-normal);
-            self.jacobian.rows[0][3] = j2.x;
-            self.jacobian.rows[0][4] = j2.y;
-            self.jacobian.rows[0][5] = j2.z;
+    // solves the row on its own, its total impulse is clamped between min and max
+    fn solve_row(&mut self, bodies: &mut BodyArena, i: usize, bias: f32, min: f32, max: f32) {
+        let row = self.rows[i];
+        if row.effective_mass <= 0.0 {
+            return;
         }
+        let velocity = row.velocity(
+            bodies.get_body(self.config.handle_a),
+            bodies.get_body(self.config.handle_b),
+        );
+        let lambda = -(velocity + bias) * row.effective_mass;
+        let total = (self.cached_lambda[i] + lambda).clamp(min, max);
+        let lambda = total - self.cached_lambda[i];
+        self.cached_lambda[i] = total;
+        self.apply_row_impulse(bodies, i, lambda);
+    }
 
-        {
-            let j3 = normal;
-            self.jacobian.rows[0][6] = j3.x;
-            self.jacobian.rows[0][7] = j3.y;
-            self.jacobian.rows[0][8] = j3.z;
+    // stops the bodies turning against each other about the row's axis, its total impulse is
+    // clamped to +-max
+    fn solve_angular_row(&mut self, bodies: &mut BodyArena, i: usize, max: f32) {
+        let row = self.angular_rows[i];
+        if row.effective_mass <= 0.0 {
+            return;
         }
+        let velocity = row.velocity(
+            bodies.get_body(self.config.handle_a),
+            bodies.get_body(self.config.handle_b),
+        );
+        let lambda = -velocity * row.effective_mass;
+        let total = (self.cached_angular_lambda[i] + lambda).clamp(-max, max);
+        let lambda = total - self.cached_angular_lambda[i];
+        self.cached_angular_lambda[i] = total;
+        self.apply_angular_row_impulse(bodies, i, lambda);
+    }
 
-        {
-            let j4 = rb.cross(normal);
-            self.jacobian.rows[0][9] = j4.x;
-            self.jacobian.rows[0][10] = j4.y;
-            self.jacobian.rows[0][11] = j4.z;
+    fn apply_angular_row_impulse(&mut self, bodies: &mut BodyArena, i: usize, lambda: f32) {
+        if lambda == 0.0 {
+            return;
         }
+        let row = self.angular_rows[i];
+        self.config.apply_impulses_linear_angular(
+            bodies,
+            Vec3::ZERO,
+            row.angular_a * lambda,
+            row.angular_b * lambda,
+        );
+    }
 
-        // friction jacobians
-        if self.friction > 0.0 {
-            {
-                let j1 = -u;
-                self.jacobian.rows[1][0] = j1.x;
-                self.jacobian.rows[1][1] = j1.y;
-                self.jacobian.rows[1][2] = j1.z;
-            }
-            {
-                let j2 = ra.cross(-u);
-                self.jacobian.rows[1][3] = j2.x;
-                self.jacobian.rows[1][4] = j2.y;
-                self.jacobian.rows[1][5] = j2.z;
-            }
-            {
-                let j3 = u;
-                self.jacobian.rows[1][6] = j3.x;
-                self.jacobian.rows[1][7] = j3.y;
-                self.jacobian.rows[1][8] = j3.z;
-            }
-            {
-                let j4 = rb.cross(u);
-                self.jacobian.rows[1][9] = j4.x;
-                self.jacobian.rows[1][10] = j4.y;
-                self.jacobian.rows[1][11] = j4.z;
-            }
+    fn apply_row_impulse(&mut self, bodies: &mut BodyArena, i: usize, lambda: f32) {
+        if lambda == 0.0 {
+            return;
+        }
+        let row = self.rows[i];
+        self.config.apply_impulses_linear_angular(
+            bodies,
+            row.dir * lambda,
+            row.angular_a * lambda,
+            row.angular_b * lambda,
+        );
+    }
 
-            {
-                let j1 = -v;
-                self.jacobian.rows[2][0] = j1.x;
-                self.jacobian.rows[2][1] = j1.y;
-                self.jacobian.rows[2][2] = j1.z;
-            }
-            {
-                let j2 = ra.cross(-v);
-                self.jacobian.rows[2][3] = j2.x;
-                self.jacobian.rows[2][4] = j2.y;
-                self.jacobian.rows[2][5] = j2.z;
-            }
-            {
-                let j3 = v;
-                self.jacobian.rows[2][6] = j3.x;
-                self.jacobian.rows[2][7] = j3.y;
-                self.jacobian.rows[2][8] = j3.z;
-            }
-            {
-                let j4 = rb.cross(v);
-                self.jacobian.rows[2][9] = j4.x;
-                self.jacobian.rows[2][10] = j4.y;
-                self.jacobian.rows[2][11] = j4.z;
-            }
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<3>, VecN<3>) {
+        let mut jacobian = MatMN::zero();
+        for (i, row) in self.rows.iter().enumerate() {
+            jacobian.rows[i] = row.to_jacobian_row();
         }
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = jacobian * inv_mass_matrix * jacobian.transpose();
+        let mut rhs = jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
+}
+
+impl Constraint for ConstraintPenetration {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Penetration(*self)
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+        if self.disabled {
+            return;
+        }
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
+        let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
+        let normal = body_a.orientation * self.normal();
+
+        self.rows = self.build_rows(bodies);
+        self.angular_rows = self.build_angular_rows(bodies);
 
         // apply warm starting from last frame
-        let impulses = self.jacobian.transpose() * self.cached_lambda;
-        self.config.apply_impulses(bodies, impulses);
+        for i in 0..3 {
+            self.apply_row_impulse(bodies, i, self.cached_lambda[i]);
+            self.apply_angular_row_impulse(bodies, i, self.cached_angular_lambda[i]);
+        }
 
         // calculate the baumgarte stabilization
         let mut c = (world_anchor_b - world_anchor_a).dot(normal);
+        if c > 0.0 {
+            // a speculative contact, the bodies can close the gap but no more
+            self.baumgarte = c / dt_sec;
+            return;
+        }
         c = f32::min(0.0, c + 0.02); // add slop
         let beta = 0.25;
         self.baumgarte = beta * c / dt_sec;
     }
 
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+        self.cached_angular_lambda = VecN::zero();
+    }
+
+    // each row is solved on its own, the normal first so the friction is limited by the normal
+    // impulse accumulated so far
     fn solve(&mut self, bodies: &mut BodyArena) {
-        let jacobian_transpose = self.jacobian.transpose();
+        if self.disabled {
+            return;
+        }
+        self.solve_row(bodies, 0, self.baumgarte, 0.0, f32::INFINITY);
 
-        // build the system of equations
-        let q_dt = self.config.get_velocities(bodies);
-        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
-        let j_w_jt = self.jacobian * inv_mass_matrix * jacobian_transpose;
-        let mut rhs = self.jacobian * q_dt * -1.0;
-        rhs[0] -= self.baumgarte;
+        if self.friction > 0.0 {
+            // coulomb friction, towards the surfaces sliding at the surface velocity
+            let max_force = self.friction * self.cached_lambda[0];
+            for i in 1..3 {
+                let bias = self.surface_velocity.dot(self.rows[i].dir);
+                self.solve_row(bodies, i, bias, -max_force, max_force);
+            }
+        }
 
-        // solve for the Lagrange multipliers
-        let mut lambda_n = lcp_gauss_seidel(&MatN::from(j_w_jt), &rhs);
+        // spinning and rolling resistance, limited by the normal impulse the same way
+        let max_spin = self.spinning_friction * self.cached_lambda[0];
+        self.solve_angular_row(bodies, 0, max_spin);
+        let max_roll = self.rolling_friction * self.cached_lambda[0];
+        for i in 1..3 {
+            self.solve_angular_row(bodies, i, max_roll);
+        }
+    }
 
-        // accumulate the impulses and clamp within the constraint limits
-        let old_lambda = self.cached_lambda;
-        self.cached_lambda += lambda_n;
-        let lambda_limit = 0.0;
-        if self.cached_lambda[0] < lambda_limit {
-            self.cached_lambda[0] = lambda_limit;
+    // the block is the rows' coupled system, the solver only uses its diagonal
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        let mut jacobian = MatMN::<3, 12>::zero();
+        for (i, row) in self.rows.iter().enumerate() {
+            jacobian.rows[i] = row.to_jacobian_row();
         }
+        system.push_block(&a, &b, &jacobian);
+    }
+}
 
-        if self.friction > 0.0 {
-            let body_a = bodies.get_body(self.config.handle_a);
-            let body_b = bodies.get_body(self.config.handle_b);
-            let umg = self.friction * 10.0 * 1.0 / (body_a.inv_mass + body_b.inv_mass);
-            let normal_force = (lambda_n[0] * self.friction).abs();
-            let max_force = if umg > normal_force {
-                umg
-            } else {
-                normal_force
-            };
-
-            if self.cached_lambda[1] > max_force {
-                self.cached_lambda[1] = max_force;
-            }
-            if self.cached_lambda[1] < -max_force {
-                self.cached_lambda[1] = -max_force;
-            }
+// one row of the jacobian, pulling body_b along dir and body_a the other way. kept as its parts
+// so it can be solved without building the matrices
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+struct ContactRow {
+    dir: Vec3,
+    // the lever arms crossed with dir
+    angular_a: Vec3,
+    angular_b: Vec3,
+    // 1 / (J W J^T), 0.0 for a row that can't move either body
+    effective_mass: f32,
+}
 
-            if self.cached_lambda[2] > max_force {
-                self.cached_lambda[2] = max_force;
-            }
-            if self.cached_lambda[2] < -max_force {
-                self.cached_lambda[2] = -max_force;
-            }
+impl ContactRow {
+    fn new(body_a: &Body, body_b: &Body, ra: Vec3, rb: Vec3, dir: Vec3) -> Self {
+        Self::from_parts(body_a, body_b, dir, ra.cross(dir), rb.cross(dir))
+    }
+
+    // only turns the bodies, body_b about the axis and body_a the other way
+    fn angular(body_a: &Body, body_b: &Body, axis: Vec3) -> Self {
+        Self::from_parts(body_a, body_b, Vec3::ZERO, axis, axis)
+    }
+
+    fn from_parts(
+        body_a: &Body,
+        body_b: &Body,
+        dir: Vec3,
+        angular_a: Vec3,
+        angular_b: Vec3,
+    ) -> Self {
+        let inv_effective_mass = dir.dot(body_a.inv_mass_world() * dir)
+            + angular_a.dot(body_a.inv_intertia_tensor_world() * angular_a)
+            + dir.dot(body_b.inv_mass_world() * dir)
+            + angular_b.dot(body_b.inv_intertia_tensor_world() * angular_b);
+        let effective_mass = if inv_effective_mass > 0.0 {
+            1.0 / inv_effective_mass
+        } else {
+            0.0
+        };
+        Self {
+            dir,
+            angular_a,
+            angular_b,
+            effective_mass,
         }
-        lambda_n = self.cached_lambda - old_lambda;
+    }
 
-        // apply the impulses
-        let impulses = jacobian_transpose * lambda_n;
-        self.config.apply_impulses(bodies, impulses);
+    // how fast the anchors move apart along dir, J * v
+    fn velocity(&self, body_a: &Body, body_b: &Body) -> f32 {
+        self.dir
+            .dot(body_b.linear_velocity - body_a.linear_velocity)
+            + self.angular_b.dot(body_b.angular_velocity)
+            - self.angular_a.dot(body_a.angular_velocity)
+    }
+
+    fn to_jacobian_row(self) -> VecN<12> {
+        let mut row = VecN::zero();
+        let parts = [-self.dir, -self.angular_a, self.dir, self.angular_b];
+        for (i, part) in parts.iter().enumerate() {
+            row[i * 3] = part.x;
+            row[i * 3 + 1] = part.y;
+            row[i * 3 + 2] = part.z;
+        }
+        row
     }
 }
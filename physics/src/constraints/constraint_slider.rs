@@ -0,0 +1,265 @@
+use super::{quat_left, quat_right, Constraint, ConstraintConfig, ConstraintState, JointMotor};
+use crate::{
+    body::BodyArena,
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
+};
+use glam::{Mat4, Quat, Vec3, Vec4};
+
+// a prismatic joint, body_b can only slide along axis_a and can't turn relative to body_a
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintSlider {
+    config: ConstraintConfig,
+    // the initial relative quaternion q1^-1 * q2
+    q0: Quat,
+    jacobian: MatMN<7, 12>,
+    cached_lambda: VecN<7>,
+    baumgarte: VecN<7>,
+    // the min and max travel along the axis from where the anchors started
+    limits: Option<(f32, f32)>,
+    // drives the travel while the limits aren't hit
+    motor: Option<JointMotor>,
+    max_motor_impulse: f32,
+    travel: f32,
+    // 1.0 when below the min travel, -1.0 when above the max and 0.0 when within the limits
+    limit_sign: f32,
+}
+
+impl ConstraintSlider {
+    pub fn new(
+        config: ConstraintConfig,
+        q0: Quat,
+        limits: Option<(f32, f32)>,
+        motor: Option<JointMotor>,
+    ) -> Self {
+        Self {
+            config,
+            q0,
+            jacobian: MatMN::zero(),
+            cached_lambda: VecN::zero(),
+            baumgarte: VecN::zero(),
+            limits,
+            motor,
+            max_motor_impulse: 0.0,
+            travel: 0.0,
+            limit_sign: 0.0,
+        }
+    }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<7>, VecN<7>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let rhs = self.jacobian * q_dt * -1.0 - self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
+
+    // the row for keeping dot(b - a, dir) at a value, dir is fixed to body_a
+    fn set_linear_row(&mut self, row: usize, dir: Vec3, ra: Vec3, rb: Vec3, d: Vec3) {
+        let j1 = -dir;
+        self.jacobian.rows[row][0] = j1.x;
+        self.jacobian.rows[row][1] = j1.y;
+        self.jacobian.rows[row][2] = j1.z;
+
+        let j2 = -(ra + d).cross(dir);
+        self.jacobian.rows[row][3] = j2.x;
+        self.jacobian.rows[row][4] = j2.y;
+        self.jacobian.rows[row][5] = j2.z;
+
+        let j3 = dir;
+        self.jacobian.rows[row][6] = j3.x;
+        self.jacobian.rows[row][7] = j3.y;
+        self.jacobian.rows[row][8] = j3.z;
+
+        let j4 = rb.cross(dir);
+        self.jacobian.rows[row][9] = j4.x;
+        self.jacobian.rows[row][10] = j4.y;
+        self.jacobian.rows[row][11] = j4.z;
+    }
+}
+
+impl Constraint for ConstraintSlider {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Slider(self.clone())
+    }
+
+    fn set_motor(&mut self, motor: Option<JointMotor>) {
+        self.motor = motor;
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Slider {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            axis: self.config.axis_a,
+            limits: self.limits,
+            motor: self.motor,
+            relative_orientation: self.q0,
+        })
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
+        let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
+
+        let d = world_anchor_b - world_anchor_a;
+        let ra = world_anchor_a - body_a.centre_of_mass_world();
+        let rb = world_anchor_b - body_b.centre_of_mass_world();
+
+        // the slide axis and two directions across it in world space
+        let axis = (body_a.orientation * self.config.axis_a).normalize();
+        let (u, v) = axis.any_orthonormal_pair();
+
+        // get the orientation information of the bodies
+        let q1 = body_a.orientation;
+        let q2 = body_b.orientation;
+        let q0_inv = self.q0.inverse();
+        let q1_inv = q1.inverse();
+
+        let p = Mat4::from_cols(Vec4::ZERO, Vec4::Y, Vec4::Z, Vec4::W);
+        let p_t = p.transpose(); // pointless but self documenting
+
+        let mat_a = p * quat_left(q1_inv) * quat_right(q2 * q0_inv) * p_t * -0.5;
+        let mat_b = p * quat_left(q1_inv) * quat_right(q2 * q0_inv) * p_t * 0.5;
+
+        self.travel = d.dot(axis);
+        self.limit_sign = match self.limits {
+            Some((min, _)) if self.travel < min => 1.0,
+            Some((_, max)) if self.travel > max => -1.0,
+            _ => 0.0,
+        };
+
+        self.jacobian = MatMN::zero();
+        self.baumgarte = VecN::zero();
+        const BETA: f32 = 0.05;
+
+        // the first two rows keep the anchors on the axis
+        self.set_linear_row(0, u, ra, rb, d);
+        self.set_linear_row(1, v, ra, rb, d);
+        self.baumgarte[0] = (BETA / dt_sec) * d.dot(u);
+        self.baumgarte[1] = (BETA / dt_sec) * d.dot(v);
+
+        // the quaternion jacobians stop the bodies turning
+        const IDX: usize = 1;
+        for (row, dir) in [Vec3::X, Vec3::Y, Vec3::Z].iter().enumerate() {
+            let row = row + 2;
+            let tmp = mat_a * Vec4::from((0.0, *dir));
+            self.jacobian.rows[row][3] = tmp[IDX];
+            self.jacobian.rows[row][4] = tmp[IDX + 1];
+            self.jacobian.rows[row][5] = tmp[IDX + 2];
+
+            let tmp = mat_b * Vec4::from((0.0, *dir));
+            self.jacobian.rows[row][9] = tmp[IDX];
+            self.jacobian.rows[row][10] = tmp[IDX + 1];
+            self.jacobian.rows[row][11] = tmp[IDX + 2];
+        }
+
+        // the limit row pushes the travel back inside the limits
+        if let Some((min, max)) = self.limits.filter(|_| self.limit_sign != 0.0) {
+            let bound = if self.limit_sign > 0.0 { min } else { max };
+            self.set_linear_row(5, axis * self.limit_sign, ra, rb, d);
+            self.baumgarte[5] = (BETA / dt_sec) * self.limit_sign * (self.travel - bound);
+        } else {
+            // the limit impulse from when it was last violated could be pushing the wrong way
+            self.cached_lambda[5] = 0.0;
+        }
+
+        // the motor row drives the travel speed, it's off while a limit is being hit
+        match self.motor {
+            Some(motor) if self.limit_sign == 0.0 => {
+                self.set_linear_row(6, axis, ra, rb, d);
+                self.baumgarte[6] = -motor.target_velocity;
+                self.max_motor_impulse = motor.max_force * dt_sec;
+            }
+            _ => {
+                self.cached_lambda[6] = 0.0;
+                self.max_motor_impulse = 0.0;
+            }
+        }
+
+        // apply warm starting from the last frame
+        let impulses = self.jacobian.transpose() * self.cached_lambda;
+        self.config.apply_impulses(bodies, impulses);
+    }
+
+    fn solve(&mut self, bodies: &mut BodyArena) {
+        let jacobian_transpose = self.jacobian.transpose();
+
+        // the motor can't push harder than its max force over the step
+        if let Some(motor) = self.motor.filter(|_| self.max_motor_impulse > 0.0) {
+            self.config.solve_motor_row(
+                bodies,
+                self.jacobian.rows[6],
+                motor.target_velocity,
+                &mut self.cached_lambda[6],
+                self.max_motor_impulse,
+            );
+        }
+
+        // build the system of equations, without the motor row
+        let (mut j_w_jt, mut rhs) = self.build_system(bodies);
+        j_w_jt.rows[6] = VecN::zero();
+        rhs[6] = 0.0;
+
+        // solve for the Lagrange multipliers
+        let mut lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
+
+        // the limit can only push the travel back inside. the total is clamped rather than this
+        // iteration's impulse so it can take back some of the warm started impulse
+        let limit_lambda = f32::max(0.0, self.cached_lambda[5] + lambda_n[5]);
+        lambda_n[5] = limit_lambda - self.cached_lambda[5];
+
+        // apply the impulses
+        let impulses = jacobian_transpose * lambda_n;
+        self.config.apply_impulses(bodies, impulses);
+
+        // accumulate the impulses for warm starting
+        self.cached_lambda += lambda_n;
+    }
+
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
+    fn post_solve(&mut self) {
+        // limit the warm starting to reasonable limits
+        for cached_lambda in self.cached_lambda.iter_mut() {
+            if !cached_lambda.is_finite() {
+                *cached_lambda = 0.0
+            }
+
+            const LIMIT: f32 = 20.0;
+            *cached_lambda = cached_lambda.clamp(-LIMIT, LIMIT);
+        }
+    }
+
+    fn residual(&self, bodies: &BodyArena) -> f32 {
+        // the anchors should stay on the axis
+        let body_a = bodies.get_body(self.config.handle_a);
+        let d = bodies
+            .get_body(self.config.handle_b)
+            .local_to_world(self.config.anchor_b)
+            - body_a.local_to_world(self.config.anchor_a);
+        let axis = (body_a.orientation * self.config.axis_a).normalize();
+        (d - axis * d.dot(axis)).length()
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
+}
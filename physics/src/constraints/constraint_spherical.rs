@@ -0,0 +1,267 @@
+use super::{Constraint, ConstraintConfig, ConstraintState, JointMotor};
+use crate::{
+    body::BodyArena,
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
+};
+use glam::Vec3;
+
+// a ball and socket joint, holds the anchors together and lets the bodies turn freely. with a
+// cone limit the swing between axis_a and axis_b is kept under the cone angle. the motor turns
+// body_b around axis_a and holds back the swing, with no target velocity it's joint friction
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintSpherical {
+    config: ConstraintConfig,
+    jacobian: MatMN<2, 12>,
+    cached_lambda: VecN<2>,
+    baumgarte: f32,
+    cone_baumgarte: f32,
+    cone_limit: Option<f32>, // in degrees
+    swing_angle: f32,        // in degrees
+    is_cone_violated: bool,
+    motor: Option<JointMotor>,
+    max_motor_impulse: f32,
+    // the twist row around axis_a then the two swing rows
+    motor_rows: [VecN<12>; 3],
+    motor_lambda: [f32; 3],
+}
+
+impl ConstraintSpherical {
+    pub fn new(config: ConstraintConfig, cone_limit: Option<f32>) -> Self {
+        Self {
+            config,
+            jacobian: MatMN::zero(),
+            cached_lambda: VecN::zero(),
+            baumgarte: 0.0,
+            cone_baumgarte: 0.0,
+            cone_limit,
+            swing_angle: 0.0,
+            is_cone_violated: false,
+            motor: None,
+            max_motor_impulse: 0.0,
+            motor_rows: [VecN::zero(); 3],
+            motor_lambda: [0.0; 3],
+        }
+    }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<2>, VecN<2>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.baumgarte;
+        rhs[1] -= self.cone_baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
+}
+
+impl Constraint for ConstraintSpherical {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Spherical(self.clone())
+    }
+
+    fn set_motor(&mut self, motor: Option<JointMotor>) {
+        self.motor = motor;
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Spherical {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            axis_a: self.config.axis_a,
+            axis_b: self.config.axis_b,
+            cone_limit: self.cone_limit,
+            motor: self.motor,
+        })
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
+        let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
+
+        let r = world_anchor_b - world_anchor_a;
+        let ra = world_anchor_a - body_a.centre_of_mass_world();
+        let rb = world_anchor_b - body_b.centre_of_mass_world();
+        let a = world_anchor_a;
+        let b = world_anchor_b;
+
+        let world_axis_a = body_a.orientation * self.config.axis_a;
+        let world_axis_b = body_b.orientation * self.config.axis_b;
+        let cos_swing = world_axis_a.dot(world_axis_b).clamp(-1.0, 1.0);
+        self.swing_angle = cos_swing.acos().to_degrees();
+        self.is_cone_violated = match self.cone_limit {
+            Some(cone_limit) => self.swing_angle > cone_limit,
+            None => false,
+        };
+
+        self.jacobian = MatMN::zero();
+
+        // first row holds the anchor points together
+        {
+            let j1 = (a - b) * 2.0;
+            self.jacobian.rows[0][0] = j1.x;
+            self.jacobian.rows[0][1] = j1.y;
+            self.jacobian.rows[0][2] = j1.z;
+
+            let j2 = ra.cross((a - b) * 2.0);
+            self.jacobian.rows[0][3] = j2.x;
+            self.jacobian.rows[0][4] = j2.y;
+            self.jacobian.rows[0][5] = j2.z;
+
+            let j3 = (b - a) * 2.0;
+            self.jacobian.rows[0][6] = j3.x;
+            self.jacobian.rows[0][7] = j3.y;
+            self.jacobian.rows[0][8] = j3.z;
+
+            let j4 = rb.cross((b - a) * 2.0);
+            self.jacobian.rows[0][9] = j4.x;
+            self.jacobian.rows[0][10] = j4.y;
+            self.jacobian.rows[0][11] = j4.z;
+        }
+
+        // second row swings the axes back inside the cone, C = dot(axis_a, axis_b) - cos(limit)
+        self.cone_baumgarte = 0.0;
+        if let (true, Some(cone_limit)) = (self.is_cone_violated, self.cone_limit) {
+            let n = world_axis_a.cross(world_axis_b);
+            self.jacobian.rows[1][3] = n.x;
+            self.jacobian.rows[1][4] = n.y;
+            self.jacobian.rows[1][5] = n.z;
+
+            self.jacobian.rows[1][9] = -n.x;
+            self.jacobian.rows[1][10] = -n.y;
+            self.jacobian.rows[1][11] = -n.z;
+
+            let c = cos_swing - cone_limit.to_radians().cos();
+            const BETA: f32 = 0.05;
+            self.cone_baumgarte = (BETA / dt_sec) * c;
+        } else {
+            // the limit impulse from when it was last violated could be pushing the wrong way
+            self.cached_lambda[1] = 0.0;
+        }
+
+        // the motor rows turn body_b relative to body_a around the twist and swing axes
+        match self.motor {
+            Some(motor) => {
+                // with no axis the twist is around y
+                let twist = if world_axis_a.length_squared() > 0.0 {
+                    world_axis_a.normalize()
+                } else {
+                    Vec3::Y
+                };
+                let (swing_a, swing_b) = twist.any_orthonormal_pair();
+                for (row, axis) in self.motor_rows.iter_mut().zip([twist, swing_a, swing_b]) {
+                    *row = VecN::zero();
+                    row[3] = -axis.x;
+                    row[4] = -axis.y;
+                    row[5] = -axis.z;
+                    row[9] = axis.x;
+                    row[10] = axis.y;
+                    row[11] = axis.z;
+                }
+                self.max_motor_impulse = motor.max_force * dt_sec;
+            }
+            None => {
+                self.motor_lambda = [0.0; 3];
+                self.max_motor_impulse = 0.0;
+            }
+        }
+
+        // apply warm starting from the last frame
+        let impulses = self.jacobian.transpose() * self.cached_lambda;
+        self.config.apply_impulses(bodies, impulses);
+        for (row, lambda) in self.motor_rows.iter().zip(self.motor_lambda) {
+            if lambda != 0.0 {
+                self.config.apply_impulses(bodies, *row * lambda);
+            }
+        }
+
+        // calculate the baumgarte stabilization
+        let c = r.dot(r);
+        let c = f32::max(0.0, c - 0.01);
+        const BETA: f32 = 0.05;
+        self.baumgarte = (BETA / dt_sec) * c;
+    }
+
+    fn solve(&mut self, bodies: &mut BodyArena) {
+        let jacobian_transpose = self.jacobian.transpose();
+
+        // the motor can't push harder than its max torque over the step
+        if let Some(motor) = self.motor.filter(|_| self.max_motor_impulse > 0.0) {
+            for i in 0..3 {
+                let target_velocity = if i == 0 { motor.target_velocity } else { 0.0 };
+                self.config.solve_motor_row(
+                    bodies,
+                    self.motor_rows[i],
+                    target_velocity,
+                    &mut self.motor_lambda[i],
+                    self.max_motor_impulse,
+                );
+            }
+        }
+
+        // build the system of equations
+        let (j_w_jt, rhs) = self.build_system(bodies);
+
+        // solve for the Lagrange multipliers
+        let mut lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
+
+        // the cone can only push the axes together
+        if self.is_cone_violated {
+            lambda_n[1] = f32::max(0.0, lambda_n[1]);
+        }
+
+        // apply the impulses
+        let impulses = jacobian_transpose * lambda_n;
+        self.config.apply_impulses(bodies, impulses);
+
+        // accumulate the impulses for warm starting
+        self.cached_lambda += lambda_n;
+    }
+
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+        self.motor_lambda = [0.0; 3];
+    }
+
+    fn post_solve(&mut self) {
+        // limit the warm starting to reasonable limits
+        for cached_lambda in self.cached_lambda.iter_mut() {
+            if !cached_lambda.is_finite() {
+                *cached_lambda = 0.0
+            }
+
+            const LIMIT: f32 = 20.0;
+            *cached_lambda = cached_lambda.clamp(-LIMIT, LIMIT);
+        }
+    }
+
+    fn residual(&self, bodies: &BodyArena) -> f32 {
+        // the anchors should be kept on top of each other
+        let world_anchor_a = bodies
+            .get_body(self.config.handle_a)
+            .local_to_world(self.config.anchor_a);
+        let world_anchor_b = bodies
+            .get_body(self.config.handle_b)
+            .local_to_world(self.config.anchor_b);
+        (world_anchor_b - world_anchor_a).length()
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
+}
@@ -0,0 +1,166 @@
+use super::{Constraint, ConstraintConfig, ConstraintState, Spring};
+use crate::{
+    body::BodyArena,
+    level::LevelConstraint,
+    math::{LcpSystem, MatMN, MatN, VecN},
+};
+use glam::Vec3;
+
+// pulls or pushes the anchors towards the spring's rest length apart. solved as a soft
+// constraint, the spring and damper give the constraint some slack each step instead of being
+// added as forces, so it stays stable however stiff the spring is
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintSpring {
+    config: ConstraintConfig,
+    spring: Spring,
+    jacobian: MatMN<1, 12>,
+    cached_lambda: VecN<1>,
+    // how much slack the constraint has, and how much of the stretch it fixes, this step
+    softness: f32,
+    bias: f32,
+}
+
+impl ConstraintSpring {
+    pub fn new(config: ConstraintConfig, spring: Spring) -> Self {
+        ConstraintSpring {
+            config,
+            spring,
+            jacobian: MatMN::zero(),
+            cached_lambda: VecN::zero(),
+            softness: 0.0,
+            bias: 0.0,
+        }
+    }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<1>, VecN<1>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let mut j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        j_w_jt.rows[0][0] += self.softness;
+        let mut rhs = self.jacobian * q_dt * -1.0;
+        rhs[0] -= self.bias + self.softness * self.cached_lambda[0];
+        (MatN::from(j_w_jt), rhs)
+    }
+
+    fn anchor_distance(&self, bodies: &BodyArena) -> f32 {
+        let world_anchor_a = bodies
+            .get_body(self.config.handle_a)
+            .local_to_world(self.config.anchor_a);
+        let world_anchor_b = bodies
+            .get_body(self.config.handle_b)
+            .local_to_world(self.config.anchor_b);
+        (world_anchor_b - world_anchor_a).length()
+    }
+}
+
+impl Constraint for ConstraintSpring {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Spring(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Spring {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            rest_length: self.spring.rest_length,
+            stiffness: self.spring.stiffness,
+            damping: self.spring.damping,
+        })
+    }
+
+    fn set_spring(&mut self, spring: Spring) {
+        self.spring = spring;
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
+        let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
+        let ra = world_anchor_a - body_a.centre_of_mass_world();
+        let rb = world_anchor_b - body_b.centre_of_mass_world();
+
+        // the spring acts along the line between the anchors, there's no line when they're on
+        // top of each other
+        let r = world_anchor_b - world_anchor_a;
+        let length = r.length();
+        let n = if length > 1e-6 {
+            r / length
+        } else {
+            Vec3::ZERO
+        };
+
+        let rows = [-n, ra.cross(-n), n, rb.cross(n)];
+        for (i, row) in rows.iter().enumerate() {
+            self.jacobian.rows[0][i * 3] = row.x;
+            self.jacobian.rows[0][i * 3 + 1] = row.y;
+            self.jacobian.rows[0][i * 3 + 2] = row.z;
+        }
+
+        // a spring with neither stiffness nor damping does nothing, infinite slack
+        let Spring {
+            rest_length,
+            stiffness,
+            damping,
+        } = self.spring;
+        let slack = dt_sec * (damping + dt_sec * stiffness);
+        if slack <= 0.0 || length <= 1e-6 {
+            self.jacobian = MatMN::zero();
+            self.cached_lambda = VecN::zero();
+            self.softness = 0.0;
+            self.bias = 0.0;
+            return;
+        }
+        self.softness = 1.0 / slack;
+        self.bias = (length - rest_length) * dt_sec * stiffness * self.softness;
+
+        // apply warm starting from the last frame
+        let impulses = self.jacobian.transpose() * self.cached_lambda;
+        self.config.apply_impulses(bodies, impulses);
+    }
+
+    fn solve(&mut self, bodies: &mut BodyArena) {
+        let (j_w_jt, rhs) = self.build_system(bodies);
+        if j_w_jt.rows[0][0] <= 0.0 {
+            return;
+        }
+        let lambda_n = rhs * (1.0 / j_w_jt.rows[0][0]);
+
+        let impulses = self.jacobian.transpose() * lambda_n;
+        self.config.apply_impulses(bodies, impulses);
+
+        self.cached_lambda += lambda_n;
+    }
+
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
+    fn post_solve(&mut self) {
+        if !self.cached_lambda[0].is_finite() {
+            self.cached_lambda[0] = 0.0
+        }
+    }
+
+    fn residual(&self, bodies: &BodyArena) -> f32 {
+        // how far the spring is from its rest length
+        (self.anchor_distance(bodies) - self.spring.rest_length).abs()
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
+}
@@ -0,0 +1,192 @@
+use super::{quat_left, quat_right, Constraint, ConstraintConfig, ConstraintState};
+use crate::{
+    body::BodyArena,
+    level::LevelConstraint,
+    math::{lcp_gauss_seidel, LcpSystem, MatMN, MatN, VecN},
+};
+use glam::{Mat4, Quat, Vec3, Vec4};
+
+// locks all six degrees of freedom between the bodies so they move as one
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintWeld {
+    config: ConstraintConfig,
+    // the initial relative quaternion q1^-1 * q2
+    q0: Quat,
+    jacobian: MatMN<6, 12>,
+    cached_lambda: VecN<6>,
+    baumgarte: VecN<6>,
+}
+
+impl ConstraintWeld {
+    pub fn new(config: ConstraintConfig, q0: Quat) -> Self {
+        Self {
+            config,
+            q0,
+            jacobian: MatMN::zero(),
+            cached_lambda: VecN::zero(),
+            baumgarte: VecN::zero(),
+        }
+    }
+
+    fn build_system(&self, bodies: &BodyArena) -> (MatN<6>, VecN<6>) {
+        let q_dt = self.config.get_velocities(bodies);
+        let inv_mass_matrix = self.config.get_inverse_mass_matrix(bodies);
+        let j_w_jt = self.jacobian * inv_mass_matrix * self.jacobian.transpose();
+        let rhs = self.jacobian * q_dt * -1.0 - self.baumgarte;
+        (MatN::from(j_w_jt), rhs)
+    }
+}
+
+impl Constraint for ConstraintWeld {
+    fn config(&self) -> &ConstraintConfig {
+        &self.config
+    }
+
+    fn config_mut(&mut self) -> &mut ConstraintConfig {
+        &mut self.config
+    }
+
+    fn to_state(&self) -> ConstraintState {
+        ConstraintState::Weld(self.clone())
+    }
+
+    fn to_level(&self, bodies: &BodyArena) -> Option<LevelConstraint> {
+        Some(LevelConstraint::Weld {
+            body_a: bodies.index(self.config.handle_a) as u32,
+            body_b: bodies.index(self.config.handle_b) as u32,
+            anchor_a: self.config.anchor_a,
+            anchor_b: self.config.anchor_b,
+            break_force: self.config.break_force,
+            break_torque: self.config.break_torque,
+            relative_orientation: self.q0,
+        })
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+        let body_a = bodies.get_body(self.config.handle_a);
+        let body_b = bodies.get_body(self.config.handle_b);
+
+        let world_anchor_a = body_a.local_to_world(self.config.anchor_a);
+        let world_anchor_b = body_b.local_to_world(self.config.anchor_b);
+
+        let d = world_anchor_b - world_anchor_a;
+        let ra = world_anchor_a - body_a.centre_of_mass_world();
+        let rb = world_anchor_b - body_b.centre_of_mass_world();
+
+        // get the orientation information of the bodies
+        let q1 = body_a.orientation;
+        let q2 = body_b.orientation;
+        let q0_inv = self.q0.inverse();
+        let q1_inv = q1.inverse();
+
+        let p = Mat4::from_cols(Vec4::ZERO, Vec4::Y, Vec4::Z, Vec4::W);
+        let p_t = p.transpose(); // pointless but self documenting
+
+        let mat_a = p * quat_left(q1_inv) * quat_right(q2 * q0_inv) * p_t * -0.5;
+        let mat_b = p * quat_left(q1_inv) * quat_right(q2 * q0_inv) * p_t * 0.5;
+
+        // how far the relative orientation has drifted, in body_a's space
+        let q_error = q1_inv * q2 * q0_inv;
+        let angle_error = if q_error.w < 0.0 {
+            -q_error.xyz()
+        } else {
+            q_error.xyz()
+        };
+
+        self.jacobian = MatMN::zero();
+        const BETA: f32 = 0.05;
+        const IDX: usize = 1;
+        for (i, dir) in [Vec3::X, Vec3::Y, Vec3::Z].iter().enumerate() {
+            // the first three rows hold the anchors together
+            let j1 = -*dir;
+            self.jacobian.rows[i][0] = j1.x;
+            self.jacobian.rows[i][1] = j1.y;
+            self.jacobian.rows[i][2] = j1.z;
+
+            let j2 = -ra.cross(*dir);
+            self.jacobian.rows[i][3] = j2.x;
+            self.jacobian.rows[i][4] = j2.y;
+            self.jacobian.rows[i][5] = j2.z;
+
+            let j3 = *dir;
+            self.jacobian.rows[i][6] = j3.x;
+            self.jacobian.rows[i][7] = j3.y;
+            self.jacobian.rows[i][8] = j3.z;
+
+            let j4 = rb.cross(*dir);
+            self.jacobian.rows[i][9] = j4.x;
+            self.jacobian.rows[i][10] = j4.y;
+            self.jacobian.rows[i][11] = j4.z;
+
+            self.baumgarte[i] = (BETA / dt_sec) * d.dot(*dir);
+
+            // the last three are the quaternion jacobians that stop the bodies turning
+            let row = i + 3;
+            let tmp = mat_a * Vec4::from((0.0, *dir));
+            self.jacobian.rows[row][3] = tmp[IDX];
+            self.jacobian.rows[row][4] = tmp[IDX + 1];
+            self.jacobian.rows[row][5] = tmp[IDX + 2];
+
+            let tmp = mat_b * Vec4::from((0.0, *dir));
+            self.jacobian.rows[row][9] = tmp[IDX];
+            self.jacobian.rows[row][10] = tmp[IDX + 1];
+            self.jacobian.rows[row][11] = tmp[IDX + 2];
+
+            self.baumgarte[row] = (BETA / dt_sec) * angle_error.dot(*dir);
+        }
+
+        // apply warm starting from the last frame
+        let impulses = self.jacobian.transpose() * self.cached_lambda;
+        self.config.apply_impulses(bodies, impulses);
+    }
+
+    fn solve(&mut self, bodies: &mut BodyArena) {
+        let jacobian_transpose = self.jacobian.transpose();
+
+        // build the system of equations
+        let (j_w_jt, rhs) = self.build_system(bodies);
+
+        // solve for the Lagrange multipliers
+        let lambda_n = lcp_gauss_seidel(&j_w_jt, &rhs);
+
+        // apply the impulses
+        let impulses = jacobian_transpose * lambda_n;
+        self.config.apply_impulses(bodies, impulses);
+
+        // accumulate the impulses for warm starting
+        self.cached_lambda += lambda_n;
+    }
+
+    fn clear_cached_lambda(&mut self) {
+        self.cached_lambda = VecN::zero();
+    }
+
+    fn post_solve(&mut self) {
+        // limit the warm starting to reasonable limits
+        for cached_lambda in self.cached_lambda.iter_mut() {
+            if !cached_lambda.is_finite() {
+                *cached_lambda = 0.0
+            }
+
+            const LIMIT: f32 = 20.0;
+            *cached_lambda = cached_lambda.clamp(-LIMIT, LIMIT);
+        }
+    }
+
+    fn residual(&self, bodies: &BodyArena) -> f32 {
+        // the anchors should be kept on top of each other
+        let world_anchor_a = bodies
+            .get_body(self.config.handle_a)
+            .local_to_world(self.config.anchor_a);
+        let world_anchor_b = bodies
+            .get_body(self.config.handle_b)
+            .local_to_world(self.config.anchor_b);
+        (world_anchor_b - world_anchor_a).length()
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        let (a, b) = self.build_system(bodies);
+        system.push_block(&a, &b, &self.jacobian);
+    }
+}
@@ -6,19 +6,29 @@ mod constraint_motor;
 mod constraint_mover;
 mod constraint_orientation;
 mod constraint_penetration;
+mod constraint_slider;
+mod constraint_spherical;
+mod constraint_spring;
+mod constraint_weld;
 
 use crate::{
     body::{BodyArena, BodyHandle},
-    math::{MatMN, VecN},
+    level::LevelConstraint,
+    math::{LcpSystem, MatMN, VecN},
 };
-use constraint_constant_velocity::ConstraintConstantVelocityLimited;
+use constraint_constant_velocity::{ConstraintConstantVelocity, ConstraintConstantVelocityLimited};
 use constraint_distance::ConstraintDistance;
-use constraint_hinge_quat::ConstraintHingeQuatLimited;
+use constraint_hinge_quat::{ConstraintHingeQuat, ConstraintHingeQuatLimited};
 use constraint_motor::ConstraintMotor;
 use constraint_mover::ConstraintMoverSimple;
 use constraint_orientation::ConstraintOrientation;
 pub use constraint_penetration::ConstraintPenetration;
+use constraint_slider::ConstraintSlider;
+use constraint_spherical::ConstraintSpherical;
+use constraint_spring::ConstraintSpring;
+use constraint_weld::ConstraintWeld;
 use glam::{Mat4, Quat, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
 
 pub fn quat_left(q: Quat) -> Mat4 {
     Mat4::from_cols(
@@ -39,19 +49,200 @@ pub fn quat_right(q: Quat) -> Mat4 {
 }
 
 pub trait Constraint: Send + Sync {
+    // the bodies and anchors this constraint joins
+    fn config(&self) -> &ConstraintConfig;
+    fn config_mut(&mut self) -> &mut ConstraintConfig;
+
+    // a copy of the constraint with its solver state, for snapshots
+    fn to_state(&self) -> ConstraintState;
+
     fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32);
     fn solve(&mut self, bodies: &mut BodyArena);
     fn post_solve(&mut self) {}
+
+    // forget the impulses kept for warm starting the next step
+    fn clear_cached_lambda(&mut self) {}
+
+    // how far the bodies are from satisfying the constraint, 0.0 if it isn't measured
+    fn residual(&self, _bodies: &BodyArena) -> f32 {
+        0.0
+    }
+
+    // adds the system of equations solve() would work on with the current body state
+    fn append_lcp_system(&self, _bodies: &BodyArena, _system: &mut LcpSystem) {}
+
+    // only hinges, sliders and spherical joints have motors, the others ignore this
+    fn set_motor(&mut self, _motor: Option<JointMotor>) {}
+
+    // only springs have a spring, the others ignore this
+    fn set_spring(&mut self, _spring: Spring) {}
+
+    // how this constraint is saved in a level, None if it can't be. the level refers to the
+    // bodies by their index in the arena
+    fn to_level(&self, _bodies: &BodyArena) -> Option<LevelConstraint> {
+        None
+    }
+}
+
+// every kind of constraint, so a constraint can be copied out of its box and saved. unlike
+// LevelConstraint this keeps everything, down to the impulses cached for warm starting
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ConstraintState {
+    ConstantVelocity(ConstraintConstantVelocity),
+    ConstantVelocityLimited(ConstraintConstantVelocityLimited),
+    Distance(ConstraintDistance),
+    Hinge(ConstraintHingeQuat),
+    HingeLimited(ConstraintHingeQuatLimited),
+    Motor(ConstraintMotor),
+    Mover(ConstraintMoverSimple),
+    Orientation(ConstraintOrientation),
+    Penetration(ConstraintPenetration),
+    Slider(ConstraintSlider),
+    Spherical(ConstraintSpherical),
+    Spring(ConstraintSpring),
+    Weld(ConstraintWeld),
+}
+
+impl ConstraintState {
+    pub fn into_constraint(self) -> Box<dyn Constraint> {
+        match self {
+            ConstraintState::ConstantVelocity(c) => Box::new(c),
+            ConstraintState::ConstantVelocityLimited(c) => Box::new(c),
+            ConstraintState::Distance(c) => Box::new(c),
+            ConstraintState::Hinge(c) => Box::new(c),
+            ConstraintState::HingeLimited(c) => Box::new(c),
+            ConstraintState::Motor(c) => Box::new(c),
+            ConstraintState::Mover(c) => Box::new(c),
+            ConstraintState::Orientation(c) => Box::new(c),
+            ConstraintState::Penetration(c) => Box::new(c),
+            ConstraintState::Slider(c) => Box::new(c),
+            ConstraintState::Spherical(c) => Box::new(c),
+            ConstraintState::Spring(c) => Box::new(c),
+            ConstraintState::Weld(c) => Box::new(c),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintHandle(pub u32);
+
+// drives a joint axis towards a speed, in radians or metres per second. max_force is the most
+// force, or torque for a hinge, the motor can use to get there
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JointMotor {
+    pub target_velocity: f32,
+    pub max_force: f32,
 }
 
+// a soft distance between two anchors. stiffness is the force for each metre the spring is
+// stretched or squashed past its rest length, damping the force for each metre per second the
+// anchors move apart or together
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spring {
+    pub rest_length: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+// sent when a constraint needed more than its break_force or break_torque to hold the bodies,
+// it's disabled when this is sent
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstraintBroken {
+    pub handle: ConstraintHandle,
+    pub handle_a: BodyHandle,
+    pub handle_b: BodyHandle,
+    pub force: f32,
+    pub torque: f32,
+}
+
+// the boxed constraints are saved through ConstraintArenaState
+#[cfg_attr(
+    feature = "serialize",
+    derive(Serialize, Deserialize),
+    serde(from = "ConstraintArenaState", into = "ConstraintArenaState")
+)]
 pub struct ConstraintArena {
     constraints: Vec<Box<dyn Constraint>>,
+    residuals: Vec<f32>,
+    // disabled constraints are skipped by the solver
+    enabled: Vec<bool>,
+    // the constraints on removed bodies stay disabled in their slots, so the handles to the ones
+    // after them still find them
+    removed: Vec<bool>,
+    dt_sec: f32,
+}
+
+impl Clone for ConstraintArena {
+    fn clone(&self) -> Self {
+        ConstraintArena {
+            constraints: self
+                .constraints
+                .iter()
+                .map(|constraint| constraint.to_state().into_constraint())
+                .collect(),
+            residuals: self.residuals.clone(),
+            enabled: self.enabled.clone(),
+            removed: self.removed.clone(),
+            dt_sec: self.dt_sec,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct ConstraintArenaState {
+    constraints: Vec<ConstraintState>,
+    residuals: Vec<f32>,
+    enabled: Vec<bool>,
+    removed: Vec<bool>,
+    dt_sec: f32,
+}
+
+#[cfg(feature = "serialize")]
+impl From<ConstraintArenaState> for ConstraintArena {
+    fn from(state: ConstraintArenaState) -> Self {
+        ConstraintArena {
+            constraints: state
+                .constraints
+                .into_iter()
+                .map(ConstraintState::into_constraint)
+                .collect(),
+            residuals: state.residuals,
+            enabled: state.enabled,
+            removed: state.removed,
+            dt_sec: state.dt_sec,
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl From<ConstraintArena> for ConstraintArenaState {
+    fn from(arena: ConstraintArena) -> Self {
+        ConstraintArenaState {
+            constraints: arena
+                .constraints
+                .iter()
+                .map(|constraint| constraint.to_state())
+                .collect(),
+            residuals: arena.residuals,
+            enabled: arena.enabled,
+            removed: arena.removed,
+            dt_sec: arena.dt_sec,
+        }
+    }
 }
 
 impl Default for ConstraintArena {
     fn default() -> Self {
         ConstraintArena {
             constraints: Vec::new(),
+            residuals: Vec::new(),
+            enabled: Vec::new(),
+            removed: Vec::new(),
+            dt_sec: 0.0,
         }
     }
 }
@@ -59,6 +250,18 @@ impl Default for ConstraintArena {
 impl ConstraintArena {
     pub fn clear(&mut self) {
         self.constraints.clear();
+        self.residuals.clear();
+        self.enabled.clear();
+        self.removed.clear();
+    }
+
+    fn push(&mut self, constraint: Box<dyn Constraint>) -> ConstraintHandle {
+        let handle = ConstraintHandle(self.constraints.len() as u32);
+        self.constraints.push(constraint);
+        self.residuals.push(0.0);
+        self.enabled.push(true);
+        self.removed.push(false);
+        handle
     }
 
     pub fn add_orientation_constraint(
@@ -66,13 +269,13 @@ impl ConstraintArena {
         bodies: &BodyArena,
         handle_a: BodyHandle,
         handle_b: BodyHandle,
-    ) {
+    ) -> ConstraintHandle {
         let body_a = bodies.get_body(handle_a);
         let body_b = bodies.get_body(handle_b);
 
         let world_space_anchor = body_a.position;
 
-        self.constraints.push(Box::new(ConstraintOrientation::new(
+        self.push(Box::new(ConstraintOrientation::new(
             ConstraintConfig {
                 handle_a,
                 handle_b,
@@ -89,7 +292,7 @@ impl ConstraintArena {
         bodies: &BodyArena,
         handle_a: BodyHandle,
         handle_b: BodyHandle,
-    ) {
+    ) -> ConstraintHandle {
         let body_a = bodies.get_body(handle_a);
         let body_b = bodies.get_body(handle_b);
         let joint_world_space_anchor = body_a.position;
@@ -97,17 +300,44 @@ impl ConstraintArena {
         let anchor_a = body_a.world_to_local(joint_world_space_anchor);
         let anchor_b = body_b.world_to_local(joint_world_space_anchor);
 
-        self.constraints
-            .push(Box::new(ConstraintDistance::new(ConstraintConfig {
+        self.push(Box::new(ConstraintDistance::new(ConstraintConfig {
+            handle_a,
+            handle_b,
+            anchor_a,
+            axis_a: Vec3::ZERO,
+            anchor_b,
+            axis_b: Vec3::ZERO,
+            ..ConstraintConfig::default()
+        })))
+    }
+
+    // a spring between an anchor on each body, see Spring. for suspensions, ropes and anything
+    // the distance constraint holds too rigidly
+    pub fn add_spring_constraint(
+        &mut self,
+        bodies: &BodyArena,
+        handle_a: BodyHandle,
+        handle_b: BodyHandle,
+        world_space_anchor_a: Vec3,
+        world_space_anchor_b: Vec3,
+        spring: Spring,
+    ) -> ConstraintHandle {
+        let body_a = bodies.get_body(handle_a);
+        let body_b = bodies.get_body(handle_b);
+
+        self.push(Box::new(ConstraintSpring::new(
+            ConstraintConfig {
                 handle_a,
                 handle_b,
-                anchor_a,
-                axis_a: Vec3::ZERO,
-                anchor_b,
-                axis_b: Vec3::ZERO,
-            })));
+                anchor_a: body_a.world_to_local(world_space_anchor_a),
+                anchor_b: body_b.world_to_local(world_space_anchor_b),
+                ..ConstraintConfig::default()
+            },
+            spring,
+        )))
     }
 
+    // a hinge that can turn up to 45 degrees either way, see add_hinge_constraint_with_limits
     pub fn add_hinge_constraint(
         &mut self,
         bodies: &BodyArena,
@@ -115,24 +345,134 @@ impl ConstraintArena {
         handle_b: BodyHandle,
         world_space_anchor: Vec3,
         axis: Vec3,
-    ) {
+    ) -> ConstraintHandle {
+        self.add_hinge_constraint_with_limits(
+            bodies,
+            handle_a,
+            handle_b,
+            world_space_anchor,
+            axis,
+            Some((-45.0, 45.0)),
+        )
+    }
+
+    // the axis is in body_a's space and the limits are the min and max angle in degrees the
+    // hinge can turn from where the bodies are now, None for no limits
+    pub fn add_hinge_constraint_with_limits(
+        &mut self,
+        bodies: &BodyArena,
+        handle_a: BodyHandle,
+        handle_b: BodyHandle,
+        world_space_anchor: Vec3,
+        axis: Vec3,
+        limits: Option<(f32, f32)>,
+    ) -> ConstraintHandle {
         let body_a = bodies.get_body(handle_a);
         let body_b = bodies.get_body(handle_b);
 
         let relative_orientation = body_a.orientation.inverse() * body_b.orientation;
 
-        self.constraints
-            .push(Box::new(ConstraintHingeQuatLimited::new(
-                ConstraintConfig {
-                    handle_a,
-                    handle_b,
-                    anchor_a: body_a.world_to_local(world_space_anchor),
-                    anchor_b: body_b.world_to_local(world_space_anchor),
-                    axis_a: axis,
-                    axis_b: Vec3::ZERO,
-                },
-                relative_orientation,
-            )))
+        self.push(Box::new(ConstraintHingeQuatLimited::new(
+            ConstraintConfig {
+                handle_a,
+                handle_b,
+                anchor_a: body_a.world_to_local(world_space_anchor),
+                anchor_b: body_b.world_to_local(world_space_anchor),
+                axis_a: axis,
+                axis_b: Vec3::ZERO,
+                ..ConstraintConfig::default()
+            },
+            relative_orientation,
+            limits,
+        )))
+    }
+
+    // a ball and socket joint at the anchor. the axis is in body_a's space and with a cone limit
+    // body_b can swing up to that many degrees away from it
+    pub fn add_spherical_constraint(
+        &mut self,
+        bodies: &BodyArena,
+        handle_a: BodyHandle,
+        handle_b: BodyHandle,
+        world_space_anchor: Vec3,
+        axis: Vec3,
+        cone_limit: Option<f32>,
+    ) -> ConstraintHandle {
+        let body_a = bodies.get_body(handle_a);
+        let body_b = bodies.get_body(handle_b);
+
+        self.push(Box::new(ConstraintSpherical::new(
+            ConstraintConfig {
+                handle_a,
+                handle_b,
+                anchor_a: body_a.world_to_local(world_space_anchor),
+                anchor_b: body_b.world_to_local(world_space_anchor),
+                axis_a: axis,
+                axis_b: body_b.orientation.inverse() * (body_a.orientation * axis),
+                ..ConstraintConfig::default()
+            },
+            cone_limit,
+        )))
+    }
+
+    // a prismatic joint, body_b slides along the axis in body_a's space. the limits are the min
+    // and max travel from where the bodies are now and the motor drives the travel
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_slider_constraint(
+        &mut self,
+        bodies: &BodyArena,
+        handle_a: BodyHandle,
+        handle_b: BodyHandle,
+        world_space_anchor: Vec3,
+        axis: Vec3,
+        limits: Option<(f32, f32)>,
+        motor: Option<JointMotor>,
+    ) -> ConstraintHandle {
+        let body_a = bodies.get_body(handle_a);
+        let body_b = bodies.get_body(handle_b);
+
+        let relative_orientation = body_a.orientation.inverse() * body_b.orientation;
+
+        self.push(Box::new(ConstraintSlider::new(
+            ConstraintConfig {
+                handle_a,
+                handle_b,
+                anchor_a: body_a.world_to_local(world_space_anchor),
+                anchor_b: body_b.world_to_local(world_space_anchor),
+                axis_a: axis,
+                axis_b: Vec3::ZERO,
+                ..ConstraintConfig::default()
+            },
+            relative_orientation,
+            limits,
+            motor,
+        )))
+    }
+
+    // rigidly attaches the bodies where they are now, see set_break_thresholds for making it
+    // breakable
+    pub fn add_weld_constraint(
+        &mut self,
+        bodies: &BodyArena,
+        handle_a: BodyHandle,
+        handle_b: BodyHandle,
+        world_space_anchor: Vec3,
+    ) -> ConstraintHandle {
+        let body_a = bodies.get_body(handle_a);
+        let body_b = bodies.get_body(handle_b);
+
+        let relative_orientation = body_a.orientation.inverse() * body_b.orientation;
+
+        self.push(Box::new(ConstraintWeld::new(
+            ConstraintConfig {
+                handle_a,
+                handle_b,
+                anchor_a: body_a.world_to_local(world_space_anchor),
+                anchor_b: body_b.world_to_local(world_space_anchor),
+                ..ConstraintConfig::default()
+            },
+            relative_orientation,
+        )))
     }
 
     pub fn add_constant_velocity_constraint(
@@ -142,24 +482,24 @@ impl ConstraintArena {
         handle_b: BodyHandle,
         world_space_anchor: Vec3,
         axis: Vec3,
-    ) {
+    ) -> ConstraintHandle {
         let body_a = bodies.get_body(handle_a);
         let body_b = bodies.get_body(handle_b);
 
         let relative_orientation = body_a.orientation.inverse() * body_b.orientation;
 
-        self.constraints
-            .push(Box::new(ConstraintConstantVelocityLimited::new(
-                ConstraintConfig {
-                    handle_a,
-                    handle_b,
-                    anchor_a: body_a.world_to_local(world_space_anchor),
-                    anchor_b: body_b.world_to_local(world_space_anchor),
-                    axis_a: axis,
-                    axis_b: Vec3::ZERO,
-                },
-                relative_orientation,
-            )))
+        self.push(Box::new(ConstraintConstantVelocityLimited::new(
+            ConstraintConfig {
+                handle_a,
+                handle_b,
+                anchor_a: body_a.world_to_local(world_space_anchor),
+                anchor_b: body_b.world_to_local(world_space_anchor),
+                axis_a: axis,
+                axis_b: Vec3::ZERO,
+                ..ConstraintConfig::default()
+            },
+            relative_orientation,
+        )))
     }
 
     pub fn add_constraint_motor(
@@ -170,14 +510,14 @@ impl ConstraintArena {
         world_space_anchor: Vec3,
         motor_axis: Vec3,
         motor_speed: f32,
-    ) {
+    ) -> ConstraintHandle {
         let body_a = bodies.get_body(handle_a);
         let body_b = bodies.get_body(handle_b);
 
         // set the initial relative orientation (in body_a's space)
         let q0 = body_a.orientation.inverse() * body_b.orientation;
 
-        self.constraints.push(Box::new(ConstraintMotor::new(
+        self.push(Box::new(ConstraintMotor::new(
             ConstraintConfig {
                 handle_a,
                 handle_b,
@@ -191,34 +531,415 @@ impl ConstraintArena {
         )))
     }
 
-    pub fn add_constraint_mover(&mut self, _bodies: &BodyArena, handle_a: BodyHandle) {
-        self.constraints
-            .push(Box::new(ConstraintMoverSimple::new(ConstraintConfig {
-                handle_a,
+    pub fn add_constraint_mover(
+        &mut self,
+        _bodies: &BodyArena,
+        handle_a: BodyHandle,
+    ) -> ConstraintHandle {
+        // the mover only drives one body, it's paired with itself so the islands don't look
+        // up a second one
+        self.push(Box::new(ConstraintMoverSimple::new(ConstraintConfig {
+            handle_a,
+            handle_b: handle_a,
+            ..ConstraintConfig::default()
+        })))
+    }
+
+    // removes the constraints on the removed body, see PhysicsScene::remove_body. the removed
+    // constraints' slots are kept so the other constraints' handles don't change
+    pub fn remove_body(&mut self, removed: BodyHandle) {
+        for i in 0..self.constraints.len() {
+            let config = self.constraints[i].config();
+            if config.handle_a == removed || config.handle_b == removed {
+                self.constraints[i].clear_cached_lambda();
+                self.residuals[i] = 0.0;
+                self.enabled[i] = false;
+                self.removed[i] = true;
+            }
+        }
+    }
+
+    // builds a constraint saved in a level, the level's body indices are the bodies' indices in
+    // the arena
+    pub fn add_level_constraint(
+        &mut self,
+        bodies: &BodyArena,
+        constraint: &LevelConstraint,
+    ) -> ConstraintHandle {
+        let handle = |index: u32| bodies.handles()[index as usize];
+        match *constraint {
+            LevelConstraint::Distance {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+            } => self.push(Box::new(ConstraintDistance::new(ConstraintConfig {
+                handle_a: handle(body_a),
+                handle_b: handle(body_b),
+                anchor_a,
+                anchor_b,
                 ..ConstraintConfig::default()
-            })))
+            }))),
+            LevelConstraint::Orientation {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                relative_orientation,
+            } => self.push(Box::new(ConstraintOrientation::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    ..ConstraintConfig::default()
+                },
+                relative_orientation,
+            ))),
+            LevelConstraint::Hinge {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                axis,
+                limits,
+                motor,
+                relative_orientation,
+            } => {
+                let mut hinge = ConstraintHingeQuatLimited::new(
+                    ConstraintConfig {
+                        handle_a: handle(body_a),
+                        handle_b: handle(body_b),
+                        anchor_a,
+                        anchor_b,
+                        axis_a: axis,
+                        axis_b: Vec3::ZERO,
+                        ..ConstraintConfig::default()
+                    },
+                    relative_orientation,
+                    limits,
+                );
+                hinge.set_motor(motor);
+                self.push(Box::new(hinge))
+            }
+            LevelConstraint::Spherical {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                axis_a,
+                axis_b,
+                cone_limit,
+                motor,
+            } => {
+                let mut spherical = ConstraintSpherical::new(
+                    ConstraintConfig {
+                        handle_a: handle(body_a),
+                        handle_b: handle(body_b),
+                        anchor_a,
+                        anchor_b,
+                        axis_a,
+                        axis_b,
+                        ..ConstraintConfig::default()
+                    },
+                    cone_limit,
+                );
+                spherical.set_motor(motor);
+                self.push(Box::new(spherical))
+            }
+            LevelConstraint::Spring {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                rest_length,
+                stiffness,
+                damping,
+            } => self.push(Box::new(ConstraintSpring::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    ..ConstraintConfig::default()
+                },
+                Spring {
+                    rest_length,
+                    stiffness,
+                    damping,
+                },
+            ))),
+            LevelConstraint::Slider {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                axis,
+                limits,
+                motor,
+                relative_orientation,
+            } => self.push(Box::new(ConstraintSlider::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    axis_a: axis,
+                    axis_b: Vec3::ZERO,
+                    ..ConstraintConfig::default()
+                },
+                relative_orientation,
+                limits,
+                motor,
+            ))),
+            LevelConstraint::Weld {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                break_force,
+                break_torque,
+                relative_orientation,
+            } => self.push(Box::new(ConstraintWeld::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    break_force,
+                    break_torque,
+                    ..ConstraintConfig::default()
+                },
+                relative_orientation,
+            ))),
+            LevelConstraint::ConstantVelocity {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                axis,
+                relative_orientation,
+            } => self.push(Box::new(ConstraintConstantVelocityLimited::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    axis_a: axis,
+                    axis_b: Vec3::ZERO,
+                    ..ConstraintConfig::default()
+                },
+                relative_orientation,
+            ))),
+            LevelConstraint::Motor {
+                body_a,
+                body_b,
+                anchor_a,
+                anchor_b,
+                axis,
+                speed,
+                relative_orientation,
+            } => self.push(Box::new(ConstraintMotor::new(
+                ConstraintConfig {
+                    handle_a: handle(body_a),
+                    handle_b: handle(body_b),
+                    anchor_a,
+                    anchor_b,
+                    ..ConstraintConfig::default()
+                },
+                relative_orientation,
+                axis,
+                speed,
+            ))),
+            LevelConstraint::Mover { body } => {
+                self.push(Box::new(ConstraintMoverSimple::new(ConstraintConfig {
+                    handle_a: handle(body),
+                    handle_b: handle(body),
+                    ..ConstraintConfig::default()
+                })))
+            }
+        }
     }
 
-    pub fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
-        for constraint in &mut self.constraints {
+    // the constraints that can be saved in a level
+    pub fn to_level(&self, bodies: &BodyArena) -> Vec<LevelConstraint> {
+        self.constraints
+            .iter()
+            .zip(&self.removed)
+            .filter(|(_, &removed)| !removed)
+            .filter_map(|(constraint, _)| constraint.to_level(bodies))
+            .collect()
+    }
+
+    // the index of each enabled constraint and the bodies it joins
+    pub fn iter_enabled_pairs(&self) -> impl Iterator<Item = (usize, BodyHandle, BodyHandle)> + '_ {
+        self.constraints
+            .iter()
+            .zip(&self.enabled)
+            .enumerate()
+            .filter(|(_, (_, &enabled))| enabled)
+            .map(|(index, (constraint, _))| {
+                let config = constraint.config();
+                (index, config.handle_a, config.handle_b)
+            })
+    }
+
+    // the bodies and anchors of each enabled constraint
+    pub fn iter_enabled_configs(&self) -> impl Iterator<Item = &ConstraintConfig> + '_ {
+        self.constraints
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(_, &enabled)| enabled)
+            .map(|(constraint, _)| constraint.config())
+    }
+
+    // detaches or reattaches the bodies, the constraint is kept so it can be enabled again. one
+    // whose body was removed stays disabled
+    pub fn set_enabled(&mut self, handle: ConstraintHandle, enabled: bool) {
+        let index = handle.0 as usize;
+        if !enabled {
+            self.constraints[index].clear_cached_lambda();
+        }
+        self.enabled[index] = enabled && !self.removed[index];
+    }
+
+    pub fn is_enabled(&self, handle: ConstraintHandle) -> bool {
+        self.enabled[handle.0 as usize]
+    }
+
+    fn iter_enabled_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Constraint>> {
+        self.constraints
+            .iter_mut()
+            .zip(&self.enabled)
+            .filter(|(_, &enabled)| enabled)
+            .map(|(constraint, _)| constraint)
+    }
+
+    // prepares the constraints at the indices, which are from iter_enabled_pairs()
+    pub fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32, indices: &[usize]) {
+        self.dt_sec = dt_sec;
+        for &index in indices {
+            let constraint = &mut self.constraints[index];
+            if constraint.config().is_resting(bodies) {
+                continue;
+            }
+            let config = constraint.config_mut();
+            config.linear_impulse = Vec3::ZERO;
+            config.angular_impulse = Vec3::ZERO;
             constraint.pre_solve(bodies, dt_sec);
         }
     }
 
-    pub fn solve(&mut self, bodies: &mut BodyArena) {
-        for constraint in &mut self.constraints {
+    pub fn solve(&mut self, bodies: &mut BodyArena, indices: &[usize]) {
+        for &index in indices {
+            let constraint = &mut self.constraints[index];
+            if constraint.config().is_resting(bodies) {
+                continue;
+            }
             constraint.solve(bodies);
         }
     }
 
     pub fn post_solve(&mut self) {
-        for constraint in &mut self.constraints {
+        for constraint in self.iter_enabled_mut() {
             constraint.post_solve();
         }
     }
+
+    // disables the constraints that needed more than their break force or torque this step
+    pub fn break_constraints(&mut self, bodies: &BodyArena) -> Vec<ConstraintBroken> {
+        let mut broken = Vec::new();
+        for (index, (constraint, enabled)) in self
+            .constraints
+            .iter_mut()
+            .zip(&mut self.enabled)
+            .enumerate()
+        {
+            let config = *constraint.config();
+            if !*enabled || (config.break_force.is_none() && config.break_torque.is_none()) {
+                continue;
+            }
+            let (force, torque) = config.applied_force_torque(bodies, self.dt_sec);
+            let exceeds = |limit: Option<f32>, value: f32| limit.map_or(false, |l| value > l);
+            if exceeds(config.break_force, force) || exceeds(config.break_torque, torque) {
+                constraint.clear_cached_lambda();
+                *enabled = false;
+                broken.push(ConstraintBroken {
+                    handle: ConstraintHandle(index as u32),
+                    handle_a: config.handle_a,
+                    handle_b: config.handle_b,
+                    force,
+                    torque,
+                });
+            }
+        }
+        broken
+    }
+
+    // starts, changes or stops the motor on a hinge or slider
+    pub fn set_motor(&mut self, handle: ConstraintHandle, motor: Option<JointMotor>) {
+        self.constraints[handle.0 as usize].set_motor(motor);
+    }
+
+    // changes a spring's rest length, stiffness or damping, reeling in a rope say
+    pub fn set_spring(&mut self, handle: ConstraintHandle, spring: Spring) {
+        self.constraints[handle.0 as usize].set_spring(spring);
+    }
+
+    // the constraint is disabled when it needs more than break_force or break_torque to hold
+    // the bodies, None never breaks
+    pub fn set_break_thresholds(
+        &mut self,
+        handle: ConstraintHandle,
+        break_force: Option<f32>,
+        break_torque: Option<f32>,
+    ) {
+        let config = self.constraints[handle.0 as usize].config_mut();
+        config.break_force = break_force;
+        config.break_torque = break_torque;
+    }
+
+    pub fn clear_cached_lambdas(&mut self) {
+        for constraint in &mut self.constraints {
+            constraint.clear_cached_lambda();
+        }
+    }
+
+    // measures the constraint errors left over at the end of a step
+    pub fn update_residuals(&mut self, bodies: &BodyArena) {
+        for ((residual, constraint), &enabled) in self
+            .residuals
+            .iter_mut()
+            .zip(&self.constraints)
+            .zip(&self.enabled)
+        {
+            // a disabled constraint isn't trying to hold anything
+            *residual = if enabled {
+                constraint.residual(bodies)
+            } else {
+                0.0
+            };
+        }
+    }
+
+    pub fn residual(&self, handle: ConstraintHandle) -> f32 {
+        self.residuals[handle.0 as usize]
+    }
+
+    pub fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        for (constraint, _) in self
+            .constraints
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(_, &enabled)| enabled)
+        {
+            constraint.append_lcp_system(bodies, system);
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstraintConfig {
     pub handle_a: BodyHandle,
     pub handle_b: BodyHandle,
@@ -228,18 +949,36 @@ pub struct ConstraintConfig {
 
     pub anchor_b: Vec3, // the anchor location in body_b's space
     pub axis_b: Vec3,   // the axis direction in body_b's space
+
+    // the constraint breaks when it needs more than this force or torque to hold body_b, the
+    // torque is about the anchor
+    pub break_force: Option<f32>,
+    pub break_torque: Option<f32>,
+
+    // the impulse applied to body_b so far this step
+    linear_impulse: Vec3,
+    angular_impulse: Vec3,
 }
 
 impl ConstraintConfig {
+    // both bodies are asleep, or one is and the other is static, so there's nothing to solve.
+    // constraints between two static bodies still run, the mover drives a static body
+    fn is_resting(&self, bodies: &BodyArena) -> bool {
+        let body_a = bodies.get_body(self.handle_a);
+        let body_b = bodies.get_body(self.handle_b);
+        body_a.is_resting() && body_b.is_resting() && (body_a.sleeping || body_b.sleeping)
+    }
+
     fn get_inverse_mass_matrix(&self, bodies: &BodyArena) -> MatMN<12, 12> {
         let mut inv_mass_matrix = MatMN::zero();
 
         {
             let body_a = bodies.get_body(self.handle_a);
 
-            inv_mass_matrix.rows[0][0] = body_a.inv_mass;
-            inv_mass_matrix.rows[1][1] = body_a.inv_mass;
-            inv_mass_matrix.rows[2][2] = body_a.inv_mass;
+            let inv_mass_a = body_a.inv_mass_world();
+            inv_mass_matrix.rows[0][0] = inv_mass_a.x;
+            inv_mass_matrix.rows[1][1] = inv_mass_a.y;
+            inv_mass_matrix.rows[2][2] = inv_mass_a.z;
 
             let inv_intertia_a = body_a.inv_intertia_tensor_world();
             for i in 0..3 {
@@ -251,9 +990,10 @@ impl ConstraintConfig {
 
         {
             let body_b = bodies.get_body(self.handle_b);
-            inv_mass_matrix.rows[6][6] = body_b.inv_mass;
-            inv_mass_matrix.rows[7][7] = body_b.inv_mass;
-            inv_mass_matrix.rows[8][8] = body_b.inv_mass;
+            let inv_mass_b = body_b.inv_mass_world();
+            inv_mass_matrix.rows[6][6] = inv_mass_b.x;
+            inv_mass_matrix.rows[7][7] = inv_mass_b.y;
+            inv_mass_matrix.rows[8][8] = inv_mass_b.z;
 
             let inv_intertia_b = body_b.inv_intertia_tensor_world();
             for i in 0..3 {
@@ -296,7 +1036,27 @@ impl ConstraintConfig {
         q_dt
     }
 
-    fn apply_impulses(&self, bodies: &mut BodyArena, impulses: VecN<12>) {
+    // the impulses of a single jacobian row, body_b gets linear and angular_b and body_a their
+    // negatives
+    fn apply_impulses_linear_angular(
+        &mut self,
+        bodies: &mut BodyArena,
+        linear: Vec3,
+        angular_a: Vec3,
+        angular_b: Vec3,
+    ) {
+        let body_a = bodies.get_body_mut(self.handle_a);
+        body_a.apply_impulse_linear(-linear);
+        body_a.apply_impulse_angular(-angular_a);
+
+        let body_b = bodies.get_body_mut(self.handle_b);
+        body_b.apply_impulse_linear(linear);
+        body_b.apply_impulse_angular(angular_b);
+        self.linear_impulse += linear;
+        self.angular_impulse += angular_b;
+    }
+
+    fn apply_impulses(&mut self, bodies: &mut BodyArena, impulses: VecN<12>) {
         {
             let force_internal_a = Vec3::from_slice(&impulses[0..]);
             let torque_internal_a = Vec3::from_slice(&impulses[3..]);
@@ -311,6 +1071,40 @@ impl ConstraintConfig {
             let body_b = bodies.get_body_mut(self.handle_b);
             body_b.apply_impulse_linear(force_internal_b);
             body_b.apply_impulse_angular(torque_internal_b);
+            self.linear_impulse += force_internal_b;
+            self.angular_impulse += torque_internal_b;
         }
     }
+
+    // solves a motor row on its own so clamping it to the motor's strength can't throw off the
+    // rows holding the joint together, which would otherwise be solved expecting all of it
+    fn solve_motor_row(
+        &mut self,
+        bodies: &mut BodyArena,
+        row: VecN<12>,
+        target_velocity: f32,
+        cached_lambda: &mut f32,
+        max_impulse: f32,
+    ) {
+        let effective_mass = row.dot(&(self.get_inverse_mass_matrix(bodies) * row));
+        if effective_mass <= 0.0 {
+            return;
+        }
+        let lambda = (target_velocity - row.dot(&self.get_velocities(bodies))) / effective_mass;
+        let total = (*cached_lambda + lambda).clamp(-max_impulse, max_impulse);
+        self.apply_impulses(bodies, row * (total - *cached_lambda));
+        *cached_lambda = total;
+    }
+
+    // the force and torque the constraint applied to body_b over a step of dt_sec
+    fn applied_force_torque(&self, bodies: &BodyArena, dt_sec: f32) -> (f32, f32) {
+        let body_b = bodies.get_body(self.handle_b);
+        let rb = body_b.local_to_world(self.anchor_b) - body_b.centre_of_mass_world();
+        // the angular impulse is about the centre of mass, move it to the anchor
+        let torque_impulse = self.angular_impulse - rb.cross(self.linear_impulse);
+        (
+            self.linear_impulse.length() / dt_sec,
+            torque_impulse.length() / dt_sec,
+        )
+    }
 }
@@ -1,7 +1,186 @@
-use crate::body::BodyHandle;
+use crate::body::{Body, BodyHandle, CombineRule, CombineRules};
 use glam::Vec3;
 
+// the order the resting contacts are solved in. ballistic contacts are always stepped to in time
+// of impact order whichever is picked, resolving a later one first would carry the bodies past the
+// earlier hits
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ContactOrder {
+    // solve the resting contacts in the order the narrowphase found them
+    #[default]
+    Found,
+    // solve the deepest penetration first
+    DeepestFirst,
+    // solve in a random order each step
+    Random,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CollisionMode {
+    // moving bodies are swept and the step is advanced to each time of impact in turn, unless a
+    // body turns it off with ccd_enabled
+    #[default]
+    Continuous,
+    // every pair is tested where it is, with speculative contacts for the gaps that could close
+    // this step. the solver keeps them all apart together, which is cheaper and steadier for
+    // stacks but lets small fast bodies tunnel through thin ones
+    Speculative,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum CollisionEventKind {
+    // two bodies hit each other this step
+    Impact,
+    // two bodies started touching this step
+    Started,
+    // two bodies are still touching, see PhysicsScene::persisted_event_interval
+    Persisted,
+    // two bodies stopped touching this step
+    Stopped,
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionEvent {
+    pub kind: CollisionEventKind,
+    pub handle_a: BodyHandle,
+    pub handle_b: BodyHandle,
+    pub point: Vec3,
+    pub normal: Vec3, // points towards body a
+    pub impulse: f32, // magnitude of the normal impulse that resolved the hit
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum TriggerEventKind {
+    // a body started overlapping a sensor this step
+    TriggerEnter,
+    // a body stopped overlapping a sensor this step
+    TriggerExit,
+}
+
+// a change in what's overlapping a sensor. when both bodies are sensors the sensor is body a of
+// the pair
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriggerEvent {
+    pub kind: TriggerEventKind,
+    pub sensor: BodyHandle,
+    pub other: BodyHandle,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyVelocity {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+
+impl BodyVelocity {
+    pub fn from_body(body: &Body) -> Self {
+        Self {
+            linear: body.linear_velocity,
+            angular: body.angular_velocity,
+        }
+    }
+}
+
+// how a pair of bodies was resolved during a step, for debugging
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairSolveTrace {
+    pub handle_a: BodyHandle,
+    pub handle_b: BodyHandle,
+    pub normal: Vec3, // points towards body a
+    // the impulse along the normal, positive when pushing the bodies apart
+    pub normal_impulse: f32,
+    // magnitude of the tangential impulse
+    pub friction_impulse: f32,
+    pub pre_velocity_a: BodyVelocity,
+    pub pre_velocity_b: BodyVelocity,
+    pub post_velocity_a: BodyVelocity,
+    pub post_velocity_b: BodyVelocity,
+}
+
+impl PairSolveTrace {
+    // the same trace seen from body b, the normal still points towards body a
+    pub fn flipped(&self) -> Self {
+        PairSolveTrace {
+            handle_a: self.handle_b,
+            handle_b: self.handle_a,
+            normal: -self.normal,
+            pre_velocity_a: self.pre_velocity_b,
+            pre_velocity_b: self.pre_velocity_a,
+            post_velocity_a: self.post_velocity_b,
+            post_velocity_b: self.post_velocity_a,
+            ..*self
+        }
+    }
+}
+
+// the surface properties used to resolve a single contact
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ContactMaterial {
+    pub friction: f32,
+    pub elasticity: f32,
+    // limit the torque resisting the bodies rolling and spinning on each other, see
+    // Body::rolling_friction
+    pub rolling_friction: f32,
+    pub spinning_friction: f32,
+    // false leaves the bodies to pass through each other at this contact for the step, like the
+    // underside of a one way platform. it's still reported as touching
+    pub enabled: bool,
+    // replaces the contact's normal when solving, it points towards body a like Contact::normal
+    pub normal: Option<Vec3>,
+    // the velocity of body a relative to body b that friction drags them towards instead of
+    // resting on each other, in world space. like a box carried along by a conveyor belt
+    pub surface_velocity: Vec3,
+}
+
+impl ContactMaterial {
+    pub fn from_bodies(body_a: &Body, body_b: &Body, rules: CombineRules) -> Self {
+        let friction = CombineRule::pick(
+            rules.friction,
+            body_a.friction_combine,
+            body_b.friction_combine,
+        );
+        let elasticity = CombineRule::pick(
+            rules.elasticity,
+            body_a.elasticity_combine,
+            body_b.elasticity_combine,
+        );
+        Self {
+            friction: friction.combine(body_a.friction, body_b.friction),
+            elasticity: elasticity.combine(body_a.elasticity, body_b.elasticity),
+            rolling_friction: friction.combine(body_a.rolling_friction, body_b.rolling_friction),
+            spinning_friction: friction.combine(body_a.spinning_friction, body_b.spinning_friction),
+            enabled: true,
+            normal: None,
+            // friction carries each body along the other's surface
+            surface_velocity: body_b.orientation * body_b.surface_velocity
+                - body_a.orientation * body_a.surface_velocity,
+        }
+    }
+
+    // turns the contact off when it's on the pass through side of a one way platform, the normal
+    // points towards body a
+    pub fn apply_one_way(&mut self, body_a: &Body, body_b: &Body, normal: Vec3) {
+        if let Some(platform) = &body_a.one_way {
+            self.enabled &= platform.blocks(body_a, -normal);
+        }
+        if let Some(platform) = &body_b.one_way {
+            self.enabled &= platform.blocks(body_b, normal);
+        }
+    }
+}
+
+// lets gameplay code change the material of a contact before it's resolved, it's called for
+// every contact each step after they've been found and before they're solved
+pub type ModifyContact = Box<dyn FnMut(&mut ContactMaterial, &Contact) + Send + Sync>;
+
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Contact {
     pub world_point_a: Vec3,
     pub world_point_b: Vec3,
@@ -16,7 +195,53 @@ pub struct Contact {
     pub handle_b: BodyHandle,
 }
 
+impl Contact {
+    pub fn handles(&self) -> (BodyHandle, BodyHandle) {
+        (self.handle_a, self.handle_b)
+    }
+
+    // the same contact seen from body b, the normal still points towards body a
+    pub fn flipped(&self) -> Self {
+        Contact {
+            world_point_a: self.world_point_b,
+            world_point_b: self.world_point_a,
+            local_point_a: self.local_point_b,
+            local_point_b: self.local_point_a,
+            normal: -self.normal,
+            handle_a: self.handle_b,
+            handle_b: self.handle_a,
+            ..*self
+        }
+    }
+}
+
+fn compare_time_of_impact(a: &Contact, b: &Contact) -> std::cmp::Ordering {
+    // TODO: fix lint?
+    #[allow(clippy::float_cmp)]
+    if a.time_of_impact < b.time_of_impact {
+        std::cmp::Ordering::Less
+    } else if a.time_of_impact == b.time_of_impact {
+        std::cmp::Ordering::Equal
+    } else {
+        std::cmp::Ordering::Greater
+    }
+}
+
+// orders things that otherwise sort the same by the bodies they're between. only with the
+// determinism feature, without it ties are left in whatever order the sort leaves them
+pub(crate) fn compare_handles(
+    a: (BodyHandle, BodyHandle),
+    b: (BodyHandle, BodyHandle),
+) -> std::cmp::Ordering {
+    if cfg!(feature = "determinism") {
+        (a.0 .0, a.1 .0).cmp(&(b.0 .0, b.1 .0))
+    } else {
+        std::cmp::Ordering::Equal
+    }
+}
+
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContactArena {
     contacts: Vec<Contact>,
 }
@@ -26,30 +251,25 @@ impl ContactArena {
         self.contacts.clear();
     }
 
-    pub fn clear_with_capacity(&mut self, capacity: usize) {
-        self.contacts.clear();
-        self.contacts.reserve(capacity);
-    }
-
     pub fn push(&mut self, contact: Contact) {
         self.contacts.push(contact);
     }
 
     pub fn sort(&mut self) {
-        self.contacts.sort_unstable_by(|a, b| {
-            // TODO: fix lint?
-            #[allow(clippy::float_cmp)]
-            if a.time_of_impact < b.time_of_impact {
-                std::cmp::Ordering::Less
-            } else if a.time_of_impact == b.time_of_impact {
-                std::cmp::Ordering::Equal
-            } else {
-                std::cmp::Ordering::Greater
-            }
+        #[cfg(not(feature = "determinism"))]
+        self.contacts.sort_unstable_by(compare_time_of_impact);
+        // a stable sort keeps a pair's contacts in the order they were found
+        #[cfg(feature = "determinism")]
+        self.contacts.sort_by(|a, b| {
+            compare_time_of_impact(a, b).then_with(|| compare_handles(a.handles(), b.handles()))
         });
     }
 
     pub fn iter(&self) -> core::slice::Iter<Contact> {
         self.contacts.iter()
     }
+
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, Contact> {
+        self.contacts.iter_mut()
+    }
 }
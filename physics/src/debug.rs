@@ -0,0 +1,355 @@
+// line segments for drawing what the simulation sees, for debugging penetration and joints. the
+// renderer draws each category in its own color
+use crate::{
+    bounds::Bounds,
+    scene::PhysicsScene,
+    shapes::{build_convex_hull, Shape},
+};
+use glam::{Quat, Vec3};
+
+// how many segments circles are drawn with
+const CIRCLE_SEGMENTS: usize = 16;
+// how far either side of its position a plane is drawn, they go on forever
+const PLANE_HALF_SIZE: f32 = 10.0;
+
+// which categories debug_lines() draws
+#[derive(Copy, Clone, Debug)]
+pub struct DebugCategories {
+    pub colliders: bool,
+    // the bounds the broadphase sweeps each body through
+    pub aabbs: bool,
+    // the contact points and their normals
+    pub contacts: bool,
+    // each joint's anchors and the line between them
+    pub constraints: bool,
+    // the step size the aabbs are swept over, 0.0 draws the bodies' bounds where they are
+    pub aabb_dt: f32,
+    // the length of the contact normals
+    pub normal_length: f32,
+}
+
+impl Default for DebugCategories {
+    fn default() -> Self {
+        Self {
+            colliders: true,
+            aabbs: false,
+            contacts: true,
+            constraints: true,
+            aabb_dt: 0.0,
+            normal_length: 0.25,
+        }
+    }
+}
+
+// world space segments, as (start, end)
+#[derive(Clone, Debug, Default)]
+pub struct DebugLines {
+    pub colliders: Vec<(Vec3, Vec3)>,
+    pub aabbs: Vec<(Vec3, Vec3)>,
+    pub contacts: Vec<(Vec3, Vec3)>,
+    pub constraints: Vec<(Vec3, Vec3)>,
+}
+
+pub fn debug_lines(scene: &PhysicsScene, categories: &DebugCategories) -> DebugLines {
+    let mut lines = DebugLines::default();
+
+    if categories.colliders || categories.aabbs {
+        for handle in scene.iter_body_handles() {
+            let body = scene.bodies.get_body(*handle);
+            if categories.colliders {
+                for (part, _, _) in body.collision_parts() {
+                    shape_lines(
+                        &mut lines.colliders,
+                        &part.shape,
+                        part.position,
+                        part.orientation,
+                    );
+                }
+            }
+            if categories.aabbs {
+                let (mins, maxs) = body.swept_aabb_over(categories.aabb_dt);
+                box_lines(
+                    &mut lines.aabbs,
+                    &Bounds { mins, maxs },
+                    Vec3::ZERO,
+                    Quat::IDENTITY,
+                );
+            }
+        }
+    }
+
+    if categories.contacts {
+        for contact in scene.iter_contacts() {
+            cross_lines(&mut lines.contacts, contact.world_point_a, 0.05);
+            cross_lines(&mut lines.contacts, contact.world_point_b, 0.05);
+            lines.contacts.push((
+                contact.world_point_b,
+                contact.world_point_b + contact.normal * categories.normal_length,
+            ));
+        }
+    }
+
+    if categories.constraints {
+        for config in scene.constraints().iter_enabled_configs() {
+            let anchor_a = scene
+                .bodies
+                .get_body(config.handle_a)
+                .local_to_world(config.anchor_a);
+            cross_lines(&mut lines.constraints, anchor_a, 0.1);
+            if config.handle_b != config.handle_a {
+                let anchor_b = scene
+                    .bodies
+                    .get_body(config.handle_b)
+                    .local_to_world(config.anchor_b);
+                cross_lines(&mut lines.constraints, anchor_b, 0.1);
+                lines.constraints.push((anchor_a, anchor_b));
+            }
+        }
+    }
+
+    lines
+}
+
+// three short lines crossing at the point
+fn cross_lines(lines: &mut Vec<(Vec3, Vec3)>, point: Vec3, size: f32) {
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        lines.push((point - axis * size, point + axis * size));
+    }
+}
+
+// the 12 edges of the bounds, placed in the world
+fn box_lines(lines: &mut Vec<(Vec3, Vec3)>, bounds: &Bounds, position: Vec3, orientation: Quat) {
+    let (mins, maxs) = (bounds.mins, bounds.maxs);
+    let corner = |i: usize| {
+        let pick = |bit: usize, min: f32, max: f32| if i & bit == 0 { min } else { max };
+        let local = Vec3::new(
+            pick(1, mins.x, maxs.x),
+            pick(2, mins.y, maxs.y),
+            pick(4, mins.z, maxs.z),
+        );
+        position + orientation * local
+    };
+    // corners one bit apart share an edge
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                lines.push((corner(i), corner(i | bit)));
+            }
+        }
+    }
+}
+
+// a circle of the radius around the axis through the centre
+fn circle_lines(lines: &mut Vec<(Vec3, Vec3)>, centre: Vec3, axis: Vec3, radius: f32) {
+    let (u, v) = axis.any_orthonormal_pair();
+    let point = |i: usize| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+        centre + (u * angle.cos() + v * angle.sin()) * radius
+    };
+    for i in 0..CIRCLE_SEGMENTS {
+        lines.push((point(i), point(i + 1)));
+    }
+}
+
+// rings around the ends of a shape along the local y axis, joined by lines down its sides.
+// a radius of 0.0 at the top draws a cone
+fn round_sides_lines(
+    lines: &mut Vec<(Vec3, Vec3)>,
+    half_height: f32,
+    (bottom_radius, top_radius): (f32, f32),
+    position: Vec3,
+    orientation: Quat,
+) {
+    let up = orientation * Vec3::Y;
+    let bottom = position - up * half_height;
+    let top = position + up * half_height;
+    circle_lines(lines, bottom, up, bottom_radius);
+    if top_radius > 0.0 {
+        circle_lines(lines, top, up, top_radius);
+    }
+    for side in [Vec3::X, Vec3::Z, -Vec3::X, -Vec3::Z] {
+        let side = orientation * side;
+        lines.push((bottom + side * bottom_radius, top + side * top_radius));
+    }
+}
+
+fn shape_lines(lines: &mut Vec<(Vec3, Vec3)>, shape: &Shape, position: Vec3, orientation: Quat) {
+    let to_world = |point: Vec3| position + orientation * point;
+    match shape {
+        Shape::Sphere(sphere) => {
+            for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                circle_lines(lines, position, orientation * axis, sphere.radius);
+            }
+        }
+        Shape::Box(data) => box_lines(lines, &data.bounds, position, orientation),
+        Shape::Capsule(capsule) => {
+            let radius = capsule.radius;
+            round_sides_lines(
+                lines,
+                capsule.half_height,
+                (radius, radius),
+                position,
+                orientation,
+            );
+            // the caps' outlines
+            let up = orientation * Vec3::Y;
+            for end in [-capsule.half_height, capsule.half_height] {
+                let centre = position + up * end;
+                circle_lines(lines, centre, orientation * Vec3::X, radius);
+                circle_lines(lines, centre, orientation * Vec3::Z, radius);
+            }
+        }
+        Shape::Cylinder(cylinder) => round_sides_lines(
+            lines,
+            cylinder.half_height,
+            (cylinder.radius, cylinder.radius),
+            position,
+            orientation,
+        ),
+        Shape::Cone(cone) => round_sides_lines(
+            lines,
+            cone.half_height,
+            (cone.radius, 0.0),
+            position,
+            orientation,
+        ),
+        Shape::Convex(convex) => {
+            // the hull's triangles aren't kept, so they're built again
+            let mut points = Vec::new();
+            let mut triangles = Vec::new();
+            build_convex_hull(convex.points(), &mut points, &mut triangles);
+            let mut edges = Vec::new();
+            for tri in &triangles {
+                for &(a, b) in &[(tri.a, tri.b), (tri.b, tri.c), (tri.c, tri.a)] {
+                    edges.push((a.min(b), a.max(b)));
+                }
+            }
+            edges.sort_unstable();
+            edges.dedup();
+            for (a, b) in edges {
+                lines.push((to_world(points[a as usize]), to_world(points[b as usize])));
+            }
+        }
+        Shape::TriMesh(trimesh) => {
+            for index in 0..trimesh.triangles().len() {
+                let [a, b, c] = trimesh.triangle(index);
+                let (a, b, c) = (to_world(a), to_world(b), to_world(c));
+                lines.extend_from_slice(&[(a, b), (b, c), (c, a)]);
+            }
+        }
+        Shape::Heightfield(heightfield) => {
+            let (columns, rows) = (heightfield.columns(), heightfield.rows());
+            for row in 0..rows {
+                for column in 0..columns {
+                    let point = to_world(heightfield.point(column, row));
+                    if column + 1 < columns {
+                        lines.push((point, to_world(heightfield.point(column + 1, row))));
+                    }
+                    if row + 1 < rows {
+                        lines.push((point, to_world(heightfield.point(column, row + 1))));
+                    }
+                }
+            }
+        }
+        Shape::Compound(compound) => {
+            for ((child_position, child_orientation), child) in compound.children() {
+                shape_lines(
+                    lines,
+                    child,
+                    to_world(*child_position),
+                    orientation * *child_orientation,
+                );
+            }
+        }
+        Shape::Plane(_) => {
+            // a grid over the middle of the plane, its normal is the local y axis
+            let steps = 4;
+            for i in -steps..=steps {
+                let offset = PLANE_HALF_SIZE * i as f32 / steps as f32;
+                let (x, z) = (Vec3::X, Vec3::Z);
+                lines.push((
+                    to_world(x * offset - z * PLANE_HALF_SIZE),
+                    to_world(x * offset + z * PLANE_HALF_SIZE),
+                ));
+                lines.push((
+                    to_world(z * offset - x * PLANE_HALF_SIZE),
+                    to_world(z * offset + x * PLANE_HALF_SIZE),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::level::{Level, LevelBody, LevelConstraint, LevelShape};
+
+    fn level_body(shape: LevelShape, position: Vec3, inv_mass: f32) -> LevelBody {
+        LevelBody {
+            shape,
+            position,
+            orientation: Quat::IDENTITY,
+            linear_velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            inv_mass,
+            elasticity: 0.5,
+            friction: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_debug_lines() {
+        // a unit cube resting on the ground, tied to a ball above it
+        let ground = LevelShape::Box {
+            mins: Vec3::new(-5.0, -1.0, -5.0),
+            maxs: Vec3::new(5.0, 0.0, 5.0),
+        };
+        let cube = LevelShape::Box {
+            mins: Vec3::splat(-0.5),
+            maxs: Vec3::splat(0.5),
+        };
+        let ball = LevelShape::Sphere { radius: 0.5 };
+        let mut scene = PhysicsScene::from_level(&Level {
+            bodies: vec![
+                level_body(ground, Vec3::ZERO, 0.0),
+                level_body(cube, Vec3::new(0.0, 0.49, 0.0), 1.0),
+                level_body(ball, Vec3::new(0.0, 2.0, 0.0), 1.0),
+            ],
+            constraints: vec![LevelConstraint::Distance {
+                body_a: 1,
+                body_b: 2,
+                anchor_a: Vec3::new(0.0, 0.5, 0.0),
+                anchor_b: Vec3::new(0.0, -0.5, 0.0),
+            }],
+        });
+        scene.update(1.0 / 60.0);
+
+        let lines = debug_lines(&scene, &DebugCategories::default());
+        // two boxes and a sphere's three circles
+        assert_eq!(12 * 2 + 3 * CIRCLE_SEGMENTS, lines.colliders.len());
+        assert!(lines.aabbs.is_empty());
+        assert!(!lines.contacts.is_empty());
+        // both anchors and the line between them
+        assert_eq!(7, lines.constraints.len());
+
+        // the cube's edges are all a unit long
+        let cube_edges = &lines.colliders[12..24];
+        assert!(cube_edges
+            .iter()
+            .all(|(start, end)| ((*end - *start).length() - 1.0).abs() < 1e-4));
+
+        let lines = debug_lines(
+            &scene,
+            &DebugCategories {
+                colliders: false,
+                aabbs: true,
+                contacts: false,
+                constraints: false,
+                ..DebugCategories::default()
+            },
+        );
+        assert!(lines.colliders.is_empty());
+        assert_eq!(12 * 3, lines.aabbs.len());
+    }
+}
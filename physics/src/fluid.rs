@@ -0,0 +1,223 @@
+// a region of water or other fluid. the bodies in it are pushed up by the weight of the fluid
+// they displace and slowed by its drag, the scene applies its fluid volumes every step along with
+// gravity
+use crate::{
+    body::{Body, BodyArena},
+    bounds::Bounds,
+    query::contains_point,
+    shapes::Shape,
+};
+use glam::{Quat, Vec3};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct FluidHandle(pub u32);
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum FluidRegion {
+    Aabb(Bounds),
+    // convex, concave shapes are filled in to their hull
+    Shape {
+        shape: Shape,
+        position: Vec3,
+        orientation: Quat,
+    },
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct FluidVolume {
+    pub region: FluidRegion,
+    // mass per unit of volume, water is about 1000.0 with bodies measured in metres and kilograms
+    pub density: f32,
+    // like Body::apply_damping, scaled by how much of the body is submerged
+    pub linear_drag: f32,
+    pub angular_drag: f32,
+}
+
+// the bodies' bounds are split into this many points along each axis to find how much of them is
+// submerged
+const SAMPLES_PER_AXIS: usize = 4;
+
+impl FluidVolume {
+    pub fn new(region: FluidRegion, density: f32) -> Self {
+        Self {
+            region,
+            density,
+            linear_drag: 1.0,
+            angular_drag: 1.0,
+        }
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        match &self.region {
+            FluidRegion::Aabb(bounds) => {
+                bounds.mins.cmple(point).all() && point.cmple(bounds.maxs).all()
+            }
+            FluidRegion::Shape {
+                shape,
+                position,
+                orientation,
+            } => {
+                let placed = Body {
+                    position: *position,
+                    orientation: *orientation,
+                    shape: shape.clone(),
+                    ..Body::default()
+                };
+                contains_point(&placed, point)
+            }
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        match &self.region {
+            FluidRegion::Aabb(bounds) => *bounds,
+            FluidRegion::Shape {
+                shape,
+                position,
+                orientation,
+            } => shape.bounds(*position, *orientation),
+        }
+    }
+
+    // the fraction of the body's shape inside the fluid and the middle of that part, sampled on a
+    // grid over the body's bounds
+    pub fn submerged(&self, body: &Body) -> Option<(f32, Vec3)> {
+        let bounds = body.shape.bounds(body.position, body.orientation);
+        if !self.bounds().does_intersect(&bounds) {
+            return None;
+        }
+
+        let step = (bounds.maxs - bounds.mins) / SAMPLES_PER_AXIS as f32;
+        let mut inside_body = 0;
+        let mut inside_both = 0;
+        let mut centre = Vec3::ZERO;
+        for i in 0..SAMPLES_PER_AXIS.pow(3) {
+            let cell = Vec3::new(
+                (i % SAMPLES_PER_AXIS) as f32,
+                (i / SAMPLES_PER_AXIS % SAMPLES_PER_AXIS) as f32,
+                (i / (SAMPLES_PER_AXIS * SAMPLES_PER_AXIS)) as f32,
+            );
+            let point = bounds.mins + (cell + Vec3::splat(0.5)) * step;
+            if !contains_point(body, point) {
+                continue;
+            }
+            inside_body += 1;
+            if self.contains(point) {
+                inside_both += 1;
+                centre += point;
+            }
+        }
+        if inside_both == 0 {
+            return None;
+        }
+        Some((
+            inside_both as f32 / inside_body as f32,
+            centre / inside_both as f32,
+        ))
+    }
+
+    // pushes the awake dynamic bodies in the fluid against gravity and drags them
+    pub fn step(&self, bodies: &mut BodyArena, gravity: Vec3, delta_seconds: f32) {
+        for body in bodies.iter_mut() {
+            if body.has_infinite_mass() || body.sleeping || body.is_sensor {
+                continue;
+            }
+            let (fraction, centre) = match self.submerged(body) {
+                Some(submerged) => submerged,
+                None => continue,
+            };
+
+            // the displaced fluid's weight acts at its centre, which rights bodies that tip over
+            let displaced_mass = self.density * body.shape.volume() * fraction;
+            body.apply_impulse(centre, -gravity * displaced_mass * delta_seconds);
+
+            body.linear_velocity /= 1.0 + self.linear_drag * fraction * delta_seconds;
+            body.angular_velocity /= 1.0 + self.angular_drag * fraction * delta_seconds;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scene::PhysicsScene, scene_shapes::make_box_ground};
+
+    fn make_pool(scene: &mut PhysicsScene) -> FluidHandle {
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        scene.add_fluid_volume(FluidVolume::new(
+            FluidRegion::Aabb(Bounds {
+                mins: Vec3::new(-10.0, 0.0, -10.0),
+                maxs: Vec3::new(10.0, 5.0, 10.0),
+            }),
+            1000.0,
+        ))
+    }
+
+    fn step(scene: &mut PhysicsScene, seconds: f32) {
+        for _ in 0..(seconds * 60.0) as u32 {
+            scene.update(1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn test_fluid_floats_light_body() {
+        // half as dense as the water, a 1m cube floats half submerged
+        let mut scene = PhysicsScene::empty();
+        make_pool(&mut scene);
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            inv_mass: 1.0 / 500.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        });
+        step(&mut scene, 10.0);
+
+        let body = scene.get_body(handle).unwrap();
+        assert!((body.position.y - 5.0).abs() < 0.15, "{}", body.position);
+        assert!(body.linear_velocity.length() < 0.1);
+    }
+
+    #[test]
+    fn test_fluid_sinks_heavy_body() {
+        let mut scene = PhysicsScene::empty();
+        make_pool(&mut scene);
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            inv_mass: 1.0 / 2000.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        });
+        step(&mut scene, 5.0);
+
+        // resting on the bottom
+        let body = scene.get_body(handle).unwrap();
+        assert!(body.position.y < 1.0, "{}", body.position);
+    }
+
+    #[test]
+    fn test_fluid_submerged_fraction() {
+        let fluid = FluidVolume::new(
+            FluidRegion::Shape {
+                shape: Shape::make_box_from_half_extents(Vec3::splat(1.0)),
+                position: Vec3::ZERO,
+                orientation: Quat::IDENTITY,
+            },
+            1000.0,
+        );
+        let at = |y: f32| Body {
+            position: Vec3::new(0.0, y, 0.0),
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        };
+        assert_eq!(1.0, fluid.submerged(&at(0.0)).unwrap().0);
+        assert_eq!(0.5, fluid.submerged(&at(1.0)).unwrap().0);
+        assert!(fluid.submerged(&at(2.0)).is_none());
+    }
+}
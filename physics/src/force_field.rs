@@ -0,0 +1,142 @@
+// pushes every awake dynamic body like gravity does, the scene applies its force fields every step
+// along with gravity. a body leaves them out with Body::force_fields_enabled
+use crate::{body::Body, query::Falloff};
+use glam::Vec3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForceFieldHandle(pub u32);
+
+// the fields accelerate the bodies, so light and heavy bodies move the same like under gravity
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForceField {
+    // the same everywhere, like a steady wind
+    Uniform {
+        acceleration: Vec3,
+    },
+    // towards the centre, negative strengths push away from it
+    Radial {
+        center: Vec3,
+        radius: f32,
+        strength: f32,
+        falloff: Falloff,
+    },
+    // around the axis through the centre, anticlockwise seen from where the axis points
+    Vortex {
+        center: Vec3,
+        axis: Vec3,
+        radius: f32,
+        strength: f32,
+        falloff: Falloff,
+    },
+}
+
+impl ForceField {
+    // the acceleration of a body at the point, measured from its centre of mass
+    pub fn acceleration_at(&self, point: Vec3) -> Vec3 {
+        match *self {
+            ForceField::Uniform { acceleration } => acceleration,
+            ForceField::Radial {
+                center,
+                radius,
+                strength,
+                falloff,
+            } => {
+                let offset = center - point;
+                let distance = offset.length();
+                offset.normalize_or_zero() * strength * falloff.scale(distance, radius)
+            }
+            ForceField::Vortex {
+                center,
+                axis,
+                radius,
+                strength,
+                falloff,
+            } => {
+                let axis = axis.normalize_or_zero();
+                // measured from the axis, not the centre
+                let offset = point - center;
+                let offset = offset - axis * axis.dot(offset);
+                let distance = offset.length();
+                axis.cross(offset).normalize_or_zero() * strength * falloff.scale(distance, radius)
+            }
+        }
+    }
+
+    pub fn apply(&self, body: &mut Body, delta_seconds: f32) {
+        if body.has_infinite_mass() || body.sleeping || !body.force_fields_enabled {
+            return;
+        }
+        let acceleration = self.acceleration_at(body.centre_of_mass_world());
+        body.apply_impulse_linear(acceleration * body.inv_mass.recip() * delta_seconds);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scene::PhysicsScene, scene_shapes::make_sphere};
+
+    #[test]
+    fn test_force_field_uniform() {
+        let mut scene = PhysicsScene::empty();
+        scene.set_gravity(Vec3::ZERO);
+        scene.add_force_field(ForceField::Uniform {
+            acceleration: Vec3::new(2.0, 0.0, 0.0),
+        });
+        let mut add_sphere = |z: f32, inv_mass: f32, force_fields_enabled: bool| {
+            scene.add_body(Body {
+                position: Vec3::new(0.0, 0.0, z),
+                inv_mass,
+                shape: make_sphere(0.5),
+                force_fields_enabled,
+                ..Body::default()
+            })
+        };
+        let light = add_sphere(0.0, 1.0, true);
+        let heavy = add_sphere(2.0, 0.1, true);
+        let opted_out = add_sphere(4.0, 1.0, false);
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+
+        for handle in [light, heavy] {
+            let body = scene.get_body(handle).unwrap();
+            assert!((body.linear_velocity - Vec3::new(2.0, 0.0, 0.0)).length() < 0.01);
+        }
+        assert_eq!(
+            Vec3::ZERO,
+            scene.get_body(opted_out).unwrap().linear_velocity
+        );
+    }
+
+    #[test]
+    fn test_force_field_radial_and_vortex() {
+        let radial = ForceField::Radial {
+            center: Vec3::ZERO,
+            radius: 4.0,
+            strength: 8.0,
+            falloff: Falloff::Linear,
+        };
+        assert_eq!(
+            Vec3::new(-6.0, 0.0, 0.0),
+            radial.acceleration_at(Vec3::new(1.0, 0.0, 0.0))
+        );
+        assert_eq!(Vec3::ZERO, radial.acceleration_at(Vec3::new(0.0, 5.0, 0.0)));
+
+        let vortex = ForceField::Vortex {
+            center: Vec3::ZERO,
+            axis: Vec3::Y,
+            radius: 4.0,
+            strength: 2.0,
+            falloff: Falloff::Constant,
+        };
+        // only the distance from the axis counts
+        assert_eq!(
+            Vec3::new(0.0, 0.0, -2.0),
+            vortex.acceleration_at(Vec3::new(1.0, 3.0, 0.0))
+        );
+        assert_eq!(Vec3::ZERO, vortex.acceleration_at(Vec3::new(0.0, 1.0, 0.0)));
+    }
+}
@@ -199,20 +199,29 @@ fn signed_volume_3d(s1: Vec3, s2: Vec3, s3: Vec3, s4: Vec3) -> Vec4 {
     }
 }
 
-fn support(body_a: &Body, body_b: &Body, dir: Vec3, bias: f32) -> Point {
+// anything gjk can run on, a convex set of points in world space
+pub trait SupportMap {
+    // the point furthest in the direction, grown outwards by the bias
+    fn support_point(&self, dir: Vec3, bias: f32) -> Vec3;
+}
+
+impl SupportMap for Body {
+    fn support_point(&self, dir: Vec3, bias: f32) -> Vec3 {
+        self.shape
+            .support(dir, self.position, self.orientation, bias)
+    }
+}
+
+fn support(body_a: &impl SupportMap, body_b: &impl SupportMap, dir: Vec3, bias: f32) -> Point {
     let dir = dir.normalize();
 
     // Find the point in A furthest direction
-    let pt_a = body_a
-        .shape
-        .support(dir, body_a.position, body_a.orientation, bias);
+    let pt_a = body_a.support_point(dir, bias);
 
     let dir = -dir;
 
     // Find the point in B furthest direction
-    let pt_b = body_b
-        .shape
-        .support(dir, body_b.position, body_b.orientation, bias);
+    let pt_b = body_b.support_point(dir, bias);
 
     // Return the point in the minkowski sum, furthest in the direction
     Point {
@@ -315,7 +324,11 @@ fn num_valids(lambdas: &Vec4) -> usize {
     num
 }
 
-pub fn gjk_does_intersect(body_a: &Body, body_b: &Body, bias: f32) -> Option<(Vec3, Vec3)> {
+pub fn gjk_does_intersect(
+    body_a: &impl SupportMap,
+    body_b: &impl SupportMap,
+    bias: f32,
+) -> Option<(Vec3, Vec3)> {
     const ORIGIN: Vec3 = Vec3::ZERO;
 
     let mut num_pts = 1;
@@ -607,8 +620,8 @@ fn find_dangling_edges(dangling_edges: &mut Vec<Edge>, triangles: &[Tri]) {
 }
 
 fn epa_expand(
-    body_a: &Body,
-    body_b: &Body,
+    body_a: &impl SupportMap,
+    body_b: &impl SupportMap,
     bias: f32,
     simplex_points: &[Point; 4],
 ) -> (Vec3, Vec3) {
@@ -727,7 +740,7 @@ fn epa_expand(
     (pt_on_a, pt_on_b)
 }
 
-pub fn gjk_closest_points(body_a: &Body, body_b: &Body) -> (Vec3, Vec3) {
+pub fn gjk_closest_points(body_a: &impl SupportMap, body_b: &impl SupportMap) -> (Vec3, Vec3) {
     let mut closest_dist = f32::MAX;
     const BIAS: f32 = 0.0;
 
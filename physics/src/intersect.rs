@@ -1,10 +1,13 @@
 use crate::{
-    body::{Body, BodyHandle},
-    contact::Contact,
-    gjk::{gjk_closest_points, gjk_does_intersect},
-    shapes::Shape,
+    body::{Body, BodyArena, BodyHandle},
+    broadphase::CollisionPair,
+    contact::{CollisionMode, Contact},
+    gjk::{gjk_closest_points, gjk_does_intersect, SupportMap},
+    shapes::{Shape, ShapeBox, ShapeCapsule},
 };
-use glam::Vec3;
+use glam::{Quat, Vec3};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::borrow::Borrow;
 
 pub fn sphere_sphere_static(
@@ -113,6 +116,705 @@ pub fn sphere_sphere_dynamic(
     Some((pt_on_a, pt_on_b, toi))
 }
 
+// shapes closer than this count as touching, the same as the bias gjk_does_intersect() grows
+// both shapes by
+pub(crate) const CONTACT_MARGIN: f32 = 0.002;
+
+// a box shape placed in the world
+#[derive(Copy, Clone, Debug)]
+struct WorldBox {
+    centre: Vec3,
+    axes: [Vec3; 3],
+    half_extents: Vec3,
+}
+
+impl WorldBox {
+    fn new(body: &Body, shape: &ShapeBox) -> Self {
+        let orientation = body.orientation;
+        Self {
+            centre: body.position + orientation * shape.center_of_mass,
+            axes: [
+                orientation * Vec3::X,
+                orientation * Vec3::Y,
+                orientation * Vec3::Z,
+            ],
+            half_extents: shape.half_extents(),
+        }
+    }
+
+    // half the box's width along the axis
+    fn extent_along(&self, axis: Vec3) -> f32 {
+        (0..3)
+            .map(|i| self.half_extents[i] * self.axes[i].dot(axis).abs())
+            .sum()
+    }
+
+    // the point at the offset from the centre, in the box's axes
+    fn point(&self, offset: Vec3) -> Vec3 {
+        self.centre + self.axes[0] * offset.x + self.axes[1] * offset.y + self.axes[2] * offset.z
+    }
+
+    // the corners of the face pointing along the axis, or against it when sign is negative
+    fn face(&self, axis: usize, sign: f32) -> [Vec3; 4] {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let centre = self.centre + self.axes[axis] * self.half_extents[axis] * sign;
+        let u = self.axes[u] * self.half_extents[u];
+        let v = self.axes[v] * self.half_extents[v];
+        [
+            centre - u - v,
+            centre + u - v,
+            centre + u + v,
+            centre - u + v,
+        ]
+    }
+}
+
+fn sign(value: f32) -> f32 {
+    if value >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+// the closest point on the box to the sphere, the sphere's deepest point towards the box, the
+// normal from the box to the sphere and how far apart they are, negative when they overlap
+fn box_sphere_static(
+    body_box: &Body,
+    shape_box: &ShapeBox,
+    centre: Vec3,
+    radius: f32,
+) -> (Vec3, Vec3, Vec3, f32) {
+    let world_box = WorldBox::new(body_box, shape_box);
+    let d = centre - world_box.centre;
+    let local = Vec3::new(
+        d.dot(world_box.axes[0]),
+        d.dot(world_box.axes[1]),
+        d.dot(world_box.axes[2]),
+    );
+    let half_extents = world_box.half_extents;
+    let clamped = local.max(-half_extents).min(half_extents);
+
+    if clamped != local {
+        let on_box = world_box.point(clamped);
+        let offset = centre - on_box;
+        let distance = offset.length();
+        let normal = offset / distance;
+        (on_box, centre - normal * radius, normal, distance - radius)
+    } else {
+        // the centre is inside the box, push it out through the nearest face
+        let gaps = half_extents - local.abs();
+        let axis = if gaps.x <= gaps.y && gaps.x <= gaps.z {
+            0
+        } else if gaps.y <= gaps.z {
+            1
+        } else {
+            2
+        };
+        let normal = world_box.axes[axis] * sign(local[axis]);
+        let on_box = centre + normal * gaps[axis];
+        (
+            on_box,
+            centre - normal * radius,
+            normal,
+            -(gaps[axis] + radius),
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum BoxAxis {
+    FaceA(usize),
+    FaceB(usize),
+    Edges(usize, usize),
+}
+
+// clips the face of the incident box that faces the reference box's face against it. returns the
+// incident points below the reference face and how deep they are, the normal points out of the
+// reference face
+fn clip_box_faces(
+    reference: &WorldBox,
+    reference_axis: usize,
+    normal: Vec3,
+    incident: &WorldBox,
+) -> Vec<(Vec3, f32)> {
+    // the incident face is the one most against the normal
+    let incident_axis = (0..3)
+        .max_by(|&i, &j| {
+            let dot_i = incident.axes[i].dot(normal).abs();
+            let dot_j = incident.axes[j].dot(normal).abs();
+            dot_i.partial_cmp(&dot_j).unwrap()
+        })
+        .unwrap();
+    let incident_sign = -sign(incident.axes[incident_axis].dot(normal));
+    let mut polygon = incident.face(incident_axis, incident_sign).to_vec();
+
+    // clip against the sides of the reference face
+    for &side in &[(reference_axis + 1) % 3, (reference_axis + 2) % 3] {
+        for &side_sign in &[1.0, -1.0] {
+            let side_normal = reference.axes[side] * side_sign;
+            let offset = side_normal.dot(reference.centre) + reference.half_extents[side];
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+            for (i, &start) in polygon.iter().enumerate() {
+                let end = polygon[(i + 1) % polygon.len()];
+                let start_distance = side_normal.dot(start) - offset;
+                let end_distance = side_normal.dot(end) - offset;
+                if start_distance <= 0.0 {
+                    clipped.push(start);
+                }
+                if (start_distance <= 0.0) != (end_distance <= 0.0) {
+                    let t = start_distance / (start_distance - end_distance);
+                    clipped.push(start + (end - start) * t);
+                }
+            }
+            polygon = clipped;
+            if polygon.is_empty() {
+                return Vec::new();
+            }
+        }
+    }
+
+    let face_offset = normal.dot(reference.centre) + reference.half_extents[reference_axis];
+    polygon
+        .into_iter()
+        .map(|point| (point, face_offset - normal.dot(point)))
+        .filter(|&(_, depth)| depth >= 0.0)
+        .collect()
+}
+
+// the contacts between two overlapping boxes, found with the separating axis test. boxes
+// touching face to face get a contact at each corner of their overlap so they can sit flat,
+// boxes touching edge to edge get one contact between the edges. empty if they don't touch
+fn box_box_static(
+    handle_a: BodyHandle,
+    body_a: &Body,
+    shape_a: &ShapeBox,
+    handle_b: BodyHandle,
+    body_b: &Body,
+    shape_b: &ShapeBox,
+) -> Vec<Contact> {
+    let box_a = WorldBox::new(body_a, shape_a);
+    let box_b = WorldBox::new(body_b, shape_b);
+    let ab = box_b.centre - box_a.centre;
+
+    let mut axes = Vec::with_capacity(15);
+    for i in 0..3 {
+        axes.push((box_a.axes[i], BoxAxis::FaceA(i)));
+        axes.push((box_b.axes[i], BoxAxis::FaceB(i)));
+    }
+    for i in 0..3 {
+        for j in 0..3 {
+            axes.push((box_a.axes[i].cross(box_b.axes[j]), BoxAxis::Edges(i, j)));
+        }
+    }
+
+    // find the axis the boxes overlap the least along, pointing from a to b. edge contacts are
+    // less stable than face ones so they have to be a bit shallower to be picked
+    const EDGE_TOLERANCE: f32 = 0.01;
+    let mut best: Option<(f32, Vec3, BoxAxis)> = None;
+    for (axis, kind) in axes {
+        let length = axis.length();
+        if length < 1e-4 {
+            // the edges are parallel so the face axes cover this
+            continue;
+        }
+        let axis = axis / length;
+        let distance = ab.dot(axis);
+        let overlap = box_a.extent_along(axis) + box_b.extent_along(axis) - distance.abs();
+        if overlap < -CONTACT_MARGIN {
+            return Vec::new();
+        }
+        let tolerance = match kind {
+            BoxAxis::Edges(..) => EDGE_TOLERANCE,
+            _ => 0.0,
+        };
+        if best.map_or(true, |(best_overlap, ..)| {
+            overlap + tolerance < best_overlap
+        }) {
+            best = Some((overlap, axis * sign(distance), kind));
+        }
+    }
+    let (_, axis, kind) = match best {
+        Some(best) => best,
+        None => return Vec::new(),
+    };
+
+    // pairs of points on a and b
+    let points: Vec<(Vec3, Vec3)> = match kind {
+        BoxAxis::FaceA(i) => clip_box_faces(&box_a, i, axis, &box_b)
+            .into_iter()
+            .map(|(point, depth)| (point + axis * depth, point))
+            .collect(),
+        BoxAxis::FaceB(i) => clip_box_faces(&box_b, i, -axis, &box_a)
+            .into_iter()
+            .map(|(point, depth)| (point, point - axis * depth))
+            .collect(),
+        BoxAxis::Edges(i, j) => {
+            // the edges of a and b that are furthest into each other
+            let mut edge_a = box_a.centre;
+            let mut edge_b = box_b.centre;
+            for k in 0..3 {
+                if k != i {
+                    edge_a += box_a.axes[k] * box_a.half_extents[k] * sign(box_a.axes[k].dot(axis));
+                }
+                if k != j {
+                    edge_b -= box_b.axes[k] * box_b.half_extents[k] * sign(box_b.axes[k].dot(axis));
+                }
+            }
+
+            // the closest points between the edges
+            let (dir_a, dir_b) = (box_a.axes[i], box_b.axes[j]);
+            let r = edge_a - edge_b;
+            let b = dir_a.dot(dir_b);
+            let c = dir_a.dot(r);
+            let f = dir_b.dot(r);
+            let denom = 1.0 - b * b;
+            let s = ((b * f - c) / denom)
+                .max(-box_a.half_extents[i])
+                .min(box_a.half_extents[i]);
+            let t = (b * s + f)
+                .max(-box_b.half_extents[j])
+                .min(box_b.half_extents[j]);
+            vec![(edge_a + dir_a * s, edge_b + dir_b * t)]
+        }
+    };
+
+    points
+        .into_iter()
+        .map(|(world_point_a, world_point_b)| Contact {
+            world_point_a,
+            world_point_b,
+            local_point_a: body_a.world_to_local(world_point_a),
+            local_point_b: body_b.world_to_local(world_point_b),
+            normal: -axis,
+            separation_dist: (world_point_b - world_point_a).dot(axis),
+            time_of_impact: 0.0,
+            handle_a,
+            handle_b,
+        })
+        .collect()
+}
+
+// the closest points between the segments start_a-end_a and start_b-end_b
+fn closest_points_segments(
+    (start_a, end_a): (Vec3, Vec3),
+    (start_b, end_b): (Vec3, Vec3),
+) -> (Vec3, Vec3) {
+    const EPSILON: f32 = 1e-6;
+    let dir_a = end_a - start_a;
+    let dir_b = end_b - start_b;
+    let r = start_a - start_b;
+    let length_sq_a = dir_a.length_squared();
+    let length_sq_b = dir_b.length_squared();
+    let f = dir_b.dot(r);
+
+    let (s, t) = if length_sq_a <= EPSILON && length_sq_b <= EPSILON {
+        // both segments are points
+        (0.0, 0.0)
+    } else if length_sq_a <= EPSILON {
+        (0.0, (f / length_sq_b).clamp(0.0, 1.0))
+    } else {
+        let c = dir_a.dot(r);
+        if length_sq_b <= EPSILON {
+            ((-c / length_sq_a).clamp(0.0, 1.0), 0.0)
+        } else {
+            // the closest points on the infinite lines, clamped back onto the segments
+            let b = dir_a.dot(dir_b);
+            let denom = length_sq_a * length_sq_b - b * b;
+            let s = if denom > EPSILON {
+                ((b * f - c * length_sq_b) / denom).clamp(0.0, 1.0)
+            } else {
+                // parallel, any point on a will do
+                0.0
+            };
+            let t = (b * s + f) / length_sq_b;
+            if t < 0.0 {
+                ((-c / length_sq_a).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / length_sq_a).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (start_a + dir_a * s, start_b + dir_b * t)
+}
+
+fn capsule_segment(body: &Body, capsule: &ShapeCapsule) -> (Vec3, Vec3) {
+    capsule.segment(body.position, body.orientation)
+}
+
+// a contact between two shapes that are a segment grown by a radius, capsules and spheres. a
+// sphere is a segment with both ends at its centre
+fn segments_contact(
+    (handle_a, body_a, segment_a, radius_a): (BodyHandle, &Body, (Vec3, Vec3), f32),
+    (handle_b, body_b, segment_b, radius_b): (BodyHandle, &Body, (Vec3, Vec3), f32),
+) -> (Contact, bool) {
+    let (closest_a, closest_b) = closest_points_segments(segment_a, segment_b);
+    let ba = closest_a - closest_b;
+    let distance = ba.length();
+    let normal = if distance > 1e-6 {
+        ba / distance
+    } else {
+        // the segments cross, push them apart across both of them
+        let across = (segment_a.1 - segment_a.0).cross(segment_b.1 - segment_b.0);
+        if across.length_squared() > 1e-12 {
+            across.normalize()
+        } else {
+            Vec3::Y
+        }
+    };
+    points_contact(
+        (handle_a, body_a, closest_a - normal * radius_a),
+        (handle_b, body_b, closest_b + normal * radius_b),
+        normal,
+        distance - radius_a - radius_b,
+    )
+}
+
+// the closest point on the triangle to the point
+fn closest_point_on_triangle(point: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    // work out which of the triangle's corners, edges or face the point is nearest to
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = point - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+// the closest points between a segment and a triangle that it doesn't pass through
+fn closest_points_segment_triangle(
+    (start, end): (Vec3, Vec3),
+    triangle: [Vec3; 3],
+) -> (Vec3, Vec3) {
+    let mut best = (start, closest_point_on_triangle(start, triangle));
+    let mut consider = |on_segment: Vec3, on_triangle: Vec3| {
+        if (on_segment - on_triangle).length_squared() < (best.0 - best.1).length_squared() {
+            best = (on_segment, on_triangle);
+        }
+    };
+    consider(end, closest_point_on_triangle(end, triangle));
+    for i in 0..3 {
+        let edge = (triangle[i], triangle[(i + 1) % 3]);
+        let (on_segment, on_edge) = closest_points_segments((start, end), edge);
+        consider(on_segment, on_edge);
+    }
+    best
+}
+
+// the contact between a triangle and a sphere or capsule, as the points on each, the normal from
+// the triangle to the shape and how far apart they are
+fn segment_triangle_static(
+    (start, end): (Vec3, Vec3),
+    radius: f32,
+    triangle: [Vec3; 3],
+) -> (Vec3, Vec3, Vec3, f32) {
+    let face_normal = (triangle[1] - triangle[0])
+        .cross(triangle[2] - triangle[0])
+        .normalize_or_zero();
+    let start_height = (start - triangle[0]).dot(face_normal);
+    let end_height = (end - triangle[0]).dot(face_normal);
+
+    // a segment passing through the triangle is pushed out the way its middle is, by its end on
+    // the other side
+    if (start_height < 0.0) != (end_height < 0.0) {
+        let t = start_height / (start_height - end_height);
+        let crossing = start + (end - start) * t;
+        if closest_point_on_triangle(crossing, triangle).distance_squared(crossing) < 1e-8 {
+            let side = if start_height + end_height >= 0.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            let normal = face_normal * side;
+            let (deepest, height) = if start_height * side < end_height * side {
+                (start, start_height * side)
+            } else {
+                (end, end_height * side)
+            };
+            return (
+                deepest - normal * height,
+                deepest - normal * radius,
+                normal,
+                height - radius,
+            );
+        }
+    }
+
+    let (on_segment, on_triangle) = closest_points_segment_triangle((start, end), triangle);
+    let offset = on_segment - on_triangle;
+    let distance = offset.length();
+    let normal = if distance > 1e-6 {
+        offset / distance
+    } else if (start_height + end_height) >= 0.0 {
+        face_normal
+    } else {
+        -face_normal
+    };
+    (
+        on_triangle,
+        on_segment - normal * radius,
+        normal,
+        distance - radius,
+    )
+}
+
+// one of a mesh's triangles placed in the world
+pub(crate) struct WorldTriangle(pub(crate) [Vec3; 3]);
+
+impl SupportMap for WorldTriangle {
+    fn support_point(&self, dir: Vec3, bias: f32) -> Vec3 {
+        let corner = self
+            .0
+            .iter()
+            .copied()
+            .max_by(|a, b| dir.dot(*a).partial_cmp(&dir.dot(*b)).unwrap())
+            .unwrap();
+        corner + dir.normalize() * bias
+    }
+}
+
+// the triangles of a mesh or heightfield near the other body, in the mesh's local space. None if
+// the body isn't made of triangles
+fn nearby_triangles(body_mesh: &Body, body_other: &Body) -> Option<Vec<[Vec3; 3]>> {
+    // the other body's bounds placed in the mesh's space
+    let inv_orientation = body_mesh.orientation.conjugate();
+    let local_bounds = || {
+        let mut bounds = body_other.shape.bounds(
+            inv_orientation * (body_other.position - body_mesh.position),
+            inv_orientation * body_other.orientation,
+        );
+        bounds.mins -= Vec3::splat(CONTACT_MARGIN);
+        bounds.maxs += Vec3::splat(CONTACT_MARGIN);
+        bounds
+    };
+
+    let mut triangles = Vec::new();
+    match &body_mesh.shape {
+        Shape::TriMesh(mesh) => {
+            let mut indices = Vec::new();
+            mesh.query(&local_bounds(), |index| indices.push(index));
+            indices.sort_unstable();
+            triangles.extend(indices.into_iter().map(|index| mesh.triangle(index)));
+        }
+        Shape::Heightfield(field) => {
+            field.query(&local_bounds(), |triangle| triangles.push(triangle));
+        }
+        _ => return None,
+    }
+    Some(triangles)
+}
+
+// the contacts between a mesh and another body, one for each of the mesh's triangles the body
+// touches. mesh_is_a says which side of the contacts the mesh is on
+fn triangle_contacts(
+    (handle_mesh, body_mesh): (BodyHandle, &Body),
+    triangles: &[[Vec3; 3]],
+    (handle_other, body_other): (BodyHandle, &Body),
+    mesh_is_a: bool,
+) -> Vec<Contact> {
+    // spheres and capsules are rounded, so their contacts are found exactly rather than with
+    // gjk's approximation of their curved sides
+    let segment = match &body_other.shape {
+        Shape::Sphere(sphere) => Some((body_other.position, body_other.position, sphere.radius)),
+        Shape::Capsule(capsule) => {
+            let (start, end) = capsule_segment(body_other, capsule);
+            Some((start, end, capsule.radius))
+        }
+        _ => None,
+    };
+
+    const BIAS: f32 = 0.001;
+    let mut contacts = Vec::new();
+    for corners in triangles {
+        let world_triangle = [
+            body_mesh.local_to_world(corners[0]),
+            body_mesh.local_to_world(corners[1]),
+            body_mesh.local_to_world(corners[2]),
+        ];
+
+        // the points on the mesh and the other body, and the normal from the other body to the
+        // mesh
+        let (on_mesh, on_other, normal, separation_dist) =
+            if let Some((start, end, radius)) = segment {
+                let (on_mesh, on_other, normal, separation_dist) =
+                    segment_triangle_static((start, end), radius, world_triangle);
+                if separation_dist >= CONTACT_MARGIN {
+                    continue;
+                }
+                (on_mesh, on_other, -normal, separation_dist)
+            } else {
+                match gjk_does_intersect(&WorldTriangle(world_triangle), body_other, BIAS) {
+                    Some((on_mesh, on_other)) => {
+                        let normal = (on_other - on_mesh).normalize_or_zero();
+                        let on_mesh = on_mesh - normal * BIAS;
+                        let on_other = on_other + normal * BIAS;
+                        (on_mesh, on_other, normal, -(on_mesh - on_other).length())
+                    }
+                    None => continue,
+                }
+            };
+
+        contacts.push(if mesh_is_a {
+            points_contact(
+                (handle_mesh, body_mesh, on_mesh),
+                (handle_other, body_other, on_other),
+                normal,
+                separation_dist,
+            )
+            .0
+        } else {
+            points_contact(
+                (handle_other, body_other, on_other),
+                (handle_mesh, body_mesh, on_mesh),
+                -normal,
+                separation_dist,
+            )
+            .0
+        });
+    }
+    contacts
+}
+
+// points around a rim that could be the lowest below a plane, the lowest point and three more a
+// quarter turn apart so a rim lying flat gets a full manifold
+fn rim_points(centre: Vec3, axis: Vec3, radius: f32, normal: Vec3) -> [Vec3; 4] {
+    let across = axis * axis.dot(normal) - normal;
+    let down = if across.length_squared() > 1e-8 {
+        across.normalize()
+    } else {
+        axis.any_orthonormal_vector()
+    };
+    let side = axis.cross(down);
+    [
+        centre + down * radius,
+        centre + side * radius,
+        centre - side * radius,
+        centre - down * radius,
+    ]
+}
+
+// the points on the body's surface that could be the deepest below a plane with the normal, in
+// world space. flat sides give a point at each corner so bodies resting on the plane settle
+fn plane_candidate_points(body: &Body, normal: Vec3) -> Vec<Vec3> {
+    let (position, orientation) = (body.position, body.orientation);
+    match &body.shape {
+        Shape::Sphere(sphere) => vec![position - normal * sphere.radius],
+        Shape::Capsule(capsule) => {
+            let (start, end) = capsule.segment(position, orientation);
+            vec![
+                start - normal * capsule.radius,
+                end - normal * capsule.radius,
+            ]
+        }
+        Shape::Box(shape_box) => shape_box
+            .points
+            .iter()
+            .map(|&pt| position + orientation * pt)
+            .collect(),
+        Shape::Convex(convex) => convex
+            .points()
+            .iter()
+            .map(|&pt| position + orientation * pt)
+            .collect(),
+        Shape::Cylinder(cylinder) => {
+            let axis = orientation * Vec3::Y;
+            let half_axis = axis * cylinder.half_height;
+            let mut points =
+                rim_points(position - half_axis, axis, cylinder.radius, normal).to_vec();
+            points.extend_from_slice(&rim_points(
+                position + half_axis,
+                axis,
+                cylinder.radius,
+                normal,
+            ));
+            points
+        }
+        Shape::Cone(cone) => {
+            let axis = orientation * Vec3::Y;
+            let half_axis = axis * cone.half_height;
+            let mut points = rim_points(position - half_axis, axis, cone.radius, normal).to_vec();
+            points.push(position + half_axis);
+            points
+        }
+        _ => vec![body.shape.support(-normal, position, orientation, 0.0)],
+    }
+}
+
+// the contacts between a plane and another body, one for each of the body's candidate points,
+// deepest first. each says whether its point is touching the plane
+fn plane_contacts(
+    (handle_plane, body_plane): (BodyHandle, &Body),
+    (handle_other, body_other): (BodyHandle, &Body),
+    plane_is_a: bool,
+) -> Vec<(Contact, bool)> {
+    // the normal points out of the plane towards the other body
+    let normal = body_plane.orientation * Vec3::Y;
+    let mut contacts: Vec<(Contact, bool)> = plane_candidate_points(body_other, normal)
+        .into_iter()
+        .map(|on_other| {
+            let separation_dist = normal.dot(on_other - body_plane.position);
+            let on_plane = on_other - normal * separation_dist;
+            if plane_is_a {
+                points_contact(
+                    (handle_plane, body_plane, on_plane),
+                    (handle_other, body_other, on_other),
+                    -normal,
+                    separation_dist,
+                )
+            } else {
+                points_contact(
+                    (handle_other, body_other, on_other),
+                    (handle_plane, body_plane, on_plane),
+                    normal,
+                    separation_dist,
+                )
+            }
+        })
+        .collect();
+    contacts.sort_by(|a, b| {
+        a.0.separation_dist
+            .partial_cmp(&b.0.separation_dist)
+            .unwrap()
+    });
+    contacts
+}
+
 fn intersect_static(
     handle_a: BodyHandle,
     body_a: &Body,
@@ -120,6 +822,13 @@ fn intersect_static(
     body_b: &Body,
 ) -> (Contact, bool) {
     match (&body_a.shape, &body_b.shape) {
+        // the deepest of the contacts, or the closest when none are touching
+        (Shape::Plane(_), _) => {
+            plane_contacts((handle_a, body_a), (handle_b, body_b), true).swap_remove(0)
+        }
+        (_, Shape::Plane(_)) => {
+            plane_contacts((handle_b, body_b), (handle_a, body_a), false).swap_remove(0)
+        }
         (Shape::Sphere(sphere_a), Shape::Sphere(sphere_b)) => {
             let pos_a = body_a.position;
             let pos_b = body_b.position;
@@ -142,14 +851,18 @@ fn intersect_static(
                     true,
                 )
             } else {
+                // the closest points along the line between the centres
+                let normal = (pos_a - pos_b).normalize_or_zero();
+                let world_point_a = pos_a - normal * sphere_a.radius;
+                let world_point_b = pos_b + normal * sphere_b.radius;
                 (
                     Contact {
-                        world_point_a: Vec3::ZERO,
-                        world_point_b: Vec3::ZERO,
-                        local_point_a: Vec3::ZERO,
-                        local_point_b: Vec3::ZERO,
-                        normal: Vec3::X,
-                        separation_dist: 0.0,
+                        world_point_a,
+                        world_point_b,
+                        local_point_a: body_a.world_to_local(world_point_a),
+                        local_point_b: body_b.world_to_local(world_point_b),
+                        normal,
+                        separation_dist: (world_point_a - world_point_b).length(),
                         time_of_impact: 0.0,
                         handle_a,
                         handle_b,
@@ -158,6 +871,82 @@ fn intersect_static(
                 )
             }
         }
+        (Shape::Box(shape_box), Shape::Sphere(sphere)) => {
+            let (on_box, on_sphere, normal, separation_dist) =
+                box_sphere_static(body_a, shape_box, body_b.position, sphere.radius);
+            points_contact(
+                (handle_a, body_a, on_box),
+                (handle_b, body_b, on_sphere),
+                -normal,
+                separation_dist,
+            )
+        }
+        (Shape::Sphere(sphere), Shape::Box(shape_box)) => {
+            let (on_box, on_sphere, normal, separation_dist) =
+                box_sphere_static(body_b, shape_box, body_a.position, sphere.radius);
+            points_contact(
+                (handle_a, body_a, on_sphere),
+                (handle_b, body_b, on_box),
+                normal,
+                separation_dist,
+            )
+        }
+        (Shape::Capsule(capsule_a), Shape::Capsule(capsule_b)) => segments_contact(
+            (
+                handle_a,
+                body_a,
+                capsule_segment(body_a, capsule_a),
+                capsule_a.radius,
+            ),
+            (
+                handle_b,
+                body_b,
+                capsule_segment(body_b, capsule_b),
+                capsule_b.radius,
+            ),
+        ),
+        (Shape::Capsule(capsule), Shape::Sphere(sphere)) => segments_contact(
+            (
+                handle_a,
+                body_a,
+                capsule_segment(body_a, capsule),
+                capsule.radius,
+            ),
+            (
+                handle_b,
+                body_b,
+                (body_b.position, body_b.position),
+                sphere.radius,
+            ),
+        ),
+        (Shape::Sphere(sphere), Shape::Capsule(capsule)) => segments_contact(
+            (
+                handle_a,
+                body_a,
+                (body_a.position, body_a.position),
+                sphere.radius,
+            ),
+            (
+                handle_b,
+                body_b,
+                capsule_segment(body_b, capsule),
+                capsule.radius,
+            ),
+        ),
+        (Shape::Box(shape_a), Shape::Box(shape_b)) => {
+            // the deepest of the contacts
+            let contacts = box_box_static(handle_a, body_a, shape_a, handle_b, body_b, shape_b);
+            match contacts
+                .into_iter()
+                .min_by(|a, b| a.separation_dist.partial_cmp(&b.separation_dist).unwrap())
+            {
+                Some(contact) => (contact, true),
+                None => (
+                    gjk_closest_contact(handle_a, body_a, handle_b, body_b),
+                    false,
+                ),
+            }
+        }
         (_, _) => {
             const BIAS: f32 = 0.001;
             if let Some((mut world_point_a, mut world_point_b)) =
@@ -181,19 +970,8 @@ fn intersect_static(
                     true,
                 )
             } else {
-                let (world_point_a, world_point_b) = gjk_closest_points(body_a, body_b);
                 (
-                    Contact {
-                        world_point_a,
-                        world_point_b,
-                        local_point_a: body_a.world_to_local(world_point_a),
-                        local_point_b: body_b.world_to_local(world_point_b),
-                        normal: Vec3::ZERO,
-                        separation_dist: (world_point_a - world_point_b).length(),
-                        time_of_impact: 0.0,
-                        handle_a,
-                        handle_b,
-                    },
+                    gjk_closest_contact(handle_a, body_a, handle_b, body_b),
                     false,
                 )
             }
@@ -201,13 +979,119 @@ fn intersect_static(
     }
 }
 
+// a contact between the closest points on each body, the normal points from b to a. they
+// intersect when they're closer than the contact margin
+fn points_contact(
+    (handle_a, body_a, world_point_a): (BodyHandle, &Body, Vec3),
+    (handle_b, body_b, world_point_b): (BodyHandle, &Body, Vec3),
+    normal: Vec3,
+    separation_dist: f32,
+) -> (Contact, bool) {
+    (
+        Contact {
+            world_point_a,
+            world_point_b,
+            local_point_a: body_a.world_to_local(world_point_a),
+            local_point_b: body_b.world_to_local(world_point_b),
+            normal,
+            separation_dist,
+            time_of_impact: 0.0,
+            handle_a,
+            handle_b,
+        },
+        separation_dist < CONTACT_MARGIN,
+    )
+}
+
+// the closest points between bodies that don't intersect
+fn gjk_closest_contact(
+    handle_a: BodyHandle,
+    body_a: &Body,
+    handle_b: BodyHandle,
+    body_b: &Body,
+) -> Contact {
+    let (world_point_a, world_point_b) = gjk_closest_points(body_a, body_b);
+    Contact {
+        world_point_a,
+        world_point_b,
+        local_point_a: body_a.world_to_local(world_point_a),
+        local_point_b: body_b.world_to_local(world_point_b),
+        normal: Vec3::ZERO,
+        separation_dist: (world_point_a - world_point_b).length(),
+        time_of_impact: 0.0,
+        handle_a,
+        handle_b,
+    }
+}
+
 pub(crate) fn intersect_dynamic(
     handle_a: BodyHandle,
     body_a: &mut Body,
     handle_b: BodyHandle,
     body_b: &mut Body,
     delta_seconds: f32,
-) -> Option<Contact> {
+) -> Vec<Contact> {
+    // planes get a contact for each of the other body's points touching them. if none are yet the
+    // body is swept towards the plane below
+    let touching = |contacts: Vec<(Contact, bool)>| -> Vec<Contact> {
+        contacts
+            .into_iter()
+            .filter(|(_, did_intersect)| *did_intersect)
+            .map(|(contact, _)| contact)
+            .collect()
+    };
+    let plane_touching = match (&body_a.shape, &body_b.shape) {
+        (Shape::Plane(_), _) => {
+            touching(plane_contacts((handle_a, body_a), (handle_b, body_b), true))
+        }
+        (_, Shape::Plane(_)) => touching(plane_contacts(
+            (handle_b, body_b),
+            (handle_a, body_a),
+            false,
+        )),
+        _ => Vec::new(),
+    };
+    if !plane_touching.is_empty() {
+        return plane_touching;
+    }
+
+    // meshes and heightfields are only tested where the bodies are now, each triangle touched
+    // gives a contact
+    match (
+        nearby_triangles(body_a, body_b),
+        nearby_triangles(body_b, body_a),
+    ) {
+        (Some(_), Some(_)) => return Vec::new(),
+        (Some(triangles), None) => {
+            return triangle_contacts((handle_a, body_a), &triangles, (handle_b, body_b), true);
+        }
+        (None, Some(triangles)) => {
+            return triangle_contacts((handle_b, body_b), &triangles, (handle_a, body_a), false);
+        }
+        (None, None) => {}
+    }
+
+    // boxes get all the corners of their overlap at once. if they're already touching there's
+    // nothing to sweep for
+    if let (Shape::Box(shape_a), Shape::Box(shape_b)) = (&body_a.shape, &body_b.shape) {
+        let contacts = box_box_static(handle_a, body_a, shape_a, handle_b, body_b, shape_b);
+        if !contacts.is_empty() {
+            return contacts;
+        }
+    }
+
+    // slow moving bodies can't tunnel so the cheaper discrete test is good enough
+    if !body_a.needs_ccd(delta_seconds) && !body_b.needs_ccd(delta_seconds) {
+        let (contact, did_intersect) = intersect_static(handle_a, body_a, handle_b, body_b);
+        return if did_intersect {
+            vec![contact]
+        } else {
+            speculative_contact(contact, body_a, body_b, delta_seconds)
+                .into_iter()
+                .collect()
+        };
+    }
+
     let shape_a = body_a.shape.clone();
     let shape_b = body_b.shape.clone();
     let shapes = (shape_a.borrow(), shape_b.borrow());
@@ -240,7 +1124,7 @@ pub(crate) fn intersect_dynamic(
                 let ab = body_a.position - body_b.position;
                 let separation_dist = ab.length() - (sphere_a.radius + sphere_b.radius);
 
-                Some(Contact {
+                vec![Contact {
                     world_point_a,
                     world_point_b,
                     local_point_a,
@@ -250,18 +1134,155 @@ pub(crate) fn intersect_dynamic(
                     time_of_impact,
                     handle_a,
                     handle_b,
-                })
+                }]
             } else {
-                None
+                Vec::new()
             }
         }
         _ => {
             // use GJK to perform conservative advancement
             conservative_advance(handle_a, body_a, handle_b, body_b, delta_seconds)
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+// bodies that aren't touching yet but could close the gap between them this step get a contact
+// anyway. the penetration constraint only lets them close it, so they meet without being swept
+fn speculative_contact(
+    mut contact: Contact,
+    body_a: &Body,
+    body_b: &Body,
+    delta_seconds: f32,
+) -> Option<Contact> {
+    let margin = (body_a.linear_velocity - body_b.linear_velocity).length() * delta_seconds;
+    let gap = contact.world_point_a - contact.world_point_b;
+    let separation_dist = gap.length();
+    if separation_dist <= 0.0 || separation_dist >= margin {
+        return None;
+    }
+    contact.normal = gap / separation_dist;
+    contact.separation_dist = separation_dist;
+    Some(contact)
+}
+
+// intersects each of the bodies' colliders against the other's, a body without colliders uses its
+// own shape and compounds are split into their children. the parts' contacts are converted back to
+// the bodies' local spaces
+pub(crate) fn intersect_colliders(
+    handle_a: BodyHandle,
+    body_a: &mut Body,
+    handle_b: BodyHandle,
+    body_b: &mut Body,
+    delta_seconds: f32,
+) -> Vec<Contact> {
+    let is_single =
+        |body: &Body| body.colliders.is_empty() && !matches!(body.shape, Shape::Compound(_));
+    if is_single(body_a) && is_single(body_b) {
+        return intersect_dynamic(handle_a, body_a, handle_b, body_b, delta_seconds);
+    }
+
+    let parts = |body: &Body| -> Vec<(Body, Quat, Vec3)> {
+        if is_single(body) {
+            vec![(body.clone(), Quat::IDENTITY, Vec3::ZERO)]
+        } else {
+            body.collision_parts()
+        }
+    };
+
+    let mut contacts = Vec::new();
+    let mut parts_b = parts(body_b);
+    for (mut part_a, rotation_a, offset_a) in parts(body_a) {
+        for (part_b, rotation_b, offset_b) in &mut parts_b {
+            for mut contact in
+                intersect_dynamic(handle_a, &mut part_a, handle_b, part_b, delta_seconds)
+            {
+                contact.local_point_a = rotation_a * contact.local_point_a + offset_a;
+                contact.local_point_b = *rotation_b * contact.local_point_b + *offset_b;
+                contacts.push(contact);
+            }
+        }
+    }
+    contacts
+}
+
+// true when one part of each body touches where they are now, the same tests the narrowphase
+// makes before sweeping anything. a mesh or heightfield is only its surface, so something wholly
+// under it doesn't touch it
+fn parts_touch(part_a: &Body, part_b: &Body) -> bool {
+    let handle = BodyHandle::default();
+    match (
+        nearby_triangles(part_a, part_b),
+        nearby_triangles(part_b, part_a),
+    ) {
+        (Some(_), Some(_)) => false,
+        (Some(triangles), None) => {
+            !triangle_contacts((handle, part_a), &triangles, (handle, part_b), true).is_empty()
+        }
+        (None, Some(triangles)) => {
+            !triangle_contacts((handle, part_b), &triangles, (handle, part_a), false).is_empty()
         }
+        (None, None) => intersect_static(handle, part_a, handle, part_b).1,
     }
 }
 
+// true when the bodies touch where they are now, with their colliders or compound children in
+// place of their shapes
+pub(crate) fn bodies_touch(body_a: &Body, body_b: &Body) -> bool {
+    let parts_b = body_b.collision_parts();
+    body_a.collision_parts().iter().any(|(part_a, _, _)| {
+        parts_b
+            .iter()
+            .any(|(part_b, _, _)| parts_touch(part_a, part_b))
+    })
+}
+
+// the contacts between a copy of each pair of bodies, the tests step the bodies around so copies
+// keep the results the same however the pairs are split up
+fn intersect_pair(
+    bodies: &BodyArena,
+    pair: &CollisionPair,
+    delta_seconds: f32,
+    mode: CollisionMode,
+) -> Vec<Contact> {
+    let mut body_a = bodies.get_body(pair.a).clone();
+    let mut body_b = bodies.get_body(pair.b).clone();
+    if mode == CollisionMode::Speculative {
+        body_a.ccd_enabled = false;
+        body_b.ccd_enabled = false;
+    }
+    intersect_colliders(pair.a, &mut body_a, pair.b, &mut body_b, delta_seconds)
+}
+
+// the contacts for each of the pairs, in the same order as the pairs
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn intersect_pairs(
+    bodies: &BodyArena,
+    pairs: &[CollisionPair],
+    delta_seconds: f32,
+    mode: CollisionMode,
+) -> Vec<Vec<Contact>> {
+    pairs
+        .iter()
+        .map(|pair| intersect_pair(bodies, pair, delta_seconds, mode))
+        .collect()
+}
+
+// the contacts for each of the pairs, in the same order as the pairs, split across threads
+#[cfg(feature = "parallel")]
+pub(crate) fn intersect_pairs(
+    bodies: &BodyArena,
+    pairs: &[CollisionPair],
+    delta_seconds: f32,
+    mode: CollisionMode,
+) -> Vec<Vec<Contact>> {
+    pairs
+        .par_iter()
+        .map(|pair| intersect_pair(bodies, pair, delta_seconds, mode))
+        .collect()
+}
+
 fn conservative_advance(
     handle_a: BodyHandle,
     body_a: &mut Body,
@@ -346,6 +1367,7 @@ mod test {
             elasticity: 0.5,
             friction: 0.5,
             shape: box_ground,
+            ..Body::default()
         };
         let mut body_b = Body {
             position: Vec3::new(-34.426125, 0.5000828, -0.022489173),
@@ -356,42 +1378,279 @@ mod test {
             elasticity: 0.5,
             friction: 0.5,
             shape: make_sphere(0.5),
+            ..Body::default()
         };
         let delta_seconds = 0.008333333;
 
         let contact = super::intersect_dynamic(
-            BodyHandle(0),
+            BodyHandle(0, 0),
             &mut body_a,
-            BodyHandle(1),
+            BodyHandle(1, 0),
             &mut body_b,
             delta_seconds,
-        );
+        )
+        .into_iter()
+        .next();
         assert!(contact.is_some());
         let c = contact.unwrap();
+        assert_eq!(Vec3::new(-34.426125, 0.0, -0.022489173), c.world_point_a);
         assert_eq!(
-            Vec3::new(-34.4261169, 0.001999998, -0.0224775206),
-            c.world_point_a
-        );
-        assert_eq!(
-            Vec3::new(-34.4261284, -0.0019171942, -0.022479806),
+            Vec3::new(-34.426125, 8.279085e-5, -0.022489173),
             c.world_point_b
         );
+        assert_eq!(Vec3::new(-34.426125, 0.5, -0.022489173), c.local_point_a);
         assert_eq!(
-            Vec3::new(-34.4261169, 0.501999974, -0.0224775206),
-            c.local_point_a
-        );
-        assert_eq!(
-            Vec3::new(-0.470389664, -0.17501742, -0.0103164278),
+            Vec3::new(-0.46851406, -0.17432386, -0.010284555),
             c.local_point_b
         );
-        assert_eq!(
-            Vec3::new(-0.00198972295, -0.999997914, -0.000583898218),
-            c.normal
-        );
-        assert_eq!(-0.00391720934, c.separation_dist);
+        assert_eq!(Vec3::new(0.0, -1.0, 0.0), c.normal);
+        assert_eq!(8.279085e-5, c.separation_dist);
         assert_eq!(0.0, c.time_of_impact);
     }
 
+    #[test]
+    fn test_ccd_motion_threshold() {
+        use crate::{
+            body::{Body, BodyHandle},
+            scene_shapes::make_sphere,
+        };
+        use glam::Vec3;
+
+        let delta_seconds = 1.0 / 60.0;
+        let intersect = |speed: f32, ccd_motion_threshold: f32| {
+            // the spheres are 0.1 apart
+            let mut body_a = Body {
+                position: Vec3::ZERO,
+                linear_velocity: Vec3::new(speed, 0.0, 0.0),
+                ccd_motion_threshold,
+                shape: make_sphere(1.0),
+                ..Body::default()
+            };
+            let mut body_b = Body {
+                position: Vec3::new(2.1, 0.0, 0.0),
+                ccd_motion_threshold,
+                shape: make_sphere(1.0),
+                ..Body::default()
+            };
+            super::intersect_dynamic(
+                BodyHandle(0, 0),
+                &mut body_a,
+                BodyHandle(1, 0),
+                &mut body_b,
+                delta_seconds,
+            )
+            .into_iter()
+            .next()
+        };
+
+        // moves 0.17 this step, enough to hit with the swept test
+        assert!(intersect(10.0, 0.0).unwrap().time_of_impact > 0.0);
+
+        // but that's under a threshold of a whole radius so only the discrete test is used, which
+        // gives a speculative contact for the gap it can close
+        let contact = intersect(10.0, 1.0).unwrap();
+        assert_eq!(0.0, contact.time_of_impact);
+        assert!((contact.separation_dist - 0.1).abs() < 1e-4);
+
+        // moving 0.05 can't close the gap
+        assert!(intersect(3.0, 1.0).is_none());
+
+        // moving 2 units is over the threshold and uses the swept test again
+        let contact = intersect(120.0, 1.0).unwrap();
+        assert!(contact.time_of_impact > 0.0);
+        assert!(contact.time_of_impact < delta_seconds);
+
+        // the static ground keeps the default threshold of 0.0, but it isn't moving so it never
+        // asks for the swept test on its own
+        let mut ground = Body {
+            position: Vec3::ZERO,
+            inv_mass: 0.0,
+            shape: crate::scene_shapes::make_box_ground(),
+            ..Body::default()
+        };
+        assert!(!ground.needs_ccd(delta_seconds));
+        // a slow sphere 0.1 above it falling 0.15 this step, under its own threshold
+        let mut sphere = Body {
+            position: Vec3::new(0.0, 1.1, 0.0),
+            linear_velocity: Vec3::new(0.0, -9.0, 0.0),
+            ccd_motion_threshold: 1.0,
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+        assert!(!sphere.needs_ccd(delta_seconds));
+        let contacts = super::intersect_dynamic(
+            BodyHandle(0, 0),
+            &mut ground,
+            BodyHandle(1, 0),
+            &mut sphere,
+            delta_seconds,
+        );
+        // the discrete test's speculative contact, the swept test would have found no hit
+        assert_eq!(1, contacts.len());
+        assert_eq!(0.0, contacts[0].time_of_impact);
+        assert!((contacts[0].separation_dist - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_ccd_disabled() {
+        use crate::{
+            body::{Body, BodyHandle},
+            scene_shapes::make_sphere,
+        };
+        use glam::Vec3;
+
+        // a sphere 0.1 from another moving 2 units this step
+        let mut body_a = Body {
+            position: Vec3::ZERO,
+            linear_velocity: Vec3::new(120.0, 0.0, 0.0),
+            ccd_enabled: false,
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+        let mut body_b = Body {
+            position: Vec3::new(2.1, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+        assert!(!body_a.needs_ccd(1.0 / 60.0));
+
+        // the other sphere isn't moving so nothing is swept, the gap is left to the solver
+        let contacts = super::intersect_dynamic(
+            BodyHandle(0, 0),
+            &mut body_a,
+            BodyHandle(1, 0),
+            &mut body_b,
+            1.0 / 60.0,
+        );
+        assert_eq!(1, contacts.len());
+        assert_eq!(0.0, contacts[0].time_of_impact);
+        assert!(contacts[0].normal.abs_diff_eq(-Vec3::X, 1e-6));
+        assert!((contacts[0].world_point_a.x - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_box_box_contacts() {
+        use crate::{
+            body::{Body, BodyHandle},
+            shapes::Shape,
+        };
+        use glam::Vec3;
+
+        // a unit box resting 0.1 into another, the whole bottom face is touching
+        let mut body_a = Body {
+            position: Vec3::ZERO,
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        };
+        let mut body_b = Body {
+            position: Vec3::new(0.0, 0.9, 0.0),
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        };
+        let contacts = super::intersect_dynamic(
+            BodyHandle(0, 0),
+            &mut body_a,
+            BodyHandle(1, 0),
+            &mut body_b,
+            1.0 / 60.0,
+        );
+        assert_eq!(4, contacts.len());
+        for c in &contacts {
+            assert!((c.separation_dist + 0.1).abs() < 1e-4);
+            assert!((c.normal - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-4);
+            assert!(c.world_point_b.x.abs() > 0.49 && c.world_point_b.z.abs() > 0.49);
+        }
+    }
+
+    #[test]
+    fn test_capsule_contacts() {
+        use crate::{
+            body::{Body, BodyHandle},
+            shapes::Shape,
+        };
+        use glam::{Quat, Vec3};
+
+        let intersect = |body_a: &Body, body_b: &Body| {
+            super::intersect_static(BodyHandle(0, 0), body_a, BodyHandle(1, 0), body_b)
+        };
+
+        // a standing capsule and one lying across it, overlapping by 0.1 at the middle
+        let standing = Body {
+            shape: Shape::make_capsule(1.0, 0.5),
+            ..Body::default()
+        };
+        let lying = Body {
+            position: Vec3::new(0.0, 0.0, 0.9),
+            orientation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            shape: Shape::make_capsule(1.0, 0.5),
+            ..Body::default()
+        };
+        let (contact, did_intersect) = intersect(&standing, &lying);
+        assert!(did_intersect);
+        assert!((contact.separation_dist + 0.1).abs() < 1e-4);
+        assert!(contact.normal.abs_diff_eq(-Vec3::Z, 1e-4));
+        assert!(contact
+            .world_point_a
+            .abs_diff_eq(Vec3::new(0.0, 0.0, 0.5), 1e-4));
+        assert!(contact
+            .world_point_b
+            .abs_diff_eq(Vec3::new(0.0, 0.0, 0.4), 1e-4));
+
+        // a sphere just above the top cap
+        let sphere = Body {
+            position: Vec3::new(0.0, 2.0, 0.0),
+            shape: Shape::make_sphere(0.4),
+            ..Body::default()
+        };
+        let (contact, did_intersect) = intersect(&sphere, &standing);
+        assert!(!did_intersect);
+        assert!((contact.separation_dist - 0.1).abs() < 1e-4);
+        assert!(contact.normal.abs_diff_eq(Vec3::Y, 1e-4));
+        assert!(contact
+            .world_point_b
+            .abs_diff_eq(Vec3::new(0.0, 1.5, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn test_cylinder_conservative_advance() {
+        use crate::{
+            body::{Body, BodyHandle},
+            scene_shapes::make_box_ground,
+            shapes::Shape,
+        };
+        use glam::{Quat, Vec3};
+
+        // a cylinder lying on its side falling fast enough to pass through the ground in a step
+        let mut ground = Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        };
+        let mut cylinder = Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            orientation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            linear_velocity: Vec3::new(0.0, -60.0, 0.0),
+            shape: Shape::make_cylinder(1.0, 0.5),
+            ..Body::default()
+        };
+        let delta_seconds = 1.0 / 60.0;
+        let contact = super::intersect_dynamic(
+            BodyHandle(0, 0),
+            &mut ground,
+            BodyHandle(1, 0),
+            &mut cylinder,
+            delta_seconds,
+        )
+        .into_iter()
+        .next()
+        .unwrap();
+
+        // the side of the cylinder starts 0.5 above the ground
+        assert!((contact.time_of_impact - 0.5 / 60.0).abs() < 1e-3);
+        assert!(contact.world_point_b.y.abs() < 0.01);
+    }
+
     // #[test]
     // fn test_convex_intersect_dynamic() {
     //     use crate::{
@@ -424,9 +1683,9 @@ mod test {
     //     let delta_seconds = 0.008333333;
 
     //     let contact = super::intersect_dynamic(
-    //         BodyHandle(0),
+    //         BodyHandle(0, 0),
     //         &mut body_a,
-    //         BodyHandle(1),
+    //         BodyHandle(1, 0),
     //         &mut body_b,
     //         delta_seconds,
     //     );
@@ -0,0 +1,127 @@
+use crate::{
+    body::{BodyArena, BodyHandle},
+    broadphase::CollisionPair,
+};
+
+// a group of dynamic bodies that only affect each other, so it can be solved on its own
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Island {
+    pub bodies: Vec<BodyHandle>,
+    // indices of the manifolds and constraints acting on the bodies
+    pub manifolds: Vec<usize>,
+    pub constraints: Vec<usize>,
+}
+
+// union find over the body indices
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, bodies: &BodyArena, pair: CollisionPair) {
+        if bodies.get_body(pair.a).has_infinite_mass()
+            || bodies.get_body(pair.b).has_infinite_mass()
+        {
+            return;
+        }
+        let root_a = self.find(bodies.index(pair.a));
+        let root_b = self.find(bodies.index(pair.b));
+        if root_a != root_b {
+            self.parents[root_b] = root_a;
+        }
+    }
+
+    // the bodies in each island, and the island each body is in
+    fn islands(&mut self, bodies: &BodyArena) -> (Vec<Vec<BodyHandle>>, Vec<usize>) {
+        // the index of each root's island in islands
+        let mut island_index = vec![usize::MAX; bodies.len()];
+        let mut islands: Vec<Vec<BodyHandle>> = Vec::new();
+        for (i, body) in bodies.iter().enumerate() {
+            if body.has_infinite_mass() {
+                continue;
+            }
+            let root = self.find(i);
+            if island_index[root] == usize::MAX {
+                island_index[root] = islands.len();
+                islands.push(Vec::new());
+            }
+            island_index[i] = island_index[root];
+            islands[island_index[root]].push(bodies.handles()[i]);
+        }
+        (islands, island_index)
+    }
+}
+
+// groups the dynamic bodies that are connected through the pairs, by contacts or constraints.
+// static bodies don't join islands together so each pile on the ground is its own island, and
+// every dynamic body is in exactly one island
+pub fn build_islands(
+    bodies: &BodyArena,
+    pairs: impl IntoIterator<Item = CollisionPair>,
+) -> Vec<Vec<BodyHandle>> {
+    let mut set = DisjointSet::new(bodies.len());
+    for pair in pairs {
+        set.union(bodies, pair);
+    }
+    set.islands(bodies).0
+}
+
+// like build_islands, but also sorts the manifolds and constraints into the island of the
+// dynamic body they act on. the ones between two static bodies, like a mover driving a
+// platform, go in an island with no bodies at the front, so the static bodies have their
+// velocities set before anything resting on them is solved
+pub fn build_solver_islands(
+    bodies: &BodyArena,
+    manifold_pairs: &[CollisionPair],
+    constraint_pairs: &[(usize, CollisionPair)],
+) -> Vec<Island> {
+    let mut set = DisjointSet::new(bodies.len());
+    for &pair in manifold_pairs {
+        set.union(bodies, pair);
+    }
+    for &(_, pair) in constraint_pairs {
+        set.union(bodies, pair);
+    }
+    let (body_islands, island_index) = set.islands(bodies);
+
+    let mut islands = vec![Island::default()];
+    islands.extend(body_islands.into_iter().map(|bodies| Island {
+        bodies,
+        ..Island::default()
+    }));
+    let island_of = |pair: &CollisionPair| {
+        if !bodies.get_body(pair.a).has_infinite_mass() {
+            island_index[bodies.index(pair.a)] + 1
+        } else if !bodies.get_body(pair.b).has_infinite_mass() {
+            island_index[bodies.index(pair.b)] + 1
+        } else {
+            0
+        }
+    };
+    for (index, pair) in manifold_pairs.iter().enumerate() {
+        islands[island_of(pair)].manifolds.push(index);
+    }
+    for (index, pair) in constraint_pairs {
+        islands[island_of(pair)].constraints.push(*index);
+    }
+
+    if islands[0].manifolds.is_empty() && islands[0].constraints.is_empty() {
+        islands.remove(0);
+    }
+    islands
+}
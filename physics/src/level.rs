@@ -0,0 +1,305 @@
+// a stable, human readable description of a scene that can be hand edited and shared. bodies
+// and constraints only keep what's needed to build them again, the solver state isn't saved
+use crate::{
+    body::Body,
+    constraints::JointMotor,
+    scene_shapes::{make_box_from_points, make_sphere},
+    shapes::{Shape, ShapeCompound, ShapeConvex, ShapeHeightfield, ShapeTriMesh},
+};
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LevelShape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        mins: Vec3,
+        maxs: Vec3,
+    },
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    Cylinder {
+        half_height: f32,
+        radius: f32,
+    },
+    Cone {
+        half_height: f32,
+        radius: f32,
+    },
+    Convex {
+        points: Vec<Vec3>,
+    },
+    TriMesh {
+        vertices: Vec<Vec3>,
+        triangles: Vec<[u32; 3]>,
+    },
+    Heightfield {
+        columns: usize,
+        rows: usize,
+        heights: Vec<f32>,
+        scale: Vec3,
+    },
+    Compound {
+        children: Vec<LevelCompoundChild>,
+    },
+    Plane,
+}
+
+// a shape in a compound, placed in the compound's space
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelCompoundChild {
+    pub position: Vec3,
+    pub orientation: Quat,
+    pub shape: LevelShape,
+}
+
+impl LevelShape {
+    pub fn from_shape(shape: &Shape) -> Self {
+        match shape {
+            Shape::Sphere(data) => LevelShape::Sphere {
+                radius: data.radius,
+            },
+            Shape::Box(data) => LevelShape::Box {
+                mins: data.bounds.mins,
+                maxs: data.bounds.maxs,
+            },
+            Shape::Capsule(data) => LevelShape::Capsule {
+                half_height: data.half_height,
+                radius: data.radius,
+            },
+            Shape::Cylinder(data) => LevelShape::Cylinder {
+                half_height: data.half_height,
+                radius: data.radius,
+            },
+            Shape::Cone(data) => LevelShape::Cone {
+                half_height: data.half_height,
+                radius: data.radius,
+            },
+            Shape::Convex(data) => LevelShape::Convex {
+                points: data.points().to_vec(),
+            },
+            Shape::TriMesh(data) => LevelShape::TriMesh {
+                vertices: data.vertices().to_vec(),
+                triangles: data.triangles().to_vec(),
+            },
+            Shape::Heightfield(data) => LevelShape::Heightfield {
+                columns: data.columns(),
+                rows: data.rows(),
+                heights: data.heights().to_vec(),
+                scale: data.scale(),
+            },
+            Shape::Compound(data) => LevelShape::Compound {
+                children: data
+                    .children()
+                    .iter()
+                    .map(|((position, orientation), shape)| LevelCompoundChild {
+                        position: *position,
+                        orientation: *orientation,
+                        shape: LevelShape::from_shape(shape),
+                    })
+                    .collect(),
+            },
+            Shape::Plane(_) => LevelShape::Plane,
+        }
+    }
+
+    pub fn to_shape(&self) -> Shape {
+        match self {
+            LevelShape::Sphere { radius } => make_sphere(*radius),
+            LevelShape::Box { mins, maxs } => make_box_from_points(&[
+                Vec3::new(mins.x, mins.y, mins.z),
+                Vec3::new(maxs.x, mins.y, mins.z),
+                Vec3::new(mins.x, maxs.y, mins.z),
+                Vec3::new(mins.x, mins.y, maxs.z),
+                Vec3::new(maxs.x, maxs.y, maxs.z),
+                Vec3::new(mins.x, maxs.y, maxs.z),
+                Vec3::new(maxs.x, mins.y, maxs.z),
+                Vec3::new(maxs.x, maxs.y, mins.z),
+            ]),
+            LevelShape::Capsule {
+                half_height,
+                radius,
+            } => Shape::make_capsule(*half_height, *radius),
+            LevelShape::Cylinder {
+                half_height,
+                radius,
+            } => Shape::make_cylinder(*half_height, *radius),
+            LevelShape::Cone {
+                half_height,
+                radius,
+            } => Shape::make_cone(*half_height, *radius),
+            LevelShape::Convex { points } => Shape::make_convex(Arc::new(ShapeConvex::new(points))),
+            LevelShape::TriMesh {
+                vertices,
+                triangles,
+            } => Shape::make_trimesh(Arc::new(ShapeTriMesh::new(
+                vertices.clone(),
+                triangles.clone(),
+            ))),
+            LevelShape::Heightfield {
+                columns,
+                rows,
+                heights,
+                scale,
+            } => Shape::make_heightfield(Arc::new(ShapeHeightfield::new(
+                *columns,
+                *rows,
+                heights.clone(),
+                *scale,
+            ))),
+            LevelShape::Compound { children } => {
+                Shape::make_compound(Arc::new(ShapeCompound::new(
+                    children
+                        .iter()
+                        .map(|child| ((child.position, child.orientation), child.shape.to_shape()))
+                        .collect(),
+                )))
+            }
+            LevelShape::Plane => Shape::make_plane(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelBody {
+    pub shape: LevelShape,
+    pub position: Vec3,
+    pub orientation: Quat,
+    pub linear_velocity: Vec3,
+    pub angular_velocity: Vec3,
+    pub inv_mass: f32,
+    pub elasticity: f32,
+    pub friction: f32,
+}
+
+impl LevelBody {
+    pub fn from_body(body: &Body) -> Self {
+        Self {
+            shape: LevelShape::from_shape(&body.shape),
+            position: body.position,
+            orientation: body.orientation,
+            linear_velocity: body.linear_velocity,
+            angular_velocity: body.angular_velocity,
+            inv_mass: body.inv_mass,
+            elasticity: body.elasticity,
+            friction: body.friction,
+        }
+    }
+
+    pub fn to_body(&self) -> Body {
+        Body {
+            position: self.position,
+            orientation: self.orientation,
+            linear_velocity: self.linear_velocity,
+            angular_velocity: self.angular_velocity,
+            inv_mass: self.inv_mass,
+            elasticity: self.elasticity,
+            friction: self.friction,
+            shape: self.shape.to_shape(),
+            ..Body::default()
+        }
+    }
+}
+
+// bodies are referred to by their index in Level::bodies, anchors and axes are in the space of
+// the body they belong to and relative_orientation is body_a's inverse orientation times
+// body_b's when the joint was made. only welds keep their break thresholds
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LevelConstraint {
+    Distance {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+    },
+    Orientation {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        relative_orientation: Quat,
+    },
+    Hinge {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        axis: Vec3,
+        // min and max angle in degrees
+        limits: Option<(f32, f32)>,
+        motor: Option<JointMotor>,
+        relative_orientation: Quat,
+    },
+    Spherical {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        axis_a: Vec3,
+        axis_b: Vec3,
+        // in degrees
+        cone_limit: Option<f32>,
+        motor: Option<JointMotor>,
+    },
+    Spring {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        rest_length: f32,
+        stiffness: f32,
+        damping: f32,
+    },
+    Slider {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        axis: Vec3,
+        limits: Option<(f32, f32)>,
+        motor: Option<JointMotor>,
+        relative_orientation: Quat,
+    },
+    Weld {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        break_force: Option<f32>,
+        break_torque: Option<f32>,
+        relative_orientation: Quat,
+    },
+    ConstantVelocity {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        axis: Vec3,
+        relative_orientation: Quat,
+    },
+    Motor {
+        body_a: u32,
+        body_b: u32,
+        anchor_a: Vec3,
+        anchor_b: Vec3,
+        axis: Vec3,
+        speed: f32,
+        relative_orientation: Quat,
+    },
+    Mover {
+        body: u32,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub bodies: Vec<LevelBody>,
+    pub constraints: Vec<LevelConstraint>,
+}
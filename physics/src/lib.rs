@@ -1,12 +1,26 @@
 pub mod body;
 pub mod bounds;
-mod broadphase;
-mod constraints;
-mod contact;
+pub mod broadphase;
+pub mod character;
+pub mod constraints;
+pub mod contact;
+pub mod debug;
+pub mod fluid;
+pub mod force_field;
 mod gjk;
 mod intersect;
+mod island;
+pub mod level;
 mod manifold;
-mod math;
+pub mod math;
+#[cfg(feature = "bevy")]
+pub mod plugin;
+pub mod query;
+pub mod ragdoll;
+pub mod rollback;
+pub mod rope;
 pub mod scene;
 mod scene_shapes;
 pub mod shapes;
+pub mod stats;
+pub mod vehicle;
@@ -1,14 +1,20 @@
 #![allow(dead_code)]
 use crate::{
-    body::{BodyArena, BodyHandle},
-    constraints::{Constraint, ConstraintConfig, ConstraintPenetration},
-    contact::Contact,
+    body::{BodyArena, BodyHandle, CombineRules},
+    broadphase::CollisionPair,
+    constraints::{Constraint, ConstraintPenetration},
+    contact::{
+        compare_handles, BodyVelocity, Contact, ContactMaterial, ModifyContact, PairSolveTrace,
+    },
+    math::LcpSystem,
 };
 use glam::Vec3;
+use rand::{seq::SliceRandom, Rng};
 
 const MAX_CONTACTS: usize = 4;
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 struct Manifold {
     contacts: [Contact; MAX_CONTACTS],
     num_contacts: u8,
@@ -103,18 +109,7 @@ impl Manifold {
         self.contacts[new_slot] = contact;
 
         // TODO: might be cheaper to reused existing constraint rather than memcpying a new one
-        let mut normal = bodies.get_body(self.handle_a).orientation.inverse() * -contact.normal;
-        normal = normal.normalize();
-        self.constraints[new_slot] = ConstraintPenetration::new(
-            ConstraintConfig {
-                handle_a: contact.handle_a,
-                handle_b: contact.handle_b,
-                anchor_a: contact.local_point_a,
-                anchor_b: contact.local_point_b,
-                ..ConstraintConfig::default()
-            },
-            normal,
-        );
+        self.constraints[new_slot] = ConstraintPenetration::from_contact(bodies, &contact);
 
         if new_slot == self.num_contacts as usize {
             self.num_contacts += 1;
@@ -133,7 +128,7 @@ impl Manifold {
             let a = body_a.local_to_world(contact.local_point_a);
             let b = body_b.local_to_world(contact.local_point_b);
 
-            let mut normal = self.constraints[i].normal();
+            let mut normal = self.constraints[i].contact_normal();
             normal = body_a.orientation * normal;
 
             // calculate the tangential separation and penetration depth
@@ -168,13 +163,27 @@ impl Manifold {
         &mut self.constraints[0..self.num_contacts as usize]
     }
 
-    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+    // a pile that's gone to sleep on the ground doesn't need solving
+    fn is_resting(&self, bodies: &BodyArena) -> bool {
+        bodies.get_body(self.handle_a).is_resting() && bodies.get_body(self.handle_b).is_resting()
+    }
+
+    fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32, baumgarte: bool) {
+        if self.is_resting(bodies) {
+            return;
+        }
         for constraint in self.constraints_as_mut_slice() {
             constraint.pre_solve(bodies, dt_sec);
+            if !baumgarte {
+                constraint.clear_baumgarte();
+            }
         }
     }
 
     fn solve(&mut self, bodies: &mut BodyArena) {
+        if self.is_resting(bodies) {
+            return;
+        }
         for constraint in self.constraints_as_mut_slice() {
             constraint.solve(bodies);
         }
@@ -186,6 +195,106 @@ impl Manifold {
         }
     }
 
+    // pushes the bodies apart along each contact normal to remove some of the penetration left
+    // after the velocity solve
+    fn correct_positions(&self, bodies: &mut BodyArena) {
+        for constraint in &self.constraints[0..self.num_contacts as usize] {
+            if constraint.is_disabled() {
+                continue;
+            }
+            let config = constraint.config();
+            let (body_a, body_b) = bodies.get_body_pair_mut(config.handle_a, config.handle_b);
+
+            let world_anchor_a = body_a.local_to_world(config.anchor_a);
+            let world_anchor_b = body_b.local_to_world(config.anchor_b);
+            let normal = body_a.orientation * constraint.normal();
+
+            // same slop as the baumgarte stabilization in the penetration constraint
+            let c = f32::min(0.0, (world_anchor_b - world_anchor_a).dot(normal) + 0.02);
+            if c >= 0.0 {
+                continue;
+            }
+
+            let ra = world_anchor_a - body_a.centre_of_mass_world();
+            let rb = world_anchor_b - body_b.centre_of_mass_world();
+            let ra_n = ra.cross(normal);
+            let rb_n = rb.cross(normal);
+            let effective_mass = body_a.inv_mass_along(normal)
+                + body_b.inv_mass_along(normal)
+                + (body_a.inv_intertia_tensor_world() * ra_n).dot(ra_n)
+                + (body_b.inv_intertia_tensor_world() * rb_n).dot(rb_n);
+            if effective_mass <= 0.0 {
+                continue;
+            }
+
+            const BETA: f32 = 0.2;
+            let lambda = -BETA * c / effective_mass;
+            body_a.apply_position_impulse(-normal * lambda, -ra_n * lambda);
+            body_b.apply_position_impulse(normal * lambda, rb_n * lambda);
+        }
+    }
+
+    fn update_materials(
+        &mut self,
+        bodies: &BodyArena,
+        rules: CombineRules,
+        modify_contact: &mut Option<ModifyContact>,
+    ) {
+        let body_a = bodies.get_body(self.handle_a);
+        let body_b = bodies.get_body(self.handle_b);
+        let material = ContactMaterial::from_bodies(body_a, body_b, rules);
+        let num_contacts = self.num_contacts as usize;
+        for (constraint, contact) in self.constraints[0..num_contacts]
+            .iter_mut()
+            .zip(&self.contacts[0..num_contacts])
+        {
+            let mut material = material;
+            // the contact's normal from when it was found may be out of date
+            let normal = -(body_a.orientation * constraint.contact_normal());
+            material.apply_one_way(body_a, body_b, normal);
+            if let Some(modify_contact) = modify_contact {
+                modify_contact(&mut material, contact);
+            }
+            constraint.set_material(&material, body_a);
+        }
+    }
+
+    fn begin_trace(&self, bodies: &BodyArena) -> PairSolveTrace {
+        let body_a = bodies.get_body(self.handle_a);
+        let body_b = bodies.get_body(self.handle_b);
+        PairSolveTrace {
+            handle_a: self.handle_a,
+            handle_b: self.handle_b,
+            // the constraint normals are in body a's space and point towards body b
+            normal: -(body_a.orientation * self.constraints[0].normal()),
+            pre_velocity_a: BodyVelocity::from_body(body_a),
+            pre_velocity_b: BodyVelocity::from_body(body_b),
+            ..PairSolveTrace::default()
+        }
+    }
+
+    fn end_trace(&self, bodies: &BodyArena, trace: &mut PairSolveTrace) {
+        for constraint in &self.constraints[0..self.num_contacts as usize] {
+            trace.normal_impulse += constraint.normal_impulse();
+            trace.friction_impulse += constraint.friction_impulse();
+        }
+        trace.post_velocity_a = BodyVelocity::from_body(bodies.get_body(self.handle_a));
+        trace.post_velocity_b = BodyVelocity::from_body(bodies.get_body(self.handle_b));
+    }
+
+    fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        for constraint in &self.constraints[0..self.num_contacts as usize] {
+            if constraint.is_disabled() {
+                continue;
+            }
+            constraint.append_lcp_system(bodies, system);
+        }
+    }
+
+    fn contacts(&self) -> &[Contact] {
+        &self.contacts[0..self.num_contacts as usize]
+    }
+
     fn contact(&self, index: usize) -> &Contact {
         assert!(index < MAX_CONTACTS as usize);
         &self.contacts[index]
@@ -194,9 +303,16 @@ impl Manifold {
     fn num_contacts(&self) -> usize {
         self.num_contacts as usize
     }
+
+    fn deepest_separation(&self) -> f32 {
+        self.contacts[0..self.num_contacts as usize]
+            .iter()
+            .fold(f32::MAX, |min, contact| min.min(contact.separation_dist))
+    }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ManifoldCollector {
     manifolds: Vec<Manifold>,
 }
@@ -233,15 +349,50 @@ impl ManifoldCollector {
             .retain(|&manifold| manifold.num_contacts() > 0);
     }
 
-    pub fn pre_solve(&mut self, bodies: &mut BodyArena, dt_sec: f32) {
+    pub fn update_materials(
+        &mut self,
+        bodies: &BodyArena,
+        rules: CombineRules,
+        modify_contact: &mut Option<ModifyContact>,
+    ) {
         for manifold in &mut self.manifolds {
-            manifold.pre_solve(bodies, dt_sec);
+            manifold.update_materials(bodies, rules, modify_contact);
         }
     }
 
-    pub fn solve(&mut self, bodies: &mut BodyArena) {
-        for manifold in &mut self.manifolds {
-            manifold.solve(bodies);
+    // starts a trace for each manifold with the velocities before solving
+    pub fn begin_traces(&self, bodies: &BodyArena, traces: &mut Vec<PairSolveTrace>) {
+        traces.extend(
+            self.manifolds
+                .iter()
+                .map(|manifold| manifold.begin_trace(bodies)),
+        );
+    }
+
+    // fills in the traces from begin_traces() with the impulses and velocities after solving
+    pub fn end_traces(&self, bodies: &BodyArena, traces: &mut [PairSolveTrace]) {
+        for (manifold, trace) in self.manifolds.iter().zip(traces) {
+            manifold.end_trace(bodies, trace);
+        }
+    }
+
+    // without baumgarte stabilization the penetration is left for correct_positions()
+    // prepares the manifolds at the indices, which are in the order of iter_pairs()
+    pub fn pre_solve(
+        &mut self,
+        bodies: &mut BodyArena,
+        dt_sec: f32,
+        baumgarte: bool,
+        indices: &[usize],
+    ) {
+        for &index in indices {
+            self.manifolds[index].pre_solve(bodies, dt_sec, baumgarte);
+        }
+    }
+
+    pub fn solve(&mut self, bodies: &mut BodyArena, indices: &[usize]) {
+        for &index in indices {
+            self.manifolds[index].solve(bodies);
         }
     }
 
@@ -251,6 +402,44 @@ impl ManifoldCollector {
         }
     }
 
+    pub fn correct_positions(&self, bodies: &mut BodyArena) {
+        for manifold in &self.manifolds {
+            manifold.correct_positions(bodies);
+        }
+    }
+
+    pub fn append_lcp_system(&self, bodies: &BodyArena, system: &mut LcpSystem) {
+        for manifold in &self.manifolds {
+            manifold.append_lcp_system(bodies, system);
+        }
+    }
+
+    pub fn iter_pairs(&self) -> impl Iterator<Item = CollisionPair> + '_ {
+        self.manifolds.iter().map(|manifold| CollisionPair {
+            a: manifold.handle_a,
+            b: manifold.handle_b,
+        })
+    }
+
+    pub fn iter_contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.manifolds
+            .iter()
+            .flat_map(|manifold| manifold.contacts().iter())
+    }
+
+    pub fn sort_deepest_first(&mut self) {
+        self.manifolds.sort_by(|a, b| {
+            a.deepest_separation()
+                .partial_cmp(&b.deepest_separation())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| compare_handles((a.handle_a, a.handle_b), (b.handle_a, b.handle_b)))
+        });
+    }
+
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.manifolds.shuffle(rng);
+    }
+
     pub fn clear(&mut self) {
         self.manifolds.clear();
     }
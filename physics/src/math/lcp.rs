@@ -1,4 +1,4 @@
-use super::{dot, MatN, VecN};
+use super::{dot, MatMN, MatN, VecN};
 
 pub fn lcp_gauss_seidel<const N: usize>(a: &MatN<N>, b: &VecN<N>) -> VecN<N> {
     let mut x = VecN::zero();
@@ -12,3 +12,63 @@ pub fn lcp_gauss_seidel<const N: usize>(a: &MatN<N>, b: &VecN<N>) -> VecN<N> {
     }
     x
 }
+
+// A dense copy of the systems of equations the solver works on, for offline analysis.
+// Each constraint contributes its own block on the diagonal of `a`, the solver never couples
+// blocks together so everything off the blocks is zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LcpSystem {
+    pub a: Vec<Vec<f32>>,
+    pub b: Vec<f32>,
+    pub jacobian: Vec<Vec<f32>>, // each row against the 12 velocities of its block's body pair
+    pub block_sizes: Vec<usize>,
+}
+
+impl LcpSystem {
+    pub fn dimension(&self) -> usize {
+        self.b.len()
+    }
+
+    pub fn push_block<const N: usize>(
+        &mut self,
+        a: &MatN<N>,
+        b: &VecN<N>,
+        jacobian: &MatMN<N, 12>,
+    ) {
+        let offset = self.dimension();
+        for row in &mut self.a {
+            row.resize(offset + N, 0.0);
+        }
+        for i in 0..N {
+            let mut row = vec![0.0; offset + N];
+            row[offset..].copy_from_slice(&a.rows[i].0);
+            self.a.push(row);
+            self.b.push(b[i]);
+            self.jacobian.push(jacobian.rows[i].to_vec());
+        }
+        self.block_sizes.push(N);
+    }
+
+    // solves each block the same way lcp_gauss_seidel does
+    pub fn solve(&self) -> Vec<f32> {
+        let mut x = vec![0.0; self.dimension()];
+        let mut offset = 0;
+        for &n in &self.block_sizes {
+            let block = offset..offset + n;
+            for _ in 0..n {
+                for i in block.clone() {
+                    let ax = self.a[i][block.clone()]
+                        .iter()
+                        .zip(&x[block.clone()])
+                        .fold(0.0, |dot, (&lhs, &rhs)| dot + lhs * rhs);
+                    let dx = (self.b[i] - ax) / self.a[i][i];
+                    if dx.is_finite() {
+                        x[i] += dx;
+                    }
+                }
+            }
+            offset += n;
+        }
+        x
+    }
+}
@@ -1,5 +1,6 @@
 use super::{dot, VecN};
 use core::ops::Mul;
+use std::convert::TryFrom;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct MatN<const N: usize> {
@@ -46,11 +47,33 @@ impl<const N: usize> From<MatMN<N, N>> for MatN<N> {
     }
 }
 
+// saved as a list of rows like VecN
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Vec<VecN<N>>", try_from = "Vec<VecN<N>>")
+)]
 pub struct MatMN<const M: usize, const N: usize> {
     pub rows: [VecN<N>; M],
 }
 
+impl<const M: usize, const N: usize> From<MatMN<M, N>> for Vec<VecN<N>> {
+    fn from(m: MatMN<M, N>) -> Self {
+        m.rows.to_vec()
+    }
+}
+
+impl<const M: usize, const N: usize> TryFrom<Vec<VecN<N>>> for MatMN<M, N> {
+    type Error = String;
+
+    fn try_from(rows: Vec<VecN<N>>) -> Result<Self, Self::Error> {
+        <[VecN<N>; M]>::try_from(rows)
+            .map(|rows| Self { rows })
+            .map_err(|rows| format!("expected {} rows, got {}", M, rows.len()))
+    }
+}
+
 impl<const M: usize, const N: usize> MatMN<M, N> {
     #[inline]
     pub const fn zero() -> Self {
@@ -4,7 +4,7 @@ mod lcp;
 mod matrix;
 mod vector;
 
-pub use lcp::lcp_gauss_seidel;
+pub use lcp::{lcp_gauss_seidel, LcpSystem};
 pub use matrix::{MatMN, MatN};
 pub use vector::VecN;
 
@@ -1,7 +1,14 @@
 use super::dot;
 use core::ops::{Add, AddAssign, Deref, DerefMut, Mul, Sub};
+use std::convert::TryFrom;
 
+// saved as a list, serde can't derive for arrays of any length
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Vec<f32>", try_from = "Vec<f32>")
+)]
 pub struct VecN<const N: usize>(pub(crate) [f32; N]);
 
 impl<const N: usize> VecN<N> {
@@ -22,6 +29,22 @@ impl<const N: usize> Default for VecN<N> {
     }
 }
 
+impl<const N: usize> From<VecN<N>> for Vec<f32> {
+    fn from(v: VecN<N>) -> Self {
+        v.0.to_vec()
+    }
+}
+
+impl<const N: usize> TryFrom<Vec<f32>> for VecN<N> {
+    type Error = String;
+
+    fn try_from(v: Vec<f32>) -> Result<Self, Self::Error> {
+        <[f32; N]>::try_from(v)
+            .map(Self)
+            .map_err(|v| format!("expected {} values, got {}", N, v.len()))
+    }
+}
+
 impl<const N: usize> Deref for VecN<N> {
     type Target = [f32; N];
     #[inline]
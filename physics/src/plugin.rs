@@ -0,0 +1,180 @@
+// a bevy plugin that keeps entities and the scene's bodies in sync, behind the bevy feature
+use crate::{
+    body::{Body, BodyHandle},
+    scene::PhysicsScene,
+    shapes::Shape,
+};
+use bevy::{prelude::*, transform::TransformSystem, utils::HashMap};
+
+// the mass and material of a rigid body entity, the entity also needs a Collider
+pub struct RigidBody {
+    pub inv_mass: f32,
+    pub elasticity: f32,
+    pub friction: f32,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        let body = Body::default();
+        Self {
+            inv_mass: body.inv_mass,
+            elasticity: body.elasticity,
+            friction: body.friction,
+        }
+    }
+}
+
+pub struct Collider {
+    pub shape: Shape,
+}
+
+// the starting velocity when the body is created, afterwards it's copied from the body every
+// frame
+#[derive(Default)]
+pub struct Velocity {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+
+// the body each entity was given, so the body can still be found once the entity is despawned
+#[derive(Default)]
+struct BodyEntities(HashMap<Entity, BodyHandle>);
+
+// adds a body to the scene for every entity with a RigidBody and Collider and keeps their
+// Transform and Velocity in sync with it. despawning the entity removes its body, and if the
+// scene is cleared or reset the body is added again where the entity is now. an empty scene is
+// added unless the app has inserted its own
+#[derive(Default)]
+pub struct PhysicsPlugin {
+    // steps the scene with update_fixed() every frame unless it's paused, leave it off to step the
+    // scene from the app's own systems
+    pub fixed_step: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, StageLabel)]
+pub enum PhysicsStage {
+    // the fixed step, after CoreStage::Update so the forces added there act in it
+    Step,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.world().contains_resource::<PhysicsScene>() {
+            let mut physics_scene = PhysicsScene::empty();
+            physics_scene.paused = false;
+            app.insert_resource(physics_scene);
+        }
+        app.init_resource::<BodyEntities>()
+            .add_system_to_stage(CoreStage::PreUpdate, create_bodies_system.system())
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                copy_transforms_system
+                    .system()
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, copy_velocities_system.system())
+            .add_system_to_stage(CoreStage::PostUpdate, remove_bodies_system.system());
+        if self.fixed_step {
+            app.add_stage_after(
+                CoreStage::Update,
+                PhysicsStage::Step,
+                SystemStage::single(fixed_step_system.system()),
+            );
+        }
+    }
+}
+
+fn fixed_step_system(time: Res<Time>, mut physics_scene: ResMut<PhysicsScene>) {
+    if !physics_scene.paused {
+        physics_scene.update_fixed(time.delta_seconds());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn create_bodies_system(
+    mut commands: Commands,
+    mut physics_scene: ResMut<PhysicsScene>,
+    mut body_entities: ResMut<BodyEntities>,
+    query: Query<(
+        Entity,
+        &RigidBody,
+        &Collider,
+        &Transform,
+        Option<&Velocity>,
+        Option<&BodyHandle>,
+    )>,
+) {
+    for (entity, rigid_body, collider, transform, velocity, body_handle) in query.iter() {
+        // already has a body, unless the scene was cleared or reset since
+        if let Some(&body_handle) = body_handle {
+            if physics_scene.is_valid(body_handle) {
+                continue;
+            }
+        }
+        let mut body = Body {
+            position: transform.translation,
+            orientation: transform.rotation,
+            inv_mass: rigid_body.inv_mass,
+            elasticity: rigid_body.elasticity,
+            friction: rigid_body.friction,
+            shape: collider.shape.clone(),
+            ..Body::default()
+        };
+        if let Some(velocity) = velocity {
+            body.linear_velocity = velocity.linear;
+            body.angular_velocity = velocity.angular;
+        }
+        let body_handle = physics_scene.add_body(body);
+        body_entities.0.insert(entity, body_handle);
+        commands.entity(entity).insert(body_handle);
+    }
+}
+
+// the bodies of despawned entities, or entities that had their BodyHandle taken off, are removed
+// from the scene
+fn remove_bodies_system(
+    mut physics_scene: ResMut<PhysicsScene>,
+    mut body_entities: ResMut<BodyEntities>,
+    removed: RemovedComponents<BodyHandle>,
+) {
+    for entity in removed.iter() {
+        let body_handle = match body_entities.0.remove(&entity) {
+            Some(body_handle) => body_handle,
+            None => continue,
+        };
+        // already gone if the scene was cleared
+        if physics_scene.is_valid(body_handle) {
+            physics_scene.remove_body(body_handle);
+        }
+    }
+}
+
+fn copy_transforms_system(
+    physics_scene: Res<PhysicsScene>,
+    mut query: Query<(&BodyHandle, &mut Transform)>,
+) {
+    for (&body_handle, mut transform) in query.iter_mut() {
+        // the entity's body has been removed
+        let body = match physics_scene.get_body(body_handle) {
+            Some(body) => body,
+            None => continue,
+        };
+        transform.translation = body.position;
+        transform.rotation = body.orientation;
+    }
+}
+
+fn copy_velocities_system(
+    physics_scene: Res<PhysicsScene>,
+    mut query: Query<(&BodyHandle, &mut Velocity)>,
+) {
+    for (&body_handle, mut velocity) in query.iter_mut() {
+        // the entity's body has been removed
+        let body = match physics_scene.get_body(body_handle) {
+            Some(body) => body,
+            None => continue,
+        };
+        velocity.linear = body.linear_velocity;
+        velocity.angular = body.angular_velocity;
+    }
+}
@@ -0,0 +1,612 @@
+use crate::{
+    body::{Body, BodyHandle},
+    bounds::Bounds,
+    gjk::{gjk_closest_points, gjk_does_intersect, SupportMap},
+    intersect::{
+        bodies_touch, intersect_dynamic, ray_sphere_intersect, WorldTriangle, CONTACT_MARGIN,
+    },
+    shapes::Shape,
+};
+use glam::{Quat, Vec3};
+
+// where a ray first hit a body
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    pub handle: BodyHandle,
+    pub point: Vec3,
+    // the surface normal at the point, facing back along the ray
+    pub normal: Vec3,
+    // how far along the ray the point is
+    pub distance: f32,
+}
+
+// where a swept shape first touched a body
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapeHit {
+    pub handle: BodyHandle,
+    // how far along the sweep the shape got, from 0 at the start to 1 at the end
+    pub time_of_impact: f32,
+    // the point on the body that was hit
+    pub point: Vec3,
+    // the normal from the body towards the shape
+    pub normal: Vec3,
+}
+
+// the convex shapes without a ray test of their own are marched towards until the ray is this
+// close to them
+const RAY_TOLERANCE: f32 = 1e-4;
+const MAX_RAY_ITERATIONS: usize = 32;
+
+// a single point, so gjk can find the gap between a point on the ray and a shape
+struct PointSupport(Vec3);
+
+impl SupportMap for PointSupport {
+    fn support_point(&self, dir: Vec3, bias: f32) -> Vec3 {
+        self.0 + dir.normalize_or_zero() * bias
+    }
+}
+
+// whether the point is inside or on the body's shape, concave shapes are filled in to their hull
+pub(crate) fn contains_point(body: &Body, point: Vec3) -> bool {
+    const BIAS: f32 = 0.001;
+    gjk_does_intersect(body, &PointSupport(point), BIAS).is_some()
+}
+
+// where the ray enters and leaves the bounds, and the normal of the side it enters through. a ray
+// starting inside enters at zero
+fn ray_bounds(origin: Vec3, dir: Vec3, bounds: &Bounds) -> Option<(f32, f32, Vec3)> {
+    let mut enter = 0.0;
+    let mut exit = f32::MAX;
+    let mut normal = -dir;
+    for axis in 0..3 {
+        if dir[axis].abs() < 1e-8 {
+            // parallel to these sides, it has to be between them
+            if origin[axis] < bounds.mins[axis] || origin[axis] > bounds.maxs[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let inv_dir = dir[axis].recip();
+        let mut near = (bounds.mins[axis] - origin[axis]) * inv_dir;
+        let mut far = (bounds.maxs[axis] - origin[axis]) * inv_dir;
+        let mut side = Vec3::ZERO;
+        side[axis] = -1.0;
+        if near > far {
+            std::mem::swap(&mut near, &mut far);
+            side = -side;
+        }
+        if near > enter {
+            enter = near;
+            normal = side;
+        }
+        exit = exit.min(far);
+        if enter > exit {
+            return None;
+        }
+    }
+    Some((enter, exit, normal))
+}
+
+// where the ray crosses the triangle from either side, and the triangle's normal facing the ray
+fn ray_triangle(origin: Vec3, dir: Vec3, [a, b, c]: [Vec3; 3]) -> Option<(f32, Vec3)> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = dir.cross(ac);
+    let det = ab.dot(p);
+    if det.abs() < 1e-8 {
+        return None;
+    }
+
+    let inv_det = det.recip();
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(ab);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = ac.dot(q) * inv_det;
+    if t < 0.0 {
+        return None;
+    }
+    let normal = ab.cross(ac).normalize();
+    Some((
+        t,
+        if normal.dot(dir) > 0.0 {
+            -normal
+        } else {
+            normal
+        },
+    ))
+}
+
+// the triangles of a mesh or heightfield inside the bounds, in the body's local space. None if the
+// body isn't made of triangles
+fn triangles_in(body: &Body, local_bounds: &Bounds) -> Option<Vec<[Vec3; 3]>> {
+    let mut triangles = Vec::new();
+    match &body.shape {
+        Shape::TriMesh(mesh) => {
+            let mut indices = Vec::new();
+            mesh.query(local_bounds, |index| indices.push(index));
+            triangles.extend(indices.into_iter().map(|index| mesh.triangle(index)));
+        }
+        Shape::Heightfield(field) => {
+            field.query(local_bounds, |triangle| triangles.push(triangle));
+        }
+        _ => return None,
+    }
+    Some(triangles)
+}
+
+// the closest of the triangles the ray crosses
+fn ray_triangles(
+    origin: Vec3,
+    dir: Vec3,
+    triangles: impl Iterator<Item = [Vec3; 3]>,
+) -> Option<(f32, Vec3)> {
+    triangles
+        .filter_map(|triangle| ray_triangle(origin, dir, triangle))
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+}
+
+// steps along the ray to the plane that separates the point on the ray from the shape, the shape
+// is convex so it can't be any closer than that
+fn ray_march(body: &Body, origin: Vec3, dir: Vec3, max_toi: f32) -> Option<(f32, Vec3)> {
+    let mut t = 0.0;
+    let mut normal = -dir;
+    for _ in 0..MAX_RAY_ITERATIONS {
+        let point = origin + dir * t;
+        let (on_shape, _) = gjk_closest_points(body, &PointSupport(point));
+        let gap = point - on_shape;
+        let distance = gap.length();
+        if distance < RAY_TOLERANCE {
+            return Some((t, normal));
+        }
+
+        normal = gap / distance;
+        let approach = -dir.dot(normal);
+        if approach <= 0.0 {
+            // heading away from the shape
+            return None;
+        }
+        t += distance / approach;
+        if t > max_toi {
+            return None;
+        }
+    }
+    None
+}
+
+// where the ray first hits the body's shape, and the world normal there. the direction has to be
+// normalized
+pub(crate) fn ray_cast_shape(
+    body: &Body,
+    origin: Vec3,
+    dir: Vec3,
+    max_toi: f32,
+) -> Option<(f32, Vec3)> {
+    let (position, orientation) = (body.position, body.orientation);
+    let to_local = || {
+        let inv_orientation = orientation.conjugate();
+        (inv_orientation * (origin - position), inv_orientation * dir)
+    };
+
+    let hit = match &body.shape {
+        Shape::Sphere(sphere) => {
+            let (t1, t2) = ray_sphere_intersect(origin, dir, position, sphere.radius)?;
+            if t2 < 0.0 {
+                return None;
+            }
+            if t1 <= 0.0 {
+                // starting inside
+                Some((0.0, -dir))
+            } else {
+                let point = origin + dir * t1;
+                Some((t1, (point - position) / sphere.radius))
+            }
+        }
+        Shape::Box(shape_box) => {
+            let (local_origin, local_dir) = to_local();
+            ray_bounds(local_origin, local_dir, &shape_box.bounds)
+                .map(|(enter, _, normal)| (enter, orientation * normal))
+        }
+        Shape::Plane(_) => {
+            let (local_origin, local_dir) = to_local();
+            if local_origin.y <= 0.0 {
+                Some((0.0, -dir))
+            } else if local_dir.y < 0.0 {
+                Some((-local_origin.y / local_dir.y, orientation * Vec3::Y))
+            } else {
+                None
+            }
+        }
+        Shape::TriMesh(_) | Shape::Heightfield(_) => {
+            let (local_origin, local_dir) = to_local();
+            let end = local_origin + local_dir * max_toi;
+            let bounds = Bounds {
+                mins: local_origin.min(end),
+                maxs: local_origin.max(end),
+            };
+            let triangles = triangles_in(body, &bounds)?;
+            ray_triangles(local_origin, local_dir, triangles.into_iter())
+                .map(|(t, normal)| (t, orientation * normal))
+        }
+        Shape::Compound(_) => body
+            .collision_parts()
+            .iter()
+            .filter_map(|(part, _, _)| ray_cast_shape(part, origin, dir, max_toi))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap()),
+        _ => ray_march(body, origin, dir, max_toi),
+    };
+    hit.filter(|&(t, _)| t <= max_toi)
+}
+
+// where the ray first hits one of the bodies the filter accepts, looking no further than
+// max_toi along it. each body's bounds are tested first and the bodies are visited from the
+// nearest bounds out, so the search stops once the next bounds are further than the best hit
+pub(crate) fn ray_cast<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    origin: Vec3,
+    dir: Vec3,
+    max_toi: f32,
+    filter: impl Fn(BodyHandle, &Body) -> bool,
+) -> Option<RayHit> {
+    let dir = dir.normalize();
+    let mut candidates: Vec<(f32, BodyHandle, &Body)> = bodies
+        .filter(|&(handle, body)| filter(handle, body))
+        .filter_map(|(handle, body)| {
+            let (mins, maxs) = body.swept_aabb_over(0.0);
+            let (enter, _, _) = ray_bounds(origin, dir, &Bounds { mins, maxs })?;
+            if enter <= max_toi {
+                Some((enter, handle, body))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut best: Option<RayHit> = None;
+    for (enter, handle, body) in candidates {
+        if best.map_or(false, |best| best.distance <= enter) {
+            break;
+        }
+
+        // colliders are hit rather than the shape, like in the narrowphase
+        let max_toi = best.map_or(max_toi, |best| best.distance);
+        let hit = body
+            .collision_parts()
+            .iter()
+            .filter_map(|(part, _, _)| ray_cast_shape(part, origin, dir, max_toi))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        if let Some((distance, normal)) = hit {
+            best = Some(RayHit {
+                handle,
+                point: origin + dir * distance,
+                normal,
+                distance,
+            });
+        }
+    }
+    best
+}
+
+// moves the shape along the motion until it touches the triangle. returns how far it got as a
+// fraction of the motion, the point on the triangle and the normal from the triangle to the shape
+fn sweep_triangle(
+    shape: &Body,
+    motion: Vec3,
+    triangle: &WorldTriangle,
+) -> Option<(f32, Vec3, Vec3)> {
+    const BIAS: f32 = 0.001;
+    let [a, b, c] = triangle.0;
+    let face_normal = (b - a).cross(c - a).normalize_or_zero();
+    if let Some((_, on_triangle)) = gjk_does_intersect(shape, triangle, BIAS) {
+        // already touching, the normal is the side of the triangle the shape started on
+        let normal = if face_normal.dot(shape.position - a) < 0.0 {
+            -face_normal
+        } else {
+            face_normal
+        };
+        return Some((0.0, on_triangle, normal));
+    }
+    sweep_to(shape, motion, triangle)
+}
+
+// moves the shape along the motion until it touches the target, which isn't touching it to start
+// with. the same steps as the ray, the target doesn't move so the gap only closes along the motion
+fn sweep_to(shape: &Body, motion: Vec3, target: &impl SupportMap) -> Option<(f32, Vec3, Vec3)> {
+    let mut moved = shape.clone();
+    let mut t = 0.0;
+    let mut normal = -motion.normalize_or_zero();
+    for _ in 0..MAX_RAY_ITERATIONS {
+        moved.position = shape.position + motion * t;
+        let (on_shape, on_target) = gjk_closest_points(&moved, target);
+        let gap = on_shape - on_target;
+        let distance = gap.length();
+        if distance < CONTACT_MARGIN {
+            // the gap is too small to give a direction, the last step's normal is kept
+            return Some((t, on_target, normal));
+        }
+
+        normal = gap / distance;
+        let approach = -motion.dot(normal);
+        if approach <= 0.0 {
+            return None;
+        }
+        t += distance / approach;
+        if t > 1.0 {
+            return None;
+        }
+    }
+    None
+}
+
+// sweeps one part of the shape into one part of a body that stays where it is, the part carries
+// the motion as its velocity over a step of one second
+fn sweep_part(shape: &mut Body, body: &mut Body) -> Option<(f32, Vec3, Vec3)> {
+    let motion = shape.linear_velocity;
+
+    // meshes and heightfields are only tested where they are by the narrowphase, so each of their
+    // triangles along the way is swept against instead
+    let inv_orientation = body.orientation.conjugate();
+    let local_bounds = |position: Vec3| {
+        shape.shape.bounds(
+            inv_orientation * (position - body.position),
+            inv_orientation * shape.orientation,
+        )
+    };
+    let mut swept = local_bounds(shape.position).union(&local_bounds(shape.position + motion));
+    swept.mins -= Vec3::splat(CONTACT_MARGIN);
+    swept.maxs += Vec3::splat(CONTACT_MARGIN);
+    if let Some(triangles) = triangles_in(body, &swept) {
+        return triangles
+            .into_iter()
+            .filter_map(|[a, b, c]| {
+                let triangle = WorldTriangle([
+                    body.local_to_world(a),
+                    body.local_to_world(b),
+                    body.local_to_world(c),
+                ]);
+                sweep_triangle(shape, motion, &triangle)
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    }
+
+    // convex shapes that aren't touching yet are stepped towards like triangles. conservative
+    // advancement gives up once the closest points are too close to give a direction
+    const BIAS: f32 = 0.001;
+    let is_convex = |body: &Body| !matches!(body.shape, Shape::Plane(_) | Shape::Compound(_));
+    if is_convex(shape) && is_convex(body) && gjk_does_intersect(shape, body, BIAS).is_none() {
+        return sweep_to(shape, motion, body);
+    }
+
+    // everything else uses the same conservative advancement as fast bodies. the contacts that
+    // are already touching come first, then the deepest
+    intersect_dynamic(
+        BodyHandle::default(),
+        shape,
+        BodyHandle::default(),
+        body,
+        1.0,
+    )
+    .into_iter()
+    .min_by(|a, b| {
+        (a.time_of_impact, a.separation_dist)
+            .partial_cmp(&(b.time_of_impact, b.separation_dist))
+            .unwrap()
+    })
+    .map(|contact| {
+        (
+            contact.time_of_impact,
+            contact.world_point_b,
+            contact.normal,
+        )
+    })
+}
+
+// where the shape, turned by the orientation, first touches one of the bodies the filter accepts
+// as it's moved from one position to the other. the bodies are tested where they are now. like
+// the ray cast, the bodies are visited from the nearest bounds out
+pub(crate) fn shape_cast<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    shape: &Shape,
+    orientation: Quat,
+    from: Vec3,
+    to: Vec3,
+    filter: impl Fn(BodyHandle, &Body) -> bool,
+) -> Option<ShapeHit> {
+    let motion = to - from;
+    let swept = Body {
+        position: from,
+        orientation,
+        linear_velocity: motion,
+        inv_mass: 0.0,
+        shape: shape.clone(),
+        ..Body::default()
+    };
+
+    // each body's bounds are grown by the shape's bounds so the shape's position can be cast like
+    // a ray into them, the distances are fractions of the motion
+    let shape_bounds = shape.bounds(from, orientation);
+    let (below, above) = (from - shape_bounds.mins, shape_bounds.maxs - from);
+    let mut candidates: Vec<(f32, BodyHandle, &Body)> = bodies
+        .filter(|&(handle, body)| filter(handle, body))
+        .filter_map(|(handle, body)| {
+            let (mins, maxs) = body.swept_aabb_over(0.0);
+            let grown = Bounds {
+                mins: mins - above,
+                maxs: maxs + below,
+            };
+            let (enter, _, _) = ray_bounds(from, motion, &grown)?;
+            if enter <= 1.0 {
+                Some((enter, handle, body))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let shape_parts = swept.collision_parts();
+    let mut best: Option<ShapeHit> = None;
+    for (enter, handle, body) in candidates {
+        if best.map_or(false, |best| best.time_of_impact <= enter) {
+            break;
+        }
+
+        for (mut body_part, _, _) in body.collision_parts() {
+            body_part.linear_velocity = Vec3::ZERO;
+            body_part.angular_velocity = Vec3::ZERO;
+            for (shape_part, _, _) in &shape_parts {
+                let hit = sweep_part(&mut shape_part.clone(), &mut body_part.clone());
+                if let Some((time_of_impact, point, normal)) = hit {
+                    if best.map_or(true, |best| time_of_impact < best.time_of_impact) {
+                        best = Some(ShapeHit {
+                            handle,
+                            time_of_impact,
+                            point,
+                            normal,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    best
+}
+
+// the bodies the filter accepts whose bounds overlap the aabb, in handle order. only the bounds
+// are compared, so a body's shape might not reach into the aabb
+pub(crate) fn intersections_with_aabb<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    aabb: &Bounds,
+    filter: impl Fn(BodyHandle, &Body) -> bool,
+) -> Vec<BodyHandle> {
+    bodies
+        .filter(|&(handle, body)| filter(handle, body))
+        .filter(|(_, body)| {
+            let (mins, maxs) = body.swept_aabb_over(0.0);
+            aabb.does_intersect(&Bounds { mins, maxs })
+        })
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+// the bodies the filter accepts that the shape touches, placed at the position and orientation,
+// in handle order. the bodies' bounds are tested first, then the same tests as the narrowphase
+pub(crate) fn intersections_with_shape<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    shape: &Shape,
+    position: Vec3,
+    orientation: Quat,
+    filter: impl Fn(BodyHandle, &Body) -> bool,
+) -> Vec<BodyHandle> {
+    let placed = Body {
+        position,
+        orientation,
+        inv_mass: 0.0,
+        shape: shape.clone(),
+        ..Body::default()
+    };
+    let mut bounds = shape.bounds(position, orientation);
+    bounds.mins -= Vec3::splat(CONTACT_MARGIN);
+    bounds.maxs += Vec3::splat(CONTACT_MARGIN);
+
+    bodies
+        .filter(|&(handle, body)| filter(handle, body))
+        .filter(|(_, body)| {
+            let (mins, maxs) = body.swept_aabb_over(0.0);
+            bounds.does_intersect(&Bounds { mins, maxs }) && bodies_touch(&placed, body)
+        })
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+// the bodies the filter accepts that the point is inside or on, in handle order. meshes and
+// heightfields are only surfaces, so the point has to be on them
+pub(crate) fn intersections_with_point<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    point: Vec3,
+    filter: impl Fn(BodyHandle, &Body) -> bool,
+) -> Vec<BodyHandle> {
+    // a sphere without a radius is just the point
+    intersections_with_shape(
+        bodies,
+        &Shape::make_sphere(0.0),
+        point,
+        Quat::IDENTITY,
+        filter,
+    )
+}
+
+// how an effect weakens from full strength at its centre to nothing at its radius
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Falloff {
+    Constant,
+    Linear,
+    Quadratic,
+}
+
+impl Falloff {
+    // the fraction of the strength left at the distance, 0.0 at and past the radius
+    pub fn scale(self, distance: f32, radius: f32) -> f32 {
+        if distance >= radius {
+            return 0.0;
+        }
+        let t = 1.0 - distance / radius;
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => t,
+            Falloff::Quadratic => t * t,
+        }
+    }
+}
+
+// the impulse pushing each of the dynamic bodies within the radius away from the centre, as the
+// body, the point it acts at and the impulse. it acts at the point on the body closest to the
+// centre, the closest point on the convex hull for meshes and compounds
+pub(crate) fn radial_impulses<'a>(
+    bodies: impl Iterator<Item = (BodyHandle, &'a Body)>,
+    center: Vec3,
+    radius: f32,
+    strength: f32,
+    falloff: Falloff,
+) -> Vec<(BodyHandle, Vec3, Vec3)> {
+    let bounds = Bounds {
+        mins: center - Vec3::splat(radius),
+        maxs: center + Vec3::splat(radius),
+    };
+
+    let mut impulses = Vec::new();
+    for (handle, body) in bodies {
+        if body.has_infinite_mass() || body.is_kinematic() || body.is_sensor {
+            continue;
+        }
+        let (mins, maxs) = body.swept_aabb_over(0.0);
+        if !bounds.does_intersect(&Bounds { mins, maxs }) {
+            continue;
+        }
+
+        let (point, dir) = if contains_point(body, center) {
+            // push it out the way its centre of mass is
+            (center, body.centre_of_mass_world() - center)
+        } else {
+            let (point, _) = gjk_closest_points(body, &PointSupport(center));
+            (point, point - center)
+        };
+        let distance = (point - center).length();
+        let dir = dir.normalize_or_zero();
+        let scale = falloff.scale(distance, radius);
+        if dir != Vec3::ZERO && scale > 0.0 {
+            impulses.push((handle, point, dir * strength * scale));
+        }
+    }
+    impulses
+}
@@ -0,0 +1,334 @@
+// builds a ragdoll from a skeleton's bones. each bone becomes a capsule body running from its
+// head to its tail, joined to its parent's body at its head by a cone limited ball and socket or
+// a limited hinge. the bodies keep the bones' orientations so an animation can be read back
+// from them, or the ragdoll driven towards an animated pose
+use crate::{
+    body::{Body, BodyHandle},
+    constraints::{ConstraintHandle, JointMotor},
+    scene::PhysicsScene,
+    shapes::Shape,
+};
+use glam::{Quat, Vec3};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoneJoint {
+    // swings up to that many degrees away from the pose it was built in and twists freely, for
+    // shoulders, hips and the spine
+    Cone { swing: f32 },
+    // turns about the axis, in the bone's space, between the min and max angle in degrees from
+    // the pose it was built in, for elbows and knees
+    Hinge { axis: Vec3, limits: (f32, f32) },
+}
+
+impl Default for BoneJoint {
+    fn default() -> Self {
+        BoneJoint::Cone { swing: 45.0 }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Bone {
+    // the index of the parent bone, which must be added before it
+    pub parent: Option<usize>,
+    // the bone's head and orientation in the world, it runs along its local y axis from the head
+    // to the tail like the bones of most skeletons
+    pub position: Vec3,
+    pub orientation: Quat,
+    pub length: f32,
+    pub radius: f32,
+    // how it turns against its parent, ignored by a bone without one
+    pub joint: BoneJoint,
+}
+
+impl Default for Bone {
+    fn default() -> Self {
+        Self {
+            parent: None,
+            position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            length: 0.3,
+            radius: 0.08,
+            joint: BoneJoint::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RagdollBuilder {
+    pub bones: Vec<Bone>,
+    // the bodies' mass comes from their capsules, about a person's by default
+    pub density: f32,
+    pub friction: f32,
+    // drives every joint, with no target velocity it's how stiff the joints are
+    pub motor: Option<JointMotor>,
+}
+
+impl Default for RagdollBuilder {
+    fn default() -> Self {
+        Self {
+            bones: Vec::new(),
+            density: 1000.0,
+            friction: 0.5,
+            motor: Some(JointMotor {
+                target_velocity: 0.0,
+                max_force: 5.0,
+            }),
+        }
+    }
+}
+
+impl RagdollBuilder {
+    // returns the bone's index, for its children's parent
+    pub fn add_bone(&mut self, bone: Bone) -> usize {
+        let index = self.bones.len();
+        assert!(
+            bone.parent.map_or(true, |parent| parent < index),
+            "a bone's parent must be added before it"
+        );
+        self.bones.push(bone);
+        index
+    }
+
+    pub fn build(&self, scene: &mut PhysicsScene) -> Ragdoll {
+        let mut bodies: Vec<BodyHandle> = Vec::with_capacity(self.bones.len());
+        let mut joints = Vec::with_capacity(self.bones.len());
+        for bone in &self.bones {
+            let handle = scene.add_body(
+                Body {
+                    position: bone.position + bone.orientation * Vec3::Y * (bone.length * 0.5),
+                    orientation: bone.orientation,
+                    friction: self.friction,
+                    shape: Shape::make_capsule(
+                        (bone.length * 0.5 - bone.radius).max(0.0),
+                        bone.radius,
+                    ),
+                    ..Body::default()
+                }
+                .with_density(self.density),
+            );
+
+            let joint = bone.parent.map(|parent| {
+                let parent_handle = bodies[parent];
+                let parent_orientation = self.bones[parent].orientation;
+                // the capsules overlap where they're joined, as do the ones sharing a parent
+                scene.set_pair_ignored(parent_handle, handle, true);
+                for (sibling, other) in self.bones.iter().zip(&bodies) {
+                    if sibling.parent == Some(parent) {
+                        scene.set_pair_ignored(*other, handle, true);
+                    }
+                }
+
+                let joint = match bone.joint {
+                    BoneJoint::Cone { swing } => scene.constraints.add_spherical_constraint(
+                        &scene.bodies,
+                        parent_handle,
+                        handle,
+                        bone.position,
+                        parent_orientation.inverse() * (bone.orientation * Vec3::Y),
+                        Some(swing),
+                    ),
+                    BoneJoint::Hinge { axis, limits } => {
+                        scene.constraints.add_hinge_constraint_with_limits(
+                            &scene.bodies,
+                            parent_handle,
+                            handle,
+                            bone.position,
+                            parent_orientation.inverse() * (bone.orientation * axis),
+                            Some(limits),
+                        )
+                    }
+                };
+                scene.constraints.set_motor(joint, self.motor);
+                joint
+            });
+            bodies.push(handle);
+            joints.push(joint);
+        }
+
+        Ragdoll {
+            bodies,
+            joints,
+            lengths: self.bones.iter().map(|bone| bone.length).collect(),
+        }
+    }
+}
+
+// the handles are only good until one of the bodies is removed from the scene
+#[derive(Clone, Debug)]
+pub struct Ragdoll {
+    // in the order the bones were added
+    pub bodies: Vec<BodyHandle>,
+    // the joint to each bone's parent, None for the roots
+    pub joints: Vec<Option<ConstraintHandle>>,
+    lengths: Vec<f32>,
+}
+
+impl Ragdoll {
+    // the bone's head and orientation, as it was given to the builder
+    pub fn bone_transform(&self, scene: &PhysicsScene, index: usize) -> (Vec3, Quat) {
+        let body = scene.bodies.get_body(self.bodies[index]);
+        let head = body.position - body.orientation * Vec3::Y * (self.lengths[index] * 0.5);
+        (head, body.orientation)
+    }
+
+    // every bone's transform, see bone_transform
+    pub fn pose(&self, scene: &PhysicsScene) -> Vec<(Vec3, Quat)> {
+        (0..self.bodies.len())
+            .map(|index| self.bone_transform(scene, index))
+            .collect()
+    }
+
+    // pushes the bones towards the pose, one transform for each bone like pose() returns, over
+    // the next step. a strength of 1.0 sets the velocities that would reach it in the step and
+    // 0.0 leaves it limp, values between blend the ragdoll with the animation
+    pub fn drive_to_pose(
+        &self,
+        scene: &mut PhysicsScene,
+        pose: &[(Vec3, Quat)],
+        strength: f32,
+        delta_seconds: f32,
+    ) {
+        assert_eq!(pose.len(), self.bodies.len(), "one transform for each bone");
+        for ((&handle, &(head, orientation)), &length) in
+            self.bodies.iter().zip(pose).zip(&self.lengths)
+        {
+            let body = scene.bodies.get_body_mut(handle);
+            let position = head + orientation * Vec3::Y * (length * 0.5);
+            let linear_velocity = (position - body.position) / delta_seconds;
+
+            // the shorter way round
+            let mut turn = orientation * body.orientation.conjugate();
+            if turn.w < 0.0 {
+                turn = -turn;
+            }
+            let (axis, angle) = turn.to_axis_angle();
+            let angular_velocity = axis * (angle / delta_seconds);
+
+            body.linear_velocity = body.linear_velocity.lerp(linear_velocity, strength);
+            body.angular_velocity = body.angular_velocity.lerp(angular_velocity, strength);
+            body.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene_shapes::make_box_ground;
+    use std::f32::consts::PI;
+
+    fn step(scene: &mut PhysicsScene, seconds: f32) {
+        for _ in 0..(seconds * 60.0) as u32 {
+            scene.update(1.0 / 60.0);
+        }
+    }
+
+    // a pelvis with a spine and head above it and two legs below, standing with its feet at 1.0
+    fn make_person() -> RagdollBuilder {
+        let mut builder = RagdollBuilder::default();
+        let down = Quat::from_rotation_z(PI);
+        let pelvis = builder.add_bone(Bone {
+            position: Vec3::new(0.0, 2.0, 0.0),
+            length: 0.3,
+            radius: 0.12,
+            ..Bone::default()
+        });
+        let spine = builder.add_bone(Bone {
+            parent: Some(pelvis),
+            position: Vec3::new(0.0, 2.3, 0.0),
+            length: 0.5,
+            radius: 0.14,
+            joint: BoneJoint::Cone { swing: 30.0 },
+            ..Bone::default()
+        });
+        builder.add_bone(Bone {
+            parent: Some(spine),
+            position: Vec3::new(0.0, 2.8, 0.0),
+            length: 0.25,
+            radius: 0.1,
+            ..Bone::default()
+        });
+        for &x in &[-0.1, 0.1] {
+            let thigh = builder.add_bone(Bone {
+                parent: Some(pelvis),
+                position: Vec3::new(x, 2.0, 0.0),
+                orientation: down,
+                length: 0.5,
+                ..Bone::default()
+            });
+            builder.add_bone(Bone {
+                parent: Some(thigh),
+                position: Vec3::new(x, 1.5, 0.0),
+                orientation: down,
+                length: 0.5,
+                joint: BoneJoint::Hinge {
+                    axis: Vec3::X,
+                    limits: (0.0, 140.0),
+                },
+                ..Bone::default()
+            });
+        }
+        builder
+    }
+
+    #[test]
+    fn test_ragdoll_build() {
+        let mut scene = PhysicsScene::empty();
+        let builder = make_person();
+        let ragdoll = builder.build(&mut scene);
+        assert_eq!(ragdoll.bodies.len(), 7);
+        assert!(ragdoll.joints[0].is_none());
+        assert!(ragdoll.joints[1..].iter().all(Option::is_some));
+
+        // the bodies sit on the bones they were built from
+        for (bone, (head, orientation)) in builder.bones.iter().zip(ragdoll.pose(&scene)) {
+            assert!((head - bone.position).length() < 1e-5);
+            assert!(orientation.dot(bone.orientation).abs() > 0.9999);
+        }
+    }
+
+    #[test]
+    fn test_ragdoll_falls() {
+        // dropped on the ground it crumples without coming apart
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let ragdoll = make_person().build(&mut scene);
+        step(&mut scene, 5.0);
+
+        for (&handle, joint) in ragdoll.bodies.iter().zip(&ragdoll.joints) {
+            let body = scene.get_body(handle).unwrap();
+            assert!(
+                body.position.y > 0.0 && body.position.y < 1.0,
+                "{}",
+                body.position
+            );
+            assert!(body.linear_velocity.length() < 0.5);
+            if let Some(joint) = joint {
+                assert!(scene.constraints().residual(*joint) < 0.15);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ragdoll_drive_to_pose() {
+        // driven at full strength it holds the pose it was built in against gravity
+        let mut scene = PhysicsScene::empty();
+        let ragdoll = make_person().build(&mut scene);
+        let pose = ragdoll.pose(&scene);
+        for _ in 0..120 {
+            ragdoll.drive_to_pose(&mut scene, &pose, 1.0, 1.0 / 60.0);
+            scene.update(1.0 / 60.0);
+        }
+        for ((head, _), (target, _)) in ragdoll.pose(&scene).iter().zip(&pose) {
+            assert!((*head - *target).length() < 0.05, "{} {}", head, target);
+        }
+
+        // and left limp it falls
+        step(&mut scene, 0.5);
+        assert!(ragdoll.bone_transform(&scene, 0).0.y < 1.0);
+    }
+}
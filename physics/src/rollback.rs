@@ -0,0 +1,163 @@
+// steps a scene at a fixed rate while keeping a snapshot and the inputs of each of the last few
+// steps, so an input that arrives late can be put in the step it belongs to and the steps since
+// then simulated again
+use crate::scene::{PhysicsScene, SceneSnapshot};
+use std::collections::VecDeque;
+
+// applies an input to the scene before a step, like adding a force or setting a kinematic target
+pub type ApplyInput<I> = Box<dyn FnMut(&mut PhysicsScene, &I) + Send + Sync>;
+
+struct Frame<I> {
+    // the scene's step_num before this step
+    step: u64,
+    // the scene before the inputs were applied
+    snapshot: SceneSnapshot,
+    inputs: Vec<I>,
+    // the scene's state_hash after the step
+    checksum: u64,
+}
+
+pub struct Rollback<I> {
+    dt: f32,
+    // how many steps back inputs can still be added
+    max_frames: usize,
+    frames: VecDeque<Frame<I>>,
+    apply: ApplyInput<I>,
+}
+
+impl<I> Rollback<I> {
+    pub fn new(dt: f32, max_frames: usize, apply: ApplyInput<I>) -> Self {
+        assert!(max_frames > 0, "rollback needs at least one frame");
+        Self {
+            dt,
+            max_frames,
+            frames: VecDeque::with_capacity(max_frames),
+            apply,
+        }
+    }
+
+    // applies the inputs and steps the scene once, returns the step the inputs were given to
+    pub fn step(&mut self, scene: &mut PhysicsScene, inputs: Vec<I>) -> u64 {
+        if self.frames.len() == self.max_frames {
+            self.frames.pop_front();
+        }
+        let step = scene.step_num();
+        let mut frame = Frame {
+            step,
+            snapshot: scene.snapshot(),
+            inputs,
+            checksum: 0,
+        };
+        self.simulate(scene, &mut frame);
+        self.frames.push_back(frame);
+        step
+    }
+
+    // adds an input to an earlier step and simulates the steps from there again. false if the
+    // step has already been forgotten or hasn't happened yet
+    pub fn add_input(&mut self, scene: &mut PhysicsScene, step: u64, input: I) -> bool {
+        let index = match self.frame_index(step) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.frames[index].inputs.push(input);
+        self.resimulate_from(scene, index);
+        true
+    }
+
+    // the scene's state_hash after the step, to compare with other peers
+    pub fn checksum(&self, step: u64) -> Option<u64> {
+        self.frame_index(step)
+            .map(|index| self.frames[index].checksum)
+    }
+
+    // the earliest step that can still be rolled back to
+    pub fn oldest_step(&self) -> Option<u64> {
+        self.frames.front().map(|frame| frame.step)
+    }
+
+    fn frame_index(&self, step: u64) -> Option<usize> {
+        let oldest = self.oldest_step()?;
+        let index = step.checked_sub(oldest)? as usize;
+        (index < self.frames.len()).then_some(index)
+    }
+
+    // restores the scene to before the frame at index and steps it through the rest again
+    fn resimulate_from(&mut self, scene: &mut PhysicsScene, index: usize) {
+        scene.restore(&self.frames[index].snapshot);
+        let mut frames = std::mem::take(&mut self.frames);
+        for (i, frame) in frames.iter_mut().enumerate().skip(index) {
+            if i > index {
+                frame.snapshot = scene.snapshot();
+            }
+            self.simulate(scene, frame);
+        }
+        self.frames = frames;
+    }
+
+    fn simulate(&mut self, scene: &mut PhysicsScene, frame: &mut Frame<I>) {
+        for input in &frame.inputs {
+            (self.apply)(scene, input);
+        }
+        scene.update(self.dt);
+        frame.checksum = scene.state_hash();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{body::Body, body::BodyHandle, scene_shapes::make_sphere};
+    use glam::Vec3;
+
+    fn make_scene() -> (PhysicsScene, BodyHandle) {
+        let mut scene = PhysicsScene::empty();
+        let handle = scene.add_body(Body {
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        (scene, handle)
+    }
+
+    // pushes the one body in the scene sideways
+    fn push() -> ApplyInput<Vec3> {
+        Box::new(|scene, force| scene.add_force(BodyHandle(0, 0), *force))
+    }
+
+    #[test]
+    fn test_late_input() {
+        let dt = 1.0 / 60.0;
+        let push_force = Vec3::new(60.0, 0.0, 0.0);
+
+        // the input arrives in time
+        let (mut on_time, _) = make_scene();
+        let mut rollback = Rollback::new(dt, 8, push());
+        for step in 0..10 {
+            let inputs = if step == 3 { vec![push_force] } else { vec![] };
+            rollback.step(&mut on_time, inputs);
+        }
+
+        // the same input arrives 4 steps late
+        let (mut late, handle) = make_scene();
+        let mut late_rollback = Rollback::new(dt, 8, push());
+        for _ in 0..7 {
+            late_rollback.step(&mut late, vec![]);
+        }
+        assert_eq!(0.0, late.get_body(handle).unwrap().position.x);
+        assert!(late_rollback.add_input(&mut late, 3, push_force));
+        for _ in 7..10 {
+            late_rollback.step(&mut late, vec![]);
+        }
+
+        assert!(late.get_body(handle).unwrap().position.x > 0.0);
+        assert_eq!(on_time.state_hash(), late.state_hash());
+        for step in 2..10 {
+            assert_eq!(rollback.checksum(step), late_rollback.checksum(step));
+        }
+
+        // only the last 8 steps are kept
+        assert_eq!(Some(2), late_rollback.oldest_step());
+        assert!(!late_rollback.add_input(&mut late, 1, push_force));
+        assert!(!late_rollback.add_input(&mut late, 10, push_force));
+    }
+}
@@ -0,0 +1,275 @@
+// a rope or chain made of capsule links. each link is an ordinary body joined to the next by a
+// ball and socket joint, the cone limit keeps it from folding back on itself and the joint's
+// motor holds back the swing so it doesn't whip about. either end can be tied to another body
+use crate::{
+    body::{Body, BodyHandle},
+    constraints::{ConstraintHandle, JointMotor},
+    scene::PhysicsScene,
+    shapes::Shape,
+};
+use glam::{Quat, Vec3};
+
+#[derive(Clone, Debug)]
+pub struct RopeConfig {
+    pub radius: f32,
+    // of each link
+    pub inv_mass: f32,
+    pub friction: f32,
+    // the most each joint can bend, in degrees, None bends all the way
+    pub cone_limit: Option<f32>,
+    // drives each joint, with no target velocity it's how stiff the rope is
+    pub motor: Option<JointMotor>,
+}
+
+impl Default for RopeConfig {
+    fn default() -> Self {
+        Self {
+            radius: 0.05,
+            inv_mass: 10.0,
+            friction: 0.5,
+            cone_limit: Some(45.0),
+            motor: Some(JointMotor {
+                target_velocity: 0.0,
+                max_force: 1.0,
+            }),
+        }
+    }
+}
+
+// the handles are only good until one of the bodies is removed from the scene
+#[derive(Clone, Debug)]
+pub struct Rope {
+    // from the start to the end
+    pub links: Vec<BodyHandle>,
+    // joints[i] joins links[i] and links[i + 1]
+    pub joints: Vec<ConstraintHandle>,
+    // the joints tying the ends to other bodies
+    pub start_attachment: Option<ConstraintHandle>,
+    pub end_attachment: Option<ConstraintHandle>,
+    // each link's length from tip to tip
+    pub link_length: f32,
+    config: RopeConfig,
+}
+
+impl Rope {
+    // a straight rope from start to end, split into that many links
+    pub fn spawn(
+        scene: &mut PhysicsScene,
+        start: Vec3,
+        end: Vec3,
+        segments: usize,
+        config: RopeConfig,
+    ) -> Self {
+        assert!(segments > 0, "a rope needs at least one link");
+        let length = (end - start).length();
+        assert!(length > 0.0, "a rope needs its ends apart");
+        let dir = (end - start) / length;
+        let link_length = length / segments as f32;
+
+        // the capsules stand along their y axis, turned to run along the rope
+        let orientation = Quat::from_rotation_arc(Vec3::Y, dir);
+        let shape =
+            Shape::make_capsule((link_length * 0.5 - config.radius).max(0.0), config.radius);
+
+        let mut links = Vec::with_capacity(segments);
+        let mut joints = Vec::with_capacity(segments - 1);
+        for i in 0..segments {
+            let handle = scene.add_body(Body {
+                position: start + dir * link_length * (i as f32 + 0.5),
+                orientation,
+                inv_mass: config.inv_mass,
+                friction: config.friction,
+                shape: shape.clone(),
+                ..Body::default()
+            });
+
+            if let Some(&previous) = links.last() {
+                // the links' caps overlap at the joint
+                scene.set_pair_ignored(previous, handle, true);
+                let joint = scene.constraints.add_spherical_constraint(
+                    &scene.bodies,
+                    previous,
+                    handle,
+                    start + dir * link_length * i as f32,
+                    Vec3::Y,
+                    config.cone_limit,
+                );
+                scene.constraints.set_motor(joint, config.motor);
+                joints.push(joint);
+            }
+            links.push(handle);
+        }
+
+        Self {
+            links,
+            joints,
+            start_attachment: None,
+            end_attachment: None,
+            link_length,
+            config,
+        }
+    }
+
+    pub fn first(&self) -> BodyHandle {
+        self.links[0]
+    }
+
+    pub fn last(&self) -> BodyHandle {
+        self.links[self.links.len() - 1]
+    }
+
+    // where the rope starts and ends in the world
+    pub fn start_point(&self, scene: &PhysicsScene) -> Vec3 {
+        self.tip(scene, self.first(), -1.0)
+    }
+
+    pub fn end_point(&self, scene: &PhysicsScene) -> Vec3 {
+        self.tip(scene, self.last(), 1.0)
+    }
+
+    // ties the start of the rope to the body where it is now
+    pub fn attach_start(&mut self, scene: &mut PhysicsScene, body: BodyHandle) -> ConstraintHandle {
+        let anchor = self.start_point(scene);
+        let joint = self.attach(scene, body, self.first(), anchor);
+        self.start_attachment = Some(joint);
+        joint
+    }
+
+    // ties the end of the rope to the body where it is now
+    pub fn attach_end(&mut self, scene: &mut PhysicsScene, body: BodyHandle) -> ConstraintHandle {
+        let anchor = self.end_point(scene);
+        let joint = self.attach(scene, body, self.last(), anchor);
+        self.end_attachment = Some(joint);
+        joint
+    }
+
+    // the tip of the link at the rope's start, -1.0, or end, 1.0
+    fn tip(&self, scene: &PhysicsScene, link: BodyHandle, side: f32) -> Vec3 {
+        scene.bodies.get_body(link).local_to_world(Vec3::new(
+            0.0,
+            side * self.link_length * 0.5,
+            0.0,
+        ))
+    }
+
+    // the body can turn any way about the tip, the cone limit only applies between links
+    fn attach(
+        &self,
+        scene: &mut PhysicsScene,
+        body: BodyHandle,
+        link: BodyHandle,
+        anchor: Vec3,
+    ) -> ConstraintHandle {
+        scene.set_pair_ignored(body, link, true);
+        let joint = scene.constraints.add_spherical_constraint(
+            &scene.bodies,
+            body,
+            link,
+            anchor,
+            Vec3::Y,
+            None,
+        );
+        scene.constraints.set_motor(joint, self.config.motor);
+        joint
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn step(scene: &mut PhysicsScene, seconds: f32) {
+        for _ in 0..(seconds * 60.0) as u32 {
+            scene.update(1.0 / 60.0);
+        }
+    }
+
+    fn make_hook(scene: &mut PhysicsScene, position: Vec3) -> BodyHandle {
+        scene.add_body(Body {
+            position,
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.1)),
+            ..Body::default()
+        })
+    }
+
+    #[test]
+    fn test_rope_spawn() {
+        let mut scene = PhysicsScene::empty();
+        let start = Vec3::new(0.0, 5.0, 0.0);
+        let end = Vec3::new(4.0, 5.0, 0.0);
+        let rope = Rope::spawn(&mut scene, start, end, 8, RopeConfig::default());
+        assert_eq!(rope.links.len(), 8);
+        assert_eq!(rope.joints.len(), 7);
+        assert!((rope.link_length - 0.5).abs() < 1e-6);
+        assert!((rope.start_point(&scene) - start).length() < 1e-5);
+        assert!((rope.end_point(&scene) - end).length() < 1e-5);
+    }
+
+    // how far the end of a rope tied to a hook at its start swings to the side after it's
+    // fallen for a while
+    fn swing(config: RopeConfig) -> f32 {
+        let mut scene = PhysicsScene::empty();
+        let hook = make_hook(&mut scene, Vec3::new(0.0, 5.0, 0.0));
+        let mut rope = Rope::spawn(
+            &mut scene,
+            Vec3::new(0.0, 5.0, 0.0),
+            Vec3::new(2.0, 5.0, 0.0),
+            8,
+            config,
+        );
+        rope.attach_start(&mut scene, hook);
+        step(&mut scene, 8.0);
+
+        let mut swing: f32 = 0.0;
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+            let end = rope.end_point(&scene);
+            swing = swing.max(end.x.abs());
+            // hanging below the hook, the joints give a little
+            let hang = Vec3::new(0.0, 5.0, 0.0) - end;
+            assert!(hang.y > 1.0 && hang.length() < 2.0 + 0.15 * 8.0, "{}", end);
+        }
+        swing
+    }
+
+    #[test]
+    fn test_rope_hanging() {
+        // the joints' motors hold back the swing
+        let free = swing(RopeConfig {
+            motor: None,
+            ..RopeConfig::default()
+        });
+        let damped = swing(RopeConfig::default());
+        assert!(free > 1.5);
+        assert!(damped < free * 0.75);
+    }
+
+    #[test]
+    fn test_rope_attached_both_ends() {
+        // slung between two hooks, it sags in the middle
+        let mut scene = PhysicsScene::empty();
+        let hook_a = make_hook(&mut scene, Vec3::new(-2.0, 5.0, 0.0));
+        let hook_b = make_hook(&mut scene, Vec3::new(2.0, 5.0, 0.0));
+        let mut rope = Rope::spawn(
+            &mut scene,
+            Vec3::new(-2.0, 5.0, 0.0),
+            Vec3::new(2.0, 5.0, 0.0),
+            10,
+            RopeConfig::default(),
+        );
+        rope.attach_start(&mut scene, hook_a);
+        rope.attach_end(&mut scene, hook_b);
+        assert!(rope.start_attachment.is_some() && rope.end_attachment.is_some());
+        step(&mut scene, 5.0);
+
+        assert!((rope.start_point(&scene) - Vec3::new(-2.0, 5.0, 0.0)).length() < 0.15);
+        assert!((rope.end_point(&scene) - Vec3::new(2.0, 5.0, 0.0)).length() < 0.15);
+        let middle = scene.get_body(rope.links[5]).unwrap().position;
+        assert!(
+            middle.x.abs() < 0.3 && middle.y < 4.5 && middle.y > 3.0,
+            "{}",
+            middle
+        );
+    }
+}
@@ -1,13 +1,35 @@
 use crate::{
-    body::{Body, BodyArena, BodyHandle},
-    broadphase::broadphase,
-    constraints::ConstraintArena,
-    contact::{Contact, ContactArena},
-    intersect::intersect_dynamic,
+    body::{Body, BodyArena, BodyHandle, CombineRules, MaterialHandle, PhysicsMaterial},
+    bounds::Bounds,
+    broadphase::{
+        Broadphase, BroadphaseBvh, BroadphaseGrid, BroadphaseKind, CollisionPair, SweepAndPrune,
+    },
+    constraints::{ConstraintArena, ConstraintBroken, ConstraintPenetration},
+    contact::{
+        BodyVelocity, CollisionEvent, CollisionEventKind, CollisionMode, Contact, ContactArena,
+        ContactMaterial, ContactOrder, ModifyContact, PairSolveTrace, TriggerEvent,
+        TriggerEventKind,
+    },
+    fluid::{FluidHandle, FluidVolume},
+    force_field::{ForceField, ForceFieldHandle},
+    intersect::{bodies_touch, intersect_pairs},
+    island::{build_islands, build_solver_islands, Island},
+    level::{Level, LevelBody},
     manifold::ManifoldCollector,
+    math::{LcpSystem, MatMN},
+    query::{
+        intersections_with_aabb, intersections_with_point, intersections_with_shape,
+        radial_impulses, ray_cast, shape_cast, Falloff, RayHit, ShapeHit,
+    },
     scene_shapes::*,
+    shapes::Shape,
+    stats::{StageTimer, StepStats},
+    vehicle::{Vehicle, VehicleHandle},
 };
-use glam::{const_vec3, Quat, Vec3};
+use glam::{const_vec3, Mat3, Quat, Vec3};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::collections::{HashMap, HashSet};
 
 #[allow(dead_code)]
 fn add_sphere(bodies: &mut BodyArena) {
@@ -80,7 +102,7 @@ fn add_orientation_constraint(bodies: &mut BodyArena, constraints: &mut Constrai
         ..Body::default()
     });
 
-    constraints.add_orientation_constraint(bodies, handle_a, handle_b)
+    constraints.add_orientation_constraint(bodies, handle_a, handle_b);
 }
 
 #[allow(dead_code)]
@@ -100,6 +122,7 @@ fn add_dynamic_balls(bodies: &mut BodyArena) {
                 elasticity: 0.5,
                 friction: 0.5,
                 shape: ball_shape.clone(),
+                ..Body::default()
             });
         }
     }
@@ -118,6 +141,7 @@ fn add_distance_constraint(bodies: &mut BodyArena, constraints: &mut ConstraintA
         elasticity: 1.0,
         friction: 0.5,
         shape: cube_shape.clone(),
+        ..Body::default()
     });
 
     let handle_b = bodies.add(Body {
@@ -129,6 +153,7 @@ fn add_distance_constraint(bodies: &mut BodyArena, constraints: &mut ConstraintA
         elasticity: 1.0,
         friction: 0.5,
         shape: cube_shape.clone(),
+        ..Body::default()
     });
 
     constraints.add_distance_constraint(bodies, handle_a, handle_b);
@@ -273,7 +298,42 @@ fn add_constant_velocity_constraint(bodies: &mut BodyArena, constraints: &mut Co
         handle_b,
         body_a.position,
         body_a.orientation.inverse() * Vec3::X,
-    )
+    );
+}
+
+#[allow(dead_code)]
+fn add_spherical_pendulum(bodies: &mut BodyArena, constraints: &mut ConstraintArena) {
+    let cube_shape = make_cube_small();
+
+    let handle_a = bodies.add(Body {
+        position: Vec3::new(6.0, 6.0, -5.0),
+        inv_mass: 0.0,
+        elasticity: 0.9,
+        friction: 0.5,
+        shape: cube_shape.clone(),
+        ..Body::default()
+    });
+
+    let handle_b = bodies.add(Body {
+        position: Vec3::new(6.0, 4.0, -5.0),
+        linear_velocity: Vec3::new(3.0, 0.0, 3.0),
+        inv_mass: 1.0,
+        elasticity: 1.0,
+        friction: 0.5,
+        shape: cube_shape,
+        ..Body::default()
+    });
+
+    // swings up to 30 degrees from hanging straight down
+    let body_a = bodies.get_body(handle_a);
+    constraints.add_spherical_constraint(
+        bodies,
+        handle_a,
+        handle_b,
+        body_a.position,
+        -Vec3::Y,
+        Some(30.0),
+    );
 }
 
 #[allow(dead_code)]
@@ -543,6 +603,7 @@ fn add_standard_sandbox(bodies: &mut BodyArena) {
         elasticity: 0.5,
         friction: 0.5,
         shape: box_ground,
+        ..Body::default()
     });
 
     bodies.add(Body {
@@ -554,6 +615,7 @@ fn add_standard_sandbox(bodies: &mut BodyArena) {
         elasticity: 0.5,
         friction: 0.0,
         shape: box_wall0.clone(),
+        ..Body::default()
     });
 
     bodies.add(Body {
@@ -565,6 +627,7 @@ fn add_standard_sandbox(bodies: &mut BodyArena) {
         elasticity: 0.5,
         friction: 0.0,
         shape: box_wall0,
+        ..Body::default()
     });
 
     bodies.add(Body {
@@ -576,6 +639,7 @@ fn add_standard_sandbox(bodies: &mut BodyArena) {
         elasticity: 0.5,
         friction: 0.0,
         shape: box_wall1.clone(),
+        ..Body::default()
     });
 
     bodies.add(Body {
@@ -587,20 +651,177 @@ fn add_standard_sandbox(bodies: &mut BodyArena) {
         elasticity: 0.5,
         friction: 0.0,
         shape: box_wall1,
+        ..Body::default()
     });
 }
 
-fn resolve_contact(bodies: &mut BodyArena, contact: &Contact) {
+// the impulses that resolved a contact
+#[derive(Copy, Clone, Debug, Default)]
+struct ContactImpulse {
+    normal: f32, // positive when pushing the bodies apart
+    friction: f32,
+}
+
+fn resolve_contact(
+    bodies: &mut BodyArena,
+    contact: &Contact,
+    material: &ContactMaterial,
+) -> ContactImpulse {
     let (body_a, body_b) = bodies.get_body_pair_mut(contact.handle_a, contact.handle_b);
     debug_assert!(!body_a.has_infinite_mass() || !body_b.has_infinite_mass());
 
+    // dynamic vs static is the most common contact, all of the static body's mass and inertia
+    // terms are zero so they can be skipped
+    let is_static_a = body_a.inv_mass * body_a.response_weight == 0.0;
+    let is_static_b = body_b.inv_mass * body_b.response_weight == 0.0;
+    if is_static_b && !is_static_a {
+        resolve_contact_dynamic_static(
+            body_a,
+            contact.local_point_a,
+            body_b,
+            contact.local_point_b,
+            contact.normal,
+            contact.time_of_impact,
+            material,
+        )
+    } else if is_static_a && !is_static_b {
+        resolve_contact_dynamic_static(
+            body_b,
+            contact.local_point_b,
+            body_a,
+            contact.local_point_a,
+            -contact.normal,
+            contact.time_of_impact,
+            // seen from body b
+            &ContactMaterial {
+                surface_velocity: -material.surface_velocity,
+                ..*material
+            },
+        )
+    } else {
+        resolve_contact_general(body_a, body_b, contact, material)
+    }
+}
+
+// resolves a contact where only `body` responds, the normal points from `fixed` towards `body`
+fn resolve_contact_dynamic_static(
+    body: &mut Body,
+    local_point: Vec3,
+    fixed: &Body,
+    fixed_local_point: Vec3,
+    normal: Vec3,
+    time_of_impact: f32,
+    material: &ContactMaterial,
+) -> ContactImpulse {
+    let point_on_a = body.local_to_world(local_point);
+    let point_on_b = fixed.local_to_world(fixed_local_point);
+
+    let elasticity = material.elasticity;
+
+    let weight = body.response_weight;
+    let inv_inertia_world = body.inv_intertia_tensor_world() * weight;
+
+    let ra = point_on_a - body.centre_of_mass_world();
+    let rb = point_on_b - fixed.centre_of_mass_world();
+
+    let angular_j = (inv_inertia_world * ra.cross(normal)).cross(ra);
+    let angular_factor = angular_j.dot(normal);
+
+    // the static body may still be moving (e.g. a scripted platform)
+    let vel_a = body.linear_velocity + body.angular_velocity.cross(ra);
+    let vel_b = fixed.linear_velocity + fixed.angular_velocity.cross(rb);
+
+    // calculate the collision impulse
+    let vab = vel_a - vel_b;
+    let denominator = body.inv_mass_along(normal) * weight + angular_factor;
+    if !denominator.is_finite() || denominator <= 0.0 {
+        // a degenerate contact can't be resolved
+        return ContactImpulse::default();
+    }
+    let impulse_j = (1.0 + elasticity) * vab.dot(normal) / denominator;
+    let vec_impulse_j = normal * impulse_j;
+
+    body.apply_impulse(point_on_a, -vec_impulse_j * weight);
+
+    // calculate the impulse caused by friction
+    let friction = material.friction;
+    let vab = vab - material.surface_velocity;
+    let vel_normal = normal * normal.dot(vab);
+    let vel_tan = vab - vel_normal;
+    let rel_vel_tan = vel_tan.normalize_or_zero();
+
+    let inertia = (inv_inertia_world * ra.cross(rel_vel_tan)).cross(ra);
+    let inv_inertia = inertia.dot(rel_vel_tan);
+
+    let reduced_mass = 1.0 / (body.inv_mass_along(rel_vel_tan) * weight + inv_inertia);
+    let impulse_friction = vel_tan * reduced_mass * friction;
+
+    body.apply_impulse(point_on_a, -impulse_friction * weight);
+
+    let impulse_angular = angular_friction_impulse(
+        body.angular_velocity - fixed.angular_velocity,
+        normal,
+        inv_inertia_world,
+        impulse_j.abs(),
+        material,
+    );
+    body.apply_impulse_angular(-impulse_angular * weight);
+
+    // the dynamic body takes all of the projection
+    if time_of_impact == 0.0 {
+        body.position += (point_on_b - point_on_a) * body.locked_axes.translation_mask();
+    }
+
+    ContactImpulse {
+        normal: -impulse_j,
+        friction: impulse_friction.length(),
+    }
+}
+
+// the angular impulse on body b that stops the bodies turning against each other, up to the
+// rolling and spinning friction's share of the normal impulse. the relative angular velocity is
+// body a's less body b's and inv_inertia is the sum of theirs, in world space
+fn angular_friction_impulse(
+    relative_angular_velocity: Vec3,
+    normal: Vec3,
+    inv_inertia: Mat3,
+    normal_impulse: f32,
+    material: &ContactMaterial,
+) -> Vec3 {
+    let stop = |angular_velocity: Vec3, coefficient: f32| {
+        let axis = angular_velocity.normalize_or_zero();
+        let inv_effective_inertia = axis.dot(inv_inertia * axis);
+        if coefficient <= 0.0 || inv_effective_inertia <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let impulse = angular_velocity.length() / inv_effective_inertia;
+        axis * impulse.min(coefficient * normal_impulse)
+    };
+    let spin = normal * normal.dot(relative_angular_velocity);
+    let roll = relative_angular_velocity - spin;
+    stop(spin, material.spinning_friction) + stop(roll, material.rolling_friction)
+}
+
+fn resolve_contact_general(
+    body_a: &mut Body,
+    body_b: &mut Body,
+    contact: &Contact,
+    material: &ContactMaterial,
+) -> ContactImpulse {
     let point_on_a = body_a.local_to_world(contact.local_point_a);
     let point_on_b = body_b.local_to_world(contact.local_point_b);
 
-    let elasticity = body_a.elasticity * body_b.elasticity;
+    let elasticity = material.elasticity;
 
-    let inv_inertia_world_a = body_a.inv_intertia_tensor_world();
-    let inv_inertia_world_b = body_b.inv_intertia_tensor_world();
+    // the response weight biases how the response is split between the bodies, a body with a
+    // weight of zero is treated as if it had infinite mass for this contact
+    let weight_a = body_a.response_weight;
+    let weight_b = body_b.response_weight;
+    let inv_mass_a = body_a.inv_mass * weight_a;
+    let inv_mass_b = body_b.inv_mass * weight_b;
+
+    let inv_inertia_world_a = body_a.inv_intertia_tensor_world() * weight_a;
+    let inv_inertia_world_b = body_b.inv_intertia_tensor_world() * weight_b;
 
     let ra = point_on_a - body_a.centre_of_mass_world();
     let rb = point_on_b - body_b.centre_of_mass_world();
@@ -615,18 +836,30 @@ fn resolve_contact(bodies: &mut BodyArena, contact: &Contact) {
 
     // calculate the collision impulse
     let vab = vel_a - vel_b;
-    let total_inv_mass = body_a.inv_mass + body_b.inv_mass;
-    let impulse_j =
-        (1.0 + elasticity) * vab.dot(contact.normal) / (total_inv_mass + angular_factor);
+    let total_inv_mass = inv_mass_a + inv_mass_b;
+    if total_inv_mass == 0.0 {
+        // neither body takes any of the response
+        return ContactImpulse::default();
+    }
+    let denominator = body_a.inv_mass_along(contact.normal) * weight_a
+        + body_b.inv_mass_along(contact.normal) * weight_b
+        + angular_factor;
+    if !denominator.is_finite() || denominator <= 0.0 {
+        // a degenerate contact can't be resolved
+        return ContactImpulse::default();
+    }
+    let impulse_j = (1.0 + elasticity) * vab.dot(contact.normal) / denominator;
     let vec_impulse_j = contact.normal * impulse_j;
 
-    body_a.apply_impulse(point_on_a, -vec_impulse_j);
-    body_b.apply_impulse(point_on_b, vec_impulse_j);
+    body_a.apply_impulse(point_on_a, -vec_impulse_j * weight_a);
+    body_b.apply_impulse(point_on_b, vec_impulse_j * weight_b);
 
     // calculate the impulse caused by friction
-    let friction = body_a.friction * body_b.friction;
+    let friction = material.friction;
 
-    // find the normal direction of the velocity with respect to the normal of the collision
+    // find the normal direction of the velocity with respect to the normal of the collision, the
+    // friction drags the surfaces towards sliding at the surface velocity
+    let vab = vab - material.surface_velocity;
     let vel_normal = contact.normal * contact.normal.dot(vab);
 
     // find the tangent direction of the velocity with respect to the normal of the collision
@@ -640,55 +873,246 @@ fn resolve_contact(bodies: &mut BodyArena, contact: &Contact) {
     let inv_inertia = (inertia_a + inertia_b).dot(rel_vel_tan);
 
     // calculate the tangential impulse for friction
-    let reduced_mass = 1.0 / (total_inv_mass + inv_inertia);
+    let reduced_mass = 1.0
+        / (body_a.inv_mass_along(rel_vel_tan) * weight_a
+            + body_b.inv_mass_along(rel_vel_tan) * weight_b
+            + inv_inertia);
     let impulse_friction = vel_tan * reduced_mass * friction;
 
     // apply kinetic friction
-    body_a.apply_impulse(point_on_a, -impulse_friction);
-    body_b.apply_impulse(point_on_b, impulse_friction);
+    body_a.apply_impulse(point_on_a, -impulse_friction * weight_a);
+    body_b.apply_impulse(point_on_b, impulse_friction * weight_b);
+
+    // resist the bodies rolling and spinning on each other
+    let impulse_angular = angular_friction_impulse(
+        body_a.angular_velocity - body_b.angular_velocity,
+        contact.normal,
+        inv_inertia_world_a + inv_inertia_world_b,
+        impulse_j.abs(),
+        material,
+    );
+    body_a.apply_impulse_angular(-impulse_angular * weight_a);
+    body_b.apply_impulse_angular(impulse_angular * weight_b);
 
     // also move colliding objects to just outside of each other (projection method)
     if contact.time_of_impact == 0.0 {
         let ds = point_on_b - point_on_a;
 
         let rcp_total_inv_mass = 1.0 / total_inv_mass;
-        let t_a = body_a.inv_mass * rcp_total_inv_mass;
-        let t_b = body_b.inv_mass * rcp_total_inv_mass;
+        let t_a = inv_mass_a * rcp_total_inv_mass;
+        let t_b = inv_mass_b * rcp_total_inv_mass;
+
+        body_a.position += ds * body_a.locked_axes.translation_mask() * t_a;
+        body_b.position -= ds * body_b.locked_axes.translation_mask() * t_b;
+    }
 
-        body_a.position += ds * t_a;
-        body_b.position -= ds * t_b;
+    ContactImpulse {
+        normal: -impulse_j,
+        friction: impulse_friction.length(),
     }
 }
 
-pub struct PhysicsScene {
+// a copy of the scene's state, everything but the pub settings and the modify_contact callback.
+// restoring it and stepping again gives the same results, see PhysicsScene::snapshot
+#[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneSnapshot {
     bodies: BodyArena,
+    materials: Vec<PhysicsMaterial>,
     constraints: ConstraintArena,
+    vehicles: Vec<Option<Vehicle>>,
+    fluids: Vec<FluidVolume>,
+    force_fields: Vec<ForceField>,
+    contacts: ContactArena,
+    manifolds: ManifoldCollector,
+    previous_contacts: Vec<Contact>,
+    step_num: u64,
+    rng: Pcg32,
+    ignored_pairs: HashSet<CollisionPair>,
+    broadphase_kind: BroadphaseKind,
+    sweep_and_prune: SweepAndPrune,
+    bvh: BroadphaseBvh,
+    grid: Option<BroadphaseGrid>,
+    accumulated_time: f32,
+    gravity: Vec3,
+    collision_events: Vec<CollisionEvent>,
+    islands: Vec<Island>,
+    sleeping_pairs: HashSet<CollisionPair>,
+    broken_constraints: Vec<ConstraintBroken>,
+    touching_pairs: Vec<(CollisionPair, Contact, u32)>,
+    sensor_overlaps: Vec<CollisionPair>,
+    trigger_events: Vec<TriggerEvent>,
+    solve_traces: Vec<PairSolveTrace>,
+}
+
+pub struct PhysicsScene {
+    pub(crate) bodies: BodyArena,
+    // shared by the bodies that refer to them, they're kept by clear() like the settings
+    materials: Vec<PhysicsMaterial>,
+    pub(crate) constraints: ConstraintArena,
+    // solved every step before the contacts. they go with their chassis, leaving None in their
+    // slots so the handles to the others stay valid
+    vehicles: Vec<Option<Vehicle>>,
+    // push the bodies in them up along with gravity
+    fluids: Vec<FluidVolume>,
+    // applied along with gravity
+    force_fields: Vec<ForceField>,
     contacts: ContactArena,
     manifolds: ManifoldCollector,
+    // the contacts from the step before the last one
+    previous_contacts: Vec<Contact>,
     step_num: u64,
+    rng: Pcg32,
+    ignored_pairs: HashSet<CollisionPair>,
+    broadphase_kind: BroadphaseKind,
+    sweep_and_prune: SweepAndPrune,
+    bvh: BroadphaseBvh,
+    // only made once the grid is picked, since it needs a cell size
+    grid: Option<BroadphaseGrid>,
     pub paused: bool,
+    pub contact_order: ContactOrder,
+    // how the bodies' friction and elasticity are combined when they don't have their own rules
+    pub combine_rules: CombineRules,
+    pub collision_mode: CollisionMode,
+    // bodies moving slower than this for long enough are put to sleep
+    pub sleep_linear_threshold: f32,
+    pub sleep_angular_threshold: f32,
+    // no body goes faster than these after the solver, a safeguard against it blowing up
+    pub max_linear_speed: f32,
+    pub max_angular_speed: f32,
+    // how quickly every body's linear and angular velocities die away, 0.0 keeps them, see
+    // Body::apply_damping
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    // update_fixed steps the scene at fixed_dt, at most max_substeps times per call
+    pub fixed_dt: f32,
+    pub max_substeps: u32,
+    // how many times the constraints and resting contacts are solved each step
+    pub velocity_iterations: u32,
+    // how many times the resting contacts are pushed apart after the bodies have moved. 0 leaves
+    // it to the baumgarte stabilization in the velocity solve, which fixes penetration by adding
+    // velocity the bodies keep afterwards
+    pub position_iterations: u32,
+    accumulated_time: f32,
+    gravity: Vec3,
+    // the up direction for slopes and grounded checks
+    pub world_up: Vec3,
+    collision_events: Vec<CollisionEvent>,
+    // the islands solved in the last step
+    islands: Vec<Island>,
+    // what holds each sleeping island together. sleeping bodies aren't checked for contacts with
+    // each other, these keep them in the same island until they wake
+    sleeping_pairs: HashSet<CollisionPair>,
+    broken_constraints: Vec<ConstraintBroken>,
+    // the pairs that were touching at the end of the last step, with how many steps they've been
+    // touching for
+    touching_pairs: Vec<(CollisionPair, Contact, u32)>,
+    // the pairs of a sensor and a body that were overlapping at the end of the last step
+    sensor_overlaps: Vec<CollisionPair>,
+    trigger_events: Vec<TriggerEvent>,
+    // Persisted events are sent every this many steps a pair stays touching, 0 never sends them
+    pub persisted_event_interval: u32,
+    solve_traces: Vec<PairSolveTrace>,
+    stats: StepStats,
+    // called for every contact before it's resolved
+    pub modify_contact: Option<ModifyContact>,
+    // add_body nudges dynamic bodies by up to this much, seeded so the same bodies always get
+    // the same nudge. breaks up perfectly symmetric stacks that can't settle
+    pub symmetry_breaking_epsilon: f32,
+    pub symmetry_breaking_seed: u64,
+    // tints sleeping bodies in render_color() for debugging the sleep system
+    pub show_sleeping: bool,
 }
 
 impl PhysicsScene {
     pub fn new() -> Self {
-        let mut scene = PhysicsScene {
+        let mut scene = Self::empty();
+        scene.reset();
+        scene
+    }
+
+    // a scene with no bodies, new() fills it with the demo scene
+    pub fn empty() -> Self {
+        PhysicsScene {
             bodies: BodyArena::default(),
+            materials: Vec::new(),
             constraints: ConstraintArena::default(),
+            vehicles: Vec::new(),
+            fluids: Vec::new(),
+            force_fields: Vec::new(),
             contacts: ContactArena::default(),
             manifolds: ManifoldCollector::default(),
+            previous_contacts: Vec::new(),
             step_num: 0,
+            rng: Pcg32::seed_from_u64(0),
+            ignored_pairs: HashSet::new(),
+            broadphase_kind: BroadphaseKind::default(),
+            sweep_and_prune: SweepAndPrune::default(),
+            bvh: BroadphaseBvh::new(),
+            grid: None,
             paused: true,
-        };
-        scene.reset();
-        scene
+            contact_order: ContactOrder::default(),
+            combine_rules: CombineRules::default(),
+            collision_mode: CollisionMode::default(),
+            sleep_linear_threshold: 0.05,
+            sleep_angular_threshold: 0.05,
+            max_linear_speed: 1000.0,
+            max_angular_speed: 100.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            fixed_dt: 1.0 / 60.0,
+            max_substeps: 4,
+            velocity_iterations: 5,
+            position_iterations: 0,
+            accumulated_time: 0.0,
+            gravity: Vec3::new(0.0, -10.0, 0.0),
+            world_up: Vec3::Y,
+            collision_events: Vec::new(),
+            islands: Vec::new(),
+            sleeping_pairs: HashSet::new(),
+            broken_constraints: Vec::new(),
+            touching_pairs: Vec::new(),
+            sensor_overlaps: Vec::new(),
+            trigger_events: Vec::new(),
+            persisted_event_interval: 1,
+            solve_traces: Vec::new(),
+            stats: StepStats::default(),
+            modify_contact: None,
+            symmetry_breaking_epsilon: 0.0,
+            symmetry_breaking_seed: 0,
+            show_sleeping: false,
+        }
     }
 
-    pub fn reset(&mut self) {
+    // removes all the bodies and constraints, the settings are kept
+    pub fn clear(&mut self) {
         self.step_num = 0;
+        self.accumulated_time = 0.0;
+        self.rng = Pcg32::seed_from_u64(0);
         self.bodies.clear();
         self.constraints.clear();
+        self.vehicles.clear();
+        self.fluids.clear();
+        self.force_fields.clear();
         self.contacts.clear();
         self.manifolds.clear();
+        self.previous_contacts.clear();
+        self.ignored_pairs.clear();
+        self.islands.clear();
+        self.sleeping_pairs.clear();
+        self.bvh.clear();
+        self.collision_events.clear();
+        self.broken_constraints.clear();
+        self.touching_pairs.clear();
+        self.sensor_overlaps.clear();
+        self.trigger_events.clear();
+        self.solve_traces.clear();
+        self.stats = StepStats::default();
+    }
+
+    // replaces everything with the demo scene
+    pub fn reset(&mut self) {
+        self.clear();
 
         // add_dynamic_balls(&mut self.bodies);
 
@@ -716,51 +1140,177 @@ impl PhysicsScene {
 
         add_constant_velocity_constraint(&mut self.bodies, &mut self.constraints);
 
+        add_spherical_pendulum(&mut self.bodies, &mut self.constraints);
+
         add_teleportation_fix(&mut self.bodies);
 
         add_orientation_constraint(&mut self.bodies, &mut self.constraints);
 
         add_standard_sandbox(&mut self.bodies);
 
-        let max_contacts = self.bodies.len() * self.bodies.len();
-        self.contacts.clear_with_capacity(max_contacts);
+        self.contacts.clear();
 
         self.paused = true;
     }
 
+    // one step, the forces added to the bodies are cleared afterwards
     pub fn update(&mut self, delta_seconds: f32) {
+        self.collect_contacts(delta_seconds);
+        self.resolve_step(delta_seconds);
+        self.clear_forces();
+    }
+
+    // clears the forces and torques added to the bodies. update() and update_fixed() clear them
+    // after their steps, so a force added each frame acts for every step of the frame
+    pub fn clear_forces(&mut self) {
+        for body in self.bodies.iter_mut() {
+            body.clear_forces();
+        }
+    }
+
+    // the first half of update(), applies gravity and the bodies' forces and finds this step's
+    // contacts. they can be looked at or changed before calling resolve_step() with the same
+    // delta_seconds
+    pub fn collect_contacts(&mut self, delta_seconds: f32) {
         self.step_num += 1;
+        self.solve_traces.clear();
+        self.stats = StepStats::default();
+
+        // keep the last step's contacts around before they're replaced
+        self.previous_contacts.clear();
+        self.previous_contacts
+            .extend(self.manifolds.iter_contacts().chain(self.contacts.iter()));
 
         self.manifolds.remove_expired(&self.bodies);
 
+        for body in self.bodies.iter_mut() {
+            if let Some(material) = body.material {
+                self.materials[material.0 as usize].apply(body);
+            }
+            body.previous_position = body.position;
+            body.previous_orientation = body.orientation;
+            // velocities set from outside the step are checked before the impulses add to them
+            body.clamp_velocity(self.max_linear_speed, self.max_angular_speed);
+        }
+
+        // kinematic bodies get the velocities that take them to their targets this step
+        for body in self.bodies.iter_mut() {
+            if body.is_kinematic() {
+                body.drive_to_kinematic_target(delta_seconds);
+            }
+        }
+
+        // before gravity, so the suspension's damper doesn't see this step's fall
+        for vehicle in self.vehicles.iter_mut().flatten() {
+            vehicle.step(&mut self.bodies, delta_seconds);
+        }
+
         // gravity impulse
+        let gravity = self.gravity;
         for body in self.bodies.iter_mut() {
-            if !body.has_infinite_mass() {
+            if !body.has_infinite_mass() && !body.sleeping {
                 // gravity needs to be an impulse
                 // I = dp, F = dp/dt => dp = F * dt => I = F * dt
                 // F = mgs
                 let impulse_gravity =
-                    Vec3::new(0.0, -10.0, 0.0) * body.inv_mass.recip() * delta_seconds;
+                    gravity * body.gravity_scale * body.inv_mass.recip() * delta_seconds;
                 body.apply_impulse_linear(impulse_gravity);
+
+                // the added forces are integrated the same way
+                body.apply_impulse_linear(body.force * delta_seconds);
+                body.apply_impulse_angular(body.torque * delta_seconds);
+
+                body.apply_damping(delta_seconds, self.linear_damping, self.angular_damping);
+            }
+        }
+
+        for field in &self.force_fields {
+            for body in self.bodies.iter_mut() {
+                field.apply(body, delta_seconds);
             }
         }
+        for fluid in &self.fluids {
+            fluid.step(&mut self.bodies, gravity, delta_seconds);
+        }
 
         // broadphase (build potential collision pairs)
-        let collision_pairs = broadphase(&self.bodies, delta_seconds);
+        let timer = StageTimer::start("broadphase");
+        let broadphase: &mut dyn Broadphase = match self.broadphase_kind {
+            BroadphaseKind::SweepAndPrune => &mut self.sweep_and_prune,
+            BroadphaseKind::Bvh => &mut self.bvh,
+            BroadphaseKind::Grid { cell_size } => self
+                .grid
+                .get_or_insert_with(|| BroadphaseGrid::new(cell_size)),
+        };
+        let collision_pairs = broadphase.find_pairs(&self.bodies, delta_seconds);
+        self.stats.broadphase_pairs = collision_pairs.len();
+
+        // drop the pairs that can't collide before the narrowphase
+        let collision_pairs: Vec<CollisionPair> = collision_pairs
+            .into_iter()
+            .filter(|pair| {
+                if self.ignored_pairs.contains(pair) {
+                    return false;
+                }
+
+                let body_a = self.bodies.get_body(pair.a);
+                let body_b = self.bodies.get_body(pair.b);
+
+                // the broadphases leave these out too, this keeps the narrowphase to the groups
+                // whichever broadphase found the pair
+                if !body_a
+                    .collision_groups
+                    .interacts_with(&body_b.collision_groups)
+                {
+                    return false;
+                }
+
+                // skip body pairs with infinite mass
+                if body_a.has_infinite_mass() && body_b.has_infinite_mass() {
+                    return false;
+                }
+
+                // sensors don't move anything, a body asleep inside one is still inside it
+                if body_a.is_sensor || body_b.is_sensor {
+                    return true;
+                }
+
+                // resting bodies can't hit anything, other than each other
+                !(body_a.is_resting() && body_b.is_resting())
+            })
+            .collect();
+
+        // sensors only need to know what's overlapping them
+        let (sensor_pairs, collision_pairs): (Vec<CollisionPair>, Vec<CollisionPair>) =
+            collision_pairs.into_iter().partition(|pair| {
+                self.bodies.get_body(pair.a).is_sensor || self.bodies.get_body(pair.b).is_sensor
+            });
+        self.update_sensor_overlaps(&sensor_pairs);
+        self.stats.broadphase_time = timer.finish();
 
         // narrowphase (perform actual collision detection)
+        let timer = StageTimer::start("narrowphase");
+        self.stats.narrowphase_tests = collision_pairs.len();
         self.contacts.clear();
-        for pair in collision_pairs {
-            let (body_a, body_b) = self.bodies.get_body_pair_mut(pair.a, pair.b);
-
-            // skip body pairs with infinite mass
-            if body_a.has_infinite_mass() && body_b.has_infinite_mass() {
-                continue;
+        let pair_contacts = intersect_pairs(
+            &self.bodies,
+            &collision_pairs,
+            delta_seconds,
+            self.collision_mode,
+        );
+        for (pair, contacts) in collision_pairs.into_iter().zip(pair_contacts) {
+            if !contacts.is_empty() {
+                // anything awake touching a sleeping body wakes it up
+                let (body_a, body_b) = self.bodies.get_body_pair_mut(pair.a, pair.b);
+                if body_a.sleeping {
+                    body_a.wake();
+                }
+                if body_b.sleeping {
+                    body_b.wake();
+                }
             }
 
-            // check for intersection
-            if let Some(contact) = intersect_dynamic(pair.a, body_a, pair.b, body_b, delta_seconds)
-            {
+            for contact in contacts {
                 if contact.time_of_impact == 0.0 {
                     // static contact
                     self.manifolds.add_contact(&self.bodies, contact);
@@ -771,23 +1321,98 @@ impl PhysicsScene {
             }
         }
 
-        // sort the times of impact from earliest to latest
+        // sort the times of impact from earliest to latest, the step is advanced to each in turn
+        // so only the resting contacts can be solved in another order
         self.contacts.sort();
+        match self.contact_order {
+            ContactOrder::Found => {}
+            ContactOrder::DeepestFirst => self.manifolds.sort_deepest_first(),
+            ContactOrder::Random => self.manifolds.shuffle(&mut self.rng),
+        }
+        self.stats.contacts = self.manifolds.iter_contacts().count() + self.contacts.iter().len();
+        self.stats.narrowphase_time = timer.finish();
+    }
+
+    // resolves each ballistic contact from collect_contacts() where the bodies are now, through
+    // resolve_contact() or always through the general path. only for benches/resolve_contact.rs
+    // to time the two paths on the same contacts
+    #[doc(hidden)]
+    pub fn resolve_ballistic_contacts(&mut self, general: bool) {
+        for contact in self.contacts.iter() {
+            let body_a = self.bodies.get_body(contact.handle_a);
+            let body_b = self.bodies.get_body(contact.handle_b);
+            let material = ContactMaterial::from_bodies(body_a, body_b, self.combine_rules);
+            if general {
+                let (body_a, body_b) = self
+                    .bodies
+                    .get_body_pair_mut(contact.handle_a, contact.handle_b);
+                resolve_contact_general(body_a, body_b, contact, &material);
+            } else {
+                resolve_contact(&mut self.bodies, contact, &material);
+            }
+        }
+    }
+
+    // the second half of update(), solves the contacts from collect_contacts() and constraints
+    // then moves the bodies
+    pub fn resolve_step(&mut self, delta_seconds: f32) {
+        let timer = StageTimer::start("solver");
+        self.manifolds
+            .update_materials(&self.bodies, self.combine_rules, &mut self.modify_contact);
+
+        // solve constraints, the resting contacts are solved alongside the other constraints in
+        // each iteration so a body that's both jointed and touching something gets a coupled
+        // result. the ballistic contacts aren't part of this, they get impulses one at a time
+        // after the joints are solved, so a jointed body hit ballistically can be pushed against
+        // its joints until the next step
+        self.manifolds
+            .begin_traces(&self.bodies, &mut self.solve_traces);
+        self.update_solver_islands();
+        let baumgarte = self.position_iterations == 0;
+        for island in &self.islands {
+            // nothing in one island can affect another, so each is iterated on its own
+            self.constraints
+                .pre_solve(&mut self.bodies, delta_seconds, &island.constraints);
+            self.manifolds.pre_solve(
+                &mut self.bodies,
+                delta_seconds,
+                baumgarte,
+                &island.manifolds,
+            );
 
-        // solve constraints
-        self.constraints.pre_solve(&mut self.bodies, delta_seconds);
-        self.manifolds.pre_solve(&mut self.bodies, delta_seconds);
+            for _ in 0..self.velocity_iterations {
+                self.constraints
+                    .solve(&mut self.bodies, &island.constraints);
+                self.manifolds.solve(&mut self.bodies, &island.manifolds);
+            }
+        }
 
-        const MAX_ITERS: u32 = 5;
-        for _ in 0..MAX_ITERS {
-            self.constraints.solve(&mut self.bodies);
-            self.manifolds.solve(&mut self.bodies);
+        for body in self.bodies.iter_mut() {
+            body.clamp_velocity(self.max_linear_speed, self.max_angular_speed);
         }
 
         self.constraints.post_solve();
         self.manifolds.post_solve();
+        let broken = self.constraints.break_constraints(&self.bodies);
+        self.broken_constraints.extend(broken);
+        self.manifolds
+            .end_traces(&self.bodies, &mut self.solve_traces);
+
+        for body in self.bodies.iter_mut() {
+            body.update_sleep(
+                delta_seconds,
+                self.sleep_linear_threshold,
+                self.sleep_angular_threshold,
+            );
+        }
+        self.update_island_sleep();
+        self.stats.islands = self.islands.len();
+        self.stats.solver_iterations =
+            self.islands.len() as u32 * self.velocity_iterations + self.position_iterations;
+        self.stats.solver_time = timer.finish();
 
         // apply ballistic impulses
+        let timer = StageTimer::start("integrate");
         let mut accumulated_time = 0.0;
         for contact in self.contacts.iter() {
             let contact_time = contact.time_of_impact - accumulated_time;
@@ -797,7 +1422,48 @@ impl PhysicsScene {
                 body.update(contact_time)
             }
 
-            resolve_contact(&mut self.bodies, contact);
+            let body_a = self.bodies.get_body(contact.handle_a);
+            let body_b = self.bodies.get_body(contact.handle_b);
+            let mut material = ContactMaterial::from_bodies(body_a, body_b, self.combine_rules);
+            material.apply_one_way(body_a, body_b, contact.normal);
+            if let Some(modify_contact) = &mut self.modify_contact {
+                modify_contact(&mut material, contact);
+            }
+            if !material.enabled {
+                accumulated_time += contact_time;
+                continue;
+            }
+            let contact = &Contact {
+                normal: material.normal.unwrap_or(contact.normal),
+                ..*contact
+            };
+            let pre_velocity_a = BodyVelocity::from_body(self.bodies.get_body(contact.handle_a));
+            let pre_velocity_b = BodyVelocity::from_body(self.bodies.get_body(contact.handle_b));
+            let impulse = resolve_contact(&mut self.bodies, contact, &material);
+            for handle in [contact.handle_a, contact.handle_b] {
+                self.bodies
+                    .get_body_mut(handle)
+                    .clamp_velocity(self.max_linear_speed, self.max_angular_speed);
+            }
+            self.collision_events.push(CollisionEvent {
+                kind: CollisionEventKind::Impact,
+                handle_a: contact.handle_a,
+                handle_b: contact.handle_b,
+                point: contact.world_point_a,
+                normal: contact.normal,
+                impulse: impulse.normal.abs(),
+            });
+            self.solve_traces.push(PairSolveTrace {
+                handle_a: contact.handle_a,
+                handle_b: contact.handle_b,
+                normal: contact.normal,
+                normal_impulse: impulse.normal,
+                friction_impulse: impulse.friction,
+                pre_velocity_a,
+                pre_velocity_b,
+                post_velocity_a: BodyVelocity::from_body(self.bodies.get_body(contact.handle_a)),
+                post_velocity_b: BodyVelocity::from_body(self.bodies.get_body(contact.handle_b)),
+            });
             accumulated_time += contact_time;
         }
 
@@ -809,20 +1475,4355 @@ impl PhysicsScene {
             }
         }
 
+        // push the resting contacts apart without adding any velocity
+        for _ in 0..self.position_iterations {
+            self.manifolds.correct_positions(&mut self.bodies);
+        }
+
+        self.constraints.update_residuals(&self.bodies);
+
+        self.update_touching_pairs();
+        self.stats.bodies_awake = self
+            .bodies
+            .iter()
+            .filter(|body| !body.has_infinite_mass() && !body.sleeping)
+            .count();
+        self.stats.integrate_time = timer.finish();
+
         // self.bodies.print_bodies(self.step_num, delta_seconds);
     }
 
-    pub fn get_body(&self, handle: BodyHandle) -> &Body {
-        self.bodies.get_body(handle)
+    // groups the manifolds and enabled constraints by the bodies they connect
+    fn update_solver_islands(&mut self) {
+        let manifold_pairs: Vec<CollisionPair> = self.manifolds.iter_pairs().collect();
+        let constraint_pairs: Vec<(usize, CollisionPair)> = self
+            .constraints
+            .iter_enabled_pairs()
+            .map(|(index, a, b)| (index, CollisionPair { a, b }))
+            .collect();
+        self.islands = build_solver_islands(&self.bodies, &manifold_pairs, &constraint_pairs);
     }
 
-    pub fn iter_body_handles(&self) -> core::slice::Iter<BodyHandle> {
-        self.bodies.handles().iter()
+    // bodies only go to sleep with the rest of their island, so a pile settles as a whole, and
+    // anything awake in an island wakes the rest of it
+    fn update_island_sleep(&mut self) {
+        let contact_pairs = self.manifolds.iter_contacts().chain(self.contacts.iter());
+        let contact_pairs = contact_pairs.map(|contact| CollisionPair {
+            a: contact.handle_a,
+            b: contact.handle_b,
+        });
+        let constraint_pairs = self
+            .constraints
+            .iter_enabled_pairs()
+            .map(|(_, a, b)| CollisionPair { a, b });
+        // sorted rather than collected into a set so nothing depends on hash order
+        let mut pairs: Vec<CollisionPair> = contact_pairs
+            .chain(constraint_pairs)
+            .chain(self.sleeping_pairs.drain())
+            .collect();
+        pairs.sort_unstable_by_key(|pair| {
+            let (a, b) = (pair.a.0, pair.b.0);
+            (a.min(b), a.max(b))
+        });
+        pairs.dedup();
+
+        for island in build_islands(&self.bodies, pairs.iter().copied()) {
+            let bodies = &mut self.bodies;
+            if island
+                .iter()
+                .all(|&handle| bodies.get_body(handle).sleeping)
+            {
+                continue;
+            }
+            let ready = island
+                .iter()
+                .all(|&handle| bodies.get_body(handle).is_ready_to_sleep());
+            for &handle in &island {
+                let body = bodies.get_body_mut(handle);
+                if ready {
+                    body.sleep();
+                } else if body.sleeping {
+                    body.wake();
+                }
+            }
+        }
+
+        // islands only have static bodies between them, so if either body is asleep the whole
+        // island is
+        let bodies = &self.bodies;
+        self.sleeping_pairs = pairs
+            .into_iter()
+            .filter(|pair| {
+                let body_a = bodies.get_body(pair.a);
+                let body_b = bodies.get_body(pair.b);
+                !body_a.has_infinite_mass() && !body_b.has_infinite_mass() && body_a.sleeping
+            })
+            .collect();
     }
-}
 
-impl Default for PhysicsScene {
-    fn default() -> Self {
-        Self::new()
+    // sends Started, Persisted and Stopped events by comparing this step's contacts with the last
+    fn update_touching_pairs(&mut self) {
+        let mut touching_pairs: Vec<(CollisionPair, Contact, u32)> = Vec::new();
+        let mut current_pairs = HashSet::new();
+        for contact in self.iter_contacts() {
+            let pair = CollisionPair {
+                a: contact.handle_a,
+                b: contact.handle_b,
+            };
+            if current_pairs.insert(pair) {
+                touching_pairs.push((pair, *contact, 0));
+            }
+        }
+
+        let impulses: HashMap<CollisionPair, f32> = self
+            .solve_traces
+            .iter()
+            .map(|trace| {
+                let pair = CollisionPair {
+                    a: trace.handle_a,
+                    b: trace.handle_b,
+                };
+                (pair, trace.normal_impulse.abs())
+            })
+            .collect();
+        let impulse = |pair: &CollisionPair| impulses.get(pair).copied().unwrap_or(0.0);
+        let event = |kind, contact: &Contact, impulse| CollisionEvent {
+            kind,
+            handle_a: contact.handle_a,
+            handle_b: contact.handle_b,
+            point: contact.world_point_a,
+            normal: contact.normal,
+            impulse,
+        };
+
+        let mut events = Vec::new();
+        let mut previous_steps = HashMap::new();
+        for (pair, contact, steps) in &self.touching_pairs {
+            previous_steps.insert(*pair, *steps);
+            if !current_pairs.contains(pair) {
+                events.push(event(CollisionEventKind::Stopped, contact, 0.0));
+            }
+        }
+        for (pair, contact, steps) in &mut touching_pairs {
+            if let Some(previous_steps) = previous_steps.get(pair) {
+                *steps = previous_steps + 1;
+                let interval = self.persisted_event_interval;
+                if interval > 0 && *steps % interval == 0 {
+                    events.push(event(CollisionEventKind::Persisted, contact, impulse(pair)));
+                }
+            } else {
+                events.push(event(CollisionEventKind::Started, contact, impulse(pair)));
+            }
+        }
+
+        self.collision_events.extend(events);
+        self.touching_pairs = touching_pairs;
+    }
+
+    // sends TriggerEnter and TriggerExit events by comparing which of the sensor pairs overlap
+    // now with the last step
+    fn update_sensor_overlaps(&mut self, sensor_pairs: &[CollisionPair]) {
+        let overlaps: Vec<CollisionPair> = sensor_pairs
+            .iter()
+            .copied()
+            .filter(|pair| bodies_touch(self.bodies.get_body(pair.a), self.bodies.get_body(pair.b)))
+            .collect();
+
+        let bodies = &self.bodies;
+        let event = |kind, pair: &CollisionPair| {
+            let (sensor, other) = if bodies.get_body(pair.a).is_sensor {
+                (pair.a, pair.b)
+            } else {
+                (pair.b, pair.a)
+            };
+            TriggerEvent {
+                kind,
+                sensor,
+                other,
+            }
+        };
+        let current: HashSet<CollisionPair> = overlaps.iter().copied().collect();
+        let previous: HashSet<CollisionPair> = self.sensor_overlaps.iter().copied().collect();
+        for pair in &self.sensor_overlaps {
+            if !current.contains(pair) {
+                self.trigger_events
+                    .push(event(TriggerEventKind::TriggerExit, pair));
+            }
+        }
+        for pair in &overlaps {
+            if !previous.contains(pair) {
+                self.trigger_events
+                    .push(event(TriggerEventKind::TriggerEnter, pair));
+            }
+        }
+        self.sensor_overlaps = overlaps;
+    }
+
+    // the jacobians the solver builds for these contacts, one normal row and two friction rows
+    // each against the linear and angular velocities of body a followed by body b
+    pub fn contact_jacobians(&self, contacts: &[Contact]) -> Vec<MatMN<3, 12>> {
+        contacts
+            .iter()
+            .map(|contact| {
+                ConstraintPenetration::from_contact(&self.bodies, contact)
+                    .build_jacobian(&self.bodies)
+            })
+            .collect()
+    }
+
+    // stops two bodies from colliding with each other, e.g. adjacent bones in a ragdoll
+    pub fn set_pair_ignored(&mut self, a: BodyHandle, b: BodyHandle, ignored: bool) {
+        let pair = CollisionPair { a, b };
+        if ignored {
+            self.ignored_pairs.insert(pair);
+        } else {
+            self.ignored_pairs.remove(&pair);
+        }
+    }
+
+    pub fn add_body(&mut self, mut body: Body) -> BodyHandle {
+        assert!(
+            !body.shape.is_plane() || body.has_infinite_mass(),
+            "planes can only be used by bodies with infinite mass"
+        );
+        assert!(
+            !body.is_kinematic() || body.has_infinite_mass(),
+            "kinematic bodies need infinite mass"
+        );
+        assert!(
+            body.inertia_scale > 0.0,
+            "inertia_scale has to be above 0.0, use locked_axes to stop a body turning"
+        );
+        if self.symmetry_breaking_epsilon > 0.0 && !body.has_infinite_mass() {
+            let index = self.bodies.len() as u64;
+            let mut rng = Pcg32::seed_from_u64(self.symmetry_breaking_seed ^ index);
+            let epsilon = self.symmetry_breaking_epsilon;
+            body.position += Vec3::new(
+                rng.gen_range(-epsilon..epsilon),
+                rng.gen_range(-epsilon..epsilon),
+                rng.gen_range(-epsilon..epsilon),
+            );
+        }
+        if let Some(material) = body.material {
+            self.material(material).apply(&mut body);
+        }
+        // nothing to interpolate from until it's been stepped
+        body.previous_position = body.position;
+        body.previous_orientation = body.orientation;
+        self.bodies.add(body)
+    }
+
+    pub fn add_material(&mut self, material: PhysicsMaterial) -> MaterialHandle {
+        self.materials.push(material);
+        MaterialHandle(self.materials.len() as u32 - 1)
+    }
+
+    pub fn material(&self, handle: MaterialHandle) -> &PhysicsMaterial {
+        &self.materials[handle.0 as usize]
+    }
+
+    // replaces the material, the bodies using it pick up the change at the start of the next step
+    pub fn set_material(&mut self, handle: MaterialHandle, material: PhysicsMaterial) {
+        self.materials[handle.0 as usize] = material;
+    }
+
+    pub fn add_vehicle(&mut self, vehicle: Vehicle) -> VehicleHandle {
+        self.vehicles.push(Some(vehicle));
+        VehicleHandle(self.vehicles.len() as u32 - 1)
+    }
+
+    // None once its chassis has been removed, see remove_body
+    pub fn vehicle(&self, handle: VehicleHandle) -> Option<&Vehicle> {
+        self.vehicles[handle.0 as usize].as_ref()
+    }
+
+    // for driving it, the changes are picked up in the next step
+    pub fn vehicle_mut(&mut self, handle: VehicleHandle) -> Option<&mut Vehicle> {
+        self.vehicles[handle.0 as usize].as_mut()
+    }
+
+    pub fn add_fluid_volume(&mut self, fluid: FluidVolume) -> FluidHandle {
+        self.fluids.push(fluid);
+        FluidHandle(self.fluids.len() as u32 - 1)
+    }
+
+    pub fn fluid_volume(&self, handle: FluidHandle) -> &FluidVolume {
+        &self.fluids[handle.0 as usize]
+    }
+
+    // for moving the region or changing the fluid, the changes are picked up in the next step
+    pub fn fluid_volume_mut(&mut self, handle: FluidHandle) -> &mut FluidVolume {
+        &mut self.fluids[handle.0 as usize]
+    }
+
+    pub fn add_force_field(&mut self, field: ForceField) -> ForceFieldHandle {
+        self.force_fields.push(field);
+        ForceFieldHandle(self.force_fields.len() as u32 - 1)
+    }
+
+    pub fn force_field(&self, handle: ForceFieldHandle) -> &ForceField {
+        &self.force_fields[handle.0 as usize]
+    }
+
+    // for moving or changing the field, the changes are picked up in the next step
+    pub fn force_field_mut(&mut self, handle: ForceFieldHandle) -> &mut ForceField {
+        &mut self.force_fields[handle.0 as usize]
+    }
+
+    // removes a body along with its constraints and the vehicles it's the chassis of, and returns
+    // it. the other bodies' handles stay valid
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Body {
+        let body = self.bodies.remove(handle);
+        self.constraints.remove_body(handle);
+        for slot in self.vehicles.iter_mut() {
+            if slot
+                .as_ref()
+                .map_or(false, |vehicle| vehicle.chassis == handle)
+            {
+                *slot = None;
+            }
+        }
+
+        let kept = |pair: &CollisionPair| pair.a != handle && pair.b != handle;
+        self.ignored_pairs.retain(kept);
+        self.sleeping_pairs.retain(kept);
+        self.sensor_overlaps.retain(kept);
+        self.touching_pairs.retain(|(pair, _, _)| kept(pair));
+
+        // the contacts and islands are found again next step
+        self.contacts.clear();
+        self.manifolds.clear();
+        self.previous_contacts.clear();
+        self.solve_traces.clear();
+        self.islands.clear();
+
+        body
+    }
+
+    // runs as many fixed steps as fit in the accumulated frame time and returns how many were run.
+    // any time left over past max_substeps is dropped, otherwise a slow frame schedules more
+    // steps making the next frame slower still
+    pub fn update_fixed(&mut self, frame_time: f32) -> u32 {
+        self.accumulated_time += frame_time;
+
+        let mut num_steps = 0;
+        while self.accumulated_time >= self.fixed_dt {
+            if num_steps >= self.max_substeps {
+                self.accumulated_time = 0.0;
+                break;
+            }
+            self.collect_contacts(self.fixed_dt);
+            self.resolve_step(self.fixed_dt);
+            self.accumulated_time -= self.fixed_dt;
+            num_steps += 1;
+        }
+        self.clear_forces();
+        num_steps
+    }
+
+    // update_fixed steps at this many steps per second
+    pub fn set_fixed_hz(&mut self, hz: f32) {
+        assert!(hz > 0.0, "the fixed step rate has to be positive");
+        self.fixed_dt = 1.0 / hz;
+    }
+
+    // how far the time update_fixed hasn't stepped yet is into the next step, from 0.0 to 1.0.
+    // rendering the bodies this far between their previous and current transforms hides the
+    // steps not lining up with the frames
+    pub fn interpolation_alpha(&self) -> f32 {
+        (self.accumulated_time / self.fixed_dt).clamp(0.0, 1.0)
+    }
+
+    // where to draw the body this frame, see interpolation_alpha()
+    pub fn interpolated_transform(&self, handle: BodyHandle) -> (Vec3, Quat) {
+        self.bodies
+            .get_body(handle)
+            .interpolated_transform(self.interpolation_alpha())
+    }
+
+    // all the contacts from the last step
+    pub fn iter_contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.manifolds.iter_contacts().chain(self.contacts.iter())
+    }
+
+    // the ballistic contacts found by collect_contacts(), resting contacts are already cached in
+    // the contact constraints and can't be changed
+    pub fn iter_contacts_mut(&mut self) -> impl Iterator<Item = &mut Contact> {
+        self.contacts.iter_mut()
+    }
+
+    // all the contacts from the step before the last one
+    pub fn iter_previous_contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.previous_contacts.iter()
+    }
+
+    // the contacts touching this body from the last step, as seen from the body so handle_a is
+    // always the body and the normal points towards it
+    pub fn contacts_for_body(&self, handle: BodyHandle) -> Vec<Contact> {
+        self.iter_contacts()
+            .filter_map(|contact| {
+                if contact.handle_a == handle {
+                    Some(*contact)
+                } else if contact.handle_b == handle {
+                    Some(contact.flipped())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    // whether the body is resting on something no steeper than max_slope_angle (radians) from
+    // world_up
+    pub fn is_grounded(&self, handle: BodyHandle, max_slope_angle: f32) -> bool {
+        let up = self.world_up.normalize();
+        let min_cos = max_slope_angle.cos();
+        self.contacts_for_body(handle)
+            .iter()
+            .any(|contact| contact.normal.dot(up) >= min_cos)
+    }
+
+    // the first body the ray hits within max_toi of its origin, out of the bodies the filter
+    // accepts. the distance is along the normalized direction
+    pub fn ray_cast(
+        &self,
+        origin: Vec3,
+        dir: Vec3,
+        max_toi: f32,
+        filter: impl Fn(BodyHandle, &Body) -> bool,
+    ) -> Option<RayHit> {
+        ray_cast(
+            self.bodies.iter_with_handles(),
+            origin,
+            dir,
+            max_toi,
+            filter,
+        )
+    }
+
+    // the first body the shape touches as it's swept from one position to the other, turned by
+    // the orientation, out of the bodies the filter accepts. the bodies are where they are now,
+    // only the shape moves
+    pub fn shape_cast(
+        &self,
+        shape: &Shape,
+        orientation: Quat,
+        from: Vec3,
+        to: Vec3,
+        filter: impl Fn(BodyHandle, &Body) -> bool,
+    ) -> Option<ShapeHit> {
+        shape_cast(
+            self.bodies.iter_with_handles(),
+            shape,
+            orientation,
+            from,
+            to,
+            filter,
+        )
+    }
+
+    // the bodies the filter accepts that the point is inside or on
+    pub fn intersections_with_point(
+        &self,
+        point: Vec3,
+        filter: impl Fn(BodyHandle, &Body) -> bool,
+    ) -> Vec<BodyHandle> {
+        intersections_with_point(self.bodies.iter_with_handles(), point, filter)
+    }
+
+    // the bodies the filter accepts whose bounds overlap the aabb, their shapes might not reach
+    // into it
+    pub fn intersections_with_aabb(
+        &self,
+        aabb: &Bounds,
+        filter: impl Fn(BodyHandle, &Body) -> bool,
+    ) -> Vec<BodyHandle> {
+        intersections_with_aabb(self.bodies.iter_with_handles(), aabb, filter)
+    }
+
+    // the bodies the filter accepts that the shape touches, placed at the position and
+    // orientation
+    pub fn intersections_with_shape(
+        &self,
+        shape: &Shape,
+        position: Vec3,
+        orientation: Quat,
+        filter: impl Fn(BodyHandle, &Body) -> bool,
+    ) -> Vec<BodyHandle> {
+        intersections_with_shape(
+            self.bodies.iter_with_handles(),
+            shape,
+            position,
+            orientation,
+            filter,
+        )
+    }
+
+    // the collisions since this was last called, update_fixed() can run several steps in between
+    pub fn drain_collision_events(&mut self) -> std::vec::Drain<'_, CollisionEvent> {
+        self.collision_events.drain(..)
+    }
+
+    // the bodies that started or stopped overlapping a sensor since this was last called
+    pub fn drain_trigger_events(&mut self) -> std::vec::Drain<'_, TriggerEvent> {
+        self.trigger_events.drain(..)
+    }
+
+    // the constraints that broke since this was last called
+    pub fn drain_broken_constraints(&mut self) -> std::vec::Drain<'_, ConstraintBroken> {
+        self.broken_constraints.drain(..)
+    }
+
+    // the mass weighted average position of the dynamic bodies, zero if there are none
+    pub fn system_center_of_mass(&self) -> Vec3 {
+        let (total_mass, weighted) = self
+            .bodies
+            .iter()
+            .filter(|body| !body.has_infinite_mass())
+            .fold((0.0, Vec3::ZERO), |(mass, sum), body| {
+                let body_mass = body.inv_mass.recip();
+                (
+                    mass + body_mass,
+                    sum + body.centre_of_mass_world() * body_mass,
+                )
+            });
+        if total_mass > 0.0 {
+            weighted / total_mass
+        } else {
+            Vec3::ZERO
+        }
+    }
+
+    // the velocity of the system_center_of_mass(), only changes with external forces
+    pub fn system_com_velocity(&self) -> Vec3 {
+        let (total_mass, momentum) = self
+            .bodies
+            .iter()
+            .filter(|body| !body.has_infinite_mass())
+            .fold((0.0, Vec3::ZERO), |(mass, sum), body| {
+                let body_mass = body.inv_mass.recip();
+                (mass + body_mass, sum + body.linear_velocity * body_mass)
+            });
+        if total_mass > 0.0 {
+            momentum / total_mass
+        } else {
+            Vec3::ZERO
+        }
+    }
+
+    pub fn gravity(&self) -> Vec3 {
+        self.gravity
+    }
+
+    // the acceleration every body falls with, scaled by each body's gravity_scale. sleeping
+    // bodies are woken so they fall the new way
+    pub fn set_gravity(&mut self, gravity: Vec3) {
+        if gravity == self.gravity {
+            return;
+        }
+        self.gravity = gravity;
+        for body in self.bodies.iter_mut() {
+            body.wake();
+        }
+    }
+
+    // where the body will be after dt, see Body::predict_position
+    pub fn predict_position(&self, handle: BodyHandle, dt: f32) -> Vec3 {
+        self.bodies
+            .get_body(handle)
+            .predict_position(dt, self.gravity())
+    }
+
+    // the color to draw the body with, blended towards SLEEPING_COLOR if it's asleep and
+    // show_sleeping is set
+    pub fn render_color(&self, handle: BodyHandle) -> Vec3 {
+        const SLEEPING_COLOR: Vec3 = glam::const_vec3!([0.2, 0.2, 1.0]);
+        let body = self.bodies.get_body(handle);
+        if self.show_sleeping && body.sleeping {
+            body.color.lerp(SLEEPING_COLOR, 0.75)
+        } else {
+            body.color
+        }
+    }
+
+    // how many steps have been run since the scene was cleared
+    pub fn step_num(&self) -> u64 {
+        self.step_num
+    }
+
+    // what the last step did and how long it took
+    pub fn stats(&self) -> &StepStats {
+        &self.stats
+    }
+
+    // a copy of the bodies, constraints, contacts and everything else the next steps depend on,
+    // for rolling back or saving a game. the pub settings aren't included
+    pub fn snapshot(&self) -> SceneSnapshot {
+        SceneSnapshot {
+            bodies: self.bodies.clone(),
+            materials: self.materials.clone(),
+            constraints: self.constraints.clone(),
+            vehicles: self.vehicles.clone(),
+            fluids: self.fluids.clone(),
+            force_fields: self.force_fields.clone(),
+            contacts: self.contacts.clone(),
+            manifolds: self.manifolds.clone(),
+            previous_contacts: self.previous_contacts.clone(),
+            step_num: self.step_num,
+            rng: self.rng.clone(),
+            ignored_pairs: self.ignored_pairs.clone(),
+            broadphase_kind: self.broadphase_kind,
+            sweep_and_prune: self.sweep_and_prune,
+            bvh: self.bvh.clone(),
+            grid: self.grid.clone(),
+            accumulated_time: self.accumulated_time,
+            gravity: self.gravity,
+            collision_events: self.collision_events.clone(),
+            islands: self.islands.clone(),
+            sleeping_pairs: self.sleeping_pairs.clone(),
+            broken_constraints: self.broken_constraints.clone(),
+            touching_pairs: self.touching_pairs.clone(),
+            sensor_overlaps: self.sensor_overlaps.clone(),
+            trigger_events: self.trigger_events.clone(),
+            solve_traces: self.solve_traces.clone(),
+        }
+    }
+
+    // puts the scene back the way it was when the snapshot was taken. handles taken since then
+    // may no longer be valid
+    pub fn restore(&mut self, snapshot: &SceneSnapshot) {
+        self.bodies = snapshot.bodies.clone();
+        self.materials = snapshot.materials.clone();
+        self.constraints = snapshot.constraints.clone();
+        self.vehicles = snapshot.vehicles.clone();
+        self.fluids = snapshot.fluids.clone();
+        self.force_fields = snapshot.force_fields.clone();
+        self.contacts = snapshot.contacts.clone();
+        self.manifolds = snapshot.manifolds.clone();
+        self.previous_contacts = snapshot.previous_contacts.clone();
+        self.step_num = snapshot.step_num;
+        self.rng = snapshot.rng.clone();
+        self.ignored_pairs = snapshot.ignored_pairs.clone();
+        self.broadphase_kind = snapshot.broadphase_kind;
+        self.sweep_and_prune = snapshot.sweep_and_prune;
+        self.bvh = snapshot.bvh.clone();
+        self.grid = snapshot.grid.clone();
+        self.accumulated_time = snapshot.accumulated_time;
+        self.gravity = snapshot.gravity;
+        self.collision_events = snapshot.collision_events.clone();
+        self.islands = snapshot.islands.clone();
+        self.sleeping_pairs = snapshot.sleeping_pairs.clone();
+        self.broken_constraints = snapshot.broken_constraints.clone();
+        self.touching_pairs = snapshot.touching_pairs.clone();
+        self.sensor_overlaps = snapshot.sensor_overlaps.clone();
+        self.trigger_events = snapshot.trigger_events.clone();
+        self.solve_traces = snapshot.solve_traces.clone();
+    }
+
+    // a hash of every body's pose and velocity in handle order, for golden tests. uses FNV-1a
+    // so the value doesn't depend on the std hasher
+    pub fn state_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut hash_f32 = |value: f32| {
+            for byte in value.to_bits().to_le_bytes().iter() {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+        for body in self.bodies.iter() {
+            let (p, q) = (body.position, body.orientation);
+            let (v, w) = (body.linear_velocity, body.angular_velocity);
+            for &value in &[
+                p.x, p.y, p.z, q.x, q.y, q.z, q.w, v.x, v.y, v.z, w.x, w.y, w.z,
+            ] {
+                hash_f32(value);
+            }
+        }
+        hash
+    }
+
+    // forces the broadphase to sweep along this axis, None picks the axis the bodies are spread
+    // along the most
+    pub fn set_sap_axis(&mut self, axis: Option<Vec3>) {
+        self.sweep_and_prune.axis = axis.map(|axis| axis.normalize());
+    }
+
+    // the axis the broadphase swept along in the last step
+    pub fn sap_axis(&self) -> Vec3 {
+        self.sweep_and_prune.chosen_axis()
+    }
+
+    // switches the broadphase used to find the pairs of bodies that might touch, from the next
+    // step
+    pub fn set_broadphase(&mut self, kind: BroadphaseKind) {
+        if kind != self.broadphase_kind {
+            // the tree is built again from the bodies when it's next used
+            self.bvh.clear();
+        }
+        self.grid = match kind {
+            BroadphaseKind::Grid { cell_size } => Some(BroadphaseGrid::new(cell_size)),
+            _ => None,
+        };
+        self.broadphase_kind = kind;
+    }
+
+    pub fn broadphase_kind(&self) -> BroadphaseKind {
+        self.broadphase_kind
+    }
+
+    // how the pair was resolved in the last step, seen from body a. None if they didn't touch
+    pub fn trace_pair(&self, a: BodyHandle, b: BodyHandle) -> Option<PairSolveTrace> {
+        self.solve_traces.iter().find_map(|trace| {
+            if trace.handle_a == a && trace.handle_b == b {
+                Some(*trace)
+            } else if trace.handle_a == b && trace.handle_b == a {
+                Some(trace.flipped())
+            } else {
+                None
+            }
+        })
+    }
+
+    // the effective coefficient of restitution of the body's hit in the last step, from the
+    // normal speed of the bodies' centres of mass before and after. None if it didn't hit
+    // anything
+    pub fn measure_restitution(&self, handle: BodyHandle) -> Option<f32> {
+        self.solve_traces
+            .iter()
+            .find(|trace| trace.handle_a == handle || trace.handle_b == handle)
+            .and_then(|trace| {
+                let incoming =
+                    (trace.pre_velocity_a.linear - trace.pre_velocity_b.linear).dot(trace.normal);
+                let outgoing =
+                    (trace.post_velocity_a.linear - trace.post_velocity_b.linear).dot(trace.normal);
+                if incoming < 0.0 {
+                    Some(-outgoing / incoming)
+                } else {
+                    None
+                }
+            })
+    }
+
+    // forget the warm starting impulses and cached contacts, e.g. after teleporting bodies around
+    // so last step's impulses don't get applied to the new arrangement
+    pub fn clear_solver_caches(&mut self) {
+        self.constraints.clear_cached_lambdas();
+        self.manifolds.clear();
+    }
+
+    // the bodies and constraints in the level format, see level.rs
+    pub fn to_level(&self) -> Level {
+        Level {
+            bodies: self.bodies.iter().map(LevelBody::from_body).collect(),
+            constraints: self.constraints.to_level(&self.bodies),
+        }
+    }
+
+    pub fn from_level(level: &Level) -> Self {
+        let mut scene = Self::empty();
+        for body in &level.bodies {
+            scene.add_body(body.to_body());
+        }
+        for constraint in &level.constraints {
+            scene
+                .constraints
+                .add_level_constraint(&scene.bodies, constraint);
+        }
+        scene
+    }
+
+    pub fn to_level_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.to_level())
+    }
+
+    pub fn from_level_json(json: &str) -> Result<Self, serde_json::Error> {
+        let level: Level = serde_json::from_str(json)?;
+        Ok(Self::from_level(&level))
+    }
+
+    pub fn constraints(&self) -> &ConstraintArena {
+        &self.constraints
+    }
+
+    // None once the body has been removed, see remove_body
+    pub fn get_body(&self, handle: BodyHandle) -> Option<&Body> {
+        self.is_valid(handle).then(|| self.bodies.get_body(handle))
+    }
+
+    // the handle still refers to the body it was given for. the other methods taking a body's
+    // handle panic once the body has been removed
+    pub fn is_valid(&self, handle: BodyHandle) -> bool {
+        self.bodies.is_valid(handle)
+    }
+
+    // moves a kinematic body to the position and orientation over the next step, pushing the
+    // bodies in its way
+    pub fn set_kinematic_target(&mut self, handle: BodyHandle, position: Vec3, orientation: Quat) {
+        let body = self.bodies.get_body_mut(handle);
+        assert!(body.is_kinematic(), "only kinematic bodies have targets");
+        body.kinematic_target = Some((position, orientation));
+    }
+
+    // adds a force that acts until the forces are cleared, waking the body
+    pub fn add_force(&mut self, handle: BodyHandle, force: Vec3) {
+        let body = self.bodies.get_body_mut(handle);
+        body.wake();
+        body.add_force(force);
+    }
+
+    pub fn add_torque(&mut self, handle: BodyHandle, torque: Vec3) {
+        let body = self.bodies.get_body_mut(handle);
+        body.wake();
+        body.add_torque(torque);
+    }
+
+    pub fn add_force_at_point(&mut self, handle: BodyHandle, force: Vec3, point: Vec3) {
+        let body = self.bodies.get_body_mut(handle);
+        body.wake();
+        body.add_force_at_point(force, point);
+    }
+
+    // wakes the body, its island wakes with it in the next step
+    pub fn apply_impulse(&mut self, handle: BodyHandle, impulse_point: Vec3, impulse: Vec3) {
+        let body = self.bodies.get_body_mut(handle);
+        body.wake();
+        body.apply_impulse(impulse_point, impulse);
+    }
+
+    // pushes the dynamic bodies within the radius away from the centre, like an explosion. the
+    // strength is the impulse at the centre, it weakens by the falloff out to the radius and acts
+    // at the point on each body closest to the centre, waking them. this is O(n) per call: the
+    // bvh broadphase refits its tree to where every body is now and only the bodies it finds near
+    // the centre get the closest point test, the other broadphases have no tree to query so every
+    // body's bounds are checked against the explosion's
+    pub fn apply_radial_impulse(
+        &mut self,
+        center: Vec3,
+        radius: f32,
+        strength: f32,
+        falloff: Falloff,
+    ) {
+        let impulses = match self.broadphase_kind {
+            BroadphaseKind::Bvh => {
+                let bounds = Bounds {
+                    mins: center - Vec3::splat(radius),
+                    maxs: center + Vec3::splat(radius),
+                };
+                let handles = self.bodies.handles();
+                let bodies = &self.bodies;
+                let nearby = self.bvh.bodies_overlapping(bodies, &bounds);
+                radial_impulses(
+                    nearby
+                        .into_iter()
+                        .map(|i| (handles[i], bodies.get_body(handles[i]))),
+                    center,
+                    radius,
+                    strength,
+                    falloff,
+                )
+            }
+            _ => radial_impulses(
+                self.bodies.iter_with_handles(),
+                center,
+                radius,
+                strength,
+                falloff,
+            ),
+        };
+        for (handle, point, impulse) in impulses {
+            self.apply_impulse(handle, point, impulse);
+        }
+    }
+
+    // the bodies in each island solved in the last step, static bodies aren't in any island
+    pub fn iter_islands(&self) -> impl Iterator<Item = &[BodyHandle]> {
+        self.islands
+            .iter()
+            .map(|island| island.bodies.as_slice())
+            .filter(|bodies| !bodies.is_empty())
+    }
+
+    pub fn iter_body_handles(&self) -> core::slice::Iter<BodyHandle> {
+        self.bodies.handles().iter()
+    }
+
+    // the systems of equations the solver would work on with the current body state, for
+    // looking into solver failures offline. constraints and contacts are only prepared during
+    // update() so this reflects the last step
+    pub fn dump_lcp_system(&self) -> LcpSystem {
+        let mut system = LcpSystem::default();
+        self.constraints
+            .append_lcp_system(&self.bodies, &mut system);
+        self.manifolds.append_lcp_system(&self.bodies, &mut system);
+        system
+    }
+}
+
+impl Default for PhysicsScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        body::{BodyType, Collider, InteractionGroups, LockedAxes, Material, OneWayPlatform},
+        bounds::Bounds,
+        constraints::{ConstraintHandle, JointMotor, Spring},
+        fluid::FluidRegion,
+        level::LevelConstraint,
+        shapes::{Shape, ShapeCompound, ShapeHeightfield, ShapeTriMesh},
+    };
+    use std::sync::Arc;
+
+    fn make_overlapping_stack(contact_order: ContactOrder) -> PhysicsScene {
+        let mut scene = PhysicsScene::empty();
+        scene.contact_order = contact_order;
+        scene.bodies.add(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+
+        // unit cubes that start out sunk 0.1 into each other and the ground
+        let cube_shape = make_cube_unit();
+        for y in 0..4 {
+            scene.bodies.add(Body {
+                position: Vec3::new(0.0, 0.9 + y as f32 * 1.9, 0.0),
+                shape: cube_shape.clone(),
+                ..Body::default()
+            });
+        }
+        scene
+    }
+
+    fn stack_speed(scene: &PhysicsScene) -> f32 {
+        scene
+            .bodies
+            .iter()
+            .map(|body| body.linear_velocity.length() + body.angular_velocity.length())
+            .sum()
+    }
+
+    // how far the most sunk in pair of bodies overlap, found where they are now
+    fn deepest_overlap(scene: &PhysicsScene) -> f32 {
+        let mut overlap = 0.0f32;
+        let handles = scene.bodies.handles();
+        for (i, &handle_a) in handles.iter().enumerate() {
+            for &handle_b in &handles[i + 1..] {
+                let mut body_a = scene.bodies.get_body(handle_a).clone();
+                let mut body_b = scene.bodies.get_body(handle_b).clone();
+                for body in [&mut body_a, &mut body_b] {
+                    body.linear_velocity = Vec3::ZERO;
+                    body.angular_velocity = Vec3::ZERO;
+                }
+                let contacts = crate::intersect::intersect_dynamic(
+                    handle_a,
+                    &mut body_a,
+                    handle_b,
+                    &mut body_b,
+                    1.0 / 120.0,
+                );
+                for contact in contacts {
+                    overlap = overlap.max(-contact.separation_dist);
+                }
+            }
+        }
+        overlap
+    }
+
+    // a column of spheres sunk into each other and the ground, deeper towards the top. the
+    // contacts are found bottom up so the shallowest is solved first unless they're sorted
+    fn make_sunk_column(contact_order: ContactOrder) -> PhysicsScene {
+        let mut scene = PhysicsScene::empty();
+        scene.contact_order = contact_order;
+        scene.position_iterations = 1;
+        let mut y = 0.0;
+        let heights: Vec<f32> = (0..6)
+            .map(|i| {
+                let sunk = 0.02 + 0.02 * i as f32;
+                y += if i == 0 { 1.0 } else { 2.0 } - sunk;
+                y
+            })
+            .collect();
+        for &y in heights.iter().rev() {
+            scene.bodies.add(Body {
+                position: Vec3::new(0.0, y, 0.0),
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+        }
+        scene.bodies.add(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        scene
+    }
+
+    #[test]
+    fn test_contact_order_deepest_first() {
+        let mut found_scene = make_sunk_column(ContactOrder::Found);
+        let mut deepest_scene = make_sunk_column(ContactOrder::DeepestFirst);
+
+        // add up how sunk in the column still is after each step while it settles
+        let mut found_sunk = 0.0;
+        let mut deepest_sunk = 0.0;
+        for _ in 0..240 {
+            found_scene.update(1.0 / 120.0);
+            deepest_scene.update(1.0 / 120.0);
+            found_sunk += deepest_overlap(&found_scene);
+            deepest_sunk += deepest_overlap(&deepest_scene);
+        }
+        assert!(
+            deepest_sunk <= found_sunk,
+            "{} {}",
+            deepest_sunk,
+            found_sunk
+        );
+        // and by enough that it's the order making the difference
+        assert!(found_sunk - deepest_sunk > 0.2);
+
+        // the cubes in a stack that started sunk into each other are pushed apart either way
+        for contact_order in [ContactOrder::Found, ContactOrder::DeepestFirst] {
+            let mut scene = make_overlapping_stack(contact_order);
+            for _ in 0..240 {
+                scene.update(1.0 / 120.0);
+            }
+            let overlap = deepest_overlap(&scene);
+            assert!(overlap < 0.02, "{}", overlap);
+        }
+    }
+
+    #[test]
+    fn test_contact_order_keeps_ballistic_contacts_in_time_order() {
+        for contact_order in [ContactOrder::DeepestFirst, ContactOrder::Random] {
+            let mut scene = PhysicsScene::empty();
+            scene.contact_order = contact_order;
+            scene.add_body(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            // both hit the ground this step, the one added first hits it last
+            let mut add_ball = |x: f32, y: f32| {
+                scene.add_body(Body {
+                    position: Vec3::new(x, y, 0.0),
+                    linear_velocity: Vec3::new(0.0, -300.0, 0.0),
+                    elasticity: 0.0,
+                    shape: make_sphere(0.1),
+                    ..Body::default()
+                })
+            };
+            let late = add_ball(3.0, 3.0);
+            let early = add_ball(-3.0, 1.0);
+            scene.update(1.0 / 60.0);
+
+            // the earlier hit is still stepped to first, so neither ball went through
+            for handle in [late, early] {
+                let body = scene.get_body(handle).unwrap();
+                assert!(
+                    body.position.y > 0.0,
+                    "{:?} {}",
+                    contact_order,
+                    body.position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_material() {
+        let mut scene = PhysicsScene::empty();
+        let ice = scene.add_material(PhysicsMaterial {
+            density: 2.0,
+            ..PhysicsMaterial::from(Material::ICE)
+        });
+        let cube = |scene: &mut PhysicsScene, x: f32| {
+            scene.add_body(Body {
+                position: Vec3::new(x, 10.0, 0.0),
+                shape: make_cube_unit(),
+                material: Some(ice),
+                ..Body::default()
+            })
+        };
+        let handles = [cube(&mut scene, 0.0), cube(&mut scene, 5.0)];
+        for &handle in &handles {
+            let body = scene.get_body(handle).unwrap();
+            assert_eq!(0.02, body.friction);
+            // a 2x2x2 cube
+            assert!((body.inv_mass - 1.0 / 16.0).abs() < 1e-6);
+        }
+
+        // editing the material changes every body using it on the next step
+        scene.set_material(
+            ice,
+            PhysicsMaterial {
+                friction: 0.8,
+                ..*scene.material(ice)
+            },
+        );
+        assert_eq!(0.02, scene.get_body(handles[0]).unwrap().friction);
+        scene.update(1.0 / 60.0);
+        for &handle in &handles {
+            assert_eq!(0.8, scene.get_body(handle).unwrap().friction);
+        }
+    }
+
+    #[test]
+    fn test_locked_axes() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        // a ball resting on the ground, off to the side of where the other lands
+        scene.add_body(Body {
+            position: Vec3::new(0.0, 0.5, 0.6),
+            inv_mass: 0.0,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        // can't move along z, so it glances off the ball without being pushed aside
+        let planar = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            locked_axes: LockedAxes::TRANSLATION_Z,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        // can't tip over however it's hit
+        let upright = scene.add_body(Body {
+            position: Vec3::new(5.0, 1.0, 0.0),
+            locked_axes: LockedAxes::ROTATION,
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+        scene
+            .bodies
+            .get_body_mut(upright)
+            .apply_impulse(Vec3::new(5.0, 2.0, 1.0), Vec3::new(0.0, 0.0, 5.0));
+
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+            assert_eq!(0.0, scene.get_body(planar).unwrap().position.z);
+            assert_eq!(Quat::IDENTITY, scene.get_body(upright).unwrap().orientation);
+        }
+        assert!(scene.get_body(planar).unwrap().position.y > 1.0);
+        assert!(scene.get_body(upright).unwrap().position.z > 0.5);
+    }
+
+    #[test]
+    fn test_collision_mode_speculative() {
+        // cubes dropped a little apart onto each other
+        let mut scene = PhysicsScene::empty();
+        scene.collision_mode = CollisionMode::Speculative;
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        for y in 0..4 {
+            scene.add_body(Body {
+                position: Vec3::new(0.0, 1.05 + y as f32 * 2.1, 0.0),
+                shape: make_cube_unit(),
+                ..Body::default()
+            });
+        }
+
+        let mut speed = 0.0;
+        for step in 0..240 {
+            scene.update(1.0 / 120.0);
+            // nothing is swept, every contact goes to the solver
+            assert_eq!(0, scene.contacts.iter().len());
+            if step >= 120 {
+                speed += stack_speed(&scene);
+            }
+        }
+        // the stack stays up and settles
+        let top = scene.bodies.iter().last().unwrap();
+        assert!(
+            (top.position.y - 7.0).abs() < 0.1,
+            "top at {}",
+            top.position.y
+        );
+        assert!(
+            speed / 120.0 < 0.1,
+            "stack still moving at {}",
+            speed / 120.0
+        );
+    }
+
+    fn make_sphere_ground_contact(ground_first: bool) -> (BodyArena, Contact) {
+        let ground = Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        };
+        let sphere = Body {
+            position: Vec3::new(0.0, 0.9, 0.0),
+            linear_velocity: Vec3::new(2.0, -5.0, 0.5),
+            angular_velocity: Vec3::new(0.3, 1.0, -2.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+
+        let mut bodies = BodyArena::new();
+        let (handle_a, handle_b) = if ground_first {
+            let handle_ground = bodies.add(ground);
+            (handle_ground, bodies.add(sphere))
+        } else {
+            let handle_sphere = bodies.add(sphere);
+            (handle_sphere, bodies.add(ground))
+        };
+
+        let point_on_ground = Vec3::ZERO;
+        let point_on_sphere = Vec3::new(0.0, -0.1, 0.0);
+        let (world_point_a, world_point_b, normal) = if ground_first {
+            (point_on_ground, point_on_sphere, -Vec3::Y)
+        } else {
+            (point_on_sphere, point_on_ground, Vec3::Y)
+        };
+        let contact = Contact {
+            world_point_a,
+            world_point_b,
+            local_point_a: bodies.get_body(handle_a).world_to_local(world_point_a),
+            local_point_b: bodies.get_body(handle_b).world_to_local(world_point_b),
+            normal,
+            separation_dist: -0.1,
+            time_of_impact: 0.0,
+            handle_a,
+            handle_b,
+        };
+        (bodies, contact)
+    }
+
+    fn contact_material(bodies: &BodyArena, contact: &Contact) -> ContactMaterial {
+        ContactMaterial::from_bodies(
+            bodies.get_body(contact.handle_a),
+            bodies.get_body(contact.handle_b),
+            CombineRules::default(),
+        )
+    }
+
+    #[test]
+    fn test_resolve_contact_degenerate() {
+        // a contact that's gone NaN gives no impulse, through either path
+        for &ground_first in &[true, false] {
+            let (mut bodies, mut contact) = make_sphere_ground_contact(ground_first);
+            contact.normal = Vec3::splat(f32::NAN);
+            let material = contact_material(&bodies, &contact);
+            let before = bodies.get_body(contact.handle_a).linear_velocity;
+            let impulse = resolve_contact(&mut bodies, &contact, &material);
+            assert_eq!(0.0, impulse.normal);
+            assert_eq!(before, bodies.get_body(contact.handle_a).linear_velocity);
+
+            let (body_a, body_b) = bodies.get_body_pair_mut(contact.handle_a, contact.handle_b);
+            let impulse = resolve_contact_general(body_a, body_b, &contact, &material);
+            assert_eq!(0.0, impulse.normal);
+        }
+    }
+
+    #[test]
+    fn test_max_speeds() {
+        let mut scene = PhysicsScene::empty();
+        scene.max_linear_speed = 20.0;
+        scene.max_angular_speed = 5.0;
+        let fast = scene.add_body(Body {
+            linear_velocity: Vec3::new(0.0, 0.0, 50.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        let broken = scene.add_body(Body {
+            position: Vec3::new(10.0, 0.0, 0.0),
+            linear_velocity: Vec3::new(f32::NAN, 0.0, 0.0),
+            angular_velocity: Vec3::new(0.0, f32::INFINITY, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        scene.bodies.get_body_mut(fast).angular_velocity = Vec3::new(8.0, 0.0, 0.0);
+
+        // falling never gets faster than the limit either
+        for _ in 0..300 {
+            scene.update(1.0 / 60.0);
+            let body = scene.get_body(fast).unwrap();
+            assert!(body.linear_velocity.length() <= 20.0 + 1e-4);
+            assert!(body.angular_velocity.length() <= 5.0 + 1e-4);
+        }
+        let body = scene.get_body(broken).unwrap();
+        assert!(body.position.is_finite());
+        assert_eq!(Vec3::ZERO, body.angular_velocity);
+
+        // the scene's limit is the only one, impulses alone don't cap the speed
+        let mut spinning = Body {
+            angular_velocity: Vec3::new(0.0, 50.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+        spinning.apply_impulse_angular(Vec3::new(0.0, 1.0, 0.0));
+        assert!(spinning.angular_velocity.y > 50.0);
+    }
+
+    #[test]
+    fn test_resolve_contact_dynamic_static_matches_general() {
+        for &ground_first in &[true, false] {
+            let (mut fast_bodies, contact) = make_sphere_ground_contact(ground_first);
+            let (mut general_bodies, _) = make_sphere_ground_contact(ground_first);
+            let material = contact_material(&fast_bodies, &contact);
+
+            resolve_contact(&mut fast_bodies, &contact, &material);
+            {
+                let (body_a, body_b) =
+                    general_bodies.get_body_pair_mut(contact.handle_a, contact.handle_b);
+                resolve_contact_general(body_a, body_b, &contact, &material);
+            }
+
+            // the general path sums the static body's zero terms in, which can round differently
+            let close = |fast: Vec3, general: Vec3| fast.abs_diff_eq(general, 1e-5);
+            for (fast, general) in fast_bodies.iter().zip(general_bodies.iter()) {
+                assert!(close(fast.position, general.position));
+                assert!(close(fast.linear_velocity, general.linear_velocity));
+                assert!(close(fast.angular_velocity, general.angular_velocity));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_contact_response_weight() {
+        let mut bodies = BodyArena::new();
+        let handle_a = bodies.add(Body {
+            position: Vec3::ZERO,
+            linear_velocity: Vec3::new(1.0, 0.0, 0.0),
+            response_weight: 0.0,
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        let handle_b = bodies.add(Body {
+            position: Vec3::new(2.0, 0.0, 0.0),
+            linear_velocity: Vec3::new(-1.0, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+
+        let world_point = Vec3::new(1.0, 0.0, 0.0);
+        let contact = Contact {
+            world_point_a: world_point,
+            world_point_b: world_point,
+            local_point_a: bodies.get_body(handle_a).world_to_local(world_point),
+            local_point_b: bodies.get_body(handle_b).world_to_local(world_point),
+            normal: Vec3::new(-1.0, 0.0, 0.0),
+            separation_dist: 0.0,
+            time_of_impact: 0.0,
+            handle_a,
+            handle_b,
+        };
+
+        let material = contact_material(&bodies, &contact);
+        resolve_contact(&mut bodies, &contact, &material);
+
+        // body a absorbs none of the response
+        let body_a = bodies.get_body(handle_a);
+        assert_eq!(Vec3::new(1.0, 0.0, 0.0), body_a.linear_velocity);
+        assert_eq!(Vec3::ZERO, body_a.angular_velocity);
+        assert_eq!(Vec3::ZERO, body_a.position);
+
+        // body b bounces off body a as if it were static
+        let body_b = bodies.get_body(handle_b);
+        let elasticity = 0.5 * 0.5;
+        let expected = 1.0 + elasticity * 2.0;
+        assert!((body_b.linear_velocity.x - expected).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_dump_lcp_system() {
+        let (bodies, contact) = make_sphere_ground_contact(true);
+        let mut scene = PhysicsScene::empty();
+        scene.bodies = bodies;
+        scene.manifolds.add_contact(&scene.bodies, contact);
+        scene
+            .manifolds
+            .pre_solve(&mut scene.bodies, 1.0 / 60.0, true, &[0]);
+
+        // a single contact is one normal row and two friction rows
+        let system = scene.dump_lcp_system();
+        assert_eq!(3, system.dimension());
+        assert_eq!(vec![3], system.block_sizes);
+        assert_eq!(3, system.a.len());
+        assert!(system.a.iter().all(|row| row.len() == 3));
+        assert_eq!(3, system.jacobian.len());
+        assert!(system.jacobian.iter().all(|row| row.len() == 12));
+
+        // solving the dumped system gives the impulses the solver applies
+        let lambda = system.solve();
+        let velocity_before = scene.get_body(contact.handle_b).unwrap().linear_velocity;
+        scene.manifolds.solve(&mut scene.bodies, &[0]);
+        let sphere = scene.get_body(contact.handle_b).unwrap();
+        let impulse = (0..3).fold(Vec3::ZERO, |impulse, i| {
+            impulse + Vec3::from_slice(&system.jacobian[i][6..9]) * lambda[i]
+        });
+        let expected = velocity_before + impulse * sphere.inv_mass;
+        assert!((sphere.linear_velocity - expected).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_sleep_threshold_per_body() {
+        let mut scene = PhysicsScene::empty();
+        scene.bodies.add(Body {
+            inv_mass: 0.0,
+            friction: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+
+        // two spheres sliding along the frictionless ground at the same low speed
+        let slow_sphere = Body {
+            position: Vec3::new(0.0, 1.0, -5.0),
+            linear_velocity: Vec3::new(0.2, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+        let handle_high = scene.bodies.add(Body {
+            sleep_linear_threshold: Some(0.5),
+            ..slow_sphere.clone()
+        });
+        let handle_low = scene.bodies.add(Body {
+            position: Vec3::new(0.0, 1.0, 5.0),
+            sleep_linear_threshold: Some(0.1),
+            ..slow_sphere
+        });
+
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+        }
+
+        assert!(scene.get_body(handle_high).unwrap().sleeping);
+        assert!(!scene.get_body(handle_low).unwrap().sleeping);
+    }
+
+    #[test]
+    fn test_set_pair_ignored() {
+        let mut scene = PhysicsScene::empty();
+        let handle_a = scene.bodies.add(Body {
+            position: Vec3::new(0.0, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        let handle_b = scene.bodies.add(Body {
+            position: Vec3::new(1.5, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+
+        // ignored in the opposite order to make sure the pair isn't order dependent
+        scene.set_pair_ignored(handle_b, handle_a, true);
+        scene.update(1.0 / 60.0);
+        assert_eq!(0.0, scene.get_body(handle_a).unwrap().linear_velocity.x);
+        assert_eq!(0.0, scene.get_body(handle_b).unwrap().linear_velocity.x);
+
+        // the overlap pushes them apart again once they collide
+        scene.set_pair_ignored(handle_a, handle_b, false);
+        scene.update(1.0 / 60.0);
+        assert!(scene.get_body(handle_a).unwrap().linear_velocity.x < 0.0);
+        assert!(scene.get_body(handle_b).unwrap().linear_velocity.x > 0.0);
+    }
+
+    #[test]
+    fn test_collision_groups() {
+        const DEBRIS: u32 = 1 << 1;
+        let debris = InteractionGroups::new(DEBRIS, !DEBRIS);
+        assert!(!debris.interacts_with(&debris));
+        assert!(debris.interacts_with(&InteractionGroups::default()));
+        assert!(!InteractionGroups::NONE.interacts_with(&InteractionGroups::ALL));
+
+        for kind in [
+            BroadphaseKind::SweepAndPrune,
+            BroadphaseKind::Bvh,
+            BroadphaseKind::Grid { cell_size: 2.0 },
+        ] {
+            // two overlapping pieces of debris, and a piece of debris overlapping a normal body
+            let mut scene = PhysicsScene::empty();
+            scene.set_broadphase(kind);
+            let mut add = |x: f32, collision_groups: InteractionGroups| {
+                scene.bodies.add(Body {
+                    position: Vec3::new(x, 0.0, 0.0),
+                    shape: make_sphere(1.0),
+                    collision_groups,
+                    ..Body::default()
+                })
+            };
+            let debris_a = add(0.0, debris);
+            let debris_b = add(1.5, debris);
+            let debris_c = add(10.0, debris);
+            let other = add(11.5, InteractionGroups::default());
+
+            scene.update(1.0 / 60.0);
+            assert_eq!(0.0, scene.get_body(debris_a).unwrap().linear_velocity.x);
+            assert_eq!(0.0, scene.get_body(debris_b).unwrap().linear_velocity.x);
+            assert!(scene.get_body(debris_c).unwrap().linear_velocity.x < 0.0);
+            assert!(scene.get_body(other).unwrap().linear_velocity.x > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_sensor_trigger_events() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let add_sensor = |scene: &mut PhysicsScene, position: Vec3| {
+            scene.add_body(Body {
+                position,
+                inv_mass: 0.0,
+                shape: Shape::make_box_from_half_extents(Vec3::new(2.0, 1.0, 2.0)),
+                is_sensor: true,
+                ..Body::default()
+            })
+        };
+        // the falling ball passes through the first sensor, the resting ball stays in the second
+        let passed_through = add_sensor(&mut scene, Vec3::new(0.0, 3.0, 0.0));
+        let rested_in = add_sensor(&mut scene, Vec3::new(10.0, 0.5, 0.0));
+        let falling = scene.add_body(Body {
+            position: Vec3::new(0.0, 6.0, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let resting = scene.add_body(Body {
+            position: Vec3::new(10.0, 0.5, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+
+        let mut events = Vec::new();
+        for _ in 0..300 {
+            scene.update(1.0 / 60.0);
+            events.extend(scene.drain_trigger_events());
+            assert_eq!(0, scene.drain_trigger_events().count());
+        }
+        let event = |kind, sensor, other| TriggerEvent {
+            kind,
+            sensor,
+            other,
+        };
+        assert_eq!(
+            vec![
+                event(TriggerEventKind::TriggerEnter, rested_in, resting),
+                event(TriggerEventKind::TriggerEnter, passed_through, falling),
+                event(TriggerEventKind::TriggerExit, passed_through, falling),
+            ],
+            events
+        );
+
+        // nothing collided with the sensors
+        assert!((scene.get_body(falling).unwrap().position.y - 0.5).abs() < 0.02);
+        assert!(scene.get_body(resting).unwrap().sleeping);
+    }
+
+    #[test]
+    fn test_gravity_scale() {
+        let mut scene = PhysicsScene::empty();
+        let mut add = |gravity_scale: f32| {
+            scene.add_body(Body {
+                position: Vec3::new(gravity_scale * 10.0, 0.0, 0.0),
+                shape: make_sphere(1.0),
+                gravity_scale,
+                ..Body::default()
+            })
+        };
+        let normal = add(1.0);
+        let half = add(0.5);
+        let floating = add(0.0);
+
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let fallen = |handle| scene.get_body(handle).unwrap().position.y;
+        assert!(fallen(normal) < -4.0);
+        assert!((fallen(half) - fallen(normal) * 0.5).abs() < 1e-3);
+        assert_eq!(0.0, fallen(floating));
+
+        // sideways gravity for a side on game, the sleeping body wakes up to fall the new way
+        scene.bodies.get_body_mut(normal).sleep();
+        scene.set_gravity(Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(Vec3::new(10.0, 0.0, 0.0), scene.gravity());
+        assert!(!scene.get_body(normal).unwrap().sleeping);
+        let before = scene.get_body(normal).unwrap().linear_velocity;
+        scene.update(1.0 / 60.0);
+        let after = scene.get_body(normal).unwrap().linear_velocity;
+        assert!((after.x - before.x - 10.0 / 60.0).abs() < 1e-4);
+        assert_eq!(before.y, after.y);
+    }
+
+    #[test]
+    fn test_kinematic_platform() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let platform = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.25, 0.0),
+            inv_mass: 0.0,
+            body_type: BodyType::Kinematic,
+            shape: Shape::make_box_from_half_extents(Vec3::new(2.0, 0.25, 2.0)),
+            ..Body::default()
+        });
+        let crate_box = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            friction: 1.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(0.5)),
+            ..Body::default()
+        });
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let settled = scene.get_body(crate_box).unwrap().position;
+
+        // slide the platform a metre along x over a second, it carries the crate with it
+        for step in 1..=60 {
+            let x = step as f32 / 60.0;
+            scene.set_kinematic_target(platform, Vec3::new(x, 0.25, 0.0), Quat::IDENTITY);
+            scene.update(1.0 / 60.0);
+            let body = scene.get_body(platform).unwrap();
+            assert!(body.position.abs_diff_eq(Vec3::new(x, 0.25, 0.0), 1e-4));
+            assert!((body.linear_velocity.x - 1.0).abs() < 1e-2);
+        }
+        // it slips a little while friction gets it up to speed
+        let carried = scene.get_body(crate_box).unwrap().position - settled;
+        assert!(carried.x > 0.8);
+        assert!((scene.get_body(crate_box).unwrap().linear_velocity.x - 1.0).abs() < 0.05);
+        assert!(carried.y.abs() < 0.02);
+
+        // it stops once it's there
+        scene.update(1.0 / 60.0);
+        assert!(scene.get_body(platform).unwrap().linear_velocity.length() < 1e-2);
+
+        // and turns at the rate it needs to reach a turned target
+        let turned = Quat::from_rotation_y(0.5);
+        scene.set_kinematic_target(platform, Vec3::new(1.0, 0.25, 0.0), turned);
+        scene.update(1.0 / 60.0);
+        let body = scene.get_body(platform).unwrap();
+        assert!(body.orientation.abs_diff_eq(turned, 1e-4));
+        assert!(body
+            .angular_velocity
+            .abs_diff_eq(Vec3::new(0.0, 30.0, 0.0), 1e-2));
+    }
+
+    #[test]
+    fn test_forces() {
+        let body = Body {
+            shape: make_sphere(1.0),
+            ..Body::default()
+        };
+
+        // a thruster holding the body up against gravity, and another pushing it sideways. added
+        // each frame, they act the same whether a frame runs one step or three
+        let mut fast = PhysicsScene::empty();
+        let mut slow = PhysicsScene::empty();
+        let fast_handle = fast.add_body(body.clone());
+        let slow_handle = slow.add_body(body.clone());
+        let thrust = Vec3::new(5.0, 10.0, 0.0);
+        for _ in 0..60 {
+            fast.add_force(fast_handle, thrust);
+            assert_eq!(1, fast.update_fixed(1.0 / 60.0));
+        }
+        for _ in 0..20 {
+            slow.add_force(slow_handle, thrust);
+            assert_eq!(3, slow.update_fixed(3.0 / 60.0 + 1e-6));
+        }
+        let fast_body = fast.get_body(fast_handle).unwrap();
+        let slow_body = slow.get_body(slow_handle).unwrap();
+        assert_eq!(0.0, fast_body.position.y);
+        assert!((fast_body.linear_velocity.x - 5.0).abs() < 1e-4);
+        assert!(fast_body.position.abs_diff_eq(slow_body.position, 1e-4));
+        assert_eq!(Vec3::ZERO, fast_body.force);
+
+        // pushing the side of a sphere turns it about its centre
+        let mut scene = PhysicsScene::empty();
+        let handle = scene.add_body(body);
+        scene.add_force_at_point(handle, Vec3::Z, Vec3::X);
+        assert_eq!(
+            Vec3::new(0.0, -1.0, 0.0),
+            scene.get_body(handle).unwrap().torque
+        );
+        scene.update(1.0 / 60.0);
+        let body = scene.get_body(handle).unwrap();
+        assert!((body.linear_velocity.z - 1.0 / 60.0).abs() < 1e-6);
+        assert!((body.angular_velocity.y + 2.5 / 60.0).abs() < 1e-5);
+        assert_eq!(Vec3::ZERO, body.torque);
+    }
+
+    #[test]
+    fn test_damping() {
+        let mut scene = PhysicsScene::empty();
+        scene.linear_damping = 1.0;
+        scene.angular_damping = 2.0;
+        let mut add = |x: f32, linear_damping: Option<f32>| {
+            scene.add_body(Body {
+                position: Vec3::new(x, 0.0, 0.0),
+                linear_velocity: Vec3::new(0.0, 0.0, 1.0),
+                angular_velocity: Vec3::new(0.0, 1.0, 0.0),
+                gravity_scale: 0.0,
+                linear_damping,
+                shape: make_sphere(1.0),
+                ..Body::default()
+            })
+        };
+        let damped = add(0.0, None);
+        let undamped = add(10.0, Some(0.0));
+
+        let dt = 1.0 / 60.0;
+        for _ in 0..60 {
+            scene.update(dt);
+        }
+        let linear = (1.0 + dt).powi(-60);
+        let angular = (1.0 + 2.0 * dt).powi(-60);
+        let body = scene.get_body(damped).unwrap();
+        assert!((body.linear_velocity.z - linear).abs() < 1e-5);
+        assert!((body.angular_velocity.y - angular).abs() < 1e-5);
+
+        // the body's own damping replaces the scene's
+        let body = scene.get_body(undamped).unwrap();
+        assert_eq!(1.0, body.linear_velocity.z);
+        assert!((body.angular_velocity.y - angular).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_contact_jacobians() {
+        let (bodies, contact) = make_sphere_ground_contact(true);
+        let mut scene = PhysicsScene::empty();
+        scene.bodies = bodies;
+
+        let jacobians = scene.contact_jacobians(&[contact]);
+        assert_eq!(1, jacobians.len());
+        let jacobian = &jacobians[0];
+
+        let ground = scene.get_body(contact.handle_a).unwrap();
+        let sphere = scene.get_body(contact.handle_b).unwrap();
+        let ra = contact.world_point_a - ground.centre_of_mass_world();
+        let rb = contact.world_point_b - sphere.centre_of_mass_world();
+
+        let rows: Vec<[Vec3; 4]> = jacobian
+            .rows
+            .iter()
+            .map(|row| {
+                [
+                    Vec3::from_slice(&row[0..3]),
+                    Vec3::from_slice(&row[3..6]),
+                    Vec3::from_slice(&row[6..9]),
+                    Vec3::from_slice(&row[9..12]),
+                ]
+            })
+            .collect();
+
+        // the normal row pushes the sphere up out of the ground
+        let normal = Vec3::Y;
+        assert_eq!(
+            [-normal, ra.cross(-normal), normal, rb.cross(normal)],
+            rows[0]
+        );
+
+        // the friction rows act along two tangents perpendicular to the normal and each other
+        let u = rows[1][2];
+        let v = rows[2][2];
+        assert!((u.length() - 1.0).abs() < 1e-6);
+        assert!((v.length() - 1.0).abs() < 1e-6);
+        assert!(u.dot(normal).abs() < 1e-6);
+        assert!(v.dot(normal).abs() < 1e-6);
+        assert!(u.dot(v).abs() < 1e-6);
+        assert_eq!([-u, ra.cross(-u), u, rb.cross(u)], rows[1]);
+        assert_eq!([-v, ra.cross(-v), v, rb.cross(v)], rows[2]);
+    }
+
+    #[test]
+    fn test_update_fixed_max_substeps() {
+        let mut scene = PhysicsScene::empty();
+        scene.bodies.add(Body {
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+
+        // a frame that took ten seconds only runs the capped number of steps
+        let num_steps = scene.update_fixed(10.0);
+        assert_eq!(scene.max_substeps, num_steps);
+        assert_eq!(scene.max_substeps as u64, scene.step_num);
+
+        // and the backlog is dropped rather than carried into the next frame
+        let num_steps = scene.update_fixed(scene.fixed_dt);
+        assert_eq!(1, num_steps);
+        assert_eq!(scene.max_substeps as u64 + 1, scene.step_num);
+    }
+
+    #[test]
+    fn test_update_fixed_keeps_every_steps_events() {
+        let mut scene = PhysicsScene::empty();
+        scene.persisted_event_interval = 0;
+        let ground = scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let sensor = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::splat(2.0)),
+            is_sensor: true,
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.99, 0.0),
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+
+        // the cube touches the ground and enters the sensor in the first of the frame's steps
+        assert_eq!(4, scene.update_fixed(4.0 * scene.fixed_dt + 1e-6));
+        let collisions: Vec<(CollisionEventKind, BodyHandle, BodyHandle)> = scene
+            .drain_collision_events()
+            .map(|event| (event.kind, event.handle_a, event.handle_b))
+            .collect();
+        assert_eq!(
+            vec![(CollisionEventKind::Started, ground, cube)],
+            collisions
+        );
+        assert_eq!(
+            vec![TriggerEvent {
+                kind: TriggerEventKind::TriggerEnter,
+                sensor,
+                other: cube,
+            }],
+            scene.drain_trigger_events().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_interpolated_transform() {
+        let mut scene = PhysicsScene::empty();
+        scene.set_gravity(Vec3::ZERO);
+        scene.set_fixed_hz(10.0);
+        let handle = scene.add_body(Body {
+            position: Vec3::new(1.0, 0.0, 0.0),
+            linear_velocity: Vec3::new(1.0, 0.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        assert_eq!(
+            (Vec3::new(1.0, 0.0, 0.0), Quat::IDENTITY),
+            scene.interpolated_transform(handle)
+        );
+
+        // one step of 0.1 and a quarter of the next one left over
+        assert_eq!(1, scene.update_fixed(0.125));
+        assert!((scene.interpolation_alpha() - 0.25).abs() < 1e-4);
+        let body = scene.get_body(handle).unwrap();
+        assert_eq!(Vec3::new(1.0, 0.0, 0.0), body.previous_position);
+        assert!((body.position.x - 1.1).abs() < 1e-5);
+        let (position, _) = scene.interpolated_transform(handle);
+        assert!((position.x - 1.025).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_is_grounded() {
+        let max_slope_angle = 45f32.to_radians();
+
+        let mut scene = PhysicsScene::empty();
+        scene.bodies.add(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.bodies.add(Body {
+            position: Vec3::new(0.0, 0.99, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        scene.update(1.0 / 60.0);
+        assert!(scene.is_grounded(handle, max_slope_angle));
+
+        // up against the side of a wall with nothing underneath
+        let mut scene = PhysicsScene::empty();
+        scene.bodies.add(Body {
+            inv_mass: 0.0,
+            shape: make_box_wall0(),
+            ..Body::default()
+        });
+        let handle = scene.bodies.add(Body {
+            position: Vec3::new(1.99, 2.5, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        scene.update(1.0 / 60.0);
+        assert!(!scene.contacts_for_body(handle).is_empty());
+        assert!(!scene.is_grounded(handle, max_slope_angle));
+    }
+
+    #[test]
+    fn test_constraint_residual() {
+        let mut scene = PhysicsScene::empty();
+        let handle_a = scene.bodies.add(Body {
+            position: Vec3::new(0.0, 5.0, 0.0),
+            inv_mass: 0.0,
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let handle_b = scene.bodies.add(Body {
+            position: Vec3::new(1.0, 5.0, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let handle = scene
+            .constraints
+            .add_distance_constraint(&scene.bodies, handle_a, handle_b);
+
+        scene.update(1.0 / 60.0);
+        assert!(scene.constraints().residual(handle) < 0.01);
+
+        // yanking the body away is more than the constraint can correct in a step
+        scene.bodies.get_body_mut(handle_b).position += Vec3::new(2.0, 0.0, 0.0);
+        scene.update(1.0 / 60.0);
+        assert!(scene.constraints().residual(handle) > 1.0);
+    }
+
+    #[test]
+    fn test_is_grounded_world_up() {
+        let max_slope_angle = 45f32.to_radians();
+
+        // the ground turned so its top faces +z
+        let mut scene = PhysicsScene::empty();
+        scene.world_up = Vec3::Z;
+        scene.bodies.add(Body {
+            orientation: Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.bodies.add(Body {
+            position: Vec3::new(0.0, 0.0, 0.99),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        scene.update(1.0 / 60.0);
+        assert!(scene.is_grounded(handle, max_slope_angle));
+
+        scene.world_up = Vec3::Y;
+        assert!(!scene.is_grounded(handle, max_slope_angle));
+    }
+
+    #[test]
+    fn test_collision_event_impulse() {
+        let drop_sphere = |speed: f32| {
+            let mut scene = PhysicsScene::empty();
+            scene.bodies.add(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            scene.bodies.add(Body {
+                position: Vec3::new(0.0, 1.0 + speed * 0.5 / 60.0, 0.0),
+                linear_velocity: Vec3::new(0.0, -speed, 0.0),
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+            scene.update(1.0 / 60.0);
+
+            let events: Vec<CollisionEvent> = scene
+                .drain_collision_events()
+                .filter(|event| event.kind == CollisionEventKind::Impact)
+                .collect();
+            assert_eq!(1, events.len());
+            assert_eq!(CollisionEventKind::Impact, events[0].kind);
+            assert_eq!(0, scene.drain_collision_events().count());
+            events[0].impulse
+        };
+
+        let soft = drop_sphere(1.0);
+        let hard = drop_sphere(20.0);
+        assert!(soft > 0.0);
+        assert!(hard > soft);
+    }
+
+    fn make_aligned_stack(epsilon: f32, seed: u64) -> PhysicsScene {
+        let mut scene = PhysicsScene::empty();
+        scene.symmetry_breaking_epsilon = epsilon;
+        scene.symmetry_breaking_seed = seed;
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let cube_shape = make_cube_unit();
+        for y in 0..8 {
+            scene.add_body(Body {
+                position: Vec3::new(0.0, 1.0 + y as f32 * 2.0, 0.0),
+                shape: cube_shape.clone(),
+                ..Body::default()
+            });
+        }
+        scene
+    }
+
+    #[test]
+    fn test_symmetry_breaking_epsilon() {
+        let epsilon = 0.01;
+        let aligned = make_aligned_stack(0.0, 0);
+        let jittered = make_aligned_stack(epsilon, 7);
+
+        // static bodies stay put, dynamic ones move by no more than epsilon on each axis
+        for (a, b) in aligned.bodies.iter().zip(jittered.bodies.iter()) {
+            let offset = b.position - a.position;
+            if a.has_infinite_mass() {
+                assert_eq!(Vec3::ZERO, offset);
+            } else {
+                assert_ne!(Vec3::ZERO, offset);
+                assert!(offset.abs().max_element() <= epsilon);
+            }
+        }
+
+        // the same seed always gives the same simulation, a different seed doesn't
+        let mut scene_a = make_aligned_stack(epsilon, 7);
+        let mut scene_b = make_aligned_stack(epsilon, 7);
+        let mut scene_c = make_aligned_stack(epsilon, 8);
+        for _ in 0..120 {
+            scene_a.update(1.0 / 120.0);
+            scene_b.update(1.0 / 120.0);
+            scene_c.update(1.0 / 120.0);
+        }
+        let positions = |scene: &PhysicsScene| -> Vec<Vec3> {
+            scene.bodies.iter().map(|body| body.position).collect()
+        };
+        assert_eq!(positions(&scene_a), positions(&scene_b));
+        assert_ne!(positions(&scene_a), positions(&scene_c));
+
+        // a tower of balls should fall over, but nothing pushes a perfectly aligned one sideways
+        // so it balances forever. a small nudge is enough for it to come down onto the ground
+        let mut aligned = make_ball_tower(0.0);
+        let mut jittered = make_ball_tower(epsilon);
+        for _ in 0..600 {
+            aligned.update(1.0 / 60.0);
+            jittered.update(1.0 / 60.0);
+        }
+        assert!(aligned.bodies.iter().any(|body| body.position.y > 3.0));
+        for body in jittered.bodies.iter().skip(1) {
+            assert!(body.position.y < 0.55, "{}", body.position);
+            assert!(
+                body.linear_velocity.y.abs() < 0.2,
+                "{}",
+                body.linear_velocity
+            );
+        }
+    }
+
+    fn make_ball_tower(epsilon: f32) -> PhysicsScene {
+        let mut scene = PhysicsScene::empty();
+        scene.symmetry_breaking_epsilon = epsilon;
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        // damped so the balls stop rolling once they're down
+        for y in 0..4 {
+            scene.add_body(Body {
+                position: Vec3::new(0.0, 0.5 + y as f32, 0.0),
+                linear_damping: Some(1.0),
+                angular_damping: Some(1.0),
+                shape: make_sphere(0.5),
+                ..Body::default()
+            });
+        }
+        scene
+    }
+
+    fn make_sliding_cube() -> (PhysicsScene, BodyHandle) {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            friction: 1.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.24, 0.0),
+            linear_velocity: Vec3::new(2.0, 0.0, 0.0),
+            friction: 1.0,
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        (scene, handle)
+    }
+
+    #[test]
+    fn test_modify_contact_friction() {
+        let (mut gripping, handle) = make_sliding_cube();
+        let (mut sliding, _) = make_sliding_cube();
+        sliding.modify_contact = Some(Box::new(|material, _contact| material.friction = 0.0));
+
+        for _ in 0..30 {
+            gripping.update(1.0 / 60.0);
+            sliding.update(1.0 / 60.0);
+        }
+
+        let gripping = gripping.get_body(handle).unwrap();
+        let sliding = sliding.get_body(handle).unwrap();
+        assert!(gripping.linear_velocity.x < 1.0);
+        assert!(sliding.linear_velocity.x > 1.9);
+        assert!(sliding.position.x > gripping.position.x + 0.25);
+    }
+
+    #[test]
+    fn test_rolling_and_spinning_friction() {
+        // a ball rolling along the ground and one spinning in place
+        let make_scene = |coefficient: f32| {
+            let mut scene = PhysicsScene::empty();
+            scene.add_body(Body {
+                inv_mass: 0.0,
+                friction: 1.0,
+                rolling_friction: 1.0,
+                spinning_friction: 1.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            let mut add_ball = |x: f32, linear_velocity: Vec3, angular_velocity: Vec3| {
+                scene.add_body(Body {
+                    position: Vec3::new(x, 1.0, 0.0),
+                    linear_velocity,
+                    angular_velocity,
+                    elasticity: 0.0,
+                    friction: 1.0,
+                    rolling_friction: coefficient,
+                    spinning_friction: coefficient,
+                    shape: make_sphere(1.0),
+                    ..Body::default()
+                })
+            };
+            let rolling = add_ball(-10.0, Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -2.0));
+            let spinning = add_ball(10.0, Vec3::ZERO, Vec3::new(0.0, 5.0, 0.0));
+            for _ in 0..300 {
+                scene.update(1.0 / 60.0);
+            }
+            let rolling = scene.get_body(rolling).unwrap();
+            let spinning = scene.get_body(spinning).unwrap();
+            (
+                rolling.linear_velocity.length(),
+                spinning.angular_velocity.length(),
+            )
+        };
+
+        // only sliding friction, they never stop
+        let (rolling, spinning) = make_scene(0.0);
+        assert!(rolling > 1.5, "{}", rolling);
+        assert!(spinning > 4.5, "{}", spinning);
+
+        let (rolling, spinning) = make_scene(0.1);
+        assert!(rolling < 0.05, "{}", rolling);
+        assert!(spinning < 0.05, "{}", spinning);
+    }
+
+    #[test]
+    fn test_modify_contact_conveyor() {
+        // the ground moves like a belt under the cube, carrying it along
+        let (mut scene, handle) = make_sliding_cube();
+        scene.bodies.get_body_mut(handle).linear_velocity = Vec3::ZERO;
+        let belt = Vec3::new(1.0, 0.0, 0.0);
+        scene.modify_contact = Some(Box::new(move |material, contact| {
+            // where body a is carried relative to body b
+            material.surface_velocity = if contact.handle_a == handle {
+                belt
+            } else {
+                -belt
+            };
+        }));
+
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let body = scene.get_body(handle).unwrap();
+        assert!(
+            (body.linear_velocity.x - 1.0).abs() < 0.1,
+            "{}",
+            body.linear_velocity
+        );
+        assert!(body.linear_velocity.z.abs() < 0.01);
+        assert!(body.position.x > 0.5);
+    }
+
+    #[test]
+    fn test_surface_velocity_conveyor() {
+        // the ground's surface moves without the ground moving, carrying the cube along
+        let (mut scene, handle) = make_sliding_cube();
+        scene.bodies.get_body_mut(handle).linear_velocity = Vec3::ZERO;
+        let (ground, _) = scene.bodies.iter_with_handles().next().unwrap();
+        scene.bodies.get_body_mut(ground).surface_velocity = Vec3::new(0.0, 0.0, -1.0);
+
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let body = scene.get_body(handle).unwrap();
+        assert!(
+            (body.linear_velocity.z + 1.0).abs() < 0.1,
+            "{}",
+            body.linear_velocity
+        );
+        assert!(body.linear_velocity.x.abs() < 0.01);
+        assert!(body.position.z < -0.5);
+        assert_eq!(Vec3::ZERO, scene.get_body(ground).unwrap().position);
+    }
+
+    #[test]
+    fn test_modify_contact_one_way() {
+        // a cube thrown up through a platform lands on top of it
+        let mut scene = PhysicsScene::empty();
+        let platform = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::new(2.0, 0.1, 2.0)),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            linear_velocity: Vec3::new(0.0, 10.0, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        scene.modify_contact = Some(Box::new(move |material, contact| {
+            // the normal points towards body a, only keep contacts pushing the cube up
+            let up = if contact.handle_a == platform {
+                -contact.normal.y
+            } else {
+                contact.normal.y
+            };
+            material.enabled = up > 0.7;
+        }));
+
+        let mut passed_through = false;
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+            passed_through |= scene.get_body(handle).unwrap().position.y > 3.5;
+        }
+        let body = scene.get_body(handle).unwrap();
+        assert!(passed_through);
+        assert!((body.position.y - 3.35).abs() < 0.05, "{}", body.position);
+        assert!(body.linear_velocity.length() < 0.1);
+    }
+
+    #[test]
+    fn test_one_way_platform() {
+        // a cube thrown up through a platform lands on top of it, a cube on top stays there
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            inv_mass: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::new(2.0, 0.1, 2.0)),
+            one_way: Some(OneWayPlatform { direction: Vec3::Y }),
+            ..Body::default()
+        });
+        let thrown = scene.add_body(Body {
+            position: Vec3::new(-1.0, 1.0, 0.0),
+            linear_velocity: Vec3::new(0.0, 10.0, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let resting = scene.add_body(Body {
+            position: Vec3::new(1.0, 3.5, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+
+        let mut passed_through = false;
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+            passed_through |= scene.get_body(thrown).unwrap().position.y > 3.5;
+        }
+        assert!(passed_through);
+        for handle in [thrown, resting] {
+            let body = scene.get_body(handle).unwrap();
+            assert!((body.position.y - 3.35).abs() < 0.05, "{}", body.position);
+            assert!(body.linear_velocity.length() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_system_com_velocity() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            position: Vec3::new(-2.0, 0.0, 0.0),
+            linear_velocity: Vec3::new(3.0, 0.0, 0.0),
+            inv_mass: 1.0,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        scene.add_body(Body {
+            position: Vec3::new(2.0, 0.0, 0.0),
+            linear_velocity: Vec3::new(-1.0, 0.0, 0.0),
+            inv_mass: 0.5,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+
+        // (1 * 3 + 2 * -1) / 3
+        let initial = scene.system_com_velocity();
+        assert!(initial.abs_diff_eq(Vec3::new(1.0 / 3.0, 0.0, 0.0), 1e-6));
+        assert!(scene
+            .system_center_of_mass()
+            .abs_diff_eq(Vec3::new(2.0 / 3.0, 0.0, 0.0), 1e-6));
+
+        let dt = 1.0 / 60.0;
+        let mut collided = false;
+        for step in 1..=90 {
+            scene.update(dt);
+            collided |= !scene.contacts_for_body(BodyHandle(0, 0)).is_empty();
+
+            // gravity is the only external force and acts on both bodies equally
+            let expected = initial + Vec3::new(0.0, -10.0, 0.0) * dt * step as f32;
+            assert!(scene.system_com_velocity().abs_diff_eq(expected, 1e-3));
+        }
+        assert!(collided);
+    }
+
+    #[test]
+    fn test_iter_previous_contacts() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        scene.add_body(Body {
+            position: Vec3::new(0.0, 1.1, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+
+        let world_points = |contacts: &mut dyn Iterator<Item = &Contact>| -> Vec<Vec3> {
+            contacts.map(|contact| contact.world_point_a).collect()
+        };
+
+        let mut last_step = Vec::new();
+        let mut first_touch = false;
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+            let previous = world_points(&mut scene.iter_previous_contacts());
+            let current = world_points(&mut scene.iter_contacts());
+            assert_eq!(last_step, previous);
+            first_touch |= previous.is_empty() && !current.is_empty();
+            last_step = current;
+        }
+        assert!(first_touch);
+    }
+
+    #[test]
+    fn test_trace_pair_head_on() {
+        let mut scene = PhysicsScene::empty();
+        let handle_a = scene.add_body(Body {
+            position: Vec3::new(-0.75, 0.0, 0.0),
+            linear_velocity: Vec3::new(30.0, 0.0, 0.0),
+            elasticity: 1.0,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let handle_b = scene.add_body(Body {
+            position: Vec3::new(0.75, 0.0, 0.0),
+            linear_velocity: Vec3::new(-30.0, 0.0, 0.0),
+            elasticity: 1.0,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        assert_eq!(None, scene.trace_pair(handle_a, handle_b));
+
+        scene.update(1.0 / 60.0);
+
+        let trace = scene.trace_pair(handle_a, handle_b).unwrap();
+        assert_eq!(handle_a, trace.handle_a);
+        assert!(trace.normal.abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), 1e-3));
+
+        // equal masses with an elastic hit swap velocities, so each body gets an impulse of
+        // m * 2v = 60 pushing them apart
+        assert!(trace.normal_impulse > 0.0);
+        assert!((trace.normal_impulse - 60.0).abs() < 0.1);
+        assert!(trace.friction_impulse.abs() < 1e-3);
+        assert!((trace.pre_velocity_a.linear.x - 30.0).abs() < 1e-3);
+        assert!((trace.post_velocity_a.linear.x + 30.0).abs() < 1e-3);
+
+        // the same trace from the other side
+        let flipped = scene.trace_pair(handle_b, handle_a).unwrap();
+        assert_eq!(trace.normal_impulse, flipped.normal_impulse);
+        assert_eq!(-trace.normal, flipped.normal);
+        assert_eq!(trace.post_velocity_a, flipped.post_velocity_b);
+
+        // resting contacts are traced through the contact constraints
+        let mut scene = PhysicsScene::empty();
+        let ground = scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.99, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+        let trace = scene.trace_pair(sphere, ground).unwrap();
+        assert!(trace.normal.abs_diff_eq(Vec3::Y, 1e-3));
+        assert!(trace.normal_impulse > 0.0);
+    }
+
+    #[test]
+    fn test_material_presets() {
+        let mut scene = PhysicsScene::empty();
+        let ice = scene.add_body(Body::default().with_material(Material::ICE));
+        let rubber = scene.add_body(Body {
+            shape: make_sphere(0.5),
+            ..Body::default().with_material(Material::RUBBER)
+        });
+
+        let ice = scene.get_body(ice).unwrap();
+        assert!(ice.friction < 0.1);
+        assert_eq!(Material::ICE.elasticity, ice.elasticity);
+
+        let rubber = scene.get_body(rubber).unwrap();
+        assert!(rubber.elasticity > 0.8);
+        assert_eq!(Material::RUBBER.friction, rubber.friction);
+    }
+
+    #[test]
+    fn test_measure_restitution() {
+        let elasticity = 0.6;
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            elasticity: 1.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            elasticity,
+            friction: 0.0,
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+
+        let mut measured = None;
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+            measured = scene.measure_restitution(handle);
+            if measured.is_some() {
+                break;
+            }
+        }
+        let measured = measured.unwrap();
+        assert!((measured - elasticity).abs() < 0.05, "{}", measured);
+    }
+
+    #[test]
+    fn test_clear_solver_caches() {
+        let make_loaded_scene = || {
+            let mut scene = PhysicsScene::empty();
+            let anchor = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_sphere(0.5),
+                ..Body::default()
+            });
+            let hanging = scene.add_body(Body {
+                position: Vec3::new(0.0, 3.0, 0.0),
+                inv_mass: 0.1,
+                shape: make_sphere(0.5),
+                ..Body::default()
+            });
+            scene
+                .constraints
+                .add_distance_constraint(&scene.bodies, anchor, hanging);
+            for _ in 0..60 {
+                scene.update(1.0 / 60.0);
+            }
+            (scene, hanging)
+        };
+
+        // warm starting kicks the body before the solver runs
+        let (mut scene, hanging) = make_loaded_scene();
+        let before = scene.get_body(hanging).unwrap().linear_velocity;
+        scene
+            .constraints
+            .pre_solve(&mut scene.bodies, 1.0 / 60.0, &[0]);
+        assert!(!scene
+            .get_body(hanging)
+            .unwrap()
+            .linear_velocity
+            .abs_diff_eq(before, 1e-3));
+
+        // until the caches are cleared
+        let (mut scene, hanging) = make_loaded_scene();
+        scene.clear_solver_caches();
+        let before = scene.get_body(hanging).unwrap().linear_velocity;
+        scene
+            .constraints
+            .pre_solve(&mut scene.bodies, 1.0 / 60.0, &[0]);
+        assert_eq!(before, scene.get_body(hanging).unwrap().linear_velocity);
+    }
+
+    #[test]
+    fn test_two_phase_update() {
+        let make_scene = || {
+            let mut scene = make_overlapping_stack(ContactOrder::Found);
+            scene.add_body(Body {
+                position: Vec3::new(3.0, 0.8, 0.0),
+                linear_velocity: Vec3::new(0.0, -30.0, 0.0),
+                shape: make_sphere(0.5),
+                ..Body::default()
+            });
+            scene
+        };
+        let positions = |scene: &PhysicsScene| -> Vec<Vec3> {
+            scene.bodies.iter().map(|body| body.position).collect()
+        };
+
+        let mut monolithic = make_scene();
+        let mut two_phase = make_scene();
+        for _ in 0..30 {
+            monolithic.update(1.0 / 60.0);
+            two_phase.collect_contacts(1.0 / 60.0);
+            two_phase.resolve_step(1.0 / 60.0);
+        }
+        assert_eq!(positions(&monolithic), positions(&two_phase));
+
+        // the falling sphere hits the ground on the first step, pointing the contact sideways
+        // sends it off in a different direction
+        let mut unmodified = make_scene();
+        let mut modified = make_scene();
+        unmodified.update(1.0 / 60.0);
+        modified.collect_contacts(1.0 / 60.0);
+        let mut num_modified = 0;
+        for contact in modified.iter_contacts_mut() {
+            contact.normal = Vec3::X;
+            num_modified += 1;
+        }
+        assert!(num_modified > 0);
+        modified.resolve_step(1.0 / 60.0);
+        assert_ne!(positions(&unmodified), positions(&modified));
+    }
+
+    #[test]
+    fn test_friction_coulomb_limit_on_slope() {
+        // friction of 0.5 on a 45 degree slope isn't enough to hold the box
+        let slope = 45f32.to_radians();
+        let friction = 0.5;
+        let orientation = Quat::from_rotation_z(slope);
+        let normal = orientation * Vec3::Y;
+
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            orientation,
+            inv_mass: 0.0,
+            elasticity: 0.0,
+            friction: 1.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: normal * 0.99,
+            orientation,
+            elasticity: 0.0,
+            friction,
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+
+        let seconds = 1.0;
+        for _ in 0..60 {
+            scene.update(seconds / 60.0);
+        }
+
+        // a = g (sin - mu cos), friction is limited by the normal force which is less than the
+        // box's weight on a slope
+        let gravity = scene.gravity();
+        let down_slope = (gravity - normal * normal.dot(gravity)).normalize();
+        let expected_speed = gravity.length() * (slope.sin() - friction * slope.cos()) * seconds;
+        let speed = scene
+            .get_body(handle)
+            .unwrap()
+            .linear_velocity
+            .dot(down_slope);
+        assert!(
+            speed > expected_speed * 0.85,
+            "{} {}",
+            speed,
+            expected_speed
+        );
+        assert!(speed < gravity.length() * slope.sin() * seconds);
+    }
+
+    #[test]
+    fn test_body_shape() {
+        let mut scene = PhysicsScene::empty();
+        let sphere = scene.add_body(Body {
+            shape: make_sphere(0.75),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+
+        let shape = scene.get_body(sphere).unwrap().shape();
+        assert_eq!(Some(0.75), shape.as_sphere());
+        assert!(shape.as_box().is_none());
+
+        let shape = scene.get_body(cube).unwrap().shape();
+        assert_eq!(None, shape.as_sphere());
+        let bounds = shape.as_box().unwrap().bounds;
+        assert_eq!(Vec3::splat(2.0), bounds.width());
+    }
+
+    // a frictionless ball on a slope, held up it by a rope that pulls a little away from the
+    // slope. the rope and the slope both hold the ball in place
+    fn make_roped_ball() -> (PhysicsScene, BodyHandle) {
+        let mut scene = PhysicsScene::empty();
+        scene.sleep_linear_threshold = 0.0;
+        scene.sleep_angular_threshold = 0.0;
+        let slope = Quat::from_rotation_z(0.5);
+        scene.add_body(Body {
+            orientation: slope,
+            inv_mass: 0.0,
+            friction: 0.0,
+            shape: Shape::make_box_from_half_extents(Vec3::new(10.0, 0.5, 2.0)),
+            ..Body::default()
+        });
+        let position = slope * Vec3::new(0.0, 1.0, 0.0);
+        let rope = slope * Vec3::new(0.3f32.cos(), 0.3f32.sin(), 0.0);
+        let anchor = scene.add_body(Body {
+            position: position + rope * 3.0,
+            inv_mass: 0.0,
+            shape: make_sphere(0.1),
+            ..Body::default()
+        });
+        let ball = scene.add_body(Body {
+            position,
+            friction: 0.0,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        scene
+            .constraints
+            .add_distance_constraint(&scene.bodies, anchor, ball);
+        (scene, ball)
+    }
+
+    #[test]
+    fn test_roped_ball_on_slope_stays_put() {
+        let (mut scene, ball) = make_roped_ball();
+        for _ in 0..240 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // the rope and the slope are solved together so neither undoes what the other did, and
+        // the ball stays where it settled
+        let settled = scene.get_body(ball).unwrap().position;
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+            let position = scene.get_body(ball).unwrap().position;
+            assert!((position - settled).length() < 1e-3, "{}", position);
+        }
+    }
+
+    #[test]
+    fn test_constrained_body_resting_on_ground() {
+        // the cube is hung from an anchor and also resting on the ground. resting contacts are
+        // constraints solved in the same iterations as the joints so the two don't fight
+        let mut scene = PhysicsScene::empty();
+        scene.sleep_linear_threshold = 0.0;
+        scene.sleep_angular_threshold = 0.0;
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let anchor = scene.add_body(Body {
+            position: Vec3::new(-1.0, 1.5, 0.0),
+            inv_mass: 0.0,
+            shape: make_sphere(0.1),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.99, 0.0),
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+        scene
+            .constraints
+            .add_distance_constraint(&scene.bodies, anchor, cube);
+
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+        }
+        let settled = scene.get_body(cube).unwrap().position;
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+            let body = scene.get_body(cube).unwrap();
+            assert!(body.position.abs_diff_eq(settled, 1e-4));
+            assert!(body.linear_velocity.length() < 1e-3);
+            assert!(body.angular_velocity.length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_render_color_show_sleeping() {
+        let mut scene = PhysicsScene::empty();
+        let handle = scene.add_body(Body {
+            color: Vec3::new(1.0, 0.5, 0.0),
+            ..Body::default()
+        });
+        let awake = scene.render_color(handle);
+        assert_eq!(Vec3::new(1.0, 0.5, 0.0), awake);
+
+        scene.bodies.get_body_mut(handle).sleeping = true;
+        assert_eq!(awake, scene.render_color(handle));
+
+        scene.show_sleeping = true;
+        assert_ne!(awake, scene.render_color(handle));
+
+        scene.bodies.get_body_mut(handle).wake();
+        assert_eq!(awake, scene.render_color(handle));
+    }
+
+    #[test]
+    fn test_state_hash_golden() {
+        // if a change to the simulation is intended, run this test with
+        // `cargo test -p physics state_hash` and replace EXPECTED with the hash from the failure
+        // message. floating point results can differ between targets and glam builds, so
+        // regenerate it the same way if it fails on a new platform
+        const EXPECTED: u64 = 0xc287_3c73_a9bc_5d9e;
+
+        let mut scene = make_overlapping_stack(ContactOrder::Found);
+        let initial = scene.state_hash();
+        assert_eq!(
+            initial,
+            make_overlapping_stack(ContactOrder::Found).state_hash()
+        );
+        for _ in 0..100 {
+            scene.update(1.0 / 60.0);
+        }
+        let hash = scene.state_hash();
+        assert_ne!(initial, hash);
+        assert_eq!(EXPECTED, hash, "state hash changed, got {:#x}", hash);
+    }
+
+    #[test]
+    fn test_mover_constraint() {
+        // a constraint on a single body, its platform should move without the other body the
+        // islands and sleeping look for
+        let mut scene = PhysicsScene::empty();
+        add_mover_constraint(&mut scene.bodies, &mut scene.constraints);
+        let start = scene.bodies.iter().next().unwrap().position;
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+        let end = scene.bodies.iter().next().unwrap().position;
+        assert!(end.z > start.z);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        // a settling stack, a hinge and a mover, so the snapshot has to carry the contact
+        // manifolds and the constraints' own state along with the bodies
+        let mut scene = make_overlapping_stack(ContactOrder::Found);
+        add_hinge_constraint(&mut scene.bodies, &mut scene.constraints);
+        add_mover_constraint(&mut scene.bodies, &mut scene.constraints);
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+
+        let snapshot = scene.snapshot();
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+        let expected = scene.state_hash();
+
+        // rolling back replays the same steps exactly, as many times as it's restored
+        for _ in 0..2 {
+            scene.restore(&snapshot);
+            assert_eq!(10, scene.step_num);
+            for _ in 0..30 {
+                scene.update(1.0 / 60.0);
+            }
+            assert_eq!(expected, scene.state_hash());
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore_fluids_and_fields() {
+        // a ball blown sideways through a pool, with a material on it
+        let mut scene = PhysicsScene::empty();
+        let material = scene.add_material(PhysicsMaterial::default());
+        let ball = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            shape: make_sphere(0.5),
+            material: Some(material),
+            ..Body::default()
+        });
+        scene.add_fluid_volume(FluidVolume::new(
+            FluidRegion::Aabb(Bounds {
+                mins: Vec3::splat(-10.0),
+                maxs: Vec3::new(10.0, 0.0, 10.0),
+            }),
+            1000.0,
+        ));
+        let field = scene.add_force_field(ForceField::Uniform {
+            acceleration: Vec3::new(2.0, 0.0, 0.0),
+        });
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+
+        let snapshot = scene.snapshot();
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+        let expected = scene.state_hash();
+
+        // changes made after the snapshot are undone by restoring it
+        *scene.force_field_mut(field) = ForceField::Uniform {
+            acceleration: Vec3::new(-2.0, 0.0, 0.0),
+        };
+        scene.fluid_volume_mut(FluidHandle(0)).density = 0.0;
+        scene.add_fluid_volume(FluidVolume::new(
+            FluidRegion::Aabb(Bounds {
+                mins: Vec3::splat(-10.0),
+                maxs: Vec3::splat(10.0),
+            }),
+            1000.0,
+        ));
+        scene.set_material(
+            material,
+            PhysicsMaterial {
+                density: 10.0,
+                ..PhysicsMaterial::default()
+            },
+        );
+        scene.restore(&snapshot);
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+        assert_eq!(expected, scene.state_hash());
+        assert!(scene.get_body(ball).unwrap().position.x > 0.0);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_snapshot_serde_round_trip() {
+        // a saved game, the snapshot read back from json replays the same steps as the original
+        let mut scene = make_overlapping_stack(ContactOrder::Found);
+        add_hinge_constraint(&mut scene.bodies, &mut scene.constraints);
+        add_mover_constraint(&mut scene.bodies, &mut scene.constraints);
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+
+        let json = serde_json::to_string(&scene.snapshot()).unwrap();
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+        let expected = scene.state_hash();
+
+        let snapshot: SceneSnapshot = serde_json::from_str(&json).unwrap();
+        scene.restore(&snapshot);
+        for _ in 0..30 {
+            scene.update(1.0 / 60.0);
+        }
+        assert_eq!(expected, scene.state_hash());
+    }
+
+    #[test]
+    fn test_step_stats() {
+        let mut scene = make_overlapping_stack(ContactOrder::Found);
+        scene.update(1.0 / 60.0);
+
+        // the ground and four cubes all pushing on each other
+        let stats = *scene.stats();
+        assert!(stats.broadphase_pairs >= 4);
+        assert_eq!(4, stats.narrowphase_tests);
+        assert!(stats.contacts >= 4);
+        assert_eq!(1, stats.islands);
+        assert_eq!(scene.velocity_iterations, stats.solver_iterations);
+        assert_eq!(4, stats.bodies_awake);
+        assert!(stats.total_time() >= stats.solver_time);
+
+        scene.clear();
+        assert_eq!(0, scene.stats().broadphase_pairs);
+    }
+
+    #[test]
+    fn test_ccd_disabled_lands() {
+        // a ball dropped without continuous collision detection stops on the ground's speculative
+        // contacts instead of being swept to it
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            elasticity: 0.0,
+            ccd_enabled: false,
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+            assert!(scene.get_body(handle).unwrap().position.y > 0.45);
+        }
+        let body = scene.get_body(handle).unwrap();
+        assert!((body.position.y - 0.5).abs() < 0.05);
+        assert!(body.linear_velocity.length() < 0.1);
+    }
+
+    #[test]
+    fn test_child_colliders() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let primary = Body {
+            position: Vec3::new(0.0, 0.6, 0.0),
+            shape: make_sphere(0.25),
+            ..Body::default()
+        };
+        let handle = scene.add_body(Body {
+            colliders: vec![
+                Collider {
+                    shape: make_sphere(0.5),
+                    offset: Vec3::new(-1.5, 0.0, 0.0),
+                },
+                Collider {
+                    shape: make_sphere(0.5),
+                    offset: Vec3::new(1.5, 0.0, 0.0),
+                },
+            ],
+            ..primary.clone()
+        });
+
+        // both spheres touch the ground and hold the body up level
+        let (mut hit_left, mut hit_right) = (false, false);
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+            for contact in scene.contacts_for_body(handle) {
+                hit_left |= contact.world_point_a.x < -1.0;
+                hit_right |= contact.world_point_a.x > 1.0;
+            }
+        }
+        assert!(hit_left && hit_right);
+        let body = scene.get_body(handle).unwrap();
+        assert!((body.position.y - 0.5).abs() < 0.05);
+        assert!(body.orientation.abs_diff_eq(Quat::IDENTITY, 1e-2));
+
+        // the colliders don't change the mass properties
+        assert_eq!(
+            primary.inv_intertia_tensor_local(),
+            body.inv_intertia_tensor_local()
+        );
+    }
+
+    #[test]
+    fn test_persisted_event_interval() {
+        let mut scene = PhysicsScene::empty();
+        scene.persisted_event_interval = 10;
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.99, 0.0),
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+
+        let mut kinds = Vec::new();
+        for step in 0..35 {
+            scene.update(1.0 / 60.0);
+            for event in scene.drain_collision_events() {
+                assert!(event.handle_a == handle || event.handle_b == handle);
+                kinds.push((step, event.kind));
+            }
+        }
+        assert_eq!(
+            vec![
+                (0, CollisionEventKind::Started),
+                (10, CollisionEventKind::Persisted),
+                (20, CollisionEventKind::Persisted),
+                (30, CollisionEventKind::Persisted),
+            ],
+            kinds
+        );
+
+        // lifting the cube off the ground stops the contact straight away
+        scene.bodies.get_body_mut(handle).position.y = 5.0;
+        scene.update(1.0 / 60.0);
+        let kinds: Vec<CollisionEventKind> = scene
+            .drain_collision_events()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(vec![CollisionEventKind::Stopped], kinds);
+    }
+
+    #[test]
+    fn test_remove_body() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let a = scene.add_body(Body {
+            position: Vec3::new(-3.0, 1.0, 0.0),
+            ..Body::default()
+        });
+        let b = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            ..Body::default()
+        });
+        let c = scene.add_body(Body {
+            position: Vec3::new(3.0, 1.0, 0.0),
+            ..Body::default()
+        });
+        let bodies = &scene.bodies;
+        scene.constraints.add_distance_constraint(bodies, b, c);
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // c is moved into a's place in the arena but keeps its handle, and so does its constraint.
+        // the level refers to it by its new index
+        let removed = scene.remove_body(a);
+        assert_eq!(removed.position.x, -3.0);
+        assert_eq!(scene.iter_body_handles().len(), 3);
+        assert!(!scene.is_valid(a) && scene.is_valid(b) && scene.is_valid(c));
+        assert!(scene.get_body(a).is_none());
+        assert!((scene.get_body(c).unwrap().position.x - 3.0).abs() < 0.01);
+        assert_eq!(1, scene.bodies.index(c));
+        match scene.constraints.to_level(&scene.bodies).as_slice() {
+            [LevelConstraint::Distance { body_a, body_b, .. }] => {
+                assert_eq!((*body_a, *body_b), (2, 1))
+            }
+            constraints => panic!("{:?}", constraints),
+        }
+
+        // its constraint goes with b
+        scene.remove_body(b);
+        assert!(scene.constraints.to_level(&scene.bodies).is_empty());
+        for _ in 0..10 {
+            scene.update(1.0 / 60.0);
+        }
+        assert!((scene.get_body(c).unwrap().position.x - 3.0).abs() < 0.01);
+
+        // a body added in b's slot doesn't take over its handle
+        let d = scene.add_body(Body::default());
+        assert_eq!(d.0, b.0);
+        assert!(scene.get_body(b).is_none() && scene.get_body(d).is_some());
+
+        scene.clear();
+        assert_eq!(scene.iter_body_handles().len(), 0);
+        assert!(!scene.is_valid(c));
+        let e = scene.add_body(Body::default());
+        assert!(scene.get_body(e).is_some());
+    }
+
+    #[test]
+    fn test_removed_handle_cant_push_another_body() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut scene = PhysicsScene::empty();
+        scene.set_gravity(Vec3::ZERO);
+        let a = scene.add_body(Body::default());
+        let b = scene.add_body(Body {
+            position: Vec3::new(3.0, 0.0, 0.0),
+            ..Body::default()
+        });
+        // b is moved into a's place, where a's handle used to point
+        scene.remove_body(a);
+        assert_eq!(0, scene.bodies.index(b));
+
+        let pushes: [&dyn Fn(&mut PhysicsScene); 4] = [
+            &|scene| scene.add_force(a, Vec3::X),
+            &|scene| scene.add_torque(a, Vec3::X),
+            &|scene| scene.add_force_at_point(a, Vec3::X, Vec3::ZERO),
+            &|scene| scene.apply_impulse(a, Vec3::ZERO, Vec3::X),
+        ];
+        for push in pushes {
+            assert!(catch_unwind(AssertUnwindSafe(|| push(&mut scene))).is_err());
+        }
+        scene.update(1.0 / 60.0);
+        let body = scene.get_body(b).unwrap();
+        assert_eq!(Vec3::ZERO, body.linear_velocity);
+        assert_eq!(Vec3::ZERO, body.angular_velocity);
+    }
+
+    #[test]
+    fn test_remove_body_keeps_constraint_handles() {
+        let mut scene = PhysicsScene::empty();
+        let mut add = |x: f32| {
+            scene.add_body(Body {
+                position: Vec3::new(x, 1.0, 0.0),
+                ..Body::default()
+            })
+        };
+        let (a, b, c) = (add(-3.0), add(0.0), add(3.0));
+        let bodies = &scene.bodies;
+        let removed = scene.constraints.add_distance_constraint(bodies, a, b);
+        let kept = scene.constraints.add_distance_constraint(bodies, b, c);
+
+        // the joint after the removed one is still found by its handle
+        scene.remove_body(a);
+        assert!(!scene.constraints.is_enabled(removed));
+        assert!(scene.constraints.is_enabled(kept));
+        scene.constraints.set_enabled(kept, false);
+        assert_eq!(0, scene.constraints.iter_enabled_pairs().count());
+        scene.constraints.set_enabled(kept, true);
+        let pairs: Vec<_> = scene.constraints.iter_enabled_pairs().collect();
+        assert_eq!(vec![(kept.0 as usize, b, c)], pairs);
+
+        // the removed joint can't be turned back on
+        scene.constraints.set_enabled(removed, true);
+        assert!(!scene.constraints.is_enabled(removed));
+        assert_eq!(1, scene.constraints.to_level(&scene.bodies).len());
+    }
+
+    #[test]
+    fn test_hinge_limits() {
+        // a pendulum kicked sideways, returns the furthest it turns in degrees
+        fn max_swing(limits: Option<(f32, f32)>) -> f32 {
+            let mut scene = PhysicsScene::empty();
+            let pivot = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bob = scene.add_body(Body {
+                position: Vec3::new(0.0, 3.0, 0.0),
+                linear_velocity: Vec3::new(4.0, 0.0, 0.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bodies = &scene.bodies;
+            scene.constraints.add_hinge_constraint_with_limits(
+                bodies,
+                pivot,
+                bob,
+                Vec3::new(0.0, 5.0, 0.0),
+                Vec3::Z,
+                limits,
+            );
+
+            let mut max_angle = 0.0f32;
+            for _ in 0..120 {
+                scene.update(1.0 / 60.0);
+                let turned = 2.0 * scene.get_body(bob).unwrap().orientation.z.asin();
+                max_angle = max_angle.max(turned.to_degrees());
+            }
+            max_angle
+        }
+
+        let free = max_swing(None);
+        let limited = max_swing(Some((-20.0, 20.0)));
+        assert!(free > 35.0, "{}", free);
+        assert!(limited < 25.0, "{}", limited);
+    }
+
+    #[test]
+    fn test_spherical_cone_limit() {
+        // returns how far the pendulum swings from hanging straight down in degrees
+        fn max_swing(cone_limit: Option<f32>) -> f32 {
+            let mut scene = PhysicsScene::empty();
+            let pivot = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bob = scene.add_body(Body {
+                position: Vec3::new(0.0, 3.0, 0.0),
+                linear_velocity: Vec3::new(3.0, 0.0, 2.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bodies = &scene.bodies;
+            scene.constraints.add_spherical_constraint(
+                bodies,
+                pivot,
+                bob,
+                Vec3::new(0.0, 5.0, 0.0),
+                -Vec3::Y,
+                cone_limit,
+            );
+
+            let mut max_angle = 0.0f32;
+            for _ in 0..120 {
+                scene.update(1.0 / 60.0);
+                let axis = scene.get_body(bob).unwrap().orientation * -Vec3::Y;
+                max_angle = max_angle.max(axis.angle_between(-Vec3::Y).to_degrees());
+            }
+            max_angle
+        }
+
+        let free = max_swing(None);
+        let limited = max_swing(Some(15.0));
+        assert!(free > 30.0, "{}", free);
+        assert!(limited < 20.0, "{}", limited);
+    }
+
+    #[test]
+    fn test_slider() {
+        fn make_slider(limits: Option<(f32, f32)>, motor: Option<JointMotor>) -> PhysicsScene {
+            let mut scene = PhysicsScene::empty();
+            let rail = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let slider = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            // a diagonal rail so gravity pulls the slider along and across it
+            let axis = Vec3::new(1.0, -1.0, 0.0).normalize();
+            let bodies = &scene.bodies;
+            scene.constraints.add_slider_constraint(
+                bodies,
+                rail,
+                slider,
+                Vec3::new(0.0, 5.0, 0.0),
+                axis,
+                limits,
+                motor,
+            );
+            scene.set_pair_ignored(rail, slider, true);
+            scene
+        }
+        let axis = Vec3::new(1.0, -1.0, 0.0).normalize();
+        let slider = BodyHandle(1, 0);
+
+        // slides down the rail without leaving it or turning
+        let mut scene = make_slider(None, None);
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let offset = scene.get_body(slider).unwrap().position - Vec3::new(0.0, 5.0, 0.0);
+        assert!(offset.dot(axis) > 1.0, "{}", offset);
+        assert!(
+            (offset - axis * offset.dot(axis)).length() < 0.05,
+            "{}",
+            offset
+        );
+        assert!(
+            scene
+                .get_body(slider)
+                .unwrap()
+                .orientation
+                .angle_between(Quat::IDENTITY)
+                < 0.05
+        );
+
+        // stops at the max travel
+        let mut scene = make_slider(Some((-0.5, 0.5)), None);
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let offset = scene.get_body(slider).unwrap().position - Vec3::new(0.0, 5.0, 0.0);
+        assert!((offset.dot(axis) - 0.5).abs() < 0.1, "{}", offset);
+
+        // the motor drives it back up the rail against gravity
+        let motor = JointMotor {
+            target_velocity: -1.0,
+            max_force: 100.0,
+        };
+        let mut scene = make_slider(None, Some(motor));
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let offset = scene.get_body(slider).unwrap().position - Vec3::new(0.0, 5.0, 0.0);
+        assert!((offset.dot(axis) + 1.0).abs() < 0.1, "{}", offset);
+
+        // gravity pulls along the rail with about 7N, more than this motor can push back with
+        let motor = JointMotor {
+            target_velocity: -1.0,
+            max_force: 2.0,
+        };
+        let mut scene = make_slider(None, Some(motor));
+        for _ in 0..60 {
+            scene.update(1.0 / 60.0);
+        }
+        let offset = scene.get_body(slider).unwrap().position - Vec3::new(0.0, 5.0, 0.0);
+        assert!(offset.dot(axis) > 0.5, "{}", offset);
+    }
+
+    #[test]
+    fn test_spring() {
+        // a unit mass hanging from a spring a metre long, gravity stretches it by 10 / stiffness
+        fn hang(stiffness: f32, damping: f32) -> (PhysicsScene, ConstraintHandle) {
+            let mut scene = PhysicsScene::empty();
+            let hook = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let weight = scene.add_body(Body {
+                position: Vec3::new(0.0, 4.0, 0.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bodies = &scene.bodies;
+            let handle = scene.constraints.add_spring_constraint(
+                bodies,
+                hook,
+                weight,
+                Vec3::new(0.0, 5.0, 0.0),
+                Vec3::new(0.0, 4.0, 0.0),
+                Spring {
+                    rest_length: 1.0,
+                    stiffness,
+                    damping,
+                },
+            );
+            scene.set_pair_ignored(hook, weight, true);
+            (scene, handle)
+        }
+        let weight = BodyHandle(1, 0);
+        let step = |scene: &mut PhysicsScene, seconds: f32| {
+            for _ in 0..(seconds * 60.0) as u32 {
+                scene.update(1.0 / 60.0);
+            }
+        };
+
+        // settles where the spring holds its weight, a stiffer spring stretches less
+        for &stiffness in &[50.0, 200.0, 1e6] {
+            let (mut scene, _) = hang(stiffness, 5.0);
+            step(&mut scene, 10.0);
+            let y = scene.get_body(weight).unwrap().position.y;
+            assert!(
+                (y - (4.0 - 10.0 / stiffness)).abs() < 0.02,
+                "{} {}",
+                stiffness,
+                y
+            );
+        }
+
+        // without damping it keeps bouncing, it's dropped from the rest length so it swings down
+        // to twice the stretch
+        let (mut scene, _) = hang(50.0, 0.0);
+        let mut lowest = f32::MAX;
+        for _ in 0..120 {
+            scene.update(1.0 / 60.0);
+            lowest = lowest.min(scene.get_body(weight).unwrap().position.y);
+        }
+        assert!(lowest < 4.0 - 0.3, "{}", lowest);
+
+        // reeling it in
+        let (mut scene, handle) = hang(200.0, 5.0);
+        scene.constraints.set_spring(
+            handle,
+            Spring {
+                rest_length: 0.5,
+                stiffness: 200.0,
+                damping: 5.0,
+            },
+        );
+        step(&mut scene, 10.0);
+        let y = scene.get_body(weight).unwrap().position.y;
+        assert!((y - 4.45).abs() < 0.02, "{}", y);
+    }
+
+    #[test]
+    fn test_hinge_motor() {
+        // a door on a vertical hinge, returns how fast it's turning after a second
+        fn door_speed(max_force: f32) -> f32 {
+            let mut scene = PhysicsScene::empty();
+            let frame = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let door = scene.add_body(Body {
+                position: Vec3::new(1.0, 5.0, 0.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bodies = &scene.bodies;
+            let handle = scene.constraints.add_hinge_constraint_with_limits(
+                bodies,
+                frame,
+                door,
+                Vec3::new(0.0, 5.0, 0.0),
+                Vec3::Y,
+                None,
+            );
+            scene.constraints.set_motor(
+                handle,
+                Some(JointMotor {
+                    target_velocity: 2.0,
+                    max_force,
+                }),
+            );
+            scene.set_pair_ignored(frame, door, true);
+            for _ in 0..60 {
+                scene.update(1.0 / 60.0);
+            }
+            scene.get_body(door).unwrap().angular_velocity.y
+        }
+
+        let strong = door_speed(100.0);
+        let weak = door_speed(0.1);
+        assert!((strong - 2.0).abs() < 0.1, "{}", strong);
+        assert!(weak > 0.0 && weak < 1.0, "{}", weak);
+    }
+
+    #[test]
+    fn test_weld() {
+        fn make_weld(break_force: Option<f32>, break_torque: Option<f32>) -> PhysicsScene {
+            let mut scene = PhysicsScene::empty();
+            let wall = scene.add_body(Body {
+                position: Vec3::new(0.0, 5.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            // sticks out to the side so gravity twists the weld as well as pulling on it
+            let beam = scene.add_body(Body {
+                position: Vec3::new(1.0, 5.0, 0.0),
+                shape: make_cube_small(),
+                ..Body::default()
+            });
+            let bodies = &scene.bodies;
+            let handle =
+                scene
+                    .constraints
+                    .add_weld_constraint(bodies, wall, beam, Vec3::new(0.5, 5.0, 0.0));
+            scene
+                .constraints
+                .set_break_thresholds(handle, break_force, break_torque);
+            scene.set_pair_ignored(wall, beam, true);
+            scene
+        }
+        let beam = BodyHandle(1, 0);
+        let handle = ConstraintHandle(0);
+        let step = |scene: &mut PhysicsScene| {
+            for _ in 0..60 {
+                scene.update(1.0 / 60.0);
+            }
+        };
+
+        // held in place, thresholds above the beam's weight don't break it
+        for &(break_force, break_torque) in &[(None, None), (Some(100.0), Some(100.0))] {
+            let mut scene = make_weld(break_force, break_torque);
+            step(&mut scene);
+            let body = scene.get_body(beam).unwrap();
+            assert!(
+                (body.position - Vec3::new(1.0, 5.0, 0.0)).length() < 0.1,
+                "{}",
+                body.position
+            );
+            assert!(body.orientation.angle_between(Quat::IDENTITY) < 0.05);
+            assert!(scene.constraints.is_enabled(handle));
+            assert_eq!(0, scene.drain_broken_constraints().count());
+
+            // detached at runtime
+            scene.constraints.set_enabled(handle, false);
+            step(&mut scene);
+            assert!(scene.get_body(beam).unwrap().position.y < 4.0);
+        }
+
+        // the beam weighs about 10N and hangs half a metre from the anchor, so it's too heavy
+        // for either of these
+        for &(break_force, break_torque) in &[(Some(5.0), None), (None, Some(2.0))] {
+            let mut scene = make_weld(break_force, break_torque);
+            step(&mut scene);
+            assert!(!scene.constraints.is_enabled(handle));
+            assert!(scene.get_body(beam).unwrap().position.y < 4.0);
+            let events: Vec<ConstraintBroken> = scene.drain_broken_constraints().collect();
+            assert_eq!(1, events.len());
+            assert_eq!(handle, events[0].handle);
+            assert_eq!(beam, events[0].handle_b);
+        }
+    }
+
+    #[test]
+    fn test_box_stack_rests() {
+        // five boxes stacked on the ground, with and without position correction. the cached
+        // contact impulses carry the weight of the boxes above from one step to the next so the
+        // stack doesn't sink or jitter
+        for &position_iterations in &[0, 4] {
+            let mut scene = PhysicsScene::empty();
+            scene.position_iterations = position_iterations;
+            scene.add_body(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            let boxes: Vec<BodyHandle> = (0..5)
+                .map(|i| {
+                    scene.add_body(Body {
+                        position: Vec3::new(0.0, 1.0 + 2.0 * i as f32, 0.0),
+                        shape: make_cube_unit(),
+                        ..Body::default()
+                    })
+                })
+                .collect();
+
+            for _ in 0..300 {
+                scene.update(1.0 / 60.0);
+            }
+            for (i, &handle) in boxes.iter().enumerate() {
+                let body = scene.get_body(handle).unwrap();
+                let expected = Vec3::new(0.0, 1.0 + 2.0 * i as f32, 0.0);
+                assert!(
+                    (body.position - expected).length() < 0.1,
+                    "{} {}",
+                    i,
+                    body.position
+                );
+                assert!(
+                    body.linear_velocity.length() < 0.05,
+                    "{}",
+                    body.linear_velocity
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_radial_impulse() {
+        // the bvh is queried, the other broadphases check every body
+        for kind in [BroadphaseKind::SweepAndPrune, BroadphaseKind::Bvh] {
+            let mut scene = PhysicsScene::empty();
+            scene.set_broadphase(kind);
+            let ground = scene.add_body(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            let add_sphere = |scene: &mut PhysicsScene, x: f32| {
+                scene.add_body(Body {
+                    position: Vec3::new(x, 2.0, 0.0),
+                    shape: make_sphere(1.0),
+                    sleeping: true,
+                    ..Body::default()
+                })
+            };
+            let near = add_sphere(&mut scene, 2.0);
+            let far = add_sphere(&mut scene, -4.0);
+            scene.update(1.0 / 60.0);
+            // added after the bvh was last built
+            let outside = add_sphere(&mut scene, 10.0);
+
+            scene.apply_radial_impulse(Vec3::new(0.0, 2.0, 0.0), 6.0, 6.0, Falloff::Linear);
+            let near_body = scene.get_body(near).unwrap();
+            let far_body = scene.get_body(far).unwrap();
+            // the closest points are 1 and 3 from the centre
+            assert!((near_body.linear_velocity - Vec3::new(5.0, 0.0, 0.0)).length() < 0.01);
+            assert!((far_body.linear_velocity - Vec3::new(-3.0, 0.0, 0.0)).length() < 0.01);
+            assert!(!near_body.sleeping && !far_body.sleeping);
+            assert!(scene.get_body(outside).unwrap().sleeping);
+            assert_eq!(Vec3::ZERO, scene.get_body(ground).unwrap().linear_velocity);
+
+            // pushes straight through a body containing the centre
+            scene.apply_radial_impulse(Vec3::new(10.0, 2.5, 0.0), 1.0, 1.0, Falloff::Constant);
+            let body = scene.get_body(outside).unwrap();
+            assert!((body.linear_velocity - Vec3::new(0.0, -1.0, 0.0)).length() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_apply_radial_impulse_bvh() {
+        // the bodies the bvh's tree finds get the same pushes as checking every body, after the
+        // bodies were moved, added and removed since the tree was built
+        let run = |kind: BroadphaseKind| {
+            let mut scene = PhysicsScene::empty();
+            scene.set_gravity(Vec3::ZERO);
+            scene.set_broadphase(kind);
+            let handles: Vec<BodyHandle> = (0..64)
+                .map(|i| {
+                    scene.add_body(Body {
+                        position: Vec3::new((i % 8) as f32 * 3.0, 0.0, (i / 8) as f32 * 3.0),
+                        shape: make_sphere(1.0),
+                        ..Body::default()
+                    })
+                })
+                .collect();
+            scene.update(1.0 / 60.0);
+
+            scene.bodies.get_body_mut(handles[63]).position = Vec3::new(1.5, 0.0, 1.5);
+            scene.remove_body(handles[9]);
+            scene.add_body(Body {
+                position: Vec3::new(3.0, 1.0, 3.0),
+                shape: make_sphere(1.0),
+                ..Body::default()
+            });
+            scene.apply_radial_impulse(Vec3::new(3.0, 0.0, 3.0), 4.5, 10.0, Falloff::Linear);
+            scene
+                .bodies
+                .iter_with_handles()
+                .map(|(handle, body)| (handle, body.linear_velocity, body.angular_velocity))
+                .collect::<Vec<_>>()
+        };
+
+        let bvh = run(BroadphaseKind::Bvh);
+        assert_eq!(run(BroadphaseKind::SweepAndPrune), bvh);
+        let pushed = bvh.iter().filter(|(_, v, _)| *v != Vec3::ZERO).count();
+        assert_eq!(10, pushed);
+    }
+
+    #[test]
+    fn test_island_sleep() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let stack: Vec<BodyHandle> = (0..2)
+            .map(|i| {
+                scene.add_body(Body {
+                    position: Vec3::new(0.0, 1.0 + 2.0 * i as f32, 0.0),
+                    shape: make_cube_unit(),
+                    ..Body::default()
+                })
+            })
+            .collect();
+        // kept moving by a motor so its island never sleeps
+        let frame = scene.add_body(Body {
+            position: Vec3::new(10.0, 5.0, 0.0),
+            inv_mass: 0.0,
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let wheel = scene.add_body(Body {
+            position: Vec3::new(11.0, 5.0, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let bodies = &scene.bodies;
+        let hinge = scene.constraints.add_hinge_constraint_with_limits(
+            bodies,
+            frame,
+            wheel,
+            Vec3::new(10.0, 5.0, 0.0),
+            Vec3::Y,
+            None,
+        );
+        scene.constraints.set_motor(
+            hinge,
+            Some(JointMotor {
+                target_velocity: 1.0,
+                max_force: 100.0,
+            }),
+        );
+
+        // the stack goes to sleep all at once
+        let is_asleep = |scene: &PhysicsScene| {
+            let sleeping: Vec<bool> = stack
+                .iter()
+                .map(|&handle| scene.get_body(handle).unwrap().sleeping)
+                .collect();
+            assert!(sleeping.iter().all(|&s| s == sleeping[0]), "{:?}", sleeping);
+            sleeping[0]
+        };
+        let mut steps = 0;
+        while !is_asleep(&scene) {
+            scene.update(1.0 / 60.0);
+            steps += 1;
+            assert!(steps < 600);
+        }
+        assert!(!scene.get_body(wheel).unwrap().sleeping);
+
+        // pushing the bottom box wakes the whole stack
+        scene.apply_impulse(stack[0], Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        scene.update(1.0 / 60.0);
+        assert!(!is_asleep(&scene));
+    }
+
+    #[test]
+    fn test_broadphase_kinds() {
+        // spheres dropped onto the ground and onto each other
+        let run = |kind: BroadphaseKind| {
+            let mut scene = PhysicsScene::empty();
+            scene.set_broadphase(kind);
+            scene.add_body(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            for i in 0..6 {
+                scene.add_body(Body {
+                    position: Vec3::new((i % 3) as f32 * 3.0, 2.0 + (i / 3) as f32 * 3.0, 0.0),
+                    shape: make_sphere(1.0),
+                    ..Body::default()
+                });
+            }
+            for _ in 0..120 {
+                scene.update(1.0 / 60.0);
+            }
+            assert_eq!(kind, scene.broadphase_kind());
+            scene
+                .bodies
+                .iter()
+                .map(|body| body.position)
+                .collect::<Vec<_>>()
+        };
+
+        let sweep_and_prune = run(BroadphaseKind::SweepAndPrune);
+        for kind in &[BroadphaseKind::Bvh, BroadphaseKind::Grid { cell_size: 2.0 }] {
+            for (a, b) in sweep_and_prune.iter().zip(&run(*kind)).skip(1) {
+                assert!(a.y > 0.5);
+                assert!(a.abs_diff_eq(*b, 1e-3), "{:?} {} {}", kind, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_islands() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        // two boxes resting apart on the ground, and two spheres hanging from each other
+        let left = scene.add_body(Body {
+            position: Vec3::new(-5.0, 1.0, 0.0),
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+        let right = scene.add_body(Body {
+            position: Vec3::new(5.0, 1.0, 0.0),
+            shape: make_cube_unit(),
+            ..Body::default()
+        });
+        let upper = scene.add_body(Body {
+            position: Vec3::new(0.0, 10.0, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let lower = scene.add_body(Body {
+            position: Vec3::new(0.0, 8.0, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        scene
+            .constraints
+            .add_distance_constraint(&scene.bodies, upper, lower);
+        scene.update(1.0 / 60.0);
+
+        let mut islands: Vec<Vec<BodyHandle>> =
+            scene.iter_islands().map(|bodies| bodies.to_vec()).collect();
+        islands.sort_by_key(|bodies| bodies[0].0);
+        assert_eq!(vec![vec![left], vec![right], vec![upper, lower]], islands);
+    }
+
+    #[test]
+    fn test_position_iterations() {
+        // a box starting half sunk into the ground, returns the highest it gets
+        fn max_height(position_iterations: u32) -> f32 {
+            let mut scene = PhysicsScene::empty();
+            scene.position_iterations = position_iterations;
+            scene.add_body(Body {
+                inv_mass: 0.0,
+                shape: make_box_ground(),
+                ..Body::default()
+            });
+            let handle = scene.add_body(Body {
+                position: Vec3::new(0.0, 0.5, 0.0),
+                shape: make_cube_unit(),
+                ..Body::default()
+            });
+            let mut max_height = 0.0f32;
+            for _ in 0..60 {
+                scene.update(1.0 / 60.0);
+                max_height = max_height.max(scene.get_body(handle).unwrap().position.y);
+            }
+            max_height
+        }
+
+        // the baumgarte stabilization throws the box into the air, correcting the positions
+        // pushes it out to where it should rest without speeding it up
+        let baumgarte = max_height(0);
+        let corrected = max_height(4);
+        assert!(baumgarte > 2.0, "{}", baumgarte);
+        assert!((corrected - 1.0).abs() < 0.1, "{}", corrected);
+    }
+
+    #[test]
+    fn test_sphere_wedged_between_walls() {
+        // the sphere is slightly wider than the gap so it's squeezed by both walls and the
+        // ground at once. the three contacts are solved together in the same iterations so
+        // neither wall wins
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        for &x in &[-2.0, 2.0] {
+            scene.add_body(Body {
+                position: Vec3::new(x, 0.0, 0.0),
+                inv_mass: 0.0,
+                shape: make_box_wall0(),
+                ..Body::default()
+            });
+        }
+        let handle = scene.add_body(Body {
+            position: Vec3::new(0.0, 3.0, 0.0),
+            shape: make_sphere(1.01),
+            ..Body::default()
+        });
+
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+        }
+
+        let body = scene.get_body(handle).unwrap();
+        assert!(body.position.x.abs() < 0.01, "{:?}", body.position);
+        assert!((body.position.y - 1.01).abs() < 0.02, "{:?}", body.position);
+        assert!(body.linear_velocity.length() < 1e-3);
+    }
+
+    #[test]
+    fn test_level_json_hand_edit() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(7.25, 3.0, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let anchor = scene.add_body(Body {
+            position: Vec3::new(0.0, 5.0, 0.0),
+            inv_mass: 0.0,
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        let bodies = &scene.bodies;
+        scene
+            .constraints
+            .add_distance_constraint(bodies, anchor, sphere);
+
+        // move the sphere by editing the json like someone would by hand
+        let json = scene.to_level_json().unwrap();
+        let mut level: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(7.25, level["bodies"][1]["position"][0]);
+        level["bodies"][1]["position"][0] = serde_json::json!(-4.5);
+        let json = serde_json::to_string_pretty(&level).unwrap();
+
+        let loaded = PhysicsScene::from_level_json(&json).unwrap();
+        let body = loaded.get_body(sphere).unwrap();
+        assert_eq!(body.position, Vec3::new(-4.5, 3.0, 0.0));
+        assert_eq!(body.shape.as_sphere(), Some(0.5));
+        assert_eq!(
+            loaded.get_body(anchor).unwrap().position,
+            Vec3::new(0.0, 5.0, 0.0)
+        );
+        assert!(loaded
+            .get_body(BodyHandle(0, 0))
+            .unwrap()
+            .shape
+            .as_box()
+            .is_some());
+        assert_eq!(
+            loaded.constraints.to_level(&loaded.bodies),
+            scene.constraints.to_level(&scene.bodies)
+        );
+    }
+
+    #[test]
+    fn test_trimesh_ground() {
+        // a flat 10x10 grid of quads, two triangles each
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for z in 0..=10 {
+            for x in 0..=10 {
+                vertices.push(Vec3::new(x as f32 * 2.0 - 10.0, 0.0, z as f32 * 2.0 - 10.0));
+            }
+        }
+        for z in 0..10 {
+            for x in 0..10 {
+                let i = z * 11 + x;
+                triangles.push([i, i + 11, i + 1]);
+                triangles.push([i + 1, i + 11, i + 12]);
+            }
+        }
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_trimesh(Arc::new(ShapeTriMesh::new(vertices, triangles))),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(-3.1, 2.0, 0.7),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(2.9, 2.0, -1.3),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // both come to rest on the mesh rather than falling through it
+        let sphere = scene.get_body(sphere).unwrap();
+        assert!((sphere.position.y - 0.5).abs() < 0.05);
+        assert!(sphere.linear_velocity.length() < 0.1);
+        let cube = scene.get_body(cube).unwrap();
+        assert!((cube.position.y - 0.25).abs() < 0.05);
+        assert!(cube.linear_velocity.length() < 0.1);
+    }
+
+    #[test]
+    fn test_heightfield_ground() {
+        // an 11x11 field raised to a height of 1, with a hill in one corner away from the bodies
+        let mut heights = vec![1.0; 11 * 11];
+        heights[9 * 11 + 9] = 4.0;
+        let field = ShapeHeightfield::new(11, 11, heights, Vec3::new(2.0, 1.0, 2.0));
+
+        // only the cells under the bounds are visited
+        let mut count = 0;
+        field.query(
+            &Bounds {
+                mins: Vec3::new(-0.5, 0.0, -0.5),
+                maxs: Vec3::new(0.5, 2.0, 0.5),
+            },
+            |_| count += 1,
+        );
+        assert_eq!(count, 8);
+
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_heightfield(Arc::new(field)),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(-3.1, 3.0, 0.7),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(2.9, 3.0, -1.3),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // both come to rest on the terrain rather than falling through it
+        let sphere = scene.get_body(sphere).unwrap();
+        assert!((sphere.position.y - 1.5).abs() < 0.05);
+        assert!(sphere.linear_velocity.length() < 0.1);
+        let cube = scene.get_body(cube).unwrap();
+        assert!((cube.position.y - 1.25).abs() < 0.05);
+        assert!(cube.linear_velocity.length() < 0.1);
+    }
+
+    #[test]
+    fn test_compound_cup() {
+        // a cup made of a base and two walls, hollow in the middle
+        let cup_shape = Shape::make_compound(Arc::new(ShapeCompound::new(vec![
+            (
+                (Vec3::new(0.0, 0.25, 0.0), Quat::IDENTITY),
+                Shape::make_box_from_half_extents(Vec3::new(1.5, 0.25, 1.5)),
+            ),
+            (
+                (Vec3::new(-1.25, 1.5, 0.0), Quat::IDENTITY),
+                Shape::make_box_from_half_extents(Vec3::new(0.25, 1.0, 1.5)),
+            ),
+            (
+                (Vec3::new(1.25, 1.5, 0.0), Quat::IDENTITY),
+                Shape::make_box_from_half_extents(Vec3::new(0.25, 1.0, 1.5)),
+            ),
+        ])));
+
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let cup = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.1, 0.0),
+            shape: cup_shape,
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(0.0, 4.0, 0.0),
+            shape: make_sphere(0.3),
+            ..Body::default()
+        });
+
+        for _ in 0..240 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // the cup stands on the ground and the sphere falls between the walls onto the base,
+        // rather than resting on the hull across the top of the walls. nothing stops the sphere
+        // rolling so it's only expected to stay inside
+        let cup = scene.get_body(cup).unwrap();
+        assert!(cup.position.y.abs() < 0.05);
+        assert!(cup.linear_velocity.length() < 0.1);
+        let sphere = scene.get_body(sphere).unwrap();
+        assert!((sphere.position.y - cup.position.y - 0.8).abs() < 0.05);
+        assert!(sphere.position.x.abs() < 1.0);
+    }
+
+    #[test]
+    fn test_plane_ground() {
+        let mut scene = PhysicsScene::empty();
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(-3.0, 2.0, 0.0),
+            shape: make_sphere(0.5),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(0.0, 2.0, 0.0),
+            shape: make_cube_small(),
+            ..Body::default()
+        });
+        // lying on its side, so it touches along a line
+        let cylinder = scene.add_body(Body {
+            position: Vec3::new(3.0, 2.0, 0.0),
+            orientation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            shape: Shape::make_cylinder(0.5, 0.25),
+            ..Body::default()
+        });
+        // far from the origin and fast enough to pass the plane's bounds in a single step
+        let bullet = scene.add_body(Body {
+            position: Vec3::new(5000.0, 3.0, 0.0),
+            linear_velocity: Vec3::new(0.0, -300.0, 0.0),
+            shape: make_sphere(0.1),
+            ..Body::default()
+        });
+
+        for _ in 0..180 {
+            scene.update(1.0 / 60.0);
+        }
+
+        // everything comes to rest on the plane rather than falling through it
+        let sphere = scene.get_body(sphere).unwrap();
+        assert!((sphere.position.y - 0.5).abs() < 0.05);
+        let cube = scene.get_body(cube).unwrap();
+        assert!((cube.position.y - 0.25).abs() < 0.05);
+        assert!(cube.linear_velocity.length() < 0.1);
+        let cylinder = scene.get_body(cylinder).unwrap();
+        assert!((cylinder.position.y - 0.25).abs() < 0.05);
+        let bullet = scene.get_body(bullet).unwrap();
+        assert!(bullet.position.y > 0.0);
+    }
+
+    #[test]
+    fn test_ray_cast() {
+        let mut scene = PhysicsScene::empty();
+        let ground = scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        // turned 45 degrees so its corner faces the ray
+        let cube = scene.add_body(Body {
+            position: Vec3::new(5.0, 1.0, 0.0),
+            orientation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_4),
+            shape: Shape::make_box_from_half_extents(Vec3::splat(1.0)),
+            ..Body::default()
+        });
+        let cylinder = scene.add_body(Body {
+            position: Vec3::new(10.0, 1.0, 0.0),
+            shape: Shape::make_cylinder(1.0, 0.5),
+            ..Body::default()
+        });
+        let terrain = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.0, 20.0),
+            inv_mass: 0.0,
+            shape: Shape::make_heightfield(Arc::new(ShapeHeightfield::new(
+                2,
+                2,
+                vec![2.0; 4],
+                Vec3::new(4.0, 1.0, 4.0),
+            ))),
+            ..Body::default()
+        });
+        let everything = |_: BodyHandle, _: &Body| true;
+
+        // along the x axis the sphere is hit first, then the corner of the box
+        let hit = scene
+            .ray_cast(Vec3::new(-5.0, 1.0, 0.0), Vec3::X, 100.0, everything)
+            .unwrap();
+        assert_eq!(sphere, hit.handle);
+        assert!((hit.distance - 4.0).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(-Vec3::X, 1e-4));
+        let hit = scene
+            .ray_cast(Vec3::new(-5.0, 1.0, 0.0), Vec3::X, 100.0, |handle, _| {
+                handle != sphere
+            })
+            .unwrap();
+        assert_eq!(cube, hit.handle);
+        assert!((hit.point.x - (5.0 - std::f32::consts::SQRT_2)).abs() < 1e-4);
+
+        // the cylinder's curved side is marched to
+        let hit = scene
+            .ray_cast(Vec3::new(10.0, 1.5, -5.0), Vec3::Z, 100.0, everything)
+            .unwrap();
+        assert_eq!(cylinder, hit.handle);
+        assert!(hit.point.abs_diff_eq(Vec3::new(10.0, 1.5, -0.5), 1e-3));
+        assert!(hit.normal.abs_diff_eq(-Vec3::Z, 1e-3));
+
+        // straight down onto the terrain and the ground, but not past max_toi
+        let hit = scene
+            .ray_cast(Vec3::new(1.0, 10.0, 21.0), -Vec3::Y, 100.0, everything)
+            .unwrap();
+        assert_eq!(terrain, hit.handle);
+        assert!((hit.distance - 8.0).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-4));
+        let hit = scene
+            .ray_cast(Vec3::new(-20.0, 10.0, 0.0), -Vec3::Y, 100.0, everything)
+            .unwrap();
+        assert_eq!(ground, hit.handle);
+        assert!((hit.distance - 10.0).abs() < 1e-4);
+        assert!(scene
+            .ray_cast(Vec3::new(-20.0, 10.0, 0.0), -Vec3::Y, 5.0, everything)
+            .is_none());
+        assert!(scene
+            .ray_cast(Vec3::new(-20.0, 10.0, 0.0), Vec3::Y, 100.0, everything)
+            .is_none());
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        let mut scene = PhysicsScene::empty();
+        let ground = scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(5.0, 1.0, 0.0),
+            shape: Shape::make_box_from_half_extents(Vec3::splat(1.0)),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(10.0, 1.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        let terrain = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.0, 20.0),
+            inv_mass: 0.0,
+            shape: Shape::make_heightfield(Arc::new(ShapeHeightfield::new(
+                2,
+                2,
+                vec![2.0; 4],
+                Vec3::new(4.0, 1.0, 4.0),
+            ))),
+            ..Body::default()
+        });
+        let everything = |_: BodyHandle, _: &Body| true;
+        let ball = make_sphere(0.5);
+        let block = Shape::make_box_from_half_extents(Vec3::splat(0.5));
+
+        // along the x axis the ball stops at the cube, or at the sphere without the cube
+        let from = Vec3::new(-5.0, 1.0, 0.0);
+        let to = Vec3::new(15.0, 1.0, 0.0);
+        let hit = scene
+            .shape_cast(&ball, Quat::IDENTITY, from, to, everything)
+            .unwrap();
+        assert_eq!(cube, hit.handle);
+        assert!((hit.time_of_impact - 0.425).abs() < 1e-3);
+        assert!((hit.point.x - 4.0).abs() < 1e-2);
+        assert!(hit.normal.abs_diff_eq(-Vec3::X, 1e-2));
+        let hit = scene
+            .shape_cast(&ball, Quat::IDENTITY, from, to, |handle, _| handle != cube)
+            .unwrap();
+        assert_eq!(sphere, hit.handle);
+        assert!((hit.time_of_impact - 0.675).abs() < 1e-3);
+
+        // dropped onto the ground and the terrain
+        let hit = scene
+            .shape_cast(
+                &block,
+                Quat::IDENTITY,
+                Vec3::new(-20.0, 5.0, 0.0),
+                Vec3::new(-20.0, -5.0, 0.0),
+                everything,
+            )
+            .unwrap();
+        assert_eq!(ground, hit.handle);
+        assert!((hit.time_of_impact - 0.45).abs() < 1e-3);
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-2));
+        let hit = scene
+            .shape_cast(
+                &block,
+                Quat::IDENTITY,
+                Vec3::new(1.0, 10.0, 21.0),
+                Vec3::new(1.0, 0.0, 21.0),
+                everything,
+            )
+            .unwrap();
+        assert_eq!(terrain, hit.handle);
+        assert!((hit.time_of_impact - 0.75).abs() < 1e-3);
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-2));
+
+        // starting inside the cube hits straight away, moving away from everything misses
+        let hit = scene
+            .shape_cast(
+                &ball,
+                Quat::IDENTITY,
+                Vec3::new(5.0, 1.0, 0.0),
+                Vec3::new(5.0, 1.0, 5.0),
+                everything,
+            )
+            .unwrap();
+        assert_eq!(cube, hit.handle);
+        assert_eq!(0.0, hit.time_of_impact);
+        assert!(scene
+            .shape_cast(
+                &ball,
+                Quat::IDENTITY,
+                Vec3::new(-20.0, 5.0, 0.0),
+                Vec3::new(-20.0, 10.0, 0.0),
+                everything,
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_intersections() {
+        let mut scene = PhysicsScene::empty();
+        let ground = scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: Shape::make_plane(),
+            ..Body::default()
+        });
+        let cube = scene.add_body(Body {
+            position: Vec3::new(5.0, 1.0, 0.0),
+            shape: Shape::make_box_from_half_extents(Vec3::splat(1.0)),
+            ..Body::default()
+        });
+        let sphere = scene.add_body(Body {
+            position: Vec3::new(10.0, 1.0, 0.0),
+            shape: make_sphere(1.0),
+            ..Body::default()
+        });
+        let terrain = scene.add_body(Body {
+            position: Vec3::new(0.0, 0.0, 20.0),
+            inv_mass: 0.0,
+            shape: Shape::make_heightfield(Arc::new(ShapeHeightfield::new(
+                2,
+                2,
+                vec![2.0; 4],
+                Vec3::new(4.0, 1.0, 4.0),
+            ))),
+            ..Body::default()
+        });
+        let everything = |_: BodyHandle, _: &Body| true;
+
+        // points inside the shapes, under the ground and on the terrain's surface
+        assert_eq!(
+            vec![cube],
+            scene.intersections_with_point(Vec3::new(5.5, 1.5, 0.5), everything)
+        );
+        assert_eq!(
+            vec![sphere],
+            scene.intersections_with_point(Vec3::new(10.0, 1.0, 0.9), everything)
+        );
+        assert_eq!(
+            vec![ground],
+            scene.intersections_with_point(Vec3::new(-20.0, -0.5, 0.0), everything)
+        );
+        assert_eq!(
+            vec![terrain],
+            scene.intersections_with_point(Vec3::new(1.0, 2.0, 21.0), everything)
+        );
+        // inside the sphere's bounds but outside the sphere
+        assert!(scene
+            .intersections_with_point(Vec3::new(10.0, 1.9, 0.9), everything)
+            .is_empty());
+
+        // only the bounds are compared, so the corner of the sphere's bounds is enough
+        let aabb = Bounds {
+            mins: Vec3::new(4.0, 0.5, -0.5),
+            maxs: Vec3::new(11.0, 0.6, 0.5),
+        };
+        assert_eq!(
+            vec![cube, sphere],
+            scene.intersections_with_aabb(&aabb, everything)
+        );
+        let corner = Bounds {
+            mins: Vec3::new(10.8, 1.8, 0.8),
+            maxs: Vec3::new(10.9, 1.9, 0.9),
+        };
+        assert_eq!(
+            vec![sphere],
+            scene.intersections_with_aabb(&corner, everything)
+        );
+
+        // a ball between the cube and the sphere reaches the ground, then the others as it grows
+        assert_eq!(
+            vec![ground],
+            scene.intersections_with_shape(
+                &make_sphere(1.0),
+                Vec3::new(7.5, 1.0, 0.0),
+                Quat::IDENTITY,
+                everything
+            )
+        );
+        assert_eq!(
+            vec![ground, cube, sphere],
+            scene.intersections_with_shape(
+                &make_sphere(1.6),
+                Vec3::new(7.5, 1.5, 0.0),
+                Quat::IDENTITY,
+                everything
+            )
+        );
+        assert_eq!(
+            vec![cube, sphere],
+            scene.intersections_with_shape(
+                &make_sphere(1.6),
+                Vec3::new(7.5, 1.5, 0.0),
+                Quat::IDENTITY,
+                |handle, _| handle != ground
+            )
+        );
     }
 }
@@ -1,14 +1,28 @@
 mod shape_box;
+mod shape_capsule;
+mod shape_compound;
+mod shape_cone;
 mod shape_convex;
+mod shape_cylinder;
+mod shape_heightfield;
+mod shape_plane;
 mod shape_sphere;
+mod shape_trimesh;
 
-use crate::bounds::Bounds;
+use crate::{body::Body, bounds::Bounds, gjk::gjk_closest_points};
 use glam::{Mat3, Quat, Vec3};
 use std::{ops::Deref, sync::Arc};
 
 pub use shape_box::ShapeBox;
-pub use shape_convex::{build_convex_hull, Edge, ShapeConvex, Tri};
+pub use shape_capsule::ShapeCapsule;
+pub use shape_compound::ShapeCompound;
+pub use shape_cone::ShapeCone;
+pub use shape_convex::{build_convex_hull, ConvexFace, ConvexHull, Edge, ShapeConvex, Tri};
+pub use shape_cylinder::ShapeCylinder;
+pub use shape_heightfield::ShapeHeightfield;
+pub use shape_plane::ShapePlane;
 pub use shape_sphere::ShapeSphere;
+pub use shape_trimesh::ShapeTriMesh;
 
 fn find_support_point(points: &[Vec3], dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
     // find the point in the furthest in direction
@@ -31,6 +45,7 @@ fn find_support_point(points: &[Vec3], dir: Vec3, pos: Vec3, orient: Quat, bias:
 trait ShapeTrait {
     fn centre_of_mass(&self) -> Vec3;
     fn inertia_tensor(&self) -> Mat3;
+    fn volume(&self) -> f32;
     fn local_bounds(&self) -> Bounds;
     fn bounds(&self, translation: Vec3, orientation: Quat) -> Bounds;
     fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3;
@@ -38,10 +53,18 @@ trait ShapeTrait {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shape {
     Sphere(ShapeSphere),
     Box(Arc<ShapeBox>),
+    Capsule(ShapeCapsule),
+    Cylinder(ShapeCylinder),
+    Cone(ShapeCone),
     Convex(Arc<ShapeConvex>),
+    TriMesh(Arc<ShapeTriMesh>),
+    Heightfield(Arc<ShapeHeightfield>),
+    Compound(Arc<ShapeCompound>),
+    Plane(ShapePlane),
 }
 
 impl Default for Shape {
@@ -61,18 +84,149 @@ impl Shape {
         Shape::Box(data)
     }
 
+    // a box centred on the body's origin
+    #[inline]
+    pub fn make_box_from_half_extents(half_extents: Vec3) -> Self {
+        Shape::Box(Arc::new(ShapeBox::from_half_extents(half_extents)))
+    }
+
+    // a capsule standing along the body's y axis
+    #[inline]
+    pub fn make_capsule(half_height: f32, radius: f32) -> Self {
+        Shape::Capsule(ShapeCapsule {
+            half_height,
+            radius,
+        })
+    }
+
+    // a cylinder standing along the body's y axis
+    #[inline]
+    pub fn make_cylinder(half_height: f32, radius: f32) -> Self {
+        Shape::Cylinder(ShapeCylinder {
+            half_height,
+            radius,
+        })
+    }
+
+    // a cone standing along the body's y axis, pointing up
+    #[inline]
+    pub fn make_cone(half_height: f32, radius: f32) -> Self {
+        Shape::Cone(ShapeCone {
+            half_height,
+            radius,
+        })
+    }
+
     #[inline]
     pub fn make_convex(data: Arc<ShapeConvex>) -> Self {
         Shape::Convex(data)
     }
 
+    // a mesh of triangles for static level geometry
+    #[inline]
+    pub fn make_trimesh(data: Arc<ShapeTriMesh>) -> Self {
+        Shape::TriMesh(data)
+    }
+
+    // terrain for static level geometry
+    #[inline]
+    pub fn make_heightfield(data: Arc<ShapeHeightfield>) -> Self {
+        Shape::Heightfield(data)
+    }
+
+    #[inline]
+    pub fn make_compound(data: Arc<ShapeCompound>) -> Self {
+        Shape::Compound(data)
+    }
+
+    // the half space below the body's xz plane, for grounds that don't need to end
+    #[inline]
+    pub fn make_plane() -> Self {
+        Shape::Plane(ShapePlane)
+    }
+
+    // the radius if this is a sphere
+    pub fn as_sphere(&self) -> Option<f32> {
+        match self {
+            Shape::Sphere(data) => Some(data.radius),
+            _ => None,
+        }
+    }
+
+    pub fn as_box(&self) -> Option<&ShapeBox> {
+        match self {
+            Shape::Box(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_capsule(&self) -> Option<&ShapeCapsule> {
+        match self {
+            Shape::Capsule(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_cylinder(&self) -> Option<&ShapeCylinder> {
+        match self {
+            Shape::Cylinder(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_cone(&self) -> Option<&ShapeCone> {
+        match self {
+            Shape::Cone(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_convex(&self) -> Option<&ShapeConvex> {
+        match self {
+            Shape::Convex(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_trimesh(&self) -> Option<&ShapeTriMesh> {
+        match self {
+            Shape::TriMesh(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_heightfield(&self) -> Option<&ShapeHeightfield> {
+        match self {
+            Shape::Heightfield(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&ShapeCompound> {
+        match self {
+            Shape::Compound(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn is_plane(&self) -> bool {
+        matches!(self, Shape::Plane(_))
+    }
+
     #[inline(always)]
     fn shape_trait(&self) -> &dyn ShapeTrait {
         // TODO: check the overhead of this
         match self {
             Shape::Sphere(data) => data,
             Shape::Box(data) => data.deref(),
+            Shape::Capsule(data) => data,
+            Shape::Cylinder(data) => data,
+            Shape::Cone(data) => data,
             Shape::Convex(data) => data.deref(),
+            Shape::TriMesh(data) => data.deref(),
+            Shape::Heightfield(data) => data.deref(),
+            Shape::Compound(data) => data.deref(),
+            Shape::Plane(data) => data,
         }
     }
 
@@ -86,6 +240,11 @@ impl Shape {
         self.shape_trait().inertia_tensor()
     }
 
+    #[inline]
+    pub fn volume(&self) -> f32 {
+        self.shape_trait().volume()
+    }
+
     #[inline]
     pub fn local_bounds(&self) -> Bounds {
         self.shape_trait().local_bounds()
@@ -101,9 +260,287 @@ impl Shape {
         self.shape_trait().support(dir, pos, orient, bias)
     }
 
+    /// Returns a copy of this shape translated so that its centre of mass is at the origin.
+    pub fn recentered(&self) -> Self {
+        match self {
+            Shape::Sphere(data) => Shape::Sphere(*data),
+            Shape::Box(data) => Shape::Box(Arc::new(data.translated(-data.center_of_mass))),
+            Shape::Capsule(data) => Shape::Capsule(*data),
+            Shape::Cylinder(data) => Shape::Cylinder(*data),
+            // cones are always measured from the middle of their height, so the centre of mass
+            // stays a quarter of the way up
+            Shape::Cone(data) => Shape::Cone(*data),
+            Shape::Convex(data) => Shape::Convex(Arc::new(data.translated(-data.centre_of_mass()))),
+            Shape::TriMesh(data) => Shape::TriMesh(data.clone()),
+            Shape::Heightfield(data) => Shape::Heightfield(data.clone()),
+            Shape::Compound(data) => {
+                Shape::Compound(Arc::new(data.translated(-data.centre_of_mass())))
+            }
+            Shape::Plane(data) => Shape::Plane(*data),
+        }
+    }
+
     #[inline]
     pub fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
         self.shape_trait()
             .fastest_linear_speed(angular_velocity, dir)
     }
 }
+
+// the mass of a solid shape of uniform density, with its centre of mass and inertia tensor about
+// that centre in the shape's space
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MassProperties {
+    pub mass: f32,
+    pub centre_of_mass: Vec3,
+    pub inertia_tensor: Mat3,
+}
+
+impl MassProperties {
+    pub fn from_shape(shape: &Shape, density: f32) -> Self {
+        let mass = density * shape.volume();
+        Self {
+            mass,
+            centre_of_mass: shape.centre_of_mass(),
+            // the shapes' inertia tensors are for a unit mass
+            inertia_tensor: shape.inertia_tensor() * mass,
+        }
+    }
+
+    // 0.0 for a shape with no volume, like a plane
+    pub fn inv_mass(&self) -> f32 {
+        if self.mass > 0.0 {
+            self.mass.recip()
+        } else {
+            0.0
+        }
+    }
+}
+
+// the gap between the surfaces of two convex shapes and the closest point on each, with the
+// shapes placed at the given (position, orientation). shapes that overlap report a gap of zero
+pub fn convex_distance(
+    a: &Shape,
+    tf_a: (Vec3, Quat),
+    b: &Shape,
+    tf_b: (Vec3, Quat),
+) -> (f32, Vec3, Vec3) {
+    let body_a = Body {
+        position: tf_a.0,
+        orientation: tf_a.1,
+        shape: a.clone(),
+        ..Body::default()
+    };
+    let body_b = Body {
+        position: tf_b.0,
+        orientation: tf_b.1,
+        shape: b.clone(),
+        ..Body::default()
+    };
+
+    let (pt_on_a, pt_on_b) = gjk_closest_points(&body_a, &body_b);
+    ((pt_on_b - pt_on_a).length(), pt_on_a, pt_on_b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_convex_distance_spheres() {
+        let (distance, pt_on_a, pt_on_b) = convex_distance(
+            &Shape::make_sphere(1.0),
+            (Vec3::ZERO, Quat::IDENTITY),
+            &Shape::make_sphere(0.5),
+            (Vec3::new(4.0, 0.0, 0.0), Quat::IDENTITY),
+        );
+
+        assert!((distance - 2.5).abs() < 1e-3);
+        assert!(pt_on_a.abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-3));
+        assert!(pt_on_b.abs_diff_eq(Vec3::new(3.5, 0.0, 0.0), 1e-3));
+    }
+
+    #[test]
+    fn test_capsule_shape() {
+        // without a cylinder the capsule is just a sphere
+        let sphere = Shape::make_sphere(0.5);
+        let capsule = Shape::make_capsule(0.0, 0.5);
+        assert!(capsule
+            .inertia_tensor()
+            .abs_diff_eq(sphere.inertia_tensor(), 1e-6));
+
+        // a long capsule is harder to turn end over end than to spin about its axis
+        let capsule = Shape::make_capsule(1.0, 0.5);
+        let inertia = capsule.inertia_tensor();
+        assert!(inertia.x_axis.x > inertia.y_axis.y);
+        assert_eq!(inertia.x_axis.x, inertia.z_axis.z);
+
+        // lying along the x axis the furthest point to the right is the end of the right cap
+        let orientation = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2);
+        let support = capsule.support(Vec3::X, Vec3::ZERO, orientation, 0.0);
+        assert!(support.abs_diff_eq(Vec3::new(1.5, 0.0, 0.0), 1e-5));
+        let bounds = capsule.bounds(Vec3::ZERO, orientation);
+        assert!(bounds.maxs.abs_diff_eq(Vec3::new(1.5, 0.5, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn test_cylinder_and_cone_shapes() {
+        let cylinder = Shape::make_cylinder(1.0, 0.5);
+        let support = cylinder.support(Vec3::new(1.0, 1.0, 0.0), Vec3::ZERO, Quat::IDENTITY, 0.0);
+        assert!(support.abs_diff_eq(Vec3::new(0.5, 1.0, 0.0), 1e-5));
+        let support = cylinder.support(-Vec3::Y, Vec3::ZERO, Quat::IDENTITY, 0.0);
+        assert!(support.abs_diff_eq(Vec3::new(0.0, -1.0, 0.0), 1e-5));
+
+        // lying on its side the cylinder's round side reaches the radius up and down
+        let orientation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let bounds = cylinder.bounds(Vec3::ZERO, orientation);
+        assert!(bounds.maxs.abs_diff_eq(Vec3::new(1.0, 0.5, 0.5), 1e-5));
+
+        // the cone's tip is the furthest point up, its rim is the furthest point out
+        let cone = Shape::make_cone(1.0, 0.5);
+        assert_eq!(Vec3::new(0.0, -0.5, 0.0), cone.centre_of_mass());
+        let support = cone.support(Vec3::Y, Vec3::ZERO, Quat::IDENTITY, 0.0);
+        assert!(support.abs_diff_eq(Vec3::new(0.0, 1.0, 0.0), 1e-5));
+        let support = cone.support(Vec3::X, Vec3::ZERO, Quat::IDENTITY, 0.0);
+        assert!(support.abs_diff_eq(Vec3::new(0.5, -1.0, 0.0), 1e-5));
+
+        // a squat puck is easier to turn end over end than to spin flat
+        let inertia = Shape::make_cylinder(0.1, 1.0).inertia_tensor();
+        assert!(inertia.x_axis.x < inertia.y_axis.y);
+    }
+
+    #[test]
+    fn test_compound_shape() {
+        // two unit cubes side by side weigh and turn the same as one 2x1x1 box
+        let cube = Shape::make_box_from_half_extents(Vec3::splat(0.5));
+        let pair = Shape::make_compound(Arc::new(ShapeCompound::new(vec![
+            ((Vec3::new(0.5, 0.0, 0.0), Quat::IDENTITY), cube.clone()),
+            ((Vec3::new(1.5, 0.0, 0.0), Quat::IDENTITY), cube.clone()),
+        ])));
+        let slab = Shape::make_box(Arc::new(ShapeBox::new(&[
+            Vec3::new(0.0, -0.5, -0.5),
+            Vec3::new(2.0, 0.5, 0.5),
+        ])));
+        assert!((pair.volume() - 2.0).abs() < 1e-5);
+        assert!(pair
+            .centre_of_mass()
+            .abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-5));
+        assert!(pair
+            .inertia_tensor()
+            .abs_diff_eq(slab.recentered().inertia_tensor(), 1e-5));
+
+        // the bounds are the union of the children, turned with the body
+        let orientation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let bounds = pair.bounds(Vec3::ZERO, orientation);
+        assert!(bounds.mins.abs_diff_eq(Vec3::new(-0.5, -0.5, -2.0), 1e-5));
+        assert!(bounds.maxs.abs_diff_eq(Vec3::new(0.5, 0.5, 0.0), 1e-5));
+
+        // compounds inside compounds are flattened, keeping where their children are
+        let nested = ShapeCompound::new(vec![(
+            (Vec3::new(0.0, 1.0, 0.0), orientation),
+            pair.clone(),
+        )]);
+        assert_eq!(2, nested.children().len());
+        let ((position, _), _) = &nested.children()[1];
+        assert!(position.abs_diff_eq(Vec3::new(0.0, 1.0, -1.5), 1e-5));
+    }
+
+    #[test]
+    fn test_convex_hull_from_points() {
+        // the corners of a 2x2x2 cube, with points inside it and on its faces. the point just
+        // off the top face adds triangles that should still merge into a single face
+        let mut points = Vec::new();
+        for &x in &[-1.0, 1.0] {
+            for &y in &[-1.0, 1.0] {
+                for &z in &[-1.0, 1.0] {
+                    points.push(Vec3::new(x, y, z));
+                }
+            }
+        }
+        points.push(Vec3::ZERO);
+        points.push(Vec3::new(0.2, -0.3, 0.4));
+        points.push(Vec3::new(1.0, 0.5, 0.0));
+        points.push(Vec3::new(0.0, 1.0 + 1e-5, 0.0));
+
+        let hull = ConvexHull::from_points(&points).unwrap();
+        assert!(hull.points().len() <= 9);
+        assert_eq!(6, hull.faces().len());
+        for face in hull.faces() {
+            assert_eq!(4, face.points.len());
+        }
+        assert!((hull.volume() - 8.0).abs() < 1e-3);
+        assert!(hull.centre_of_mass().abs_diff_eq(Vec3::ZERO, 1e-3));
+        let cube = Shape::make_box_from_half_extents(Vec3::ONE);
+        assert!(hull
+            .inertia_tensor()
+            .abs_diff_eq(cube.inertia_tensor(), 1e-3));
+
+        // turned, the box's inertia picks up products of inertia
+        let rotation = Quat::from_rotation_z(0.5);
+        let long_box = Vec3::new(2.0, 0.5, 1.0);
+        let turned: Vec<Vec3> = points[..8]
+            .iter()
+            .map(|&pt| rotation * (pt * long_box))
+            .collect();
+        let turned_hull = ConvexHull::from_points(&turned).unwrap();
+        let rotation = Mat3::from_quat(rotation);
+        let expected = rotation
+            * Shape::make_box_from_half_extents(long_box).inertia_tensor()
+            * rotation.transpose();
+        assert!(turned_hull.inertia_tensor().abs_diff_eq(expected, 1e-3));
+
+        let shape = Shape::make_convex(Arc::new(ShapeConvex::from_hull(&hull)));
+        assert_eq!(hull.centre_of_mass(), shape.centre_of_mass());
+        let bounds = shape.local_bounds();
+        assert!(bounds
+            .maxs
+            .abs_diff_eq(Vec3::new(1.0, 1.0 + 1e-5, 1.0), 1e-6));
+
+        // there's no volume in too few points or points in a plane
+        assert!(ConvexHull::from_points(&points[..3]).is_none());
+        let flat: Vec<Vec3> = points.iter().map(|pt| Vec3::new(pt.x, 0.0, pt.z)).collect();
+        assert!(ConvexHull::from_points(&flat).is_none());
+    }
+
+    #[test]
+    fn test_mass_properties() {
+        // a 2x2x2 cube, as a box and as a hull
+        let cube = Shape::make_box_from_half_extents(Vec3::ONE);
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                let pick = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+                Vec3::new(pick(1), pick(2), pick(4))
+            })
+            .collect();
+        let hull = ConvexHull::from_points(&corners).unwrap();
+        let hull = Shape::make_convex(Arc::new(ShapeConvex::from_hull(&hull)));
+        for shape in [cube, hull] {
+            let mass = MassProperties::from_shape(&shape, 3.0);
+            assert!((mass.mass - 24.0).abs() < 1e-3);
+            assert!(mass.centre_of_mass.abs_diff_eq(Vec3::ZERO, 1e-3));
+            // m * (2^2 + 2^2) / 12
+            assert!(mass
+                .inertia_tensor
+                .abs_diff_eq(Mat3::from_diagonal(Vec3::splat(16.0)), 1e-2));
+
+            // the body simulates with the same properties
+            let body = Body {
+                shape,
+                ..Body::default()
+            }
+            .with_density(3.0);
+            assert!((body.inv_mass - 1.0 / 24.0).abs() < 1e-6);
+            assert!(body
+                .mass_properties()
+                .inertia_tensor
+                .abs_diff_eq(mass.inertia_tensor, 1e-2));
+        }
+
+        let plane = Body {
+            shape: Shape::make_plane(),
+            ..Body::default()
+        }
+        .with_density(3.0);
+        assert!(plane.has_infinite_mass());
+    }
+}
@@ -1,8 +1,9 @@
-use super::{find_support_point, ShapeTrait};
+use super::ShapeTrait;
 use crate::bounds::Bounds;
 use glam::{Mat3, Quat, Vec3};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeBox {
     pub points: [Vec3; 8],
     pub bounds: Bounds,
@@ -37,6 +38,24 @@ impl ShapeBox {
             center_of_mass,
         }
     }
+
+    // a box centred on the origin
+    pub fn from_half_extents(half_extents: Vec3) -> Self {
+        Self::new(&[-half_extents, half_extents])
+    }
+
+    // half the box's width along each of its axes
+    pub fn half_extents(&self) -> Vec3 {
+        (self.bounds.maxs - self.bounds.mins) * 0.5
+    }
+
+    pub fn translated(&self, offset: Vec3) -> Self {
+        let mut points = self.points;
+        for pt in &mut points {
+            *pt += offset;
+        }
+        ShapeBox::new(&points)
+    }
 }
 
 impl ShapeTrait for ShapeBox {
@@ -71,6 +90,11 @@ impl ShapeTrait for ShapeBox {
         tensor + pat_tensor
     }
 
+    fn volume(&self) -> f32 {
+        let d = self.bounds.width();
+        d.x * d.y * d.z
+    }
+
     fn local_bounds(&self) -> Bounds {
         self.bounds
     }
@@ -97,7 +121,14 @@ impl ShapeTrait for ShapeBox {
     }
 
     fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
-        find_support_point(&self.points, dir, pos, orient, bias)
+        // the corner on the side of each axis the direction points to
+        let local_dir = orient.conjugate() * dir;
+        let corner = Vec3::select(
+            local_dir.cmpge(Vec3::ZERO),
+            self.bounds.maxs,
+            self.bounds.mins,
+        );
+        (orient * corner) + pos + dir.normalize() * bias
     }
 
     fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
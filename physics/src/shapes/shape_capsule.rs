@@ -0,0 +1,80 @@
+use super::ShapeTrait;
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// a cylinder with a hemisphere on each end, standing along the y axis
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeCapsule {
+    // half the length of the cylinder, not counting the caps
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+impl ShapeCapsule {
+    // the ends of the segment running down the middle of the capsule, in world space
+    pub fn segment(&self, pos: Vec3, orient: Quat) -> (Vec3, Vec3) {
+        let axis = orient * Vec3::new(0.0, self.half_height, 0.0);
+        (pos - axis, pos + axis)
+    }
+}
+
+impl ShapeTrait for ShapeCapsule {
+    fn centre_of_mass(&self) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        // split the mass between the cylinder and the caps by volume
+        let r = self.radius;
+        let h = 2.0 * self.half_height;
+        let cylinder_volume = r * r * h;
+        let caps_volume = 4.0 * r * r * r / 3.0;
+        let cylinder_mass = cylinder_volume / (cylinder_volume + caps_volume);
+        let caps_mass = 1.0 - cylinder_mass;
+
+        // the caps are moved out to the ends of the cylinder with the parallel axis theorem
+        let axial = cylinder_mass * r * r / 2.0 + caps_mass * 2.0 * r * r / 5.0;
+        let lateral = cylinder_mass * (r * r / 4.0 + h * h / 12.0)
+            + caps_mass * (2.0 * r * r / 5.0 + h * h / 4.0 + 3.0 * h * r / 8.0);
+        Mat3::from_diagonal(Vec3::new(lateral, axial, lateral))
+    }
+
+    fn volume(&self) -> f32 {
+        let r = self.radius;
+        std::f32::consts::PI * r * r * (2.0 * self.half_height + 4.0 * r / 3.0)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let half_extents = Vec3::new(self.radius, self.half_height + self.radius, self.radius);
+        Bounds {
+            mins: -half_extents,
+            maxs: half_extents,
+        }
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        let (start, end) = self.segment(pos, orient);
+        Bounds {
+            mins: start.min(end) - Vec3::splat(self.radius),
+            maxs: start.max(end) + Vec3::splat(self.radius),
+        }
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        let (start, end) = self.segment(pos, orient);
+        let end_point = if dir.dot(end - start) >= 0.0 {
+            end
+        } else {
+            start
+        };
+        end_point + dir.normalize() * (self.radius + bias)
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        // the caps spin about the ends of the segment, so only the ends move the surface. the
+        // ends move in opposite directions, one of them is always moving along dir
+        let axis = Vec3::new(0.0, self.half_height, 0.0);
+        dir.dot(angular_velocity.cross(axis)).abs()
+    }
+}
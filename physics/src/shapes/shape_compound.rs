@@ -0,0 +1,164 @@
+use super::{Shape, ShapeTrait};
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// a shape made of other shapes, each placed at a (position, orientation) in the compound's space.
+// the mass is shared between the children by their volume
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeCompound {
+    children: Vec<((Vec3, Quat), Shape)>,
+    centre_of_mass: Vec3,
+    inertia_tensor: Mat3,
+    volume: f32,
+    bounds: Bounds,
+}
+
+impl ShapeCompound {
+    pub fn new(children: Vec<((Vec3, Quat), Shape)>) -> Self {
+        assert!(!children.is_empty(), "a compound needs at least one child");
+
+        // compounds inside compounds are flattened into this one
+        let mut flattened = Vec::with_capacity(children.len());
+        for ((position, orientation), shape) in children {
+            match &shape {
+                Shape::Compound(compound) => {
+                    for ((child_position, child_orientation), child) in &compound.children {
+                        flattened.push((
+                            (
+                                position + orientation * *child_position,
+                                orientation * *child_orientation,
+                            ),
+                            child.clone(),
+                        ));
+                    }
+                }
+                _ => flattened.push(((position, orientation), shape)),
+            }
+        }
+        let children = flattened;
+
+        let volume: f32 = children.iter().map(|(_, shape)| shape.volume()).sum();
+        let child_centre = |(position, orientation): (Vec3, Quat), shape: &Shape| -> Vec3 {
+            position + orientation * shape.centre_of_mass()
+        };
+        let centre_of_mass = children
+            .iter()
+            .map(|&(placement, ref shape)| child_centre(placement, shape) * shape.volume())
+            .fold(Vec3::ZERO, |acc, centre| acc + centre)
+            / volume;
+
+        // each child's inertia is rotated into the compound's space and moved to the compound's
+        // centre of mass with the parallel axis theorem
+        let mut inertia_tensor = Mat3::ZERO;
+        for &(placement, ref shape) in &children {
+            let mass = shape.volume() / volume;
+            let rotation = Mat3::from_quat(placement.1);
+            let r = child_centre(placement, shape) - centre_of_mass;
+            let r2 = r.length_squared();
+            let pat_tensor = Mat3::from_cols(
+                Vec3::new(r2 - r.x * r.x, -r.x * r.y, -r.x * r.z),
+                Vec3::new(-r.y * r.x, r2 - r.y * r.y, -r.y * r.z),
+                Vec3::new(-r.z * r.x, -r.z * r.y, r2 - r.z * r.z),
+            );
+            inertia_tensor +=
+                (rotation * shape.inertia_tensor() * rotation.transpose() + pat_tensor) * mass;
+        }
+
+        let bounds =
+            children
+                .iter()
+                .fold(Bounds::new(), |bounds, ((position, orientation), shape)| {
+                    bounds.union(&shape.bounds(*position, *orientation))
+                });
+
+        Self {
+            children,
+            centre_of_mass,
+            inertia_tensor,
+            volume,
+            bounds,
+        }
+    }
+
+    // the children and where they're placed, nested compounds have been flattened
+    pub fn children(&self) -> &[((Vec3, Quat), Shape)] {
+        &self.children
+    }
+
+    pub fn translated(&self, offset: Vec3) -> Self {
+        ShapeCompound {
+            children: self
+                .children
+                .iter()
+                .map(|((position, orientation), shape)| {
+                    ((*position + offset, *orientation), shape.clone())
+                })
+                .collect(),
+            centre_of_mass: self.centre_of_mass + offset,
+            // the inertia tensor is relative to the centre of mass so translation doesn't change it
+            inertia_tensor: self.inertia_tensor,
+            volume: self.volume,
+            bounds: Bounds {
+                mins: self.bounds.mins + offset,
+                maxs: self.bounds.maxs + offset,
+            },
+        }
+    }
+}
+
+impl ShapeTrait for ShapeCompound {
+    fn centre_of_mass(&self) -> Vec3 {
+        self.centre_of_mass
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        self.inertia_tensor
+    }
+
+    fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        // the union of the children's bounds is tighter than turning the compound's bounds
+        self.children
+            .iter()
+            .fold(Bounds::new(), |bounds, ((position, orientation), shape)| {
+                bounds.union(&shape.bounds(pos + orient * *position, orient * *orientation))
+            })
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        // the hull around all of the children, only used when something needs the compound as a
+        // whole
+        self.children
+            .iter()
+            .map(|((position, orientation), shape)| {
+                shape.support(dir, pos + orient * *position, orient * *orientation, bias)
+            })
+            .max_by(|a, b| dir.dot(*a).partial_cmp(&dir.dot(*b)).unwrap())
+            .unwrap()
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        // each child's centre moves about the compound's centre, and its surface moves about its
+        // own centre
+        self.children
+            .iter()
+            .map(|((position, orientation), shape)| {
+                let r = *position + *orientation * shape.centre_of_mass() - self.centre_of_mass;
+                let inv_orientation = orientation.conjugate();
+                dir.dot(angular_velocity.cross(r))
+                    + shape.fastest_linear_speed(
+                        inv_orientation * angular_velocity,
+                        inv_orientation * dir,
+                    )
+            })
+            .fold(0.0, f32::max)
+    }
+}
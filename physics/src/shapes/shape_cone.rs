@@ -0,0 +1,92 @@
+use super::ShapeTrait;
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// a solid cone standing along the y axis, with its base at -half_height and its tip at
+// half_height
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeCone {
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+impl ShapeCone {
+    // the point furthest along the direction, in local space
+    fn local_support(&self, dir: Vec3) -> Vec3 {
+        // the tip wins when the direction is closer to the axis than the cone's side is
+        let height = 2.0 * self.half_height;
+        let sin_angle = self.radius / (self.radius * self.radius + height * height).sqrt();
+        if dir.y > dir.length() * sin_angle {
+            return Vec3::new(0.0, self.half_height, 0.0);
+        }
+
+        // otherwise it's on the rim of the base, or the base's centre when pointing straight down
+        let across = Vec3::new(dir.x, 0.0, dir.z);
+        let length = across.length();
+        let base = Vec3::new(0.0, -self.half_height, 0.0);
+        if length > 1e-6 {
+            across * (self.radius / length) + base
+        } else {
+            base
+        }
+    }
+}
+
+impl ShapeTrait for ShapeCone {
+    fn centre_of_mass(&self) -> Vec3 {
+        // a quarter of the way up from the base
+        Vec3::new(0.0, -self.half_height * 0.5, 0.0)
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        // about the centre of mass
+        let r2 = self.radius * self.radius;
+        let h = 2.0 * self.half_height;
+        let axial = 3.0 * r2 / 10.0;
+        let lateral = 3.0 * r2 / 20.0 + 3.0 * h * h / 80.0;
+        Mat3::from_diagonal(Vec3::new(lateral, axial, lateral))
+    }
+
+    fn volume(&self) -> f32 {
+        std::f32::consts::PI * self.radius * self.radius * 2.0 * self.half_height / 3.0
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let half_extents = Vec3::new(self.radius, self.half_height, self.radius);
+        Bounds {
+            mins: -half_extents,
+            maxs: half_extents,
+        }
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        // the tip and the base's disc
+        let axis = orient * Vec3::Y;
+        let tip = pos + axis * self.half_height;
+        let base = pos - axis * self.half_height;
+        let base_extent = Vec3::new(
+            (axis.y * axis.y + axis.z * axis.z).sqrt(),
+            (axis.x * axis.x + axis.z * axis.z).sqrt(),
+            (axis.x * axis.x + axis.y * axis.y).sqrt(),
+        ) * self.radius;
+        Bounds {
+            mins: tip.min(base - base_extent),
+            maxs: tip.max(base + base_extent),
+        }
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        let local_dir = orient.conjugate() * dir;
+        (orient * self.local_support(local_dir)) + pos + dir.normalize() * bias
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        // the speed of a point r along dir is dir.(w x r) = r.(dir x w), the fastest point is the
+        // one furthest along dir x w from the centre of mass
+        let spin = dir.cross(angular_velocity);
+        (self.local_support(spin) - self.centre_of_mass())
+            .dot(spin)
+            .max(0.0)
+    }
+}
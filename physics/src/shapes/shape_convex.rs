@@ -98,6 +98,23 @@ fn build_tetrahedron(verts: &[Vec3], hull_points: &mut Vec<Vec3>, hull_tris: &mu
     ]);
 }
 
+// whether the points build_tetrahedron() would pick enclose a volume, it can't find the normals
+// of a tetrahedron that's flat
+fn spans_volume(verts: &[Vec3]) -> bool {
+    let point0 = verts[find_point_furthest_in_dir(verts, Vec3::X)];
+    let point1 = verts[find_point_furthest_in_dir(verts, -point0)];
+    if point0.distance_squared(point1) <= f32::EPSILON {
+        return false;
+    }
+    let point2 = find_point_furthest_from_line(verts, point0, point1);
+    let normal = (point1 - point0).cross(point2 - point0);
+    if normal.length_squared() <= f32::EPSILON {
+        return false;
+    }
+    let point3 = find_point_furthest_from_triangle(verts, point0, point1, point2);
+    distance_from_triangle(point0, point1, point2, point3).abs() > f32::EPSILON
+}
+
 fn remove_internal_points(hull_points: &[Vec3], hull_tris: &[Tri], check_pts: &mut Vec<Vec3>) {
     // for i in 0..check_pts.len() {
     let mut i = 0;
@@ -483,6 +500,21 @@ fn tetrahedron_volume(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> f32 {
     volume.abs()
 }
 
+fn calculate_volume(pts: &[Vec3], tris: &[Tri]) -> f32 {
+    // the hull split into tetrahedra that share a point inside it
+    let centerish = pts.iter().sum::<Vec3>() * (pts.len() as f32).recip();
+    tris.iter()
+        .map(|tri| {
+            tetrahedron_volume(
+                centerish,
+                pts[tri.a as usize],
+                pts[tri.b as usize],
+                pts[tri.c as usize],
+            )
+        })
+        .sum()
+}
+
 fn calculate_center_of_mass_tetrahedron(pts: &[Vec3], tris: &[Tri]) -> Vec3 {
     let mut cms = Vec::with_capacity(tris.len());
     let mut volumes = Vec::with_capacity(tris.len());
@@ -545,14 +577,15 @@ fn inertia_tensor_tetrahedron(pt_a: Vec3, pt_b: Vec3, pt_c: Vec3, pt_d: Vec3) ->
     let mut xz = 0.0;
     let mut yz = 0.0;
 
+    // each pair of corners once, along with each corner with itself
     for i in 0..4 {
-        for j in 0..4 {
+        for j in i..4 {
             // diagonals
             xx += pts[i].x * pts[j].x;
             yy += pts[i].y * pts[j].y;
             zz += pts[i].z * pts[j].z;
 
-            // off-diagonals
+            // off-diagonals, a corner with itself counts twice
             xy += pts[i].x * pts[j].y + pts[j].x * pts[i].y;
             xz += pts[i].x * pts[j].z + pts[j].x * pts[i].z;
             yz += pts[i].y * pts[j].z + pts[j].y * pts[i].z;
@@ -593,12 +626,187 @@ fn calculate_inertia_tensor_tetrahedron(pts: &[Vec3], tris: &[Tri], cm: Vec3) ->
     inertia_tensor * total_volume.recip()
 }
 
+// triangles closer than this to a face's plane are merged into it
+const COPLANAR_TOLERANCE: f32 = 1e-3;
+
+// a flat side of a hull, made of one or more of its triangles
+#[derive(Clone, Debug)]
+pub struct ConvexFace {
+    pub normal: Vec3,
+    // the corners around the outside of the face, counter clockwise looking at its front
+    pub points: Vec<u32>,
+}
+
+// the triangles that share an edge with each of the triangles
+fn triangle_neighbours(tris: &[Tri]) -> Vec<Vec<usize>> {
+    let edges = |tri: &Tri| {
+        [
+            Edge { a: tri.a, b: tri.b },
+            Edge { a: tri.b, b: tri.c },
+            Edge { a: tri.c, b: tri.a },
+        ]
+    };
+    tris.iter()
+        .enumerate()
+        .map(|(i, tri)| {
+            let tri_edges = edges(tri);
+            tris.iter()
+                .enumerate()
+                .filter(|&(j, other)| {
+                    i != j
+                        && edges(other)
+                            .iter()
+                            .any(|edge| tri_edges.iter().any(|e| e == edge))
+                })
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect()
+}
+
+// merges the triangles into faces, spreading out from each triangle to its neighbours that lie
+// in the same plane
+fn merge_coplanar_triangles(pts: &[Vec3], tris: &[Tri]) -> Vec<ConvexFace> {
+    let neighbours = triangle_neighbours(tris);
+    let mut face_of = vec![None; tris.len()];
+    let mut faces = Vec::new();
+    for seed in 0..tris.len() {
+        if face_of[seed].is_some() {
+            continue;
+        }
+
+        let tri = tris[seed];
+        let a = pts[tri.a as usize];
+        let normal = (pts[tri.b as usize] - a)
+            .cross(pts[tri.c as usize] - a)
+            .normalize();
+        let in_plane = |tri: &Tri| {
+            [tri.a, tri.b, tri.c]
+                .iter()
+                .all(|&i| normal.dot(pts[i as usize] - a).abs() < COPLANAR_TOLERANCE)
+        };
+
+        let mut members = vec![seed];
+        face_of[seed] = Some(faces.len());
+        let mut next = 0;
+        while next < members.len() {
+            for &j in &neighbours[members[next]] {
+                if face_of[j].is_none() && in_plane(&tris[j]) {
+                    face_of[j] = Some(faces.len());
+                    members.push(j);
+                }
+            }
+            next += 1;
+        }
+
+        // the outline is made of the edges that aren't shared with another triangle of the face,
+        // they all run the same way around so they can be followed from one to the next
+        let directed: Vec<(u32, u32)> = members
+            .iter()
+            .flat_map(|&i| {
+                let tri = tris[i];
+                vec![(tri.a, tri.b), (tri.b, tri.c), (tri.c, tri.a)]
+            })
+            .collect();
+        let outline: Vec<(u32, u32)> = directed
+            .iter()
+            .copied()
+            .filter(|&(a, b)| !directed.contains(&(b, a)))
+            .collect();
+        let mut points = vec![outline[0].0];
+        let mut current = outline[0].1;
+        while current != points[0] && points.len() < outline.len() {
+            points.push(current);
+            current = outline
+                .iter()
+                .find(|&&(a, _)| a == current)
+                .map(|&(_, b)| b)
+                .unwrap_or(points[0]);
+        }
+
+        faces.push(ConvexFace { normal, points });
+    }
+    faces
+}
+
+// the convex hull around a cloud of points, with its triangles merged into flat faces and the
+// mass properties of a solid of unit density
+#[derive(Clone, Debug)]
+pub struct ConvexHull {
+    points: Vec<Vec3>,
+    triangles: Vec<Tri>,
+    faces: Vec<ConvexFace>,
+    centre_of_mass: Vec3,
+    // per unit mass, about the centre of mass
+    inertia_tensor: Mat3,
+    volume: f32,
+}
+
+impl ConvexHull {
+    // None when there's no volume to wrap, fewer than four points or all of them in a plane
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        if points.len() < 4 || !spans_volume(points) {
+            return None;
+        }
+
+        let mut hull_points = Vec::new();
+        let mut hull_tris = Vec::new();
+        build_convex_hull(points, &mut hull_points, &mut hull_tris);
+
+        // the tetrahedra are exact, unlike the sampling ShapeConvex::new() uses. a nearly flat hull
+        // can still come out with no volume
+        let volume = calculate_volume(&hull_points, &hull_tris);
+        if volume.is_nan() || volume <= f32::EPSILON {
+            return None;
+        }
+        let centre_of_mass = calculate_center_of_mass_tetrahedron(&hull_points, &hull_tris);
+        let inertia_tensor =
+            calculate_inertia_tensor_tetrahedron(&hull_points, &hull_tris, centre_of_mass);
+        let faces = merge_coplanar_triangles(&hull_points, &hull_tris);
+
+        Some(Self {
+            points: hull_points,
+            triangles: hull_tris,
+            faces,
+            centre_of_mass,
+            inertia_tensor,
+            volume,
+        })
+    }
+
+    // the corners of the hull, the points that were inside it are dropped
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    pub fn triangles(&self) -> &[Tri] {
+        &self.triangles
+    }
+
+    pub fn faces(&self) -> &[ConvexFace] {
+        &self.faces
+    }
+
+    pub fn centre_of_mass(&self) -> Vec3 {
+        self.centre_of_mass
+    }
+
+    pub fn inertia_tensor(&self) -> Mat3 {
+        self.inertia_tensor
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ShapeConvex {
     points: Vec<Vec3>,
     bounds: Bounds,
     centre_of_mass: Vec3,
     inertia_tensor: Mat3,
+    volume: f32,
 }
 
 impl ShapeConvex {
@@ -614,17 +822,45 @@ impl ShapeConvex {
 
         let inertia_tensor = calculate_inertia_tensor(&hull_points, &hull_tris, centre_of_mass);
 
+        let volume = calculate_volume(&hull_points, &hull_tris);
+
         ShapeConvex {
             points: hull_points,
             bounds,
             centre_of_mass,
             inertia_tensor,
+            volume,
+        }
+    }
+
+    // a shape for the hull, keeping its mass properties
+    pub fn from_hull(hull: &ConvexHull) -> Self {
+        ShapeConvex {
+            points: hull.points.clone(),
+            bounds: Bounds::from_points(&hull.points),
+            centre_of_mass: hull.centre_of_mass,
+            inertia_tensor: hull.inertia_tensor,
+            volume: hull.volume,
         }
     }
 
     pub fn points(&self) -> &[Vec3] {
         &self.points
     }
+
+    pub fn translated(&self, offset: Vec3) -> Self {
+        ShapeConvex {
+            points: self.points.iter().map(|&pt| pt + offset).collect(),
+            bounds: Bounds {
+                mins: self.bounds.mins + offset,
+                maxs: self.bounds.maxs + offset,
+            },
+            centre_of_mass: self.centre_of_mass + offset,
+            // the inertia tensor is relative to the centre of mass so translation doesn't change it
+            inertia_tensor: self.inertia_tensor,
+            volume: self.volume,
+        }
+    }
 }
 
 impl ShapeTrait for ShapeConvex {
@@ -636,6 +872,10 @@ impl ShapeTrait for ShapeConvex {
         self.inertia_tensor
     }
 
+    fn volume(&self) -> f32 {
+        self.volume
+    }
+
     fn local_bounds(&self) -> Bounds {
         self.bounds
     }
@@ -0,0 +1,83 @@
+use super::ShapeTrait;
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// a solid cylinder standing along the y axis
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeCylinder {
+    pub half_height: f32,
+    pub radius: f32,
+}
+
+impl ShapeCylinder {
+    // the point furthest along the direction, in local space
+    fn local_support(&self, dir: Vec3) -> Vec3 {
+        let y = if dir.y >= 0.0 {
+            self.half_height
+        } else {
+            -self.half_height
+        };
+        // straight up or down any point on the cap will do, so use its centre
+        let across = Vec3::new(dir.x, 0.0, dir.z);
+        let length = across.length();
+        if length > 1e-6 {
+            across * (self.radius / length) + Vec3::new(0.0, y, 0.0)
+        } else {
+            Vec3::new(0.0, y, 0.0)
+        }
+    }
+}
+
+impl ShapeTrait for ShapeCylinder {
+    fn centre_of_mass(&self) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        let r2 = self.radius * self.radius;
+        let h = 2.0 * self.half_height;
+        let axial = r2 / 2.0;
+        let lateral = r2 / 4.0 + h * h / 12.0;
+        Mat3::from_diagonal(Vec3::new(lateral, axial, lateral))
+    }
+
+    fn volume(&self) -> f32 {
+        std::f32::consts::PI * self.radius * self.radius * 2.0 * self.half_height
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let half_extents = Vec3::new(self.radius, self.half_height, self.radius);
+        Bounds {
+            mins: -half_extents,
+            maxs: half_extents,
+        }
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        // the caps are discs, each stretches as far as the radius across the world axes
+        let axis = orient * Vec3::Y;
+        let extent = Vec3::new(
+            (axis.y * axis.y + axis.z * axis.z).sqrt(),
+            (axis.x * axis.x + axis.z * axis.z).sqrt(),
+            (axis.x * axis.x + axis.y * axis.y).sqrt(),
+        ) * self.radius
+            + axis.abs() * self.half_height;
+        Bounds {
+            mins: pos - extent,
+            maxs: pos + extent,
+        }
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        let local_dir = orient.conjugate() * dir;
+        (orient * self.local_support(local_dir)) + pos + dir.normalize() * bias
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        // the speed of a point r along dir is dir.(w x r) = r.(dir x w), the fastest point is the
+        // one furthest along dir x w
+        let spin = dir.cross(angular_velocity);
+        self.local_support(spin).dot(spin).max(0.0)
+    }
+}
@@ -0,0 +1,172 @@
+use super::{find_support_point, ShapeTrait};
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// terrain from a grid of heights, centred on the origin in x and z. like a mesh it has no volume
+// so it's only meant for static bodies. the cells under another body are found straight from
+// its bounds, so the grid can be as large as a heightmap without being turned into triangles
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeHeightfield {
+    columns: usize,
+    rows: usize,
+    // row major, a row runs along x
+    heights: Vec<f32>,
+    // the size of a cell in x and z, and what the heights are multiplied by
+    scale: Vec3,
+    bounds: Bounds,
+}
+
+// the cell the local position falls in along one axis
+fn cell_along(position: f32, scale: f32, count: usize) -> f32 {
+    (position / scale + (count - 1) as f32 * 0.5).floor()
+}
+
+impl ShapeHeightfield {
+    pub fn new(columns: usize, rows: usize, heights: Vec<f32>, scale: Vec3) -> Self {
+        assert!(
+            columns >= 2 && rows >= 2,
+            "a heightfield needs at least 2x2 heights"
+        );
+        assert_eq!(
+            columns * rows,
+            heights.len(),
+            "expected a height for every point"
+        );
+
+        let mut field = Self {
+            columns,
+            rows,
+            heights,
+            scale,
+            bounds: Bounds::new(),
+        };
+        let (min_height, max_height) = field
+            .heights
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &h| {
+                (min.min(h), max.max(h))
+            });
+        let corner_a = field.point(0, 0);
+        let corner_b = field.point(columns - 1, rows - 1);
+        field.bounds = Bounds {
+            mins: Vec3::new(corner_a.x, min_height * scale.y, corner_a.z),
+            maxs: Vec3::new(corner_b.x, max_height * scale.y, corner_b.z),
+        };
+        field
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    pub fn scale(&self) -> Vec3 {
+        self.scale
+    }
+
+    // the grid point in the field's local space
+    pub fn point(&self, column: usize, row: usize) -> Vec3 {
+        Vec3::new(
+            (column as f32 - (self.columns - 1) as f32 * 0.5) * self.scale.x,
+            self.heights[row * self.columns + column] * self.scale.y,
+            (row as f32 - (self.rows - 1) as f32 * 0.5) * self.scale.z,
+        )
+    }
+
+    // calls hit with each of the triangles in the cells under the bounds, both in the field's
+    // local space. each cell is split into two triangles facing up
+    pub fn query(&self, bounds: &Bounds, mut hit: impl FnMut([Vec3; 3])) {
+        if !self.bounds.does_intersect(bounds) {
+            return;
+        }
+
+        let last_column = (self.columns - 2) as f32;
+        let last_row = (self.rows - 2) as f32;
+        let first_column =
+            cell_along(bounds.mins.x, self.scale.x, self.columns).clamp(0.0, last_column) as usize;
+        let end_column =
+            cell_along(bounds.maxs.x, self.scale.x, self.columns).clamp(0.0, last_column) as usize;
+        let first_row =
+            cell_along(bounds.mins.z, self.scale.z, self.rows).clamp(0.0, last_row) as usize;
+        let end_row =
+            cell_along(bounds.maxs.z, self.scale.z, self.rows).clamp(0.0, last_row) as usize;
+
+        for row in first_row..=end_row {
+            for column in first_column..=end_column {
+                let corners = [
+                    self.point(column, row),
+                    self.point(column, row + 1),
+                    self.point(column + 1, row),
+                    self.point(column + 1, row + 1),
+                ];
+
+                // skip cells that are entirely above or below the bounds
+                let (low, high) = corners
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(low, high), pt| {
+                        (low.min(pt.y), high.max(pt.y))
+                    });
+                if low > bounds.maxs.y || high < bounds.mins.y {
+                    continue;
+                }
+
+                hit([corners[0], corners[1], corners[2]]);
+                hit([corners[2], corners[1], corners[3]]);
+            }
+        }
+    }
+}
+
+impl ShapeTrait for ShapeHeightfield {
+    fn centre_of_mass(&self) -> Vec3 {
+        // the field is static, keep it where it was placed
+        Vec3::ZERO
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        // a heightfield has no volume to speak of, treat it like its bounding box
+        let d = self.bounds.width();
+        let dd = d * d;
+        Mat3::from_diagonal(Vec3::new(dd.y + dd.z, dd.x + dd.z, dd.x + dd.y) / 12.0)
+    }
+
+    fn volume(&self) -> f32 {
+        let d = self.bounds.width();
+        d.x * d.y * d.z
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        self.bounds.transformed(pos, orient)
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        // the hull of every grid point, only used when something needs the field as a whole
+        let points: Vec<Vec3> = (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+            .map(|(column, row)| self.point(column, row))
+            .collect();
+        find_support_point(&points, dir, pos, orient, bias)
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        // the speed of a point r along dir is r.(dir x w), which is largest at a corner of the
+        // bounds since the bounds hold every point
+        let spin = dir.cross(angular_velocity);
+        let centre = (self.bounds.mins + self.bounds.maxs) * 0.5;
+        let half_extents = self.bounds.width() * 0.5;
+        let reach = (half_extents * spin).abs();
+        (centre.dot(spin) + reach.x + reach.y + reach.z).max(0.0)
+    }
+}
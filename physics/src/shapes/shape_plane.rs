@@ -0,0 +1,55 @@
+use super::ShapeTrait;
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// how far the plane reaches along its surface. it's meant to be infinite, but the broadphase
+// needs finite bounds
+const PLANE_EXTENT: f32 = 1.0e5;
+
+// the bounds only go this far below the surface, like the ground boxes. anything heading deeper
+// is caught with a swept test instead
+const PLANE_DEPTH: f32 = 1.0;
+
+// the half space below the xz plane, with the surface facing up along y. it has no mass or volume
+// so it's only for bodies with infinite mass, like the ground
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapePlane;
+
+impl ShapeTrait for ShapePlane {
+    fn centre_of_mass(&self) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        // never used, the body's mass is infinite
+        Mat3::IDENTITY
+    }
+
+    fn volume(&self) -> f32 {
+        0.0
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds {
+            mins: Vec3::new(-PLANE_EXTENT, -PLANE_DEPTH, -PLANE_EXTENT),
+            maxs: Vec3::new(PLANE_EXTENT, 0.0, PLANE_EXTENT),
+        }
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        self.local_bounds().transformed(pos, orient)
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        // the corner of the bounds, only used when something needs the plane as a convex shape
+        let bounds = self.local_bounds();
+        let local_dir = orient.conjugate() * dir;
+        let corner = Vec3::select(local_dir.cmpge(Vec3::ZERO), bounds.maxs, bounds.mins);
+        (orient * corner) + pos + dir.normalize() * bias
+    }
+
+    fn fastest_linear_speed(&self, _angular_velocity: Vec3, _dir: Vec3) -> f32 {
+        0.0
+    }
+}
@@ -3,6 +3,7 @@ use crate::bounds::Bounds;
 use glam::{Mat3, Quat, Vec3};
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeSphere {
     pub radius: f32,
 }
@@ -17,6 +18,10 @@ impl ShapeTrait for ShapeSphere {
         Mat3::from_diagonal(Vec3::splat(i))
     }
 
+    fn volume(&self) -> f32 {
+        4.0 * std::f32::consts::PI * self.radius * self.radius * self.radius / 3.0
+    }
+
     fn local_bounds(&self) -> Bounds {
         Bounds {
             mins: Vec3::splat(-self.radius),
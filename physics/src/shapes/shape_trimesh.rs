@@ -0,0 +1,178 @@
+use super::{find_support_point, ShapeTrait};
+use crate::bounds::Bounds;
+use glam::{Mat3, Quat, Vec3};
+
+// leaves hold at most this many triangles
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+struct TriMeshNode {
+    bounds: Bounds,
+    // None for leaves
+    children: Option<[usize; 2]>,
+    // the range of triangle_order a leaf holds
+    first: usize,
+    count: usize,
+}
+
+// an arbitrary triangle mesh, like level geometry exported from a modelling tool. it has no
+// volume so it's only meant for static bodies, the triangles are kept in a bounding volume
+// hierarchy so only the ones near another body are tested against it
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeTriMesh {
+    vertices: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+    nodes: Vec<TriMeshNode>,
+    // the triangles in the order the leaves refer to them
+    triangle_order: Vec<usize>,
+    bounds: Bounds,
+}
+
+impl ShapeTriMesh {
+    pub fn new(vertices: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Self {
+        let bounds = Bounds::from_points(&vertices);
+        let mut mesh = Self {
+            vertices,
+            triangles,
+            nodes: Vec::new(),
+            triangle_order: Vec::new(),
+            bounds,
+        };
+        let mut triangle_order: Vec<usize> = (0..mesh.triangles.len()).collect();
+        if !triangle_order.is_empty() {
+            mesh.build_node(&mut triangle_order, 0);
+        }
+        mesh.triangle_order = triangle_order;
+        mesh
+    }
+
+    pub fn vertices(&self) -> &[Vec3] {
+        &self.vertices
+    }
+
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    // the corners of the triangle, in the mesh's local space
+    pub fn triangle(&self, index: usize) -> [Vec3; 3] {
+        let [a, b, c] = self.triangles[index];
+        [
+            self.vertices[a as usize],
+            self.vertices[b as usize],
+            self.vertices[c as usize],
+        ]
+    }
+
+    // the indices of the triangles whose bounds overlap the bounds, in the mesh's local space
+    pub fn query(&self, bounds: &Bounds, mut hit: impl FnMut(usize)) {
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.does_intersect(bounds) {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend_from_slice(&children),
+                None => {
+                    for &triangle in &self.triangle_order[node.first..node.first + node.count] {
+                        hit(triangle);
+                    }
+                }
+            }
+        }
+    }
+
+    fn triangle_bounds(&self, index: usize) -> Bounds {
+        Bounds::from_points(&self.triangle(index))
+    }
+
+    // builds the node for the triangles, which start at first in triangle_order. they're split in
+    // half along the longest axis of their centres until there's few enough for a leaf
+    fn build_node(&mut self, triangles: &mut [usize], first: usize) -> usize {
+        let mut bounds = Bounds::new();
+        let mut centre_bounds = Bounds::new();
+        for &triangle in triangles.iter() {
+            let triangle_bounds = self.triangle_bounds(triangle);
+            bounds.expand_by_bounds(&triangle_bounds);
+            centre_bounds.expand_by_point((triangle_bounds.mins + triangle_bounds.maxs) * 0.5);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(TriMeshNode {
+            bounds,
+            children: None,
+            first,
+            count: triangles.len(),
+        });
+        if triangles.len() <= MAX_LEAF_TRIANGLES {
+            return index;
+        }
+
+        let extents = centre_bounds.width();
+        let axis = if extents.x >= extents.y && extents.x >= extents.z {
+            0
+        } else if extents.y >= extents.z {
+            1
+        } else {
+            2
+        };
+        let centre = |triangle: usize| {
+            let bounds = self.triangle_bounds(triangle);
+            (bounds.mins[axis] + bounds.maxs[axis]) * 0.5
+        };
+        let mid = triangles.len() / 2;
+        triangles.select_nth_unstable_by(mid, |&a, &b| centre(a).partial_cmp(&centre(b)).unwrap());
+
+        let (left, right) = triangles.split_at_mut(mid);
+        let child_a = self.build_node(left, first);
+        let child_b = self.build_node(right, first + mid);
+        self.nodes[index].children = Some([child_a, child_b]);
+        index
+    }
+}
+
+impl ShapeTrait for ShapeTriMesh {
+    fn centre_of_mass(&self) -> Vec3 {
+        // the mesh is static, keep the vertices where they were modelled
+        Vec3::ZERO
+    }
+
+    fn inertia_tensor(&self) -> Mat3 {
+        // a mesh has no volume to speak of, treat it like its bounding box
+        let d = self.bounds.width();
+        let dd = d * d;
+        Mat3::from_diagonal(Vec3::new(dd.y + dd.z, dd.x + dd.z, dd.x + dd.y) / 12.0)
+    }
+
+    fn volume(&self) -> f32 {
+        let d = self.bounds.width();
+        d.x * d.y * d.z
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn bounds(&self, pos: Vec3, orient: Quat) -> Bounds {
+        self.bounds.transformed(pos, orient)
+    }
+
+    fn support(&self, dir: Vec3, pos: Vec3, orient: Quat, bias: f32) -> Vec3 {
+        // the mesh's convex hull, only used when something needs the mesh as a whole
+        find_support_point(&self.vertices, dir, pos, orient, bias)
+    }
+
+    fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
+        self.vertices
+            .iter()
+            .map(|&pt| dir.dot(angular_velocity.cross(pt)))
+            .fold(0.0, f32::max)
+    }
+}
@@ -0,0 +1,50 @@
+// counts and timings for the last step, for seeing where the time goes
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StepStats {
+    // the pairs the broadphase found, before the ignored and resting pairs are dropped
+    pub broadphase_pairs: usize,
+    // the pairs the narrowphase looked for contacts between
+    pub narrowphase_tests: usize,
+    // the resting contacts in the manifolds and the ballistic contacts
+    pub contacts: usize,
+    pub islands: usize,
+    // the velocity iterations of every island, then the position iterations
+    pub solver_iterations: u32,
+    // dynamic bodies that aren't asleep at the end of the step
+    pub bodies_awake: usize,
+    pub broadphase_time: Duration,
+    pub narrowphase_time: Duration,
+    // the constraints and resting contacts, and the sleep checks
+    pub solver_time: Duration,
+    // the ballistic impacts and moving the bodies
+    pub integrate_time: Duration,
+}
+
+impl StepStats {
+    pub fn total_time(&self) -> Duration {
+        self.broadphase_time + self.narrowphase_time + self.solver_time + self.integrate_time
+    }
+}
+
+// times one stage of a step. with the trace feature the stage is a tracing span too
+pub(crate) struct StageTimer {
+    start: Instant,
+    #[cfg(feature = "trace")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl StageTimer {
+    pub(crate) fn start(_stage: &'static str) -> Self {
+        Self {
+            start: Instant::now(),
+            #[cfg(feature = "trace")]
+            _span: tracing::info_span!("physics", stage = _stage).entered(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Duration {
+        self.start.elapsed()
+    }
+}
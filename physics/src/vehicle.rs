@@ -0,0 +1,400 @@
+// a raycast vehicle. the chassis is an ordinary body and each wheel is a ray cast down from it,
+// the suspension pushes the chassis up off whatever the ray hits and the tyre pushes it along and
+// keeps it from sliding sideways. the scene solves its vehicles every step, before the contacts
+use crate::{
+    body::{Body, BodyArena, BodyHandle},
+    query::ray_cast,
+};
+use glam::{Quat, Vec3};
+use std::f32::consts::TAU;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct VehicleHandle(pub u32);
+
+// where a wheel touched the ground in the last step
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct WheelContact {
+    pub handle: BodyHandle,
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wheel {
+    // where the suspension is attached to the chassis, relative to its centre of mass
+    pub mount: Vec3,
+    pub radius: f32,
+    // how far the wheel's centre hangs below the mount with nothing pushing on it
+    pub suspension_rest_length: f32,
+    // the spring's force for each unit it's compressed
+    pub suspension_stiffness: f32,
+    // the force resisting each unit per second the suspension moves at
+    pub suspension_damping: f32,
+    pub max_suspension_force: f32,
+    // how hard the tyre grips, as a fraction of the load on it
+    pub friction: f32,
+    // how much the tyre's push rolls the chassis, 1.0 pushes where the wheel touches the ground
+    // and 0.0 level with the chassis' centre of mass. low values keep it from tipping in turns
+    pub roll_influence: f32,
+    // turned by the vehicle's steering
+    pub steered: bool,
+    // pushed by the vehicle's engine force
+    pub driven: bool,
+    // updated every step
+    pub contact: Option<WheelContact>,
+    // how far the wheel's centre is below the mount
+    pub suspension_length: f32,
+    pub suspension_force: f32,
+    // how far the wheel has turned about its axle, for drawing it
+    pub rotation: f32,
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Self {
+            mount: Vec3::ZERO,
+            radius: 0.4,
+            suspension_rest_length: 0.3,
+            suspension_stiffness: 20000.0,
+            suspension_damping: 2000.0,
+            max_suspension_force: 50000.0,
+            friction: 1.0,
+            roll_influence: 0.1,
+            steered: false,
+            driven: false,
+            contact: None,
+            suspension_length: 0.3,
+            suspension_force: 0.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vehicle {
+    pub chassis: BodyHandle,
+    pub wheels: Vec<Wheel>,
+    // the chassis' up and forward directions, in its local space
+    pub up: Vec3,
+    pub forward: Vec3,
+    // split between the driven wheels, negative reverses
+    pub engine_force: f32,
+    // the most force each wheel brakes with
+    pub brake_force: f32,
+    // how far the steered wheels are turned, in radians, positive turns left
+    pub steering: f32,
+}
+
+impl Vehicle {
+    pub fn new(chassis: BodyHandle) -> Self {
+        Self {
+            chassis,
+            wheels: Vec::new(),
+            up: Vec3::Y,
+            forward: -Vec3::Z,
+            engine_force: 0.0,
+            brake_force: 0.0,
+            steering: 0.0,
+        }
+    }
+
+    pub fn with_wheel(mut self, wheel: Wheel) -> Self {
+        self.wheels.push(wheel);
+        self
+    }
+
+    // the wheel's centre and orientation in the world, for drawing it. the orientation includes
+    // the steering and how far it has rolled
+    pub fn wheel_transform(&self, chassis: &Body, index: usize) -> (Vec3, Quat) {
+        let wheel = &self.wheels[index];
+        let up = chassis.orientation * self.up;
+        let position = chassis.local_to_world(wheel.mount) - up * wheel.suspension_length;
+        let axle = self.up.cross(self.forward).normalize();
+        let orientation = chassis.orientation
+            * Quat::from_axis_angle(self.up, if wheel.steered { self.steering } else { 0.0 })
+            * Quat::from_axis_angle(axle, wheel.rotation);
+        (position, orientation)
+    }
+
+    // casts the wheels against the other bodies and pushes the chassis and what the wheels are
+    // on with the suspension and tyres over the step. every wheel works from the velocities at
+    // the start of the step, the tyres share out stopping the chassis sliding sideways between
+    // them so one wheel's push doesn't undo another's
+    pub(crate) fn step(&mut self, bodies: &mut BodyArena, delta_seconds: f32) {
+        let chassis_handle = self.chassis;
+        if bodies.get_body(chassis_handle).sleeping {
+            // parked until the engine starts
+            if self.engine_force == 0.0 {
+                return;
+            }
+            bodies.get_body_mut(chassis_handle).wake();
+        }
+        let chassis = bodies.get_body(chassis_handle);
+        let up = (chassis.orientation * self.up).normalize();
+        let chassis_forward = chassis.orientation * self.forward;
+        let num_driven = self.wheels.iter().filter(|wheel| wheel.driven).count();
+
+        for wheel in self.wheels.iter_mut() {
+            let mount = chassis.local_to_world(wheel.mount);
+            let hit = ray_cast(
+                bodies.iter_with_handles(),
+                mount,
+                -up,
+                wheel.suspension_rest_length + wheel.radius,
+                |handle, body| handle != chassis_handle && !body.is_sensor,
+            );
+            wheel.contact = hit.map(|hit| WheelContact {
+                handle: hit.handle,
+                point: hit.point,
+                normal: hit.normal,
+            });
+            // hanging down as far as it goes when it's off the ground
+            wheel.suspension_length = hit.map_or(wheel.suspension_rest_length, |hit| {
+                (hit.distance - wheel.radius).max(0.0)
+            });
+        }
+        let num_grounded = self
+            .wheels
+            .iter()
+            .filter(|wheel| wheel.contact.is_some())
+            .count();
+
+        let mut impulses = Vec::with_capacity(num_grounded);
+        for wheel in self.wheels.iter_mut() {
+            let contact = match wheel.contact {
+                Some(contact) => contact,
+                None => {
+                    wheel.suspension_force = 0.0;
+                    continue;
+                }
+            };
+            let ground = bodies.get_body(contact.handle);
+            let (point, normal) = (contact.point, contact.normal);
+            let velocity = point_velocity(chassis, point) - point_velocity(ground, point);
+            let forward_speed = velocity.dot(chassis_forward);
+            wheel.rotation = (wheel.rotation + forward_speed / wheel.radius * delta_seconds) % TAU;
+
+            // the spring pushes back against being compressed, the damper against the suspension
+            // moving either way
+            let compression = wheel.suspension_rest_length - wheel.suspension_length;
+            let force = wheel.suspension_stiffness * compression
+                - wheel.suspension_damping * velocity.dot(up);
+            wheel.suspension_force = force.clamp(0.0, wheel.max_suspension_force);
+            let suspension_impulse = wheel.suspension_force * delta_seconds;
+
+            // the tyre's directions, flat against the ground
+            let steering = if wheel.steered { self.steering } else { 0.0 };
+            let forward = Quat::from_axis_angle(up, steering) * chassis_forward;
+            let forward = (forward - normal * forward.dot(normal)).normalize_or_zero();
+            let side = normal.cross(forward);
+
+            let height = (point - chassis.centre_of_mass_world()).dot(up);
+            let tyre_point = point - up * height * (1.0 - wheel.roll_influence);
+            let tyre_mass = |dir: Vec3| {
+                let inv_mass =
+                    inv_mass_at(chassis, tyre_point, dir) + inv_mass_at(ground, point, dir);
+                if inv_mass > 0.0 {
+                    1.0 / inv_mass
+                } else {
+                    0.0
+                }
+            };
+
+            // sideways the tyre stops the slip, along it the engine drives and the brake stops
+            let mut side_impulse = -velocity.dot(side) * tyre_mass(side) / num_grounded as f32;
+            let mut forward_impulse = 0.0;
+            if wheel.driven {
+                forward_impulse += self.engine_force / num_driven as f32 * delta_seconds;
+            }
+            if self.brake_force > 0.0 {
+                let max_brake = self.brake_force * delta_seconds;
+                let stop = -velocity.dot(forward) * tyre_mass(forward) / num_grounded as f32;
+                forward_impulse += stop.clamp(-max_brake, max_brake);
+            }
+
+            // the tyre can't grip harder than the load on it
+            let max_grip = wheel.friction * suspension_impulse;
+            let grip = (forward_impulse * forward_impulse + side_impulse * side_impulse).sqrt();
+            if grip > max_grip {
+                let scale = max_grip / grip;
+                forward_impulse *= scale;
+                side_impulse *= scale;
+            }
+
+            impulses.push((
+                contact,
+                tyre_point,
+                normal * suspension_impulse,
+                forward * forward_impulse + side * side_impulse,
+            ));
+        }
+
+        for (contact, tyre_point, suspension_impulse, tyre_impulse) in impulses {
+            let (chassis, ground) = bodies.get_body_pair_mut(chassis_handle, contact.handle);
+            chassis.apply_impulse(contact.point, suspension_impulse);
+            chassis.apply_impulse(tyre_point, tyre_impulse);
+            ground.apply_impulse(contact.point, -suspension_impulse - tyre_impulse);
+        }
+    }
+}
+
+// the velocity of the point on the body
+fn point_velocity(body: &Body, point: Vec3) -> Vec3 {
+    let r = point - body.centre_of_mass_world();
+    body.linear_velocity + body.angular_velocity.cross(r)
+}
+
+// the inverse mass an impulse at the point along the unit direction sees, with the turning it
+// causes
+fn inv_mass_at(body: &Body, point: Vec3, dir: Vec3) -> f32 {
+    if body.has_infinite_mass() {
+        return 0.0;
+    }
+    let r = point - body.centre_of_mass_world();
+    let angular = (body.inv_intertia_tensor_world() * r.cross(dir)).cross(r);
+    body.inv_mass_along(dir) + angular.dot(dir)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scene::PhysicsScene, scene_shapes::make_box_ground, shapes::Shape};
+
+    // a one tonne car, its wheels reach the ground with the chassis 0.825 up once the springs
+    // hold its weight
+    fn make_car(scene: &mut PhysicsScene) -> VehicleHandle {
+        scene.add_body(Body {
+            inv_mass: 0.0,
+            shape: make_box_ground(),
+            ..Body::default()
+        });
+        let chassis = scene.add_body(Body {
+            position: Vec3::new(0.0, 1.0, 0.0),
+            inv_mass: 1.0 / 1000.0,
+            shape: Shape::make_box_from_half_extents(Vec3::new(1.0, 0.25, 2.0)),
+            friction: 0.5,
+            ..Body::default()
+        });
+        let mut vehicle = Vehicle::new(chassis);
+        for &(x, z) in &[(-0.9, -1.5), (0.9, -1.5), (-0.9, 1.5), (0.9, 1.5)] {
+            vehicle = vehicle.with_wheel(Wheel {
+                mount: Vec3::new(x, -0.25, z),
+                // the front wheels steer, the back ones drive
+                steered: z < 0.0,
+                driven: z > 0.0,
+                ..Wheel::default()
+            });
+        }
+        scene.add_vehicle(vehicle)
+    }
+
+    fn step(scene: &mut PhysicsScene, seconds: f32) {
+        for _ in 0..(seconds * 60.0) as u32 {
+            scene.update(1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn test_vehicle_suspension() {
+        let mut scene = PhysicsScene::empty();
+        let handle = make_car(&mut scene);
+        step(&mut scene, 3.0);
+
+        let vehicle = scene.vehicle(handle).unwrap();
+        let chassis = scene.get_body(vehicle.chassis).unwrap();
+        assert!((chassis.position.y - 0.825).abs() < 0.02);
+        assert!(chassis.linear_velocity.length() < 0.01);
+        // the springs hold it up between them, it never touches the ground itself
+        assert!(scene.contacts_for_body(vehicle.chassis).is_empty());
+        let total: f32 = vehicle
+            .wheels
+            .iter()
+            .map(|wheel| {
+                assert!(wheel.contact.is_some());
+                wheel.suspension_force
+            })
+            .sum();
+        assert!((total - 10000.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_vehicle_driving() {
+        let mut scene = PhysicsScene::empty();
+        let handle = make_car(&mut scene);
+        step(&mut scene, 1.0);
+
+        // the engine pushes it forward
+        scene.vehicle_mut(handle).unwrap().engine_force = 4000.0;
+        step(&mut scene, 2.0);
+        let chassis = scene.vehicle(handle).unwrap().chassis;
+        let body = scene.get_body(chassis).unwrap();
+        assert!(body.position.z < -5.0);
+        assert!(body.position.x.abs() < 0.01);
+        assert!(body.linear_velocity.z < -5.0);
+        assert!(scene.vehicle(handle).unwrap().wheels[0].rotation != 0.0);
+
+        // the brakes stop it
+        let vehicle = scene.vehicle_mut(handle).unwrap();
+        vehicle.engine_force = 0.0;
+        vehicle.brake_force = 5000.0;
+        step(&mut scene, 3.0);
+        assert!(scene.get_body(chassis).unwrap().linear_velocity.length() < 0.1);
+
+        // steering left while driving turns it left
+        let vehicle = scene.vehicle_mut(handle).unwrap();
+        vehicle.brake_force = 0.0;
+        vehicle.engine_force = 2000.0;
+        vehicle.steering = 0.3;
+        step(&mut scene, 2.0);
+        let body = scene.get_body(chassis).unwrap();
+        let heading = body.orientation * -Vec3::Z;
+        assert!(heading.x < -0.2);
+        assert!(body.position.x < 0.0);
+        assert!(scene.contacts_for_body(chassis).is_empty());
+    }
+
+    #[test]
+    fn test_vehicle_snapshot_restore() {
+        let mut scene = PhysicsScene::empty();
+        let handle = make_car(&mut scene);
+        scene.vehicle_mut(handle).unwrap().engine_force = 4000.0;
+        step(&mut scene, 1.0);
+
+        let snapshot = scene.snapshot();
+        let rotation = scene.vehicle(handle).unwrap().wheels[3].rotation;
+        step(&mut scene, 1.0);
+        let expected = scene.state_hash();
+        let expected_rotation = scene.vehicle(handle).unwrap().wheels[3].rotation;
+
+        // the driver's inputs and the wheels' state go back with the chassis
+        scene.vehicle_mut(handle).unwrap().engine_force = -4000.0;
+        scene.restore(&snapshot);
+        assert_eq!(rotation, scene.vehicle(handle).unwrap().wheels[3].rotation);
+        step(&mut scene, 1.0);
+        assert_eq!(expected, scene.state_hash());
+        assert_eq!(
+            expected_rotation,
+            scene.vehicle(handle).unwrap().wheels[3].rotation
+        );
+    }
+
+    #[test]
+    fn test_remove_chassis_keeps_vehicle_handles() {
+        let mut scene = PhysicsScene::empty();
+        let first = make_car(&mut scene);
+        let second = make_car(&mut scene);
+        let chassis = scene.vehicle(second).unwrap().chassis;
+
+        // the second car keeps its handle when the first goes with its chassis
+        scene.remove_body(scene.vehicle(first).unwrap().chassis);
+        assert!(scene.vehicle(first).is_none());
+        assert_eq!(chassis, scene.vehicle(second).unwrap().chassis);
+        step(&mut scene, 3.0);
+        let body = scene.get_body(chassis).unwrap();
+        assert!((body.position.y - 0.825).abs() < 0.02);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::time_accumulator::TimeAccumulator;
+use bevy::{prelude::*, render::pipeline::PrimitiveTopology};
+use physics::{
+    debug::{debug_lines, DebugCategories},
+    scene::PhysicsScene,
+};
+
+// draws the lines from physics::debug over the scene, each category in its own color
+pub struct DebugRenderPlugin;
+
+impl Plugin for DebugRenderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // everything starts hidden
+        app.insert_resource(DebugCategories {
+            colliders: false,
+            aabbs: false,
+            contacts: false,
+            constraints: false,
+            ..DebugCategories::default()
+        })
+        .add_startup_system(setup_debug_render.system())
+        .add_system(debug_render_system.system());
+    }
+}
+
+#[derive(Copy, Clone)]
+enum DebugCategory {
+    Colliders,
+    Aabbs,
+    Contacts,
+    Constraints,
+}
+
+// the line mesh a category is drawn with
+struct DebugLineMesh(DebugCategory);
+
+fn line_mesh(lines: &[(Vec3, Vec3)]) -> Mesh {
+    let positions: Vec<[f32; 3]> = lines
+        .iter()
+        .flat_map(|&(start, end)| [start.into(), end.into()])
+        .collect();
+    // the default shader wants normals and uvs even though the lines are unlit
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let uvs = vec![[0.0; 2]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
+fn setup_debug_render(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let categories = [
+        (DebugCategory::Colliders, Color::rgb(0.2, 1.0, 0.2)),
+        (DebugCategory::Aabbs, Color::rgb(1.0, 0.6, 0.1)),
+        (DebugCategory::Contacts, Color::rgb(1.0, 0.1, 0.1)),
+        (DebugCategory::Constraints, Color::rgb(0.2, 0.6, 1.0)),
+    ];
+    for &(category, color) in &categories {
+        commands
+            .spawn_bundle(PbrBundle {
+                // a mesh can't be empty, this is replaced when there's something to draw
+                mesh: meshes.add(line_mesh(&[(Vec3::ZERO, Vec3::ZERO)])),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    ..Default::default()
+                }),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: false,
+                },
+                ..Default::default()
+            })
+            .insert(DebugLineMesh(category));
+    }
+}
+
+fn debug_render_system(
+    keys: Res<Input<KeyCode>>,
+    accum: Res<TimeAccumulator>,
+    physics_scene: Res<PhysicsScene>,
+    mut categories: ResMut<DebugCategories>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&DebugLineMesh, &Handle<Mesh>, &mut Visible)>,
+) {
+    // 1 to 4 toggle the colliders, broadphase bounds, contacts and joints
+    if keys.just_released(KeyCode::Key1) {
+        categories.colliders = !categories.colliders;
+    }
+    if keys.just_released(KeyCode::Key2) {
+        categories.aabbs = !categories.aabbs;
+    }
+    if keys.just_released(KeyCode::Key3) {
+        categories.contacts = !categories.contacts;
+    }
+    if keys.just_released(KeyCode::Key4) {
+        categories.constraints = !categories.constraints;
+    }
+
+    // match the sub step size used in physics_update_system
+    categories.aabb_dt = accum.step_secs() * 0.5;
+    let lines = debug_lines(&physics_scene, &categories);
+    for (line_mesh_category, mesh_handle, mut visible) in query.iter_mut() {
+        let (enabled, lines) = match line_mesh_category.0 {
+            DebugCategory::Colliders => (categories.colliders, &lines.colliders),
+            DebugCategory::Aabbs => (categories.aabbs, &lines.aabbs),
+            DebugCategory::Contacts => (categories.contacts, &lines.contacts),
+            DebugCategory::Constraints => (categories.constraints, &lines.constraints),
+        };
+        visible.is_visible = enabled && !lines.is_empty();
+        if visible.is_visible {
+            if let Some(mesh) = meshes.get_mut(mesh_handle) {
+                *mesh = line_mesh(lines);
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod debug_render;
 mod render;
 mod time_accumulator;
 
@@ -10,7 +11,13 @@ use bevy::{
 };
 
 use bevy_flycam::PlayerPlugin;
-use physics::{body::BodyHandle, scene::PhysicsScene};
+use debug_render::DebugRenderPlugin;
+use physics::{
+    body::BodyHandle,
+    plugin::{Collider, PhysicsPlugin, RigidBody, Velocity},
+    scene::PhysicsScene,
+    shapes::Shape,
+};
 use std::borrow::Borrow;
 use time_accumulator::TimeAccumulator;
 
@@ -19,6 +26,8 @@ fn physics_update_system(
     time: Res<Time>,
     mut accum: ResMut<TimeAccumulator>,
     mut scene: ResMut<PhysicsScene>,
+    mut scene_bodies: Query<&mut BodyHandle, Without<RigidBody>>,
+    mut swept_aabbs: Query<&mut SweptAabb>,
 ) {
     // T pauses the sim
     if keys.just_released(KeyCode::T) {
@@ -59,7 +68,23 @@ fn physics_update_system(
 
     // R resets the scene
     if keys.just_released(KeyCode::R) {
+        // the demo's bodies are added again in the same order, so their meshes move over to the
+        // new handles. the plugin adds the bodies made from components again where they are now
+        let old_handles: Vec<BodyHandle> = scene.iter_body_handles().copied().collect();
         scene.reset();
+        let new_handles: Vec<BodyHandle> = scene.iter_body_handles().copied().collect();
+        let remap = |handle: &mut BodyHandle| {
+            let index = old_handles.iter().position(|&old| old == *handle);
+            if let Some(&new) = index.and_then(|index| new_handles.get(index)) {
+                *handle = new;
+            }
+        };
+        for mut handle in scene_bodies.iter_mut() {
+            remap(&mut *handle);
+        }
+        for mut swept_aabb in swept_aabbs.iter_mut() {
+            remap(&mut swept_aabb.0);
+        }
     }
 
     let step_secs = accum.step_secs();
@@ -71,14 +96,33 @@ fn physics_update_system(
     }
 }
 
-fn copy_transforms_system(
+// debug view of the bounds the broadphase sweeps each body through for ccd
+struct SweptAabb(BodyHandle);
+struct ShowSweptAabbs(bool);
+
+fn swept_aabb_system(
+    keys: Res<Input<KeyCode>>,
+    accum: Res<TimeAccumulator>,
     physics_scene: Res<PhysicsScene>,
-    mut query: Query<(&BodyHandle, &mut Transform)>,
+    mut show: ResMut<ShowSweptAabbs>,
+    mut query: Query<(&SweptAabb, &mut Transform, &mut Visible)>,
 ) {
-    for (&body_handle, mut transform) in query.iter_mut() {
-        let body = physics_scene.get_body(body_handle);
-        transform.translation = body.position;
-        transform.rotation = body.orientation;
+    // B toggles the swept bounds
+    if keys.just_released(KeyCode::B) {
+        show.0 = !show.0;
+    }
+
+    // match the sub step size used in physics_update_system
+    let dt = accum.step_secs() * 0.5;
+    for (swept_aabb, mut transform, mut visible) in query.iter_mut() {
+        let body = match physics_scene.get_body(swept_aabb.0) {
+            Some(body) => body,
+            None => continue,
+        };
+        visible.is_visible = show.0;
+        let (mins, maxs) = body.swept_aabb_over(dt);
+        transform.translation = (mins + maxs) * 0.5;
+        transform.scale = maxs - mins;
     }
 }
 
@@ -87,6 +131,7 @@ fn setup_rendering(
     asset_server: ResMut<AssetServer>,
     mut pipelines: ResMut<Assets<PipelineDescriptor>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
     physics_scene: Res<PhysicsScene>,
 ) {
     // watch for changes
@@ -108,7 +153,7 @@ fn setup_rendering(
     });
 
     for &body_handle in physics_scene.iter_body_handles() {
-        let body = physics_scene.get_body(body_handle);
+        let body = physics_scene.get_body(body_handle).unwrap();
         // let base_color = Color::rgb(color.x, color.y, color.z);
         let mesh = meshes.add(render::create_mesh_from_shape(body.shape.borrow()));
         commands
@@ -122,6 +167,43 @@ fn setup_rendering(
             // .insert(material)
             .insert(body_handle);
     }
+
+    // a body made from components, the physics plugin adds it to the scene
+    let ball_shape = Shape::make_sphere(0.5);
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(render::create_mesh_from_shape(&ball_shape)),
+            render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
+                pipeline_handle.clone(),
+            )]),
+            transform: Transform::from_translation(Vec3::new(10.0, 10.0, 0.0)),
+            ..Default::default()
+        })
+        .insert(RigidBody::default())
+        .insert(Collider { shape: ball_shape })
+        .insert(Velocity {
+            linear: Vec3::new(-2.0, 0.0, 0.0),
+            ..Velocity::default()
+        });
+
+    let swept_aabb_mesh = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    let swept_aabb_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1.0, 0.2, 0.2, 0.25),
+        ..Default::default()
+    });
+    for &body_handle in physics_scene.iter_body_handles() {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: swept_aabb_mesh.clone(),
+                material: swept_aabb_material.clone(),
+                visible: Visible {
+                    is_visible: false,
+                    is_transparent: true,
+                },
+                ..Default::default()
+            })
+            .insert(SweptAabb(body_handle));
+    }
 }
 
 fn main() {
@@ -129,10 +211,13 @@ fn main() {
         .insert_resource(Msaa { samples: 4 })
         .insert_resource(PhysicsScene::new())
         .insert_resource(TimeAccumulator::new())
+        .insert_resource(ShowSweptAabbs(false))
         .add_plugins(DefaultPlugins)
         .add_plugin(PlayerPlugin)
+        .add_plugin(PhysicsPlugin::default())
+        .add_plugin(DebugRenderPlugin)
         .add_startup_system(setup_rendering.system())
         .add_system(physics_update_system.system())
-        .add_system(copy_transforms_system.system())
+        .add_system(swept_aabb_system.system())
         .run();
 }
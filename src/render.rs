@@ -1,17 +1,30 @@
 use bevy::{
     prelude::*,
     render::{
-        mesh::{shape::Icosphere, Indices},
+        mesh::{shape::Icosphere, Indices, VertexAttributeValues},
         pipeline::PrimitiveTopology,
     },
 };
-use physics::shapes::{build_convex_hull, Shape, ShapeConvex};
+use physics::shapes::{build_convex_hull, Shape, ShapeCompound, ShapeConvex, ShapeHeightfield};
 
 fn create_mesh_from_convex_shape(convex_shape: &ShapeConvex) -> Mesh {
+    create_mesh_from_points(convex_shape.points())
+}
+
+// points around a circle in the xz plane at the height
+fn ring_points(radius: f32, y: f32) -> impl Iterator<Item = Vec3> {
+    const SEGMENTS: usize = 32;
+    (0..SEGMENTS).map(move |i| {
+        let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+        Vec3::new(angle.cos() * radius, y, angle.sin() * radius)
+    })
+}
+
+fn create_mesh_from_points(points: &[Vec3]) -> Mesh {
     // Build the connected convex hull from the points
     let mut hull_pts = Vec::new();
     let mut hull_tris = Vec::new();
-    build_convex_hull(convex_shape.points(), &mut hull_pts, &mut hull_tris);
+    build_convex_hull(points, &mut hull_pts, &mut hull_tris);
 
     // calculate smoothed normals
     // TODO: Could use map?
@@ -59,6 +72,109 @@ fn create_mesh_from_convex_shape(convex_shape: &ShapeConvex) -> Mesh {
     mesh
 }
 
+fn create_mesh_from_heightfield_shape(heightfield_shape: &ShapeHeightfield) -> Mesh {
+    let (columns, rows) = (heightfield_shape.columns(), heightfield_shape.rows());
+    let mut vertices = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            vertices.push(heightfield_shape.point(column, row));
+        }
+    }
+
+    // two triangles facing up for each cell
+    let mut triangles = Vec::with_capacity((columns - 1) * (rows - 1) * 2);
+    for row in 0..rows - 1 {
+        for column in 0..columns - 1 {
+            let i = (row * columns + column) as u32;
+            let next_row = i + columns as u32;
+            triangles.push([i, next_row, i + 1]);
+            triangles.push([i + 1, next_row, next_row + 1]);
+        }
+    }
+
+    create_mesh_from_triangles(&vertices, &triangles)
+}
+
+fn create_mesh_from_triangles(vertices: &[Vec3], triangles: &[[u32; 3]]) -> Mesh {
+    // each vertex's normal is the average of the faces around it, weighted by their area
+    let mut vertex_normals = vec![Vec3::ZERO; vertices.len()];
+    for &[a, b, c] in triangles {
+        let (a, b, c) = (a as usize, b as usize, c as usize);
+        let n = (vertices[b] - vertices[a]).cross(vertices[c] - vertices[a]);
+        vertex_normals[a] += n;
+        vertex_normals[b] += n;
+        vertex_normals[c] += n;
+    }
+    let normals: Vec<[f32; 3]> = vertex_normals
+        .iter()
+        .map(|n| n.normalize_or_zero().into())
+        .collect();
+    let positions: Vec<[f32; 3]> = vertices.iter().map(|pt| (*pt).into()).collect();
+    let indices: Vec<u32> = triangles
+        .iter()
+        .flat_map(|tri| tri.iter().copied())
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+
+    // fake some UVs for the default shader
+    let uvs: Vec<[f32; 2]> = std::iter::repeat([0.0; 2]).take(vertices.len()).collect();
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+
+    mesh
+}
+
+fn create_mesh_from_compound_shape(compound_shape: &ShapeCompound) -> Mesh {
+    // each child's mesh moved to where it sits in the compound, joined into one mesh
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for ((position, orientation), shape) in compound_shape.children() {
+        let child = create_mesh_from_shape(shape);
+        let first = positions.len() as u32;
+        if let Some(VertexAttributeValues::Float3(child_positions)) =
+            child.attribute(Mesh::ATTRIBUTE_POSITION)
+        {
+            positions.extend(
+                child_positions
+                    .iter()
+                    .map(|&pt| (*position + *orientation * Vec3::from(pt)).into()),
+            );
+        }
+        if let Some(VertexAttributeValues::Float3(child_normals)) =
+            child.attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            normals.extend(
+                child_normals
+                    .iter()
+                    .map(|&n| (*orientation * Vec3::from(n)).into()),
+            );
+        }
+        match child.indices() {
+            Some(Indices::U32(child_indices)) => {
+                indices.extend(child_indices.iter().map(|&i| first + i))
+            }
+            Some(Indices::U16(child_indices)) => {
+                indices.extend(child_indices.iter().map(|&i| first + i as u32))
+            }
+            None => {}
+        }
+    }
+
+    // fake some UVs for the default shader
+    let uvs: Vec<[f32; 2]> = std::iter::repeat([0.0; 2]).take(positions.len()).collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
 pub fn create_mesh_from_shape(shape: &Shape) -> Mesh {
     match shape {
         Shape::Sphere(sphere_shape) => {
@@ -80,6 +196,34 @@ pub fn create_mesh_from_shape(shape: &Shape) -> Mesh {
                 max_z: bounds.maxs.z,
             })
         }
+        Shape::Capsule(capsule_shape) => Mesh::from(shape::Capsule {
+            radius: capsule_shape.radius,
+            depth: capsule_shape.half_height * 2.0,
+            ..Default::default()
+        }),
+        Shape::Cylinder(cylinder_shape) => {
+            let (radius, half_height) = (cylinder_shape.radius, cylinder_shape.half_height);
+            let points: Vec<Vec3> = ring_points(radius, -half_height)
+                .chain(ring_points(radius, half_height))
+                .collect();
+            create_mesh_from_points(&points)
+        }
+        Shape::Cone(cone_shape) => {
+            let (radius, half_height) = (cone_shape.radius, cone_shape.half_height);
+            let points: Vec<Vec3> = ring_points(radius, -half_height)
+                .chain(std::iter::once(Vec3::new(0.0, half_height, 0.0)))
+                .collect();
+            create_mesh_from_points(&points)
+        }
         Shape::Convex(convex_shape) => create_mesh_from_convex_shape(convex_shape),
+        Shape::TriMesh(trimesh_shape) => {
+            create_mesh_from_triangles(trimesh_shape.vertices(), trimesh_shape.triangles())
+        }
+        Shape::Heightfield(heightfield_shape) => {
+            create_mesh_from_heightfield_shape(heightfield_shape)
+        }
+        Shape::Compound(compound_shape) => create_mesh_from_compound_shape(compound_shape),
+        // planes go on forever, draw enough of one to cover the sandbox
+        Shape::Plane(_) => Mesh::from(shape::Plane { size: 200.0 }),
     }
 }